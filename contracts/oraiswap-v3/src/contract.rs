@@ -2,20 +2,26 @@
 use cosmwasm_std::entry_point;
 
 use crate::entrypoints::*;
-use crate::state::CONFIG;
+use crate::state::{get_position_index, CONFIG, POOLS};
 use oraiswap_v3_common::{
     error::ContractError,
     oraiswap_v3_msg::{ExecuteMsg, InstantiateMsg, MigrateMsg, QueryMsg},
     storage::Config,
 };
 
-use cosmwasm_std::{to_json_binary, Binary, Deps, DepsMut, Env, MessageInfo, Response, StdResult};
+use cosmwasm_std::{
+    to_json_binary, Binary, Deps, DepsMut, Env, MessageInfo, Reply, Response, StdResult,
+};
 use cw2::set_contract_version;
 
 // version info for migration info
 const CONTRACT_NAME: &str = "crates.io:oraiswap_v3";
 const CONTRACT_VERSION: &str = env!("CARGO_PKG_VERSION");
 
+// id for each reply
+pub const FLASH_REPLY_ID: u64 = 1;
+pub const INCENTIVE_REPLY_ID: u64 = 2;
+
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn instantiate(
     deps: DepsMut,
@@ -29,6 +35,7 @@ pub fn instantiate(
         admin: info.sender,
         protocol_fee: msg.protocol_fee,
         incentives_fund_manager: msg.incentives_fund_manager,
+        token_uri: None,
     };
     CONFIG.save(deps.storage, &config)?;
 
@@ -55,6 +62,7 @@ pub fn execute(
             pool_key,
             fee_receiver,
         } => change_fee_receiver(deps, info, pool_key, fee_receiver),
+        ExecuteMsg::ChangeTokenUri { token_uri } => change_token_uri(deps, info, token_uri),
         ExecuteMsg::CreatePosition {
             pool_key,
             lower_tick,
@@ -62,16 +70,19 @@ pub fn execute(
             liquidity_delta,
             slippage_limit_lower,
             slippage_limit_upper,
+            limit_order,
         } => create_position(
             deps,
             env,
             info,
+            None,
             pool_key,
             lower_tick,
             upper_tick,
             liquidity_delta,
             slippage_limit_lower,
             slippage_limit_upper,
+            limit_order,
         ),
         ExecuteMsg::Swap {
             pool_key,
@@ -83,6 +94,7 @@ pub fn execute(
             deps,
             env,
             info,
+            None,
             pool_key,
             x_to_y,
             amount,
@@ -98,16 +110,201 @@ pub fn execute(
             deps,
             env,
             info,
+            None,
             amount_in,
             expected_amount_out,
             slippage,
             swaps,
         ),
+        ExecuteMsg::SwapRouteExactOut {
+            amount_out,
+            max_amount_in,
+            swaps,
+        } => swap_route_exact_out(deps, env, info, None, amount_out, max_amount_in, swaps),
+        ExecuteMsg::Receive(msg) => receive_cw20(deps, env, info, msg),
+        ExecuteMsg::Flash {
+            pool_key,
+            amount_x,
+            amount_y,
+            recipient,
+            msg,
+        } => flash(
+            deps, env, info, pool_key, amount_x, amount_y, recipient, msg,
+        ),
+        ExecuteMsg::FlashRepay { amount_x, amount_y } => {
+            flash_repay(deps, env, info, None, amount_x, amount_y)
+        }
         ExecuteMsg::TransferPosition { index, receiver } => {
-            transfer_position(deps, env, info, index, receiver)
+            let owner = info.sender.clone();
+            transfer_position(deps, env, info, &owner, index, receiver)
+        }
+        ExecuteMsg::TransferPositionByTokenId { token_id, receiver } => {
+            let (owner, index) = authorize_position(deps.as_ref(), &env, &info, token_id)?;
+            transfer_position(deps, env, info, &owner, index, receiver)
+        }
+        ExecuteMsg::ClaimFee { index } => claim_fee(deps, env, &info.sender, index, &info.sender),
+        ExecuteMsg::ClaimFeeByTokenId {
+            token_id,
+            recipient,
+        } => {
+            let (owner, index) = authorize_position(deps.as_ref(), &env, &info, token_id)?;
+            let recipient = recipient.unwrap_or_else(|| owner.clone());
+            claim_fee(deps, env, &owner, index, &recipient)
+        }
+        ExecuteMsg::CompoundFees {
+            index,
+            max_slippage,
+            slippage_limit_lower,
+            slippage_limit_upper,
+        } => compound_fees(
+            deps,
+            env,
+            info,
+            index,
+            max_slippage,
+            slippage_limit_lower,
+            slippage_limit_upper,
+        ),
+        ExecuteMsg::CompoundFeesByTokenId {
+            token_id,
+            max_slippage,
+            slippage_limit_lower,
+            slippage_limit_upper,
+        } => {
+            let index = get_position_index(deps.storage, &info.sender, token_id)?;
+            compound_fees(
+                deps,
+                env,
+                info,
+                index,
+                max_slippage,
+                slippage_limit_lower,
+                slippage_limit_upper,
+            )
+        }
+        ExecuteMsg::Rebalance {
+            index,
+            new_lower_tick,
+            new_upper_tick,
+            slippage,
+            slippage_limit_lower,
+            slippage_limit_upper,
+        } => rebalance(
+            deps,
+            env,
+            info,
+            index,
+            new_lower_tick,
+            new_upper_tick,
+            slippage,
+            slippage_limit_lower,
+            slippage_limit_upper,
+        ),
+        ExecuteMsg::RebalanceByTokenId {
+            token_id,
+            new_lower_tick,
+            new_upper_tick,
+            slippage,
+            slippage_limit_lower,
+            slippage_limit_upper,
+        } => {
+            let index = get_position_index(deps.storage, &info.sender, token_id)?;
+            rebalance(
+                deps,
+                env,
+                info,
+                index,
+                new_lower_tick,
+                new_upper_tick,
+                slippage,
+                slippage_limit_lower,
+                slippage_limit_upper,
+            )
+        }
+        ExecuteMsg::RemovePosition { index } => {
+            remove_position(deps, env, &info.sender, index, &info.sender)
+        }
+        ExecuteMsg::RemovePositionByTokenId {
+            token_id,
+            recipient,
+        } => {
+            let (owner, index) = authorize_position(deps.as_ref(), &env, &info, token_id)?;
+            let recipient = recipient.unwrap_or_else(|| owner.clone());
+            remove_position(deps, env, &owner, index, &recipient)
+        }
+        ExecuteMsg::ClaimFilledOrder { index } => {
+            claim_filled_order(deps, &info.sender, index, &info.sender)
+        }
+        ExecuteMsg::ClaimFilledOrderByTokenId {
+            token_id,
+            recipient,
+        } => {
+            let (owner, index) = authorize_position(deps.as_ref(), &env, &info, token_id)?;
+            let recipient = recipient.unwrap_or_else(|| owner.clone());
+            claim_filled_order(deps, &owner, index, &recipient)
+        }
+        ExecuteMsg::IncreaseLiquidity {
+            index,
+            liquidity_delta,
+            slippage_limit_lower,
+            slippage_limit_upper,
+        } => increase_liquidity(
+            deps,
+            env,
+            info,
+            index,
+            liquidity_delta,
+            slippage_limit_lower,
+            slippage_limit_upper,
+        ),
+        ExecuteMsg::IncreaseLiquidityByTokenId {
+            token_id,
+            liquidity_delta,
+            slippage_limit_lower,
+            slippage_limit_upper,
+        } => {
+            let index = get_position_index(deps.storage, &info.sender, token_id)?;
+            increase_liquidity(
+                deps,
+                env,
+                info,
+                index,
+                liquidity_delta,
+                slippage_limit_lower,
+                slippage_limit_upper,
+            )
+        }
+        ExecuteMsg::DecreaseLiquidity {
+            index,
+            liquidity_delta,
+            slippage_limit_lower,
+            slippage_limit_upper,
+        } => decrease_liquidity(
+            deps,
+            env,
+            info,
+            index,
+            liquidity_delta,
+            slippage_limit_lower,
+            slippage_limit_upper,
+        ),
+        ExecuteMsg::DecreaseLiquidityByTokenId {
+            token_id,
+            liquidity_delta,
+            slippage_limit_lower,
+            slippage_limit_upper,
+        } => {
+            let index = get_position_index(deps.storage, &info.sender, token_id)?;
+            decrease_liquidity(
+                deps,
+                env,
+                info,
+                index,
+                liquidity_delta,
+                slippage_limit_lower,
+                slippage_limit_upper,
+            )
         }
-        ExecuteMsg::ClaimFee { index } => claim_fee(deps, env, info, index),
-        ExecuteMsg::RemovePosition { index } => remove_position(deps, env, info, index),
         ExecuteMsg::CreatePool {
             token_0,
             token_1,
@@ -165,17 +362,40 @@ pub fn execute(
             total_reward,
             reward_per_sec,
             start_timestamp,
+            end_timestamp,
         } => create_incentive(
             deps,
             env,
             info,
+            None,
             pool_key,
             reward_token,
             total_reward,
             reward_per_sec,
             start_timestamp,
+            end_timestamp,
         ),
-        ExecuteMsg::ClaimIncentive { index } => claim_incentives(deps, env, info, index),
+        ExecuteMsg::TopUpIncentive {
+            pool_key,
+            incentive_id,
+            amount,
+        } => top_up_incentive(deps, env, info, None, pool_key, incentive_id, amount),
+        ExecuteMsg::RetireIncentive {
+            pool_key,
+            incentive_id,
+        } => retire_incentive(deps, env, info, pool_key, incentive_id),
+        ExecuteMsg::ClaimIncentive { index } => {
+            claim_incentives(deps, env, &info.sender, index, &info.sender)
+        }
+        ExecuteMsg::ClaimIncentiveByTokenId {
+            token_id,
+            recipient,
+        } => {
+            let (owner, index) = authorize_position(deps.as_ref(), &env, &info, token_id)?;
+            let recipient = recipient.unwrap_or_else(|| owner.clone());
+            claim_incentives(deps, env, &owner, index, &recipient)
+        }
+        ExecuteMsg::ClaimAll { indexes, pool_key } => claim_all(deps, env, info, indexes, pool_key),
         ExecuteMsg::UpdateIncentive {
             pool_key,
             incentive_id,
@@ -195,6 +415,16 @@ pub fn execute(
         ExecuteMsg::UpdatePoolStatus { pool_key, status } => {
             update_pool_status(deps, info, pool_key, status)
         }
+        ExecuteMsg::IncreaseObservationCardinalityNext {
+            pool_key,
+            observation_cardinality_next,
+        } => {
+            increase_observation_cardinality_next(deps, env, pool_key, observation_cardinality_next)
+        }
+        ExecuteMsg::UpdateDynamicFee {
+            pool_key,
+            dynamic_fee,
+        } => update_dynamic_fee(deps, env, info, pool_key, dynamic_fee),
     }
 }
 
@@ -207,11 +437,19 @@ pub fn query(deps: Deps, env: Env, msg: QueryMsg) -> StdResult<Binary> {
         QueryMsg::Position { owner_id, index } => {
             to_json_binary(&get_position(deps, owner_id, index)?)
         }
+        QueryMsg::PositionByTokenId { token_id } => {
+            to_json_binary(&get_position_by_token_id(deps, token_id)?)
+        }
         QueryMsg::Positions {
             owner_id,
             limit,
             offset,
         } => to_json_binary(&get_positions(deps, owner_id, limit, offset)?),
+        QueryMsg::LimitOrders {
+            owner,
+            start_after,
+            limit,
+        } => to_json_binary(&get_limit_orders(deps, owner, start_after, limit)?),
         QueryMsg::FeeTierExist { fee_tier } => to_json_binary(&fee_tier_exist(deps, fee_tier)?),
         QueryMsg::Pool {
             token_0,
@@ -276,6 +514,9 @@ pub fn query(deps: Deps, env: Env, msg: QueryMsg) -> StdResult<Binary> {
         QueryMsg::QuoteRoute { amount_in, swaps } => {
             to_json_binary(&quote_route(deps, env, amount_in, swaps)?)
         }
+        QueryMsg::QuoteRouteExactOut { amount_out, swaps } => {
+            to_json_binary(&quote_route_exact_out(deps, env, amount_out, swaps)?)
+        }
         QueryMsg::OwnerOf {
             token_id,
             include_expired,
@@ -285,6 +526,26 @@ pub fn query(deps: Deps, env: Env, msg: QueryMsg) -> StdResult<Binary> {
             token_id,
             include_expired.unwrap_or(false),
         )?),
+        QueryMsg::Approval {
+            token_id,
+            spender,
+            include_expired,
+        } => to_json_binary(&query_approval(
+            deps,
+            env,
+            token_id,
+            spender,
+            include_expired.unwrap_or(false),
+        )?),
+        QueryMsg::Approvals {
+            token_id,
+            include_expired,
+        } => to_json_binary(&query_approvals(
+            deps,
+            env,
+            token_id,
+            include_expired.unwrap_or(false),
+        )?),
         QueryMsg::ApprovedForAll {
             owner,
             include_expired,
@@ -313,19 +574,63 @@ pub fn query(deps: Deps, env: Env, msg: QueryMsg) -> StdResult<Binary> {
             start_after,
             limit,
         } => to_json_binary(&query_tokens(deps, owner, start_after, limit)?),
+        QueryMsg::PositionTokens {
+            owner,
+            start_after,
+            limit,
+        } => to_json_binary(&query_position_tokens(deps, owner, start_after, limit)?),
         QueryMsg::AllTokens { start_after, limit } => {
             to_json_binary(&query_all_tokens(deps, start_after, limit)?)
         }
         QueryMsg::NumTokens {} => to_json_binary(&query_num_tokens(deps)?),
+        QueryMsg::ContractInfo {} => to_json_binary(&query_contract_info()),
+        QueryMsg::Minter {} => to_json_binary(&query_minter()),
         QueryMsg::PositionIncentives { owner_id, index } => {
             to_json_binary(&query_position_incentives(deps, env, owner_id, index)?)
         }
+        QueryMsg::PositionIncentivesByTokenId { token_id } => {
+            to_json_binary(&query_position_incentives_by_token_id(deps, env, token_id)?)
+        }
+        QueryMsg::PoolIncentives { pool_key } => {
+            to_json_binary(&query_pool_incentives(deps, env, pool_key)?)
+        }
+        QueryMsg::PendingIncentives {
+            owner,
+            start_after,
+            limit,
+        } => to_json_binary(&query_pending_incentives(
+            deps,
+            env,
+            owner,
+            start_after,
+            limit,
+        )?),
         QueryMsg::PoolsByPoolKeys { pool_keys } => {
             to_json_binary(&get_pools_with_pool_keys(deps, pool_keys)?)
         }
         QueryMsg::AllPosition { limit, start_after } => {
             to_json_binary(&query_all_positions(deps, limit, start_after)?)
         }
+        QueryMsg::SnapshotCumulativesInside {
+            pool_key,
+            lower_tick,
+            upper_tick,
+        } => to_json_binary(&snapshot_cumulatives_inside(
+            deps, env, pool_key, lower_tick, upper_tick,
+        )?),
+        QueryMsg::Observe {
+            pool_key,
+            seconds_agos,
+        } => to_json_binary(&observe(deps, env, pool_key, seconds_agos)?),
+    }
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn reply(deps: DepsMut, _env: Env, msg: Reply) -> Result<Response, ContractError> {
+    match msg.id {
+        FLASH_REPLY_ID => flash_reply(deps),
+        INCENTIVE_REPLY_ID => incentive_reply(msg),
+        id => Err(ContractError::UnrecognizedReplyId { id }),
     }
 }
 
@@ -334,6 +639,9 @@ pub fn migrate(deps: DepsMut, _env: Env, _msg: MigrateMsg) -> Result<Response, C
     let original_version =
         cw2::ensure_from_older_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
 
+    // token ids are strings in the nft messages from this version and `Tokens` pages by token id
+    // like cw721, clients paging by position index move to `PositionTokens`
+
     // // query all position, then update token id
     // let positions: Vec<_> = crate::state::POSITIONS
     //     .range_raw(deps.storage, None, None, cosmwasm_std::Order::Ascending)
@@ -359,5 +667,22 @@ pub fn migrate(deps: DepsMut, _env: Env, _msg: MigrateMsg) -> Result<Response, C
     // crate::state::TOKEN_COUNT.save(deps.storage, &token_id)?;
     // crate::state::TOKEN_ID.save(deps.storage, &token_id)?;
 
+    // count the open positions of every pool, retired incentives wait for them to settle
+    let mut position_counts: std::collections::BTreeMap<Vec<u8>, u64> = Default::default();
+    for position in
+        crate::state::POSITIONS.range_raw(deps.storage, None, None, cosmwasm_std::Order::Ascending)
+    {
+        let (_, position) = position?;
+        *position_counts.entry(position.pool_key.key()).or_default() += 1;
+    }
+    let pool_keys = POOLS
+        .keys_raw(deps.storage, None, None, cosmwasm_std::Order::Ascending)
+        .collect::<Vec<_>>();
+    for key in pool_keys {
+        let mut pool = POOLS.load(deps.storage, &key)?;
+        pool.position_count = position_counts.get(&key).copied().unwrap_or_default();
+        POOLS.save(deps.storage, &key, &pool)?;
+    }
+
     Ok(Response::new().add_attribute("new_version", original_version.to_string()))
 }
@@ -1,6 +1,4 @@
-use cosmwasm_std::{
-    Addr, Api, CosmosMsg, Deps, DepsMut, Env, MessageInfo, Order, Storage, Timestamp, Uint64,
-};
+use cosmwasm_std::{Addr, Api, Deps, DepsMut, Env, MessageInfo, Order, Storage, Timestamp, Uint64};
 
 use cw20::Expiration;
 use cw_storage_plus::Bound;
@@ -16,7 +14,7 @@ use oraiswap_v3_common::{
         token_amount::TokenAmount,
         MAX_SQRT_PRICE, MIN_SQRT_PRICE,
     },
-    storage::{PoolKey, Position, Tick, UpdatePoolTick},
+    storage::{FeeTier, Observation, Pool, PoolKey, Position, Tick, UpdatePoolTick},
 };
 
 use crate::state;
@@ -35,18 +33,114 @@ pub fn create_tick(
     store: &mut dyn Storage,
     current_timestamp: u64,
     pool_key: &PoolKey,
+    pool: &Pool,
     index: i32,
 ) -> Result<Tick, ContractError> {
     check_tick(index, pool_key.fee_tier.tick_spacing)?;
-    let pool = state::get_pool(store, pool_key)?;
 
-    let tick = Tick::create(index, &pool, current_timestamp);
+    let tick = Tick::create(index, pool, current_timestamp);
     state::add_tick(store, pool_key, index, &tick)?;
     state::flip_bitmap(store, true, index, pool_key.fee_tier.tick_spacing, pool_key)?;
 
     Ok(tick)
 }
 
+/// Writes an oracle observation accumulated with the current tick and liquidity of the pool.
+/// Must be called before the tick or the liquidity of the pool changes, at most one
+/// observation is written per block.
+pub fn write_observation(
+    store: &mut dyn Storage,
+    pool_key: &PoolKey,
+    pool: &mut Pool,
+    block_timestamp: u64,
+) -> Result<(), ContractError> {
+    // first observation, also covers pools created before the oracle existed
+    if pool.observation_cardinality == 0 {
+        state::save_observation(store, pool_key, 0, &Observation::new(block_timestamp))?;
+        pool.observation_index = 0;
+        pool.observation_cardinality = 1;
+        pool.observation_cardinality_next = pool.observation_cardinality_next.max(1);
+        return Ok(());
+    }
+
+    let last = state::get_observation(store, pool_key, pool.observation_index);
+    if last.block_timestamp == block_timestamp {
+        return Ok(());
+    }
+
+    // grow the ring buffer once the last slot has been reached
+    if pool.observation_cardinality_next > pool.observation_cardinality
+        && pool.observation_index == pool.observation_cardinality - 1
+    {
+        pool.observation_cardinality = pool.observation_cardinality_next;
+    }
+
+    let index = (pool.observation_index + 1) % pool.observation_cardinality;
+    let observation = last.transform(block_timestamp, pool.current_tick_index, pool.liquidity)?;
+    state::save_observation(store, pool_key, index, &observation)?;
+    pool.observation_index = index;
+
+    Ok(())
+}
+
+/// Returns the observation at `block_timestamp - seconds_ago`, interpolating between
+/// stored observations when needed.
+pub fn observe_single(
+    store: &dyn Storage,
+    pool_key: &PoolKey,
+    pool: &Pool,
+    block_timestamp: u64,
+    seconds_ago: u64,
+) -> Result<Observation, ContractError> {
+    if pool.observation_cardinality == 0 {
+        return Err(ContractError::OracleUninitialized);
+    }
+
+    let target = block_timestamp
+        .checked_sub(seconds_ago)
+        .ok_or(ContractError::OldObservation)?;
+
+    let newest = state::get_observation(store, pool_key, pool.observation_index);
+    if newest.block_timestamp <= target {
+        return newest.transform(target, pool.current_tick_index, pool.liquidity);
+    }
+
+    let cardinality = pool.observation_cardinality as u32;
+    let oldest_index = (pool.observation_index as u32 + 1) % cardinality;
+    let mut oldest = state::get_observation(store, pool_key, oldest_index as u16);
+    let mut low = oldest_index;
+    if !oldest.initialized {
+        oldest = state::get_observation(store, pool_key, 0);
+        low = 0;
+    }
+    if oldest.block_timestamp > target {
+        return Err(ContractError::OldObservation);
+    }
+
+    // binary search over the ring buffer, ordered from the oldest to the newest observation,
+    // the target lies strictly before the newest one
+    let mut high = pool.observation_index as u32;
+    if high < low {
+        high += cardinality;
+    }
+    high -= 1;
+    loop {
+        let middle = (low + high) / 2;
+        let before = state::get_observation(store, pool_key, (middle % cardinality) as u16);
+        let after = state::get_observation(store, pool_key, ((middle + 1) % cardinality) as u16);
+
+        if before.block_timestamp <= target && target <= after.block_timestamp {
+            return before.interpolate(&after, target);
+        }
+
+        if before.block_timestamp > target {
+            high = middle - 1;
+        } else {
+            low = middle + 1;
+        }
+    }
+}
+
 pub fn calculate_swap(
     store: &dyn Storage,
     current_timestamp: u64,
@@ -62,6 +156,11 @@ pub fn calculate_swap(
 
     let mut ticks: Vec<Tick> = vec![];
     let mut pool = state::get_pool(store, pool_key)?;
+    // the fee is fixed for the whole swap, a dynamic fee follows the price before the swap
+    let fee_tier = FeeTier {
+        fee: pool.swap_fee(pool_key.fee_tier, current_timestamp),
+        ..pool_key.fee_tier
+    };
 
     if x_to_y {
         if pool.sqrt_price <= sqrt_price_limit || sqrt_price_limit > SqrtPrice::new(MAX_SQRT_PRICE)
@@ -104,7 +203,7 @@ pub fn calculate_swap(
             pool.liquidity,
             remaining_amount,
             by_amount_in,
-            pool_key.fee_tier.fee,
+            fee_tier.fee,
         )?;
 
         // make remaining amount smaller
@@ -156,7 +255,7 @@ pub fn calculate_swap(
             x_to_y,
             current_timestamp,
             state::CONFIG.load(store)?.protocol_fee,
-            pool_key.fee_tier,
+            fee_tier,
         )?;
 
         remaining_amount = amount_after_tick_update;
@@ -187,18 +286,15 @@ pub fn calculate_swap(
         start_sqrt_price: event_start_sqrt_price,
         target_sqrt_price: pool.sqrt_price,
         fee: event_fee_amount,
+        fee_rate: fee_tier.fee,
         pool,
         ticks,
     })
 }
 
-#[allow(clippy::too_many_arguments)]
+/// Executes the swap on the pool state, the tokens are moved by the caller.
 pub fn swap_internal(
     store: &mut dyn Storage,
-    api: &dyn Api,
-    info: &MessageInfo,
-    msgs: &mut Vec<CosmosMsg>,
-    contract_address: &Addr,
     current_timestamp: u64,
     pool_key: &PoolKey,
     x_to_y: bool,
@@ -206,7 +302,12 @@ pub fn swap_internal(
     by_amount_in: bool,
     sqrt_price_limit: SqrtPrice,
 ) -> Result<CalculateSwapResult, ContractError> {
-    let calculate_swap_result = calculate_swap(
+    // record the price before the swap moves it
+    let mut pool = state::get_pool(store, pool_key)?;
+    write_observation(store, pool_key, &mut pool, current_timestamp / 1000)?;
+    state::save_pool(store, &pool_key.key(), &pool)?;
+
+    let mut calculate_swap_result = calculate_swap(
         store,
         current_timestamp,
         pool_key,
@@ -220,44 +321,52 @@ pub fn swap_internal(
         state::update_tick(store, pool_key, tick.index, tick)?;
     }
 
-    state::POOLS.save(store, &pool_key.key(), &calculate_swap_result.pool)?;
+    // the crossed ticks are saved first, so the orders are filled with up to date fee growths
+    for tick in &calculate_swap_result.ticks {
+        fill_limit_orders(
+            store,
+            pool_key,
+            &mut calculate_swap_result.pool,
+            tick.index,
+            current_timestamp,
+        )?;
+    }
+
+    state::save_pool(store, &pool_key.key(), &calculate_swap_result.pool)?;
 
-    let (token_0, token_1) = if x_to_y {
+    Ok(calculate_swap_result)
+}
+
+/// Returns the tokens going in and out of a swap, in that order.
+pub fn swap_assets(
+    api: &dyn Api,
+    pool_key: &PoolKey,
+    x_to_y: bool,
+    amount_in: TokenAmount,
+    amount_out: TokenAmount,
+) -> (Asset, Asset) {
+    let (token_in, token_out) = if x_to_y {
         (&pool_key.token_x, &pool_key.token_y)
     } else {
         (&pool_key.token_y, &pool_key.token_x)
     };
 
-    let asset_0 = Asset {
-        info: AssetInfo::from_denom(api, token_0.as_str()),
-        amount: calculate_swap_result.amount_in.into(),
-    };
-
-    let asset_1 = Asset {
-        info: AssetInfo::from_denom(api, token_1.as_str()),
-        amount: calculate_swap_result.amount_out.into(),
-    };
-
-    asset_0.transfer_from(msgs, info, contract_address.to_string())?;
-    asset_1.transfer(msgs, info)?;
-
-    Ok(calculate_swap_result)
+    (
+        Asset::new(AssetInfo::from_denom(api, token_in), amount_in.into()),
+        Asset::new(AssetInfo::from_denom(api, token_out), amount_out.into()),
+    )
 }
 
+/// Executes the swaps of the route one after another, the intermediate tokens stay in the contract.
 pub fn swap_route_internal(
     store: &mut dyn Storage,
-    api: &dyn Api,
-    env: Env,
-    info: &MessageInfo,
-    msgs: &mut Vec<CosmosMsg>,
+    current_timestamp: u64,
     amount_in: TokenAmount,
-    swaps: Vec<SwapHop>,
+    swaps: &[SwapHop],
 ) -> Result<TokenAmount, ContractError> {
     let mut next_swap_amount = amount_in;
 
-    let current_timestamp = env.block.time.millis();
-
-    for swap_hop in &swaps {
+    for swap_hop in swaps {
         let sqrt_price_limit = if swap_hop.x_to_y {
             SqrtPrice::new(MIN_SQRT_PRICE)
         } else {
@@ -266,10 +375,6 @@ pub fn swap_route_internal(
 
         next_swap_amount = swap_internal(
             store,
-            api,
-            info,
-            msgs,
-            &env.contract.address,
             current_timestamp,
             &swap_hop.pool_key,
             swap_hop.x_to_y,
@@ -283,6 +388,42 @@ pub fn swap_route_internal(
     Ok(next_swap_amount)
 }
 
+/// Executes the swaps of the route from the last one, each hop buys exactly what the next one takes.
+/// Returns the amount of the first token spent.
+pub fn swap_route_exact_out_internal(
+    store: &mut dyn Storage,
+    current_timestamp: u64,
+    amount_out: TokenAmount,
+    swaps: &[SwapHop],
+) -> Result<TokenAmount, ContractError> {
+    let mut next_swap_amount = amount_out;
+
+    for swap_hop in swaps.iter().rev() {
+        let sqrt_price_limit = if swap_hop.x_to_y {
+            SqrtPrice::new(MIN_SQRT_PRICE)
+        } else {
+            SqrtPrice::new(MAX_SQRT_PRICE)
+        };
+
+        let result = swap_internal(
+            store,
+            current_timestamp,
+            &swap_hop.pool_key,
+            swap_hop.x_to_y,
+            next_swap_amount,
+            false,
+            sqrt_price_limit,
+        )?;
+        // the contract must not pay the next hop out of the pool reserves
+        if result.amount_out < next_swap_amount {
+            return Err(ContractError::AmountUnderMinimumAmountOut);
+        }
+        next_swap_amount = result.amount_in;
+    }
+
+    Ok(next_swap_amount)
+}
+
 pub fn route(
     store: &dyn Storage,
     env: Env,
@@ -315,6 +456,38 @@ pub fn route(
     Ok(next_swap_amount)
 }
 
+pub fn route_exact_out(
+    store: &dyn Storage,
+    env: Env,
+    amount_out: TokenAmount,
+    swaps: Vec<SwapHop>,
+) -> Result<TokenAmount, ContractError> {
+    let mut next_swap_amount = amount_out;
+
+    let current_timestamp = env.block.time.millis();
+
+    for swap_hop in swaps.iter().rev() {
+        let sqrt_price_limit = if swap_hop.x_to_y {
+            SqrtPrice::new(MIN_SQRT_PRICE)
+        } else {
+            SqrtPrice::new(MAX_SQRT_PRICE)
+        };
+
+        next_swap_amount = calculate_swap(
+            store,
+            current_timestamp,
+            &swap_hop.pool_key,
+            swap_hop.x_to_y,
+            next_swap_amount,
+            false,
+            sqrt_price_limit,
+        )?
+        .amount_in;
+    }
+
+    Ok(next_swap_amount)
+}
+
 pub fn tickmap_slice(
     store: &dyn Storage,
     min_chunk: u16,
@@ -345,6 +518,38 @@ pub fn tickmap_slice(
     tickmap_slice
 }
 
+/// Fills the limit orders waiting for `tick_index` to be crossed, their liquidity is withdrawn
+/// into owed tokens that the owners claim with `ClaimFilledOrder`.
+pub fn fill_limit_orders(
+    store: &mut dyn Storage,
+    pool_key: &PoolKey,
+    pool: &mut Pool,
+    tick_index: i32,
+    current_timestamp: u64,
+) -> Result<(), ContractError> {
+    for token_id in state::take_limit_orders(store, pool_key, tick_index)? {
+        let (mut db_key, index) = state::POSITION_KEYS_BY_TOKEN_ID.load(store, token_id)?;
+        db_key.extend_from_slice(&index.to_be_bytes());
+        let mut position = state::get_position_by_key(store, &db_key)?;
+
+        let mut lower_tick = state::get_tick(store, pool_key, position.lower_tick_index)?;
+        let mut upper_tick = state::get_tick(store, pool_key, position.upper_tick_index)?;
+
+        position.fill(pool, &mut upper_tick, &mut lower_tick, current_timestamp)?;
+        state::update_position(store, &position)?;
+
+        for tick in [lower_tick, upper_tick] {
+            if tick.liquidity_gross.is_zero() {
+                remove_tick_and_flip_bitmap(store, pool_key, &tick)?;
+            } else {
+                state::update_tick(store, pool_key, tick.index, &tick)?;
+            }
+        }
+    }
+
+    Ok(())
+}
+
 pub fn remove_tick_and_flip_bitmap(
     storage: &mut dyn Storage,
     key: &PoolKey,
@@ -426,6 +631,21 @@ pub fn check_can_send(
     }
 }
 
+/// Resolves the owner and index of the position minted as `token_id`, the sender must be its
+/// owner, an approved spender or an operator of the owner
+pub fn authorize_position(
+    deps: Deps,
+    env: &Env,
+    info: &MessageInfo,
+    token_id: u64,
+) -> Result<(Addr, u32), ContractError> {
+    let (owner, index) = state::get_position_owner_and_index(deps.storage, token_id)?;
+    let pos = state::get_position(deps.storage, &owner, index)?;
+    check_can_send(deps, env, info, owner.as_bytes(), &pos)?;
+
+    Ok((owner, index))
+}
+
 pub fn update_approvals(
     deps: DepsMut,
     env: &Env,
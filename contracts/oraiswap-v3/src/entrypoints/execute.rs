@@ -1,29 +1,43 @@
-use crate::state::{self, CONFIG, POOLS};
-use oraiswap_v3_common::asset::{Asset, AssetInfo};
+use std::collections::{btree_map::Entry, BTreeMap};
+
+use crate::contract::{FLASH_REPLY_ID, INCENTIVE_REPLY_ID};
+use crate::state::{
+    self, CONFIG, FLASH_LOAN, MAX_INCENTIVES_PER_POOL, MAX_OBSERVATION_CARDINALITY,
+    MIN_INCENTIVE_DURATION, MIN_LIMIT_ORDER_LIQUIDITY, POOLS,
+};
+use oraiswap_v3_common::asset::{
+    merge_assets, transfer_assets, transfer_from_sender, Asset, AssetInfo,
+};
 use oraiswap_v3_common::error::ContractError;
 use oraiswap_v3_common::incentives_fund_manager;
 use oraiswap_v3_common::interface::{
-    CalculateSwapResult, Cw721ReceiveMsg, PoolWithPoolKey, SwapHop,
+    CalculateSwapResult, Cw721ReceiveMsg, FlashCallbackMsg, PoolWithPoolKey, SwapHop,
 };
+use oraiswap_v3_common::logic::{get_liquidity, get_rebalance_swap};
 use oraiswap_v3_common::math::fee_growth::FeeGrowth;
 use oraiswap_v3_common::math::liquidity::Liquidity;
 use oraiswap_v3_common::math::percentage::Percentage;
-use oraiswap_v3_common::math::sqrt_price::SqrtPrice;
+use oraiswap_v3_common::math::sqrt_price::{get_max_sqrt_price, get_min_sqrt_price, SqrtPrice};
 use oraiswap_v3_common::math::token_amount::TokenAmount;
-use oraiswap_v3_common::math::{calculate_min_amount_out, check_tick};
-use oraiswap_v3_common::storage::incentive::IncentiveRecord;
-use oraiswap_v3_common::storage::{FeeTier, Pool, PoolKey, PoolStatus, Position};
+use oraiswap_v3_common::math::{calculate_min_amount_out, check_tick, check_ticks};
+use oraiswap_v3_common::oraiswap_v3_msg::Cw20HookMsg;
+use oraiswap_v3_common::storage::incentive::{IncentiveRecord, IncentiveRetirement};
+use oraiswap_v3_common::storage::{
+    DynamicFee, DynamicFeeConfig, FeeTier, FlashLoan, LimitOrder, Pool, PoolKey, PoolStatus,
+    Position, Tick,
+};
 
 use super::{
-    check_can_send, create_tick, remove_tick_and_flip_bitmap, swap_internal, swap_route_internal,
-    transfer_nft, update_approvals, TimeStampExt,
+    authorize_position, calculate_swap, create_tick, remove_tick_and_flip_bitmap, swap_assets,
+    swap_internal, swap_route_exact_out_internal, swap_route_internal, transfer_nft,
+    update_approvals, write_observation, TimeStampExt,
 };
 use cosmwasm_std::{
-    attr, wasm_execute, Addr, Attribute, Binary, DepsMut, Env, MessageInfo, Order, Response,
-    StdResult,
+    attr, from_json, wasm_execute, Addr, Api, Attribute, Binary, CosmosMsg, DepsMut, Env,
+    MessageInfo, Order, Reply, Response, StdResult, Storage, SubMsg,
 };
-use cw20::Expiration;
-use decimal::Decimal;
+use cw20::{Cw20ReceiveMsg, Expiration};
+use decimal::{BigOps, CheckedOps, Decimal};
 
 /// Allows an admin to adjust admin.
 ///
@@ -93,7 +107,7 @@ pub fn withdraw_all_protocol_fee(
         }
         let pool_key_db = pool_info.pool_key.key();
         let (fee_protocol_token_x, fee_protocol_token_y) = pool_info.pool.withdraw_protocol_fee();
-        POOLS.save(deps.storage, &pool_key_db, &pool_info.pool)?;
+        state::save_pool(deps.storage, &pool_key_db, &pool_info.pool)?;
 
         let asset_0 = Asset {
             info: AssetInfo::from_denom(deps.api, pool_info.pool_key.token_x.as_str()),
@@ -140,7 +154,7 @@ pub fn withdraw_protocol_fee(
     }
 
     let (fee_protocol_token_x, fee_protocol_token_y) = pool.withdraw_protocol_fee();
-    POOLS.save(deps.storage, &pool_key_db, &pool)?;
+    state::save_pool(deps.storage, &pool_key_db, &pool)?;
 
     let asset_0 = Asset {
         info: AssetInfo::from_denom(deps.api, pool_key.token_x.as_str()),
@@ -226,7 +240,7 @@ pub fn change_fee_receiver(
     let mut pool = POOLS.load(deps.storage, &pool_key_db)?;
     let old_fee_receiver = pool.fee_receiver.clone();
     pool.fee_receiver = fee_receiver.to_string();
-    POOLS.save(deps.storage, &pool_key_db, &pool)?;
+    state::save_pool(deps.storage, &pool_key_db, &pool)?;
 
     let event_attributes = vec![
         attr("action", "change_fee_receiver"),
@@ -239,15 +253,47 @@ pub fn change_fee_receiver(
     Ok(Response::new().add_attributes(event_attributes))
 }
 
+/// Allows an admin to set the base of the token uri of position NFTs.
+///
+/// # Parameters
+/// - `token_uri`: The base the token id is appended to, none removes the token uri.
+///
+/// # Errors
+/// - Reverts the call when the caller is an unauthorized user.
+pub fn change_token_uri(
+    deps: DepsMut,
+    info: MessageInfo,
+    token_uri: Option<String>,
+) -> Result<Response, ContractError> {
+    let mut config = CONFIG.load(deps.storage)?;
+
+    if info.sender != config.admin {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    config.token_uri = token_uri;
+    CONFIG.save(deps.storage, &config)?;
+
+    let event_attributes = vec![
+        attr("action", "change_token_uri"),
+        attr("admin", info.sender.as_str()),
+        attr("token_uri", config.token_uri.unwrap_or_default()),
+    ];
+
+    Ok(Response::new().add_attributes(event_attributes))
+}
+
 /// Opens a position.
 ///
 /// # Parameters
+/// - `received`: The cw20 tokens sent along through the `Receive` hook, if any.
 /// - `pool_key`: A unique key that identifies the specified pool.
 /// - `lower_tick`: The index of the lower tick for opening the position.
 /// - `upper_tick`: The index of the upper tick for opening the position.
 /// - `liquidity_delta`: The desired liquidity provided by the user in the specified range.
 /// - `slippage_limit_lower`: The price limit for downward movement to execute the position creation.
 /// - `slippage_limit_upper`: The price limit for upward movement to execute the position creation.
+/// - `limit_order`: Opens the position as a limit order, filled once the price crosses the far tick of the range.
 ///
 /// # Events
 /// - On successful transfer, emits a `Create Position` event for the newly opened position.
@@ -255,20 +301,26 @@ pub fn change_fee_receiver(
 /// # Errors
 /// - Fails if the user attempts to open a position with zero liquidity.
 /// - Fails if the user attempts to create a position with invalid tick indexes or tick spacing.
+/// - Fails if a limit order range contains the current tick.
+/// - Fails if a limit order has less than `MIN_LIMIT_ORDER_LIQUIDITY` or its tick already holds `MAX_LIMIT_ORDERS_PER_TICK` orders,
+///   or `MAX_LIMIT_ORDERS_PER_OWNER_PER_TICK` orders of the user.
 /// - Fails if the price has reached the slippage limit.
 /// - Fails if the allowance is insufficient or the user balance transfer fails.
+/// - Fails if the native funds sent are not enough or contain a denom the call does not take, excess funds are refunded.
 /// - Fails if pool does not exist
 #[allow(clippy::too_many_arguments)]
 pub fn create_position(
     deps: DepsMut,
     env: Env,
     info: MessageInfo,
+    received: Option<Asset>,
     pool_key: PoolKey,
     lower_tick: i32,
     upper_tick: i32,
     liquidity_delta: Liquidity,
     slippage_limit_lower: SqrtPrice,
     slippage_limit_upper: SqrtPrice,
+    limit_order: Option<bool>,
 ) -> Result<Response, ContractError> {
     let current_timestamp = env.block.time.millis();
     let current_block_number = env.block.height;
@@ -289,17 +341,56 @@ pub fn create_position(
         return Err(ContractError::PoolPaused {});
     }
 
+    // a limit order holds a single token and is filled by crossing the tick on the far side
+    let limit_order = match limit_order {
+        Some(true) => {
+            let tick_index = if pool.current_tick_index < lower_tick {
+                upper_tick
+            } else if pool.current_tick_index >= upper_tick {
+                lower_tick
+            } else {
+                return Err(ContractError::InvalidLimitOrder {});
+            };
+            if liquidity_delta < MIN_LIMIT_ORDER_LIQUIDITY {
+                return Err(ContractError::LimitOrderTooSmall {});
+            }
+            Some(LimitOrder {
+                tick_index,
+                filled: false,
+            })
+        }
+        _ => None,
+    };
+
     // update global incentives
     pool.update_global_incentives(env.block.time.seconds())?;
 
+    // record the price before the liquidity changes
+    write_observation(deps.storage, &pool_key, &mut pool, env.block.time.seconds())?;
+
+    // new ticks snapshot the up to date seconds per liquidity
+    pool.update_seconds_per_liquidity_global(current_timestamp)?;
+
     let mut lower_tick = match state::get_tick(deps.storage, &pool_key, lower_tick) {
         Ok(tick) => tick,
-        _ => create_tick(deps.storage, current_timestamp, &pool_key, lower_tick)?,
+        _ => create_tick(
+            deps.storage,
+            current_timestamp,
+            &pool_key,
+            &pool,
+            lower_tick,
+        )?,
     };
 
     let mut upper_tick = match state::get_tick(deps.storage, &pool_key, upper_tick) {
         Ok(tick) => tick,
-        _ => create_tick(deps.storage, current_timestamp, &pool_key, upper_tick)?,
+        _ => create_tick(
+            deps.storage,
+            current_timestamp,
+            &pool_key,
+            &pool,
+            upper_tick,
+        )?,
     };
 
     let (mut position, x, y) = Position::create(
@@ -316,8 +407,19 @@ pub fn create_position(
     )?;
 
     position.token_id = state::next_token_id(deps.storage)?;
+    position.limit_order = limit_order;
+    if let Some(order) = limit_order {
+        state::add_limit_order(
+            deps.storage,
+            &pool_key,
+            order.tick_index,
+            &info.sender,
+            position.token_id,
+        )?;
+    }
+    pool.position_count += 1;
 
-    POOLS.save(deps.storage, &pool_key_db, &pool)?;
+    state::save_pool(deps.storage, &pool_key_db, &pool)?;
 
     state::add_position(deps.storage, &info.sender, &position)?;
 
@@ -335,8 +437,13 @@ pub fn create_position(
     };
 
     let mut msgs = vec![];
-    asset_0.transfer_from(&mut msgs, &info, env.contract.address.to_string())?;
-    asset_1.transfer_from(&mut msgs, &info, env.contract.address.to_string())?;
+    transfer_from_sender(
+        &mut msgs,
+        &info,
+        received.as_ref(),
+        env.contract.address.to_string(),
+        &[asset_0, asset_1],
+    )?;
 
     let event_attributes = vec![
         attr("action", "create_position"),
@@ -361,6 +468,7 @@ pub fn create_position(
 /// Performs a single swap based on the provided parameters.
 ///
 /// # Parameters
+/// - `received`: The cw20 tokens sent along through the `Receive` hook, if any.
 /// - `pool_key`: A unique key that identifies the specified pool.
 /// - `x_to_y`: A boolean specifying the swap direction.
 /// - `amount`: TokenAmount that the user wants to swap.
@@ -376,6 +484,7 @@ pub fn create_position(
 /// - Fails if the price has reached the specified price limit (or price associated with specified square root of price).
 /// - Fails if the user would receive zero tokens.
 /// - Fails if the allowance is insufficient or the user balance transfer fails.
+/// - Fails if the native funds sent are not enough or contain a denom the call does not take, excess funds are refunded.
 /// - Fails if there is insufficient liquidity in pool
 /// - Fails if pool does not
 #[allow(clippy::too_many_arguments)]
@@ -383,6 +492,7 @@ pub fn swap(
     deps: DepsMut,
     env: Env,
     info: MessageInfo,
+    received: Option<Asset>,
     pool_key: PoolKey,
     x_to_y: bool,
     amount: TokenAmount,
@@ -396,22 +506,17 @@ pub fn swap(
         return Err(ContractError::PoolPaused {});
     }
     pool.update_global_incentives(env.block.time.seconds())?;
-    POOLS.save(deps.storage, &pool_key.key(), &pool)?;
-
-    let mut msgs = vec![];
+    state::save_pool(deps.storage, &pool_key.key(), &pool)?;
 
     let CalculateSwapResult {
         amount_in,
         amount_out,
         fee,
+        fee_rate,
         pool: after_pool,
         ..
     } = swap_internal(
         deps.storage,
-        deps.api,
-        &info,
-        &mut msgs,
-        &env.contract.address,
         env.block.time.millis(),
         &pool_key,
         x_to_y,
@@ -420,6 +525,17 @@ pub fn swap(
         sqrt_price_limit,
     )?;
 
+    let (asset_in, asset_out) = swap_assets(deps.api, &pool_key, x_to_y, amount_in, amount_out);
+    let mut msgs = vec![];
+    transfer_from_sender(
+        &mut msgs,
+        &info,
+        received.as_ref(),
+        env.contract.address.to_string(),
+        &[asset_in],
+    )?;
+    asset_out.transfer(&mut msgs, &info)?;
+
     let event_attributes = vec![
         attr("action", "swap"),
         attr("pool_key", pool_key.to_string()),
@@ -434,6 +550,7 @@ pub fn swap(
         attr("liquidity", after_pool.liquidity.get().to_string()),
         attr("x_to_y", x_to_y.to_string()),
         attr("fee", fee.to_string()),
+        attr("fee_rate", fee_rate.get().to_string()),
     ];
 
     Ok(Response::new()
@@ -444,6 +561,7 @@ pub fn swap(
 /// Performs atomic swap involving several pools based on the provided parameters.
 ///
 /// # Parameters
+/// - `received`: The cw20 tokens sent along through the `Receive` hook, if any.
 /// - `amount_in`: The amount of tokens that the user wants to swap.
 /// - `expected_amount_out`: The amount of tokens that the user wants to receive as a result of the swaps.
 /// - `slippage`: The max acceptable percentage difference between the expected and actual amount of output tokens in a trade, not considering square root of target price as in the case of a swap.
@@ -457,69 +575,511 @@ pub fn swap(
 /// - Fails if the user attempts to perform a swap with zero amounts.
 /// - Fails if the user would receive zero tokens.
 /// - Fails if the allowance is insufficient or the user balance transfer fails.
+/// - Fails if the native funds sent are not enough or contain a denom the call does not take, excess funds are refunded.
 /// - Fails if the minimum amount out after a single swap is insufficient to perform the next swap to achieve the expected amount out.
 /// - Fails if pool does not exist
 ///
 /// # External contracts
+#[allow(clippy::too_many_arguments)]
 pub fn swap_route(
     deps: DepsMut,
     env: Env,
     info: MessageInfo,
+    received: Option<Asset>,
     amount_in: TokenAmount,
     expected_amount_out: TokenAmount,
     slippage: Percentage,
     swaps: Vec<SwapHop>,
 ) -> Result<Response, ContractError> {
     // update incentives first
-    for hop in &swaps {
-        let mut pool = state::get_pool(deps.storage, &hop.pool_key)?;
+    update_route_pools(deps.storage, &swaps, env.block.time.seconds())?;
+
+    let amount_out = swap_route_internal(deps.storage, env.block.time.millis(), amount_in, &swaps)?;
+
+    let min_amount_out = calculate_min_amount_out(expected_amount_out, slippage);
+
+    if amount_out < min_amount_out {
+        return Err(ContractError::AmountUnderMinimumAmountOut);
+    }
+
+    let msgs = settle_route(
+        deps.api, &env, &info, received, &swaps, amount_in, amount_out,
+    )?;
+
+    Ok(Response::new()
+        .add_messages(msgs)
+        .add_attribute("action", "swap_route")
+        .add_attribute("amount_out", amount_out.to_string())
+        .add_attribute("swap_hop", format!("{:?}", swaps)))
+}
+
+/// Performs atomic swap involving several pools for an exact amount of the last token.
+/// The hops are executed from the last one, each buying exactly what the next one takes.
+///
+/// # Parameters
+/// - `received`: The cw20 tokens sent along through the `Receive` hook, if any.
+/// - `amount_out`: The amount of the last token that the user wants to receive.
+/// - `max_amount_in`: The max amount of the first token that the user is willing to spend.
+/// - `swaps`: A vector containing all parameters needed to identify separate swap steps.
+///
+/// # Events
+/// - On a successful swap, emits a `Swap Route Exact Out` event with the amount spent.
+///
+/// # Errors
+/// - Fails if the user attempts to perform a swap with zero amounts.
+/// - Fails if a pool cannot provide the amount out.
+/// - Fails if the amount in is above the max amount in.
+/// - Fails if the allowance is insufficient or the user balance transfer fails.
+/// - Fails if the native funds sent are not enough or contain a denom the call does not take, excess funds are refunded.
+/// - Fails if pool does not exist or is paused.
+pub fn swap_route_exact_out(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    received: Option<Asset>,
+    amount_out: TokenAmount,
+    max_amount_in: TokenAmount,
+    swaps: Vec<SwapHop>,
+) -> Result<Response, ContractError> {
+    update_route_pools(deps.storage, &swaps, env.block.time.seconds())?;
+
+    let amount_in =
+        swap_route_exact_out_internal(deps.storage, env.block.time.millis(), amount_out, &swaps)?;
+
+    if amount_in > max_amount_in {
+        return Err(ContractError::AmountOverMaximumAmountIn);
+    }
+
+    let msgs = settle_route(
+        deps.api, &env, &info, received, &swaps, amount_in, amount_out,
+    )?;
+
+    Ok(Response::new()
+        .add_messages(msgs)
+        .add_attribute("action", "swap_route_exact_out")
+        .add_attribute("amount_in", amount_in.to_string())
+        .add_attribute("amount_out", amount_out.to_string())
+        .add_attribute("swap_hop", format!("{:?}", swaps)))
+}
+
+// checks every pool of the route is open and updates its incentives
+fn update_route_pools(
+    store: &mut dyn Storage,
+    swaps: &[SwapHop],
+    current_timestamp: u64,
+) -> Result<(), ContractError> {
+    for hop in swaps {
+        let mut pool = state::get_pool(store, &hop.pool_key)?;
         // check pool is opening for swap
         if !pool.can_swap() {
             return Err(ContractError::PoolPaused {});
         }
-        pool.update_global_incentives(env.block.time.seconds())?;
-        POOLS.save(deps.storage, &hop.pool_key.key(), &pool)?;
+        pool.update_global_incentives(current_timestamp)?;
+        state::save_pool(store, &hop.pool_key.key(), &pool)?;
     }
+    Ok(())
+}
 
+// only the first token of the route is pulled from the sender and the last one paid out,
+// the intermediate tokens stay in the contract
+#[allow(clippy::too_many_arguments)]
+fn settle_route(
+    api: &dyn Api,
+    env: &Env,
+    info: &MessageInfo,
+    received: Option<Asset>,
+    swaps: &[SwapHop],
+    amount_in: TokenAmount,
+    amount_out: TokenAmount,
+) -> Result<Vec<CosmosMsg>, ContractError> {
+    let mut assets_in = vec![];
     let mut msgs = vec![];
-    let amount_out = swap_route_internal(
-        deps.storage,
-        deps.api,
-        env,
-        &info,
+    if let (Some(first), Some(last)) = (swaps.first(), swaps.last()) {
+        let (asset_in, _) = swap_assets(
+            api,
+            &first.pool_key,
+            first.x_to_y,
+            amount_in,
+            TokenAmount(0),
+        );
+        let (_, asset_out) =
+            swap_assets(api, &last.pool_key, last.x_to_y, TokenAmount(0), amount_out);
+        assets_in.push(asset_in);
+        asset_out.transfer(&mut msgs, info)?;
+    }
+    transfer_from_sender(
         &mut msgs,
-        amount_in,
-        swaps.clone(),
+        info,
+        received.as_ref(),
+        env.contract.address.to_string(),
+        &assets_in,
     )?;
+    Ok(msgs)
+}
 
-    let min_amount_out = calculate_min_amount_out(expected_amount_out, slippage);
+/// Runs the hook of a cw20 `Send`, the tokens sent pay for the action in place of an allowance.
+///
+/// # Parameters
+/// - `cw20_msg`: The amount and sender of the tokens, with a `Cw20HookMsg` as message.
+///
+/// # Events
+/// - Emits the events of the action run by the hook.
+///
+/// # Errors
+/// - Fails if the message is not a `Cw20HookMsg`.
+/// - Fails if the action does not take the token sent or needs more of it.
+/// - Fails for the same reasons as the action run by the hook.
+pub fn receive_cw20(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    cw20_msg: Cw20ReceiveMsg,
+) -> Result<Response, ContractError> {
+    let received = Asset::new(
+        AssetInfo::Token {
+            contract_addr: info.sender,
+        },
+        cw20_msg.amount,
+    );
+    let amount = TokenAmount(cw20_msg.amount.u128());
+    // the action runs on behalf of the account that sent the tokens
+    let info = MessageInfo {
+        sender: deps.api.addr_validate(&cw20_msg.sender)?,
+        funds: vec![],
+    };
 
-    if amount_out < min_amount_out {
-        return Err(ContractError::AmountUnderMinimumAmountOut);
+    match from_json(&cw20_msg.msg)? {
+        Cw20HookMsg::Swap {
+            pool_key,
+            x_to_y,
+            sqrt_price_limit,
+        } => swap(
+            deps,
+            env,
+            info,
+            Some(received),
+            pool_key,
+            x_to_y,
+            amount,
+            true,
+            sqrt_price_limit,
+        ),
+        Cw20HookMsg::SwapRoute {
+            expected_amount_out,
+            slippage,
+            swaps,
+        } => swap_route(
+            deps,
+            env,
+            info,
+            Some(received),
+            amount,
+            expected_amount_out,
+            slippage,
+            swaps,
+        ),
+        Cw20HookMsg::SwapRouteExactOut { amount_out, swaps } => {
+            swap_route_exact_out(deps, env, info, Some(received), amount_out, amount, swaps)
+        }
+        Cw20HookMsg::CreatePosition {
+            pool_key,
+            lower_tick,
+            upper_tick,
+            liquidity_delta,
+            slippage_limit_lower,
+            slippage_limit_upper,
+            limit_order,
+        } => create_position(
+            deps,
+            env,
+            info,
+            Some(received),
+            pool_key,
+            lower_tick,
+            upper_tick,
+            liquidity_delta,
+            slippage_limit_lower,
+            slippage_limit_upper,
+            limit_order,
+        ),
+        Cw20HookMsg::CreateIncentive {
+            pool_key,
+            reward_per_sec,
+            start_timestamp,
+            end_timestamp,
+        } => create_incentive(
+            deps,
+            env,
+            info,
+            Some(received.clone()),
+            pool_key,
+            received.info,
+            amount,
+            reward_per_sec,
+            start_timestamp,
+            end_timestamp,
+        ),
+        Cw20HookMsg::TopUpIncentive {
+            pool_key,
+            incentive_id,
+        } => top_up_incentive(
+            deps,
+            env,
+            info,
+            Some(received),
+            pool_key,
+            incentive_id,
+            amount,
+        ),
+        Cw20HookMsg::FlashRepay {} => {
+            // the tokens sent repay the side of the loan they belong to
+            let flash_loan = FLASH_LOAN
+                .may_load(deps.storage)?
+                .ok_or(ContractError::NoFlashLoan {})?;
+            let (amount_x, amount_y) = if received.info.denom() == flash_loan.pool_key.token_x {
+                (amount, TokenAmount(0))
+            } else {
+                (TokenAmount(0), amount)
+            };
+            flash_repay(deps, env, info, Some(received), amount_x, amount_y)
+        }
+    }
+}
+
+/// Lends tokens of a pool to a contract within a single transaction.
+/// The recipient receives a `FlashCallback` message and must repay the borrowed amounts plus the fees
+/// with `FlashRepay` before returning, the repayment is checked in reply.
+/// Until then the pool is locked, while the other pools can be used.
+///
+/// # Parameters
+/// - `pool_key`: A unique key that identifies the specified pool.
+/// - `amount_x`: The amount of token x to borrow.
+/// - `amount_y`: The amount of token y to borrow.
+/// - `recipient`: The contract receiving the tokens and the callback.
+/// - `msg`: Custom data forwarded to the recipient in the callback.
+///
+/// # Events
+/// - Emits a `Flash` event with the borrowed amounts and the fees to pay.
+/// - On successful repayment, emits a `Flash Repaid` event with the paid amounts.
+///
+/// # Errors
+/// - Fails if another flash loan is in progress.
+/// - Fails if both amounts are zero.
+/// - Fails if the pool does not exist, is paused or has no liquidity in range.
+/// - Fails if the liquidity of the pool does not hold the borrowed amounts.
+/// - Fails if the borrowed amounts plus the fees are not repaid.
+#[allow(clippy::too_many_arguments)]
+pub fn flash(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    pool_key: PoolKey,
+    amount_x: TokenAmount,
+    amount_y: TokenAmount,
+    recipient: Addr,
+    msg: Binary,
+) -> Result<Response, ContractError> {
+    if FLASH_LOAN.may_load(deps.storage)?.is_some() {
+        return Err(ContractError::FlashLoanInProgress {});
+    }
+    if amount_x.is_zero() && amount_y.is_zero() {
+        return Err(ContractError::AmountIsZero);
+    }
+
+    let pool = state::get_pool(deps.storage, &pool_key)?;
+    // check pool is opening for swap
+    if !pool.can_swap() {
+        return Err(ContractError::PoolPaused {});
+    }
+    // fees are shared by the liquidity in range
+    if pool.liquidity.is_zero() {
+        return Err(ContractError::InsufficientLiquidity);
+    }
+
+    // the contract holds the tokens of every pool, only lend what the liquidity of this one could
+    // pay out in a swap
+    let tick_spacing = pool_key.fee_tier.tick_spacing;
+    for (x_to_y, amount, sqrt_price_limit) in [
+        (false, amount_x, get_max_sqrt_price(tick_spacing)),
+        (true, amount_y, get_min_sqrt_price(tick_spacing)),
+    ] {
+        if !amount.is_zero() {
+            calculate_swap(
+                deps.storage,
+                env.block.time.millis(),
+                &pool_key,
+                x_to_y,
+                amount,
+                false,
+                sqrt_price_limit,
+            )
+            .map_err(|_| ContractError::InsufficientLiquidity)?;
+        }
+    }
+
+    let token_x = AssetInfo::from_denom(deps.api, pool_key.token_x.as_str());
+    let token_y = AssetInfo::from_denom(deps.api, pool_key.token_y.as_str());
+
+    let flash_loan = FlashLoan {
+        pool_key: pool_key.clone(),
+        amount_x,
+        amount_y,
+        fee_x: amount_x.big_mul_up(pool_key.fee_tier.fee),
+        fee_y: amount_y.big_mul_up(pool_key.fee_tier.fee),
+        paid_x: TokenAmount(0),
+        paid_y: TokenAmount(0),
+    };
+    FLASH_LOAN.save(deps.storage, &flash_loan)?;
+
+    let mut msgs = vec![];
+    if !amount_x.is_zero() {
+        token_x.transfer(&mut msgs, recipient.to_string(), amount_x.into())?;
+    }
+    if !amount_y.is_zero() {
+        token_y.transfer(&mut msgs, recipient.to_string(), amount_y.into())?;
+    }
+
+    let callback = FlashCallbackMsg {
+        sender: info.sender.clone(),
+        pool_key: pool_key.clone(),
+        amount_x,
+        amount_y,
+        fee_x: flash_loan.fee_x,
+        fee_y: flash_loan.fee_y,
+        msg,
     }
+    .into_cosmos_msg(recipient.to_string())?;
 
     Ok(Response::new()
         .add_messages(msgs)
-        .add_attribute("action", "swap_route")
-        .add_attribute("amount_out", amount_out.to_string())
-        .add_attribute("swap_hop", format!("{:?}", swaps)))
+        .add_submessage(SubMsg::reply_on_success(callback, FLASH_REPLY_ID))
+        .add_attributes(vec![
+            attr("action", "flash"),
+            attr("pool_key", pool_key.to_string()),
+            attr("sender", info.sender.as_str()),
+            attr("recipient", recipient.as_str()),
+            attr("amount_x", amount_x.to_string()),
+            attr("amount_y", amount_y.to_string()),
+            attr("fee_x", flash_loan.fee_x.to_string()),
+            attr("fee_y", flash_loan.fee_y.to_string()),
+        ]))
+}
+
+/// Repays the pending flash loan, anything paid on top of the borrowed amounts counts towards the fees.
+///
+/// # Parameters
+/// - `received`: The cw20 tokens sent along through the `Receive` hook, if any.
+/// - `amount_x`: The amount of token x to repay.
+/// - `amount_y`: The amount of token y to repay.
+///
+/// # Errors
+/// - Fails if no flash loan is in progress.
+/// - Fails if the native funds sent or the cw20 tokens received do not match the amounts.
+pub fn flash_repay(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    received: Option<Asset>,
+    amount_x: TokenAmount,
+    amount_y: TokenAmount,
+) -> Result<Response, ContractError> {
+    let mut flash_loan = FLASH_LOAN
+        .may_load(deps.storage)?
+        .ok_or(ContractError::NoFlashLoan {})?;
+    let pool_key = &flash_loan.pool_key;
+
+    let mut msgs = vec![];
+    transfer_from_sender(
+        &mut msgs,
+        &info,
+        received.as_ref(),
+        env.contract.address.to_string(),
+        &[
+            Asset::new(
+                AssetInfo::from_denom(deps.api, pool_key.token_x.as_str()),
+                amount_x.into(),
+            ),
+            Asset::new(
+                AssetInfo::from_denom(deps.api, pool_key.token_y.as_str()),
+                amount_y.into(),
+            ),
+        ],
+    )?;
+
+    flash_loan.paid_x = flash_loan.paid_x.checked_add(amount_x)?;
+    flash_loan.paid_y = flash_loan.paid_y.checked_add(amount_y)?;
+    FLASH_LOAN.save(deps.storage, &flash_loan)?;
+
+    Ok(Response::new().add_messages(msgs).add_attributes(vec![
+        attr("action", "flash_repay"),
+        attr("pool_key", flash_loan.pool_key.to_string()),
+        attr("amount_x", amount_x.to_string()),
+        attr("amount_y", amount_y.to_string()),
+    ]))
+}
+
+/// Checks that the pending flash loan has been repaid with its fees,
+/// everything paid on top of the borrowed amounts is credited to the pool as swap fees.
+pub fn flash_reply(deps: DepsMut) -> Result<Response, ContractError> {
+    let flash_loan = FLASH_LOAN.load(deps.storage)?;
+    FLASH_LOAN.remove(deps.storage);
+
+    let pool_key = flash_loan.pool_key;
+    let paid_x = flash_loan
+        .paid_x
+        .checked_sub(flash_loan.amount_x)
+        .map_err(|_| ContractError::FlashLoanNotRepaid {})?;
+    let paid_y = flash_loan
+        .paid_y
+        .checked_sub(flash_loan.amount_y)
+        .map_err(|_| ContractError::FlashLoanNotRepaid {})?;
+    if paid_x < flash_loan.fee_x || paid_y < flash_loan.fee_y {
+        return Err(ContractError::FlashLoanNotRepaid {});
+    }
+
+    let config = CONFIG.load(deps.storage)?;
+    let mut pool = state::get_pool(deps.storage, &pool_key)?;
+    pool.add_fee(paid_x, true, config.protocol_fee)?;
+    pool.add_fee(paid_y, false, config.protocol_fee)?;
+    state::save_pool(deps.storage, &pool_key.key(), &pool)?;
+
+    Ok(Response::new().add_attributes(vec![
+        attr("action", "flash_repaid"),
+        attr("pool_key", pool_key.to_string()),
+        attr("paid_x", paid_x.to_string()),
+        attr("paid_y", paid_y.to_string()),
+    ]))
+}
+
+/// Records an incentive payout that failed, the rewards stay with the fund manager so a reward
+/// token that cannot be transferred does not block the positions of the pool.
+pub fn incentive_reply(msg: Reply) -> Result<Response, ContractError> {
+    // only called on errors
+    let error = msg.result.into_result().err().unwrap_or_default();
+
+    Ok(Response::new().add_attributes(vec![
+        attr("action", "incentive_payout_failed"),
+        attr("error", error),
+    ]))
 }
 
 /// Transfers a position between users.
 ///
 /// # Parameters
+/// - `owner`: The owner of the position, the caller must be authorized by them.
 /// - `index`: The index of the user position to transfer.
 /// - `receiver`: An `AccountId` identifying the user who will own the position.
 pub fn transfer_position(
     deps: DepsMut,
     _env: Env,
     info: MessageInfo,
+    owner: &Addr,
     index: u32,
     receiver: String,
 ) -> Result<Response, ContractError> {
-    let mut position = state::get_position(deps.storage, &info.sender, index)?;
+    let mut position = state::get_position(deps.storage, owner, index)?;
 
-    state::remove_position(deps.storage, &info.sender, index)?;
+    state::remove_position(deps.storage, owner, index)?;
 
     let receiver_addr = deps.api.addr_validate(&receiver)?;
     // reset approvals
@@ -529,6 +1089,7 @@ pub fn transfer_position(
     let event_attributes = vec![
         attr("action", "transfer_position"),
         attr("sender", info.sender.as_str()),
+        attr("owner", owner.as_str()),
         attr("receiver", receiver),
         attr("position_token_id", position.token_id.to_string()),
         attr("pool_key", position.pool_key.to_string()),
@@ -540,19 +1101,26 @@ pub fn transfer_position(
 /// Allows an authorized user (owner of the position) to claim collected fees.
 ///
 /// # Parameters
+/// - `owner`: The owner of the position, the caller must be authorized by them.
 /// - `index`: The index of the user position from which fees will be claimed.
+/// - `recipient`: Receives the fees and incentives.
 ///
 /// # Errors
 /// - Fails if the position cannot be found.
+/// - Fails if the position is a filled limit order.
 pub fn claim_fee(
     deps: DepsMut,
     env: Env,
-    info: MessageInfo,
+    owner: &Addr,
     index: u32,
+    recipient: &Addr,
 ) -> Result<Response, ContractError> {
     let current_timestamp = env.block.time.millis();
 
-    let mut position = state::get_position(deps.storage, &info.sender, index)?;
+    let mut position = state::get_position(deps.storage, owner, index)?;
+    if position.is_filled_order() {
+        return Err(ContractError::LimitOrderFilled {});
+    }
 
     let mut lower_tick =
         state::get_tick(deps.storage, &position.pool_key, position.lower_tick_index)?;
@@ -569,7 +1137,7 @@ pub fn claim_fee(
     )?;
 
     state::update_position(deps.storage, &position)?;
-    POOLS.save(deps.storage, &pool_key_db, &pool)?;
+    state::save_pool(deps.storage, &pool_key_db, &pool)?;
     state::update_tick(
         deps.storage,
         &position.pool_key,
@@ -594,19 +1162,19 @@ pub fn claim_fee(
     };
 
     let mut msgs = vec![];
-    asset_0.transfer(&mut msgs, &info)?;
-    asset_1.transfer(&mut msgs, &info)?;
+    transfer_assets(&mut msgs, recipient, [asset_0, asset_1])?;
 
     let event_attributes = vec![
         attr("action", "claim_fee"),
-        attr("owner", info.sender.as_str()),
+        attr("owner", owner.as_str()),
+        attr("recipient", recipient.as_str()),
         attr("pool_key", position.pool_key.to_string()),
         attr("position_token_id", position.token_id.to_string()),
         attr("amount_x", x.to_string()),
         attr("amount_y", y.to_string()),
     ];
 
-    let resp: Response = claim_incentives(deps, env, info, index)?;
+    let resp: Response = claim_incentives(deps, env, owner, index, recipient)?;
 
     Ok(resp.add_messages(msgs).add_attributes(event_attributes))
 }
@@ -614,17 +1182,24 @@ pub fn claim_fee(
 /// Allows an authorized user (owner of the position) to claim incentives.
 ///
 /// # Parameters
+/// - `owner`: The owner of the position, the caller must be authorized by them.
 /// - `index`: The index of the user position from which fees will be claimed.
+/// - `recipient`: Receives the incentives.
 ///
 /// # Errors
 /// - Fails if the position cannot be found.
+/// - Fails if the position is a filled limit order.
 pub fn claim_incentives(
     deps: DepsMut,
     env: Env,
-    info: MessageInfo,
+    owner: &Addr,
     index: u32,
+    recipient: &Addr,
 ) -> Result<Response, ContractError> {
-    let mut position = state::get_position(deps.storage, &info.sender, index)?;
+    let mut position = state::get_position(deps.storage, owner, index)?;
+    if position.is_filled_order() {
+        return Err(ContractError::LimitOrderFilled {});
+    }
     let config = CONFIG.load(deps.storage)?;
 
     let lower_tick = state::get_tick(deps.storage, &position.pool_key, position.lower_tick_index)?;
@@ -638,21 +1213,18 @@ pub fn claim_incentives(
     let incentives = position
         .claim_incentives(&pool, &upper_tick, &lower_tick)
         .unwrap_or(vec![]);
+    position.settle_retired_incentives(&mut pool);
 
     state::update_position(deps.storage, &position)?;
-    POOLS.save(deps.storage, &pool_key_db, &pool)?;
+    state::save_pool(deps.storage, &pool_key_db, &pool)?;
 
-    let mut msgs = vec![];
+    let mut sub_msgs = vec![];
     for asset in incentives.clone() {
-        msgs.push(wasm_execute(
-            config.incentives_fund_manager.clone(),
-            &incentives_fund_manager::ExecuteMsg::SendFund {
-                asset,
-                receiver: info.sender.clone(),
-            },
-            vec![],
+        sub_msgs.push(send_incentive(
+            &config.incentives_fund_manager,
+            asset,
+            recipient.clone(),
         )?);
-        // asset.transfer(&mut msgs, &info)?;
     }
 
     let mut event_attributes: Vec<Attribute> = vec![];
@@ -679,49 +1251,642 @@ pub fn claim_incentives(
     }
 
     Ok(Response::new()
-        .add_messages(msgs)
+        .add_submessages(sub_msgs)
         .add_attributes(event_attributes))
 }
 
-/// Removes a position. Sends tokens associated with specified position to the owner.
+/// Allows an authorized user (owner of the positions) to claim the fees and incentives of many
+/// positions at once. Transfers are merged per asset: the native fees are sent in one bank message,
+/// each cw20 token in one transfer and each reward asset in one `SendFund`.
 ///
 /// # Parameters
-/// - `index`: The index of the user position to be removed.
-///
-/// # Events
-/// - Emits a `Remove Position` event upon success.
+/// - `indexes`: The indexes of the user positions to claim from, all of them when unset.
+/// - `pool_key`: Only claims from the positions of this pool when set.
 ///
 /// # Errors
-/// - Fails if Position cannot be found
-pub fn remove_position(
+/// - Fails if one of the positions cannot be found.
+pub fn claim_all(
     deps: DepsMut,
     env: Env,
     info: MessageInfo,
-    index: u32,
+    indexes: Option<Vec<u32>>,
+    pool_key: Option<PoolKey>,
 ) -> Result<Response, ContractError> {
     let current_timestamp = env.block.time.millis();
-    let mut position = state::get_position(deps.storage, &info.sender, index)?;
-    let withdrawed_liquidity = position.liquidity;
+    let config = CONFIG.load(deps.storage)?;
+    let indexes = match indexes {
+        Some(mut indexes) => {
+            indexes.sort_unstable();
+            indexes.dedup();
+            indexes
+        }
+        None => (0..state::get_position_length(deps.storage, &info.sender)).collect(),
+    };
 
-    let mut lower_tick =
-        state::get_tick(deps.storage, &position.pool_key, position.lower_tick_index)?;
-    let mut upper_tick =
-        state::get_tick(deps.storage, &position.pool_key, position.upper_tick_index)?;
+    // pools and ticks are shared between positions, they are saved once all claims are done
+    let mut pools: BTreeMap<Vec<u8>, Pool> = BTreeMap::new();
+    let mut ticks: BTreeMap<(Vec<u8>, i32), Tick> = BTreeMap::new();
+    let mut fees = vec![];
+    let mut incentives = vec![];
+    let mut token_ids = vec![];
+
+    for index in indexes {
+        let mut position = state::get_position(deps.storage, &info.sender, index)?;
+        if position.is_filled_order()
+            || pool_key
+                .as_ref()
+                .map_or(false, |pool_key| pool_key.ne(&position.pool_key))
+        {
+            continue;
+        }
 
-    let pool_key_db = position.pool_key.key();
-    let mut pool = POOLS.load(deps.storage, &pool_key_db)?;
-    // check pool is opening for lp
-    if !pool.can_lp() {
-        return Err(ContractError::PoolPaused {});
-    }
+        let pool_key_db = position.pool_key.key();
+        let pool = match pools.entry(pool_key_db.clone()) {
+            Entry::Occupied(entry) => entry.into_mut(),
+            Entry::Vacant(entry) => {
+                let mut pool = POOLS.load(deps.storage, &pool_key_db)?;
+                pool.update_global_incentives(env.block.time.seconds())?;
+                entry.insert(pool)
+            }
+        };
+        let mut lower_tick = match ticks.remove(&(pool_key_db.clone(), position.lower_tick_index)) {
+            Some(tick) => tick,
+            None => state::get_tick(deps.storage, &position.pool_key, position.lower_tick_index)?,
+        };
+        let mut upper_tick = match ticks.remove(&(pool_key_db.clone(), position.upper_tick_index)) {
+            Some(tick) => tick,
+            None => state::get_tick(deps.storage, &position.pool_key, position.upper_tick_index)?,
+        };
 
-    // update global incentives first
-    pool.update_global_incentives(env.block.time.seconds())?;
+        let (x, y) =
+            position.claim_fee(pool, &mut upper_tick, &mut lower_tick, current_timestamp)?;
+        fees.push(Asset {
+            info: AssetInfo::from_denom(deps.api, position.pool_key.token_x.as_str()),
+            amount: x.into(),
+        });
+        fees.push(Asset {
+            info: AssetInfo::from_denom(deps.api, position.pool_key.token_y.as_str()),
+            amount: y.into(),
+        });
+        incentives.append(
+            &mut position
+                .claim_incentives(pool, &upper_tick, &lower_tick)
+                .unwrap_or(vec![]),
+        );
+        position.settle_retired_incentives(pool);
 
-    // calculate pending incentives
-    let incentives = position.claim_incentives(&pool, &upper_tick, &lower_tick)?;
+        state::update_position(deps.storage, &position)?;
+        ticks.insert((pool_key_db.clone(), lower_tick.index), lower_tick);
+        ticks.insert((pool_key_db, upper_tick.index), upper_tick);
+        token_ids.push(position.token_id.to_string());
+    }
 
-    let mut event_attributes: Vec<Attribute> = vec![attr("action", "remove_position")];
+    for ((pool_key_db, index), tick) in ticks {
+        let pool_key = PoolKey::from_bytes(&pool_key_db)?;
+        state::update_tick(deps.storage, &pool_key, index, &tick)?;
+    }
+    for (pool_key_db, pool) in pools {
+        state::save_pool(deps.storage, &pool_key_db, &pool)?;
+    }
+
+    let fees = merge_assets(fees);
+    let incentives: Vec<Asset> = merge_assets(incentives)
+        .into_iter()
+        .filter(|asset| !asset.amount.is_zero())
+        .collect();
+
+    let mut msgs = vec![];
+    transfer_assets(&mut msgs, &info.sender, fees.clone())?;
+    let mut sub_msgs = vec![];
+    for asset in incentives.clone() {
+        sub_msgs.push(send_incentive(
+            &config.incentives_fund_manager,
+            asset,
+            info.sender.clone(),
+        )?);
+    }
+
+    let event_attributes = vec![
+        attr("action", "claim_all"),
+        attr("owner", info.sender.as_str()),
+        attr("position_token_ids", token_ids.join(",")),
+        attr(
+            "fees_token_address",
+            fees.iter()
+                .map(|x| x.info.denom())
+                .collect::<Vec<String>>()
+                .join(","),
+        ),
+        attr(
+            "fees_amount",
+            fees.iter()
+                .map(|x| x.amount.to_string())
+                .collect::<Vec<String>>()
+                .join(","),
+        ),
+        attr(
+            "incentives_token_address",
+            incentives
+                .iter()
+                .map(|x| x.info.denom())
+                .collect::<Vec<String>>()
+                .join(","),
+        ),
+        attr(
+            "incentives_amount",
+            incentives
+                .iter()
+                .map(|x| x.amount.to_string())
+                .collect::<Vec<String>>()
+                .join(","),
+        ),
+    ];
+
+    Ok(Response::new()
+        .add_messages(msgs)
+        .add_submessages(sub_msgs)
+        .add_attributes(event_attributes))
+}
+
+/// Allows an authorized user (owner of the position) to reinvest the collected fees into the same
+/// position. The fees are split with an internal swap into the ratio the range needs at the current
+/// price and deposited as extra liquidity, whatever cannot be deposited stays owed to the position.
+///
+/// # Parameters
+/// - `index`: The index of the user position.
+/// - `max_slippage`: The maximum loss of the internal swap against the current price, fee included.
+/// - `slippage_limit_lower`: The price limit for downward movement to execute the swap and the deposit.
+/// - `slippage_limit_upper`: The price limit for upward movement to execute the swap and the deposit.
+///
+/// # Errors
+/// - Fails if the position cannot be found.
+/// - Fails if the position is a filled limit order.
+/// - Fails if there are no fees to compound or they are too small to add liquidity.
+/// - Fails if the internal swap loses more than `max_slippage`.
+/// - Fails if the price is out of the slippage limits before the swap or before the deposit.
+/// - Fails if pool is paused
+#[allow(clippy::too_many_arguments)]
+pub fn compound_fees(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    index: u32,
+    max_slippage: Percentage,
+    slippage_limit_lower: SqrtPrice,
+    slippage_limit_upper: SqrtPrice,
+) -> Result<Response, ContractError> {
+    let current_timestamp = env.block.time.millis();
+    let mut position = state::get_position(deps.storage, &info.sender, index)?;
+    if position.is_filled_order() {
+        return Err(ContractError::LimitOrderFilled {});
+    }
+    let pool_key = position.pool_key.clone();
+    let pool_key_db = pool_key.key();
+    let mut pool = POOLS.load(deps.storage, &pool_key_db)?;
+    if !pool.can_lp() {
+        return Err(ContractError::PoolPaused {});
+    }
+    if pool.sqrt_price < slippage_limit_lower || pool.sqrt_price > slippage_limit_upper {
+        return Err(ContractError::PriceLimitReached);
+    }
+    let mut lower_tick = state::get_tick(deps.storage, &pool_key, position.lower_tick_index)?;
+    let mut upper_tick = state::get_tick(deps.storage, &pool_key, position.upper_tick_index)?;
+
+    pool.update_global_incentives(env.block.time.seconds())?;
+    write_observation(deps.storage, &pool_key, &mut pool, env.block.time.seconds())?;
+
+    // accumulate pending incentives with the current liquidity before it grows
+    position.update_incentives(&pool, &upper_tick, &lower_tick)?;
+
+    // collect the fees into tokens_owed_x/y
+    position.modify(
+        &mut pool,
+        &mut upper_tick,
+        &mut lower_tick,
+        Liquidity::new(0),
+        true,
+        current_timestamp,
+        pool_key.fee_tier.tick_spacing,
+    )?;
+    if position.tokens_owed_x.is_zero() && position.tokens_owed_y.is_zero() {
+        return Err(ContractError::AmountIsZero);
+    }
+
+    state::save_pool(deps.storage, &pool_key_db, &pool)?;
+    state::update_tick(deps.storage, &pool_key, lower_tick.index, &lower_tick)?;
+    state::update_tick(deps.storage, &pool_key, upper_tick.index, &upper_tick)?;
+
+    let mut event_attributes = vec![
+        attr("action", "compound_fees"),
+        attr("pool_key", pool_key.to_string()),
+        attr("token_id", position.token_id.to_string()),
+        attr("owner", info.sender.as_str()),
+        attr("fee_x", position.tokens_owed_x.to_string()),
+        attr("fee_y", position.tokens_owed_y.to_string()),
+    ];
+
+    if let Some(rebalance) = get_rebalance_swap(
+        position.tokens_owed_x,
+        position.tokens_owed_y,
+        position.lower_tick_index,
+        position.upper_tick_index,
+        pool.sqrt_price,
+    )? {
+        if !pool.can_swap() {
+            return Err(ContractError::PoolPaused {});
+        }
+        let sqrt_price_limit = if rebalance.x_to_y {
+            get_min_sqrt_price(pool_key.fee_tier.tick_spacing)
+        } else {
+            get_max_sqrt_price(pool_key.fee_tier.tick_spacing)
+        };
+        let CalculateSwapResult {
+            amount_in,
+            amount_out,
+            pool: after_pool,
+            ..
+        } = swap_internal(
+            deps.storage,
+            current_timestamp,
+            &pool_key,
+            rebalance.x_to_y,
+            rebalance.amount_in,
+            true,
+            sqrt_price_limit,
+        )?;
+        if amount_out < calculate_min_amount_out(rebalance.amount_out, max_slippage) {
+            return Err(ContractError::AmountUnderMinimumAmountOut);
+        }
+
+        // the tokens never leave the contract, only what the position is owed changes
+        if rebalance.x_to_y {
+            position.tokens_owed_x = position.tokens_owed_x.checked_sub(amount_in)?;
+            position.tokens_owed_y = position.tokens_owed_y.checked_add(amount_out)?;
+        } else {
+            position.tokens_owed_y = position.tokens_owed_y.checked_sub(amount_in)?;
+            position.tokens_owed_x = position.tokens_owed_x.checked_add(amount_out)?;
+        }
+
+        // the swap may have crossed the ticks of the position
+        pool = after_pool;
+        lower_tick = state::get_tick(deps.storage, &pool_key, position.lower_tick_index)?;
+        upper_tick = state::get_tick(deps.storage, &pool_key, position.upper_tick_index)?;
+
+        event_attributes.append(&mut vec![
+            attr("swap_x_to_y", rebalance.x_to_y.to_string()),
+            attr("swap_amount_in", amount_in.to_string()),
+            attr("swap_amount_out", amount_out.to_string()),
+        ]);
+    }
+
+    // the swap moved the price, the deposit is only made inside the limits of the caller
+    if pool.sqrt_price < slippage_limit_lower || pool.sqrt_price > slippage_limit_upper {
+        return Err(ContractError::PriceLimitReached);
+    }
+    let liquidity_delta = get_liquidity(
+        position.tokens_owed_x,
+        position.tokens_owed_y,
+        position.lower_tick_index,
+        position.upper_tick_index,
+        pool.sqrt_price,
+        false,
+    )?
+    .l;
+    if liquidity_delta.is_zero() {
+        return Err(ContractError::InsufficientLiquidity {});
+    }
+
+    // the owed tokens are taken before the deposit, fees earned on the swap are owed again by it
+    let (owed_x, owed_y) = (position.tokens_owed_x, position.tokens_owed_y);
+    position.tokens_owed_x = TokenAmount(0);
+    position.tokens_owed_y = TokenAmount(0);
+    position.update_incentives(&pool, &upper_tick, &lower_tick)?;
+    let (x, y) = position.modify(
+        &mut pool,
+        &mut upper_tick,
+        &mut lower_tick,
+        liquidity_delta,
+        true,
+        current_timestamp,
+        pool_key.fee_tier.tick_spacing,
+    )?;
+    position.tokens_owed_x = position.tokens_owed_x.checked_add(owed_x.checked_sub(x)?)?;
+    position.tokens_owed_y = position.tokens_owed_y.checked_add(owed_y.checked_sub(y)?)?;
+
+    state::save_pool(deps.storage, &pool_key_db, &pool)?;
+    state::update_position(deps.storage, &position)?;
+    state::update_tick(deps.storage, &pool_key, lower_tick.index, &lower_tick)?;
+    state::update_tick(deps.storage, &pool_key, upper_tick.index, &upper_tick)?;
+
+    event_attributes.append(&mut vec![
+        attr("liquidity_delta", liquidity_delta.get().to_string()),
+        attr("position_liquidity", position.liquidity.get().to_string()),
+        attr("liquidity_x", x.to_string()),
+        attr("liquidity_y", y.to_string()),
+        attr("after_liquidity", pool.liquidity.get().to_string()),
+        attr("after_tick_index", pool.current_tick_index.to_string()),
+    ]);
+
+    Ok(Response::new().add_attributes(event_attributes))
+}
+
+/// Allows an authorized user (owner of the position) to move its liquidity to a new tick range in
+/// one step. The liquidity and fees are withdrawn, swapped in the same pool into the ratio the new
+/// range needs and deposited again under the same token id. Pending incentives are paid out and
+/// the tokens that cannot be deposited are sent back.
+///
+/// # Parameters
+/// - `index`: The index of the user position.
+/// - `new_lower_tick`: The index of the new lower tick.
+/// - `new_upper_tick`: The index of the new upper tick.
+/// - `slippage`: The maximum loss of the internal swap against the current price, fee included.
+/// - `slippage_limit_lower`: The price limit for downward movement to execute the rebalance.
+/// - `slippage_limit_upper`: The price limit for upward movement to execute the rebalance.
+///
+/// # Errors
+/// - Fails if the position cannot be found.
+/// - Fails if the position is a limit order.
+/// - Fails if the new ticks are invalid for the fee tier.
+/// - Fails if the withdrawn tokens are too small to add liquidity in the new range.
+/// - Fails if the internal swap loses more than `slippage`.
+/// - Fails if the price is out of the slippage limits before the withdrawal or before the deposit.
+/// - Fails if pool is paused
+#[allow(clippy::too_many_arguments)]
+pub fn rebalance(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    index: u32,
+    new_lower_tick: i32,
+    new_upper_tick: i32,
+    slippage: Percentage,
+    slippage_limit_lower: SqrtPrice,
+    slippage_limit_upper: SqrtPrice,
+) -> Result<Response, ContractError> {
+    let current_timestamp = env.block.time.millis();
+    let mut position = state::get_position(deps.storage, &info.sender, index)?;
+    if position.limit_order.is_some() {
+        return Err(ContractError::InvalidLimitOrder {});
+    }
+    let pool_key = position.pool_key.clone();
+    if new_lower_tick == new_upper_tick {
+        return Err(ContractError::InvalidTickIndex {});
+    }
+    check_ticks(
+        new_lower_tick,
+        new_upper_tick,
+        pool_key.fee_tier.tick_spacing,
+    )?;
+
+    let pool_key_db = pool_key.key();
+    let mut pool = POOLS.load(deps.storage, &pool_key_db)?;
+    if !pool.can_lp() {
+        return Err(ContractError::PoolPaused {});
+    }
+    if pool.sqrt_price < slippage_limit_lower || pool.sqrt_price > slippage_limit_upper {
+        return Err(ContractError::PriceLimitReached);
+    }
+    let mut lower_tick = state::get_tick(deps.storage, &pool_key, position.lower_tick_index)?;
+    let mut upper_tick = state::get_tick(deps.storage, &pool_key, position.upper_tick_index)?;
+
+    pool.update_global_incentives(env.block.time.seconds())?;
+    write_observation(deps.storage, &pool_key, &mut pool, env.block.time.seconds())?;
+
+    let incentives = position.claim_incentives(&pool, &upper_tick, &lower_tick)?;
+    position.settle_retired_incentives(&mut pool);
+
+    // withdraw everything from the old range
+    let (mut x, mut y, ..) = position.remove(
+        &mut pool,
+        current_timestamp,
+        &mut lower_tick,
+        &mut upper_tick,
+        pool_key.fee_tier.tick_spacing,
+    )?;
+    for tick in [&lower_tick, &upper_tick] {
+        if tick.liquidity_gross.is_zero() {
+            remove_tick_and_flip_bitmap(deps.storage, &pool_key, tick)?;
+        } else {
+            state::update_tick(deps.storage, &pool_key, tick.index, tick)?;
+        }
+    }
+    state::save_pool(deps.storage, &pool_key_db, &pool)?;
+
+    let mut event_attributes = vec![
+        attr("action", "rebalance"),
+        attr("pool_key", pool_key.to_string()),
+        attr("token_id", position.token_id.to_string()),
+        attr("owner", info.sender.as_str()),
+        attr("old_lower_tick", position.lower_tick_index.to_string()),
+        attr("old_upper_tick", position.upper_tick_index.to_string()),
+        attr("withdrawn_x", x.to_string()),
+        attr("withdrawn_y", y.to_string()),
+    ];
+
+    if let Some(rebalance) =
+        get_rebalance_swap(x, y, new_lower_tick, new_upper_tick, pool.sqrt_price)?
+    {
+        if !pool.can_swap() {
+            return Err(ContractError::PoolPaused {});
+        }
+        let sqrt_price_limit = if rebalance.x_to_y {
+            get_min_sqrt_price(pool_key.fee_tier.tick_spacing)
+        } else {
+            get_max_sqrt_price(pool_key.fee_tier.tick_spacing)
+        };
+        let CalculateSwapResult {
+            amount_in,
+            amount_out,
+            pool: after_pool,
+            ..
+        } = swap_internal(
+            deps.storage,
+            current_timestamp,
+            &pool_key,
+            rebalance.x_to_y,
+            rebalance.amount_in,
+            true,
+            sqrt_price_limit,
+        )?;
+        if amount_out < calculate_min_amount_out(rebalance.amount_out, slippage) {
+            return Err(ContractError::AmountUnderMinimumAmountOut);
+        }
+
+        if rebalance.x_to_y {
+            x = x.checked_sub(amount_in)?;
+            y = y.checked_add(amount_out)?;
+        } else {
+            y = y.checked_sub(amount_in)?;
+            x = x.checked_add(amount_out)?;
+        }
+        pool = after_pool;
+
+        event_attributes.append(&mut vec![
+            attr("swap_x_to_y", rebalance.x_to_y.to_string()),
+            attr("swap_amount_in", amount_in.to_string()),
+            attr("swap_amount_out", amount_out.to_string()),
+        ]);
+    }
+
+    let liquidity_delta =
+        get_liquidity(x, y, new_lower_tick, new_upper_tick, pool.sqrt_price, false)?.l;
+    if liquidity_delta.is_zero() {
+        return Err(ContractError::InsufficientLiquidity {});
+    }
+
+    // deposit into the new range, the ticks are created when needed
+    pool.update_seconds_per_liquidity_global(current_timestamp)?;
+    let mut lower_tick = match state::get_tick(deps.storage, &pool_key, new_lower_tick) {
+        Ok(tick) => tick,
+        _ => create_tick(
+            deps.storage,
+            current_timestamp,
+            &pool_key,
+            &pool,
+            new_lower_tick,
+        )?,
+    };
+    let mut upper_tick = match state::get_tick(deps.storage, &pool_key, new_upper_tick) {
+        Ok(tick) => tick,
+        _ => create_tick(
+            deps.storage,
+            current_timestamp,
+            &pool_key,
+            &pool,
+            new_upper_tick,
+        )?,
+    };
+    // the price may have moved with the swap, the deposit checks it against the caller limits
+    let (mut new_position, required_x, required_y) = Position::create(
+        &mut pool,
+        pool_key.clone(),
+        &mut lower_tick,
+        &mut upper_tick,
+        current_timestamp,
+        liquidity_delta,
+        slippage_limit_lower,
+        slippage_limit_upper,
+        env.block.height,
+        pool_key.fee_tier.tick_spacing,
+    )?;
+
+    // the position keeps its token id, approvals and settlements of retired incentives
+    new_position.token_id = position.token_id;
+    new_position.approvals = position.approvals;
+    new_position
+        .incentives
+        .extend(position.incentives.into_iter().filter(|incentive| {
+            incentive.settled
+                && pool
+                    .incentives
+                    .iter()
+                    .any(|record| record.id == incentive.incentive_id && record.is_retired())
+        }));
+
+    state::save_pool(deps.storage, &pool_key_db, &pool)?;
+    state::update_position(deps.storage, &new_position)?;
+    state::update_tick(deps.storage, &pool_key, lower_tick.index, &lower_tick)?;
+    state::update_tick(deps.storage, &pool_key, upper_tick.index, &upper_tick)?;
+
+    // send back what could not be deposited together with the incentives
+    let mut msgs = vec![];
+    transfer_assets(
+        &mut msgs,
+        &info.sender,
+        [
+            Asset {
+                info: AssetInfo::from_denom(deps.api, pool_key.token_x.as_str()),
+                amount: x.checked_sub(required_x)?.into(),
+            },
+            Asset {
+                info: AssetInfo::from_denom(deps.api, pool_key.token_y.as_str()),
+                amount: y.checked_sub(required_y)?.into(),
+            },
+        ],
+    )?;
+    let config = CONFIG.load(deps.storage)?;
+    let mut sub_msgs = vec![];
+    for asset in incentives {
+        sub_msgs.push(send_incentive(
+            &config.incentives_fund_manager,
+            asset,
+            info.sender.clone(),
+        )?);
+    }
+
+    event_attributes.append(&mut vec![
+        attr("lower_tick", new_lower_tick.to_string()),
+        attr("upper_tick", new_upper_tick.to_string()),
+        attr("position_liquidity", liquidity_delta.get().to_string()),
+        attr("liquidity_x", required_x.to_string()),
+        attr("liquidity_y", required_y.to_string()),
+        attr("current_sqrt_price", pool.sqrt_price.get().to_string()),
+        attr("after_liquidity", pool.liquidity.get().to_string()),
+        attr("after_tick_index", pool.current_tick_index.to_string()),
+    ]);
+
+    Ok(Response::new()
+        .add_messages(msgs)
+        .add_submessages(sub_msgs)
+        .add_attributes(event_attributes))
+}
+
+/// Removes a position. Sends tokens associated with specified position to the recipient.
+/// A filled limit order is claimed instead.
+///
+/// # Parameters
+/// - `owner`: The owner of the position, the caller must be authorized by them.
+/// - `index`: The index of the user position to be removed.
+/// - `recipient`: Receives the tokens, fees and incentives of the position.
+///
+/// # Events
+/// - Emits a `Remove Position` event upon success.
+///
+/// # Errors
+/// - Fails if Position cannot be found
+pub fn remove_position(
+    deps: DepsMut,
+    env: Env,
+    owner: &Addr,
+    index: u32,
+    recipient: &Addr,
+) -> Result<Response, ContractError> {
+    let current_timestamp = env.block.time.millis();
+    let mut position = state::get_position(deps.storage, owner, index)?;
+    if position.is_filled_order() {
+        return claim_filled_order(deps, owner, index, recipient);
+    }
+    let withdrawed_liquidity = position.liquidity;
+
+    let mut lower_tick =
+        state::get_tick(deps.storage, &position.pool_key, position.lower_tick_index)?;
+    let mut upper_tick =
+        state::get_tick(deps.storage, &position.pool_key, position.upper_tick_index)?;
+
+    let pool_key_db = position.pool_key.key();
+    let mut pool = POOLS.load(deps.storage, &pool_key_db)?;
+    // check pool is opening for lp
+    if !pool.can_lp() {
+        return Err(ContractError::PoolPaused {});
+    }
+
+    // update global incentives first
+    pool.update_global_incentives(env.block.time.seconds())?;
+
+    // record the price before the liquidity changes
+    write_observation(
+        deps.storage,
+        &position.pool_key,
+        &mut pool,
+        env.block.time.seconds(),
+    )?;
+
+    // calculate pending incentives
+    let incentives = position.claim_incentives(&pool, &upper_tick, &lower_tick)?;
+    position.settle_retired_incentives(&mut pool);
+    pool.position_count = pool.position_count.saturating_sub(1);
+
+    let mut event_attributes: Vec<Attribute> = vec![attr("action", "remove_position")];
 
     if !incentives.is_empty() {
         event_attributes.append(&mut vec![
@@ -745,55 +1910,424 @@ pub fn remove_position(
         ]);
     }
 
-    let (
-        amount_x,
-        amount_y,
-        liquidity_x,
-        liquidity_y,
-        fee_x,
-        fee_y,
-        after_liquidity,
-        ater_tick_index,
-        deinitialize_lower_tick,
-        deinitialize_upper_tick,
-    ) = position.remove(
+    let (
+        amount_x,
+        amount_y,
+        liquidity_x,
+        liquidity_y,
+        fee_x,
+        fee_y,
+        after_liquidity,
+        ater_tick_index,
+        deinitialize_lower_tick,
+        deinitialize_upper_tick,
+    ) = position.remove(
+        &mut pool,
+        current_timestamp,
+        &mut lower_tick,
+        &mut upper_tick,
+        position.pool_key.fee_tier.tick_spacing,
+    )?;
+
+    event_attributes.append(&mut vec![
+        attr("fee_x", fee_x.to_string()),
+        attr("fee_y", fee_y.to_string()),
+    ]);
+
+    state::save_pool(deps.storage, &pool_key_db, &pool)?;
+
+    if deinitialize_lower_tick {
+        remove_tick_and_flip_bitmap(deps.storage, &position.pool_key, &lower_tick)?;
+    } else {
+        state::update_tick(
+            deps.storage,
+            &position.pool_key,
+            position.lower_tick_index,
+            &lower_tick,
+        )?;
+    }
+
+    if deinitialize_upper_tick {
+        remove_tick_and_flip_bitmap(deps.storage, &position.pool_key, &upper_tick)?;
+    } else {
+        state::update_tick(
+            deps.storage,
+            &position.pool_key,
+            position.upper_tick_index,
+            &upper_tick,
+        )?;
+    }
+
+    let position = state::remove_position(deps.storage, owner, index)?;
+    if let Some(order) = position.limit_order {
+        state::remove_limit_order(
+            deps.storage,
+            &position.pool_key,
+            order.tick_index,
+            position.token_id,
+        )?;
+    }
+
+    let asset_0 = Asset {
+        info: AssetInfo::from_denom(deps.api, position.pool_key.token_x.as_str()),
+        amount: amount_x.into(),
+    };
+
+    let asset_1 = Asset {
+        info: AssetInfo::from_denom(deps.api, position.pool_key.token_y.as_str()),
+        amount: amount_y.into(),
+    };
+
+    let mut msgs = vec![];
+    transfer_assets(&mut msgs, recipient, [asset_0, asset_1])?;
+    // claim incentives
+    let config = CONFIG.load(deps.storage)?;
+    let mut sub_msgs = vec![];
+    for asset in incentives.clone() {
+        sub_msgs.push(send_incentive(
+            &config.incentives_fund_manager,
+            asset,
+            recipient.clone(),
+        )?);
+    }
+
+    event_attributes.append(&mut vec![
+        attr("pool_key", position.pool_key.to_string()),
+        attr("token_id", position.token_id.to_string()),
+        attr("owner", owner.as_str()),
+        attr("recipient", recipient.as_str()),
+        attr("position_liquidity", withdrawed_liquidity.get().to_string()),
+        attr("lower_tick", lower_tick.index.to_string()),
+        attr("upper_tick", upper_tick.index.to_string()),
+        attr("current_sqrt_price", pool.sqrt_price.get().to_string()),
+        attr("liquidity_x", liquidity_x.to_string()),
+        attr("liquidity_y", liquidity_y.to_string()),
+        attr("after_liquidity ", after_liquidity.get().to_string()),
+        attr("after_tick_index", ater_tick_index.to_string()),
+    ]);
+
+    Ok(Response::new()
+        .add_messages(msgs)
+        .add_submessages(sub_msgs)
+        .add_attributes(event_attributes))
+}
+
+/// Sends the tokens of a filled limit order together with its fees and pending incentives
+/// to the recipient, then removes the position.
+///
+/// # Parameters
+/// - `owner`: The owner of the position, the caller must be authorized by them.
+/// - `index`: The index of the user position.
+/// - `recipient`: Receives the tokens and incentives of the order.
+///
+/// # Events
+/// - Emits a `Claim Filled Order` event upon success.
+///
+/// # Errors
+/// - Fails if Position cannot be found
+/// - Fails if the position is not a filled limit order.
+pub fn claim_filled_order(
+    deps: DepsMut,
+    owner: &Addr,
+    index: u32,
+    recipient: &Addr,
+) -> Result<Response, ContractError> {
+    let mut position = state::get_position(deps.storage, owner, index)?;
+    if !position.is_filled_order() {
+        return Err(ContractError::LimitOrderNotFilled {});
+    }
+
+    // the liquidity is gone, incentives only need to be paid out
+    let pool_key_db = position.pool_key.key();
+    let mut pool = POOLS.load(deps.storage, &pool_key_db)?;
+    let incentives = position.take_pending_incentives(&pool);
+    position.settle_retired_incentives(&mut pool);
+    pool.position_count = pool.position_count.saturating_sub(1);
+    state::save_pool(deps.storage, &pool_key_db, &pool)?;
+
+    state::remove_position(deps.storage, owner, index)?;
+
+    let asset_0 = Asset {
+        info: AssetInfo::from_denom(deps.api, position.pool_key.token_x.as_str()),
+        amount: position.tokens_owed_x.into(),
+    };
+
+    let asset_1 = Asset {
+        info: AssetInfo::from_denom(deps.api, position.pool_key.token_y.as_str()),
+        amount: position.tokens_owed_y.into(),
+    };
+
+    let mut msgs = vec![];
+    transfer_assets(&mut msgs, recipient, [asset_0, asset_1])?;
+    let config = CONFIG.load(deps.storage)?;
+    let mut sub_msgs = vec![];
+    for asset in incentives {
+        sub_msgs.push(send_incentive(
+            &config.incentives_fund_manager,
+            asset,
+            recipient.clone(),
+        )?);
+    }
+
+    let event_attributes = vec![
+        attr("action", "claim_filled_order"),
+        attr("pool_key", position.pool_key.to_string()),
+        attr("token_id", position.token_id.to_string()),
+        attr("owner", owner.as_str()),
+        attr("recipient", recipient.as_str()),
+        attr("amount_x", position.tokens_owed_x.to_string()),
+        attr("amount_y", position.tokens_owed_y.to_string()),
+    ];
+
+    Ok(Response::new()
+        .add_messages(msgs)
+        .add_submessages(sub_msgs)
+        .add_attributes(event_attributes))
+}
+
+/// Adds liquidity to an existing position, keeping its index and token id.
+/// Pending fees and incentives are settled into the position before the liquidity grows,
+/// so they can still be claimed later.
+///
+/// # Parameters
+/// - `index`: The index of the user position.
+/// - `liquidity_delta`: The liquidity to add to the position.
+/// - `slippage_limit_lower`: The price limit for downward movement to execute the deposit.
+/// - `slippage_limit_upper`: The price limit for upward movement to execute the deposit.
+///
+/// # Events
+/// - Emits an `Increase Liquidity` event upon success.
+///
+/// # Errors
+/// - Fails if Position cannot be found
+/// - Fails if the position is a filled limit order.
+/// - Fails if the user attempts to add zero liquidity.
+/// - Fails if the price has reached the slippage limit.
+/// - Fails if the allowance is insufficient or the user balance transfer fails.
+/// - Fails if the native funds sent are not enough or contain a denom the call does not take, excess funds are refunded.
+/// - Fails if pool is paused
+pub fn increase_liquidity(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    index: u32,
+    liquidity_delta: Liquidity,
+    slippage_limit_lower: SqrtPrice,
+    slippage_limit_upper: SqrtPrice,
+) -> Result<Response, ContractError> {
+    let current_timestamp = env.block.time.millis();
+    let mut position = state::get_position(deps.storage, &info.sender, index)?;
+    if position.is_filled_order() {
+        return Err(ContractError::LimitOrderFilled {});
+    }
+
+    // liquidity delta = 0 => return
+    if liquidity_delta.is_zero() {
+        return Err(ContractError::InsufficientLiquidity {});
+    }
+
+    let mut lower_tick =
+        state::get_tick(deps.storage, &position.pool_key, position.lower_tick_index)?;
+    let mut upper_tick =
+        state::get_tick(deps.storage, &position.pool_key, position.upper_tick_index)?;
+
+    let pool_key_db = position.pool_key.key();
+    let mut pool = POOLS.load(deps.storage, &pool_key_db)?;
+    // check pool is opening for lp
+    if !pool.can_lp() {
+        return Err(ContractError::PoolPaused {});
+    }
+
+    if pool.sqrt_price < slippage_limit_lower || pool.sqrt_price > slippage_limit_upper {
+        return Err(ContractError::PriceLimitReached);
+    }
+
+    // update global incentives first
+    pool.update_global_incentives(env.block.time.seconds())?;
+
+    // record the price before the liquidity changes
+    write_observation(
+        deps.storage,
+        &position.pool_key,
+        &mut pool,
+        env.block.time.seconds(),
+    )?;
+
+    // accumulate pending incentives with the current liquidity before it grows
+    position.update_incentives(&pool, &upper_tick, &lower_tick)?;
+
+    // owed fees are accumulated into tokens_owed_x/y while updating the position
+    let (x, y) = position.modify(
+        &mut pool,
+        &mut upper_tick,
+        &mut lower_tick,
+        liquidity_delta,
+        true,
+        current_timestamp,
+        position.pool_key.fee_tier.tick_spacing,
+    )?;
+
+    state::save_pool(deps.storage, &pool_key_db, &pool)?;
+    state::update_position(deps.storage, &position)?;
+    state::update_tick(
+        deps.storage,
+        &position.pool_key,
+        lower_tick.index,
+        &lower_tick,
+    )?;
+    state::update_tick(
+        deps.storage,
+        &position.pool_key,
+        upper_tick.index,
+        &upper_tick,
+    )?;
+
+    let asset_0 = Asset {
+        info: AssetInfo::from_denom(deps.api, position.pool_key.token_x.as_str()),
+        amount: x.into(),
+    };
+
+    let asset_1 = Asset {
+        info: AssetInfo::from_denom(deps.api, position.pool_key.token_y.as_str()),
+        amount: y.into(),
+    };
+
+    let mut msgs = vec![];
+    transfer_from_sender(
+        &mut msgs,
+        &info,
+        None,
+        env.contract.address.to_string(),
+        &[asset_0, asset_1],
+    )?;
+
+    let event_attributes = vec![
+        attr("action", "increase_liquidity"),
+        attr("pool_key", position.pool_key.to_string()),
+        attr("token_id", position.token_id.to_string()),
+        attr("owner", info.sender.as_str()),
+        attr("liquidity_delta", liquidity_delta.get().to_string()),
+        attr("position_liquidity", position.liquidity.get().to_string()),
+        attr("lower_tick", lower_tick.index.to_string()),
+        attr("upper_tick", upper_tick.index.to_string()),
+        attr("current_sqrt_price", pool.sqrt_price.get().to_string()),
+        attr("liquidity_x", x.to_string()),
+        attr("liquidity_y", y.to_string()),
+        attr("after_liquidity", pool.liquidity.get().to_string()),
+        attr("after_tick_index", pool.current_tick_index.to_string()),
+    ];
+
+    Ok(Response::new()
+        .add_messages(msgs)
+        .add_attributes(event_attributes))
+}
+
+/// Withdraws part of the liquidity of a position. Sends the withdrawn tokens together with the
+/// owed fees to the owner, while the position, its token id and its incentives are kept.
+///
+/// # Parameters
+/// - `index`: The index of the user position.
+/// - `liquidity_delta`: The liquidity to withdraw, must be lower than the position liquidity.
+/// - `slippage_limit_lower`: The price limit for downward movement to execute the withdrawal.
+/// - `slippage_limit_upper`: The price limit for upward movement to execute the withdrawal.
+///
+/// # Events
+/// - Emits a `Decrease Liquidity` event upon success.
+///
+/// # Errors
+/// - Fails if Position cannot be found
+/// - Fails if the position is a filled limit order.
+/// - Fails if the liquidity delta is zero or not lower than the position liquidity, use `RemovePosition` instead.
+/// - Fails if an unfilled limit order would be left with less than `MIN_LIMIT_ORDER_LIQUIDITY`.
+/// - Fails if the price has reached the slippage limit.
+/// - Fails if pool is paused
+pub fn decrease_liquidity(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    index: u32,
+    liquidity_delta: Liquidity,
+    slippage_limit_lower: SqrtPrice,
+    slippage_limit_upper: SqrtPrice,
+) -> Result<Response, ContractError> {
+    let current_timestamp = env.block.time.millis();
+    let mut position = state::get_position(deps.storage, &info.sender, index)?;
+    if position.is_filled_order() {
+        return Err(ContractError::LimitOrderFilled {});
+    }
+
+    if liquidity_delta.is_zero() || liquidity_delta >= position.liquidity {
+        return Err(ContractError::InsufficientLiquidity {});
+    }
+    // unfilled orders keep the minimum size they were opened with
+    if position.limit_order.is_some()
+        && position.liquidity - liquidity_delta < MIN_LIMIT_ORDER_LIQUIDITY
+    {
+        return Err(ContractError::LimitOrderTooSmall {});
+    }
+
+    let mut lower_tick =
+        state::get_tick(deps.storage, &position.pool_key, position.lower_tick_index)?;
+    let mut upper_tick =
+        state::get_tick(deps.storage, &position.pool_key, position.upper_tick_index)?;
+
+    let pool_key_db = position.pool_key.key();
+    let mut pool = POOLS.load(deps.storage, &pool_key_db)?;
+    // check pool is opening for lp
+    if !pool.can_lp() {
+        return Err(ContractError::PoolPaused {});
+    }
+
+    if pool.sqrt_price < slippage_limit_lower || pool.sqrt_price > slippage_limit_upper {
+        return Err(ContractError::PriceLimitReached);
+    }
+
+    // update global incentives first
+    pool.update_global_incentives(env.block.time.seconds())?;
+
+    // record the price before the liquidity changes
+    write_observation(
+        deps.storage,
+        &position.pool_key,
         &mut pool,
-        current_timestamp,
-        &mut lower_tick,
-        &mut upper_tick,
-        position.pool_key.fee_tier.tick_spacing,
+        env.block.time.seconds(),
     )?;
 
-    event_attributes.append(&mut vec![
-        attr("fee_x", fee_x.to_string()),
-        attr("fee_y", fee_y.to_string()),
-    ]);
+    // accumulate pending incentives with the current liquidity, they stay on the position
+    position.update_incentives(&pool, &upper_tick, &lower_tick)?;
 
-    POOLS.save(deps.storage, &pool_key_db, &pool)?;
+    let (liquidity_x, liquidity_y) = position.modify(
+        &mut pool,
+        &mut upper_tick,
+        &mut lower_tick,
+        liquidity_delta,
+        false,
+        current_timestamp,
+        position.pool_key.fee_tier.tick_spacing,
+    )?;
 
-    if deinitialize_lower_tick {
-        remove_tick_and_flip_bitmap(deps.storage, &position.pool_key, &lower_tick)?;
-    } else {
-        state::update_tick(
-            deps.storage,
-            &position.pool_key,
-            position.lower_tick_index,
-            &lower_tick,
-        )?;
-    }
+    let fee_x = position.tokens_owed_x;
+    let fee_y = position.tokens_owed_y;
+    position.tokens_owed_x = TokenAmount::new(0);
+    position.tokens_owed_y = TokenAmount::new(0);
 
-    if deinitialize_upper_tick {
-        remove_tick_and_flip_bitmap(deps.storage, &position.pool_key, &upper_tick)?;
-    } else {
-        state::update_tick(
-            deps.storage,
-            &position.pool_key,
-            position.upper_tick_index,
-            &upper_tick,
-        )?;
-    }
+    let amount_x = liquidity_x.checked_add(fee_x)?;
+    let amount_y = liquidity_y.checked_add(fee_y)?;
 
-    let position = state::remove_position(deps.storage, &info.sender, index)?;
+    state::save_pool(deps.storage, &pool_key_db, &pool)?;
+    state::update_position(deps.storage, &position)?;
+    state::update_tick(
+        deps.storage,
+        &position.pool_key,
+        lower_tick.index,
+        &lower_tick,
+    )?;
+    state::update_tick(
+        deps.storage,
+        &position.pool_key,
+        upper_tick.index,
+        &upper_tick,
+    )?;
 
     let asset_0 = Asset {
         info: AssetInfo::from_denom(deps.api, position.pool_key.token_x.as_str()),
@@ -808,36 +2342,24 @@ pub fn remove_position(
     let mut msgs = vec![];
     asset_0.transfer(&mut msgs, &info)?;
     asset_1.transfer(&mut msgs, &info)?;
-    // claim incentives
-    let config = CONFIG.load(deps.storage)?;
-    for asset in incentives.clone() {
-        msgs.push(
-            wasm_execute(
-                config.incentives_fund_manager.clone(),
-                &incentives_fund_manager::ExecuteMsg::SendFund {
-                    asset,
-                    receiver: info.sender.clone(),
-                },
-                vec![],
-            )?
-            .into(),
-        );
-        // asset.transfer(&mut msgs, &info)?;
-    }
 
-    event_attributes.append(&mut vec![
+    let event_attributes = vec![
+        attr("action", "decrease_liquidity"),
         attr("pool_key", position.pool_key.to_string()),
         attr("token_id", position.token_id.to_string()),
         attr("owner", info.sender.as_str()),
-        attr("position_liquidity", withdrawed_liquidity.get().to_string()),
+        attr("liquidity_delta", liquidity_delta.get().to_string()),
+        attr("position_liquidity", position.liquidity.get().to_string()),
         attr("lower_tick", lower_tick.index.to_string()),
         attr("upper_tick", upper_tick.index.to_string()),
         attr("current_sqrt_price", pool.sqrt_price.get().to_string()),
         attr("liquidity_x", liquidity_x.to_string()),
         attr("liquidity_y", liquidity_y.to_string()),
-        attr("after_liquidity ", after_liquidity.get().to_string()),
-        attr("after_tick_index", ater_tick_index.to_string()),
-    ]);
+        attr("fee_x", fee_x.to_string()),
+        attr("fee_y", fee_y.to_string()),
+        attr("after_liquidity", pool.liquidity.get().to_string()),
+        attr("after_tick_index", pool.current_tick_index.to_string()),
+    ];
 
     Ok(Response::new()
         .add_messages(msgs)
@@ -891,7 +2413,7 @@ pub fn create_pool(
 
     let config = CONFIG.load(deps.storage)?;
 
-    let pool = Pool::create(
+    let mut pool = Pool::create(
         init_sqrt_price,
         init_tick,
         current_timestamp,
@@ -899,7 +2421,10 @@ pub fn create_pool(
         config.admin,
     )?;
 
-    POOLS.save(deps.storage, &db_key, &pool)?;
+    // initialize the price oracle
+    write_observation(deps.storage, &pool_key, &mut pool, env.block.time.seconds())?;
+
+    state::save_pool(deps.storage, &db_key, &pool)?;
 
     let event_attributes = vec![
         attr("action", "create_pool"),
@@ -1100,15 +2625,10 @@ pub fn handle_burn(
     info: MessageInfo,
     token_id: u64,
 ) -> Result<Response, ContractError> {
-    let (owner_raw, index) = state::POSITION_KEYS_BY_TOKEN_ID.load(deps.storage, token_id)?;
-    let mut position_key = owner_raw.to_vec();
-    position_key.extend_from_slice(&index.to_be_bytes());
-    let pos = state::get_position_by_key(deps.storage, &position_key)?;
-
-    check_can_send(deps.as_ref(), &env, &info, &owner_raw, &pos)?;
+    let (owner, index) = authorize_position(deps.as_ref(), &env, &info, token_id)?;
 
     // remain action to help sync backend
-    remove_position(deps, env, info.clone(), index)
+    remove_position(deps, env, &owner, index, &info.sender)
 }
 
 pub fn handle_send_nft(
@@ -1157,68 +2677,252 @@ pub fn handle_mint(
         deps,
         env,
         info,
+        None,
         pool_key,
         lower_tick,
         upper_tick,
         liquidity_delta,
         slippage_limit_lower,
         slippage_limit_upper,
+        None,
     )
 }
 
-// only owner can execute
+// pulls the rewards of an incentive program from the sender into the incentives fund manager
+fn deposit_incentive(
+    msgs: &mut Vec<CosmosMsg>,
+    info: &MessageInfo,
+    received: Option<&Asset>,
+    fund_manager: &Addr,
+    reward_token: &AssetInfo,
+    amount: TokenAmount,
+) -> Result<(), ContractError> {
+    let asset = Asset::new(reward_token.clone(), amount.into());
+    transfer_from_sender(
+        msgs,
+        info,
+        received,
+        fund_manager.to_string(),
+        &[asset.clone()],
+    )?;
+    // native coins and cw20 tokens sent through a hook are held by the contract, forward them
+    if received.is_some() || matches!(reward_token, AssetInfo::NativeToken { .. }) {
+        reward_token.transfer(msgs, fund_manager.to_string(), asset.amount)?;
+    }
+
+    Ok(())
+}
+
+/// Creates an incentive program for the pool. Anyone can create a program by depositing
+/// its whole reward, which is held by the incentives fund manager until it is claimed.
+/// Payouts failing to transfer are skipped, so a reward token cannot block the positions.
+///
+/// # Parameters
+/// - `received`: cw20 tokens already sent to the contract through a hook.
+/// - `pool_key`: a unique key that identifies the specified pool.
+/// - `reward_token`: the token paid to the liquidity providers.
+/// - `total_reward`: the amount deposited, native coins must be sent with the message.
+/// - `reward_per_sec`: the amount emitted every second.
+/// - `start_timestamp`: when the emission starts, defaults to the current block time.
+/// - `end_timestamp`: when the emission stops, defaults to running until the reward is spent.
+///
+/// # Errors
+/// - Fails if the pool does not exist.
+/// - Fails if the reward or the reward per second is zero.
+/// - Fails if the end time is not after the start time and the current block time.
+/// - Fails if the program emits for less than `MIN_INCENTIVE_DURATION`.
+/// - Fails if the pool already has `MAX_INCENTIVES_PER_POOL` programs that are not retired.
+/// - Fails if the reward is not deposited.
 #[allow(clippy::too_many_arguments)]
 pub fn create_incentive(
     deps: DepsMut,
     env: Env,
     info: MessageInfo,
+    received: Option<Asset>,
     pool_key: PoolKey,
     reward_token: AssetInfo,
-    total_reward: Option<TokenAmount>,
+    total_reward: TokenAmount,
     reward_per_sec: TokenAmount,
     start_timestamp: Option<u64>,
+    end_timestamp: Option<u64>,
 ) -> Result<Response, ContractError> {
-    let config = CONFIG.load(deps.storage)?;
-    if info.sender != config.admin {
-        return Err(ContractError::Unauthorized {});
+    if total_reward.is_zero() || reward_per_sec.is_zero() {
+        return Err(ContractError::AmountIsZero);
+    }
+    let start_timestamp = start_timestamp.unwrap_or(env.block.time.seconds());
+    let mut duration = u64::try_from(total_reward.0 / reward_per_sec.0).unwrap_or(u64::MAX);
+    if let Some(end_timestamp) = end_timestamp {
+        if end_timestamp <= start_timestamp.max(env.block.time.seconds()) {
+            return Err(ContractError::InvalidEndTimestamp);
+        }
+        duration = duration.min(end_timestamp - start_timestamp.max(env.block.time.seconds()));
+    }
+    if duration < MIN_INCENTIVE_DURATION {
+        return Err(ContractError::IncentiveTooShort);
     }
+    let config = CONFIG.load(deps.storage)?;
 
     let pool_key_db = pool_key.key();
     let mut pool = POOLS.load(deps.storage, &pool_key_db)?;
+    if pool.incentives.iter().filter(|i| !i.is_retired()).count() >= MAX_INCENTIVES_PER_POOL {
+        return Err(ContractError::TooManyIncentives);
+    }
     pool.update_global_incentives(env.block.time.seconds())?;
 
-    let id = pool.incentives.len() as u64;
-    let remaining = total_reward.unwrap_or(TokenAmount(u128::MAX));
+    let id = pool.next_incentive_id();
     let incentive = IncentiveRecord {
         id,
         reward_per_sec,
         reward_token: reward_token.clone(),
-        remaining,
-        start_timestamp: start_timestamp.unwrap_or(env.block.time.seconds()),
+        remaining: total_reward,
+        start_timestamp,
         incentive_growth_global: FeeGrowth(0),
         last_updated: env.block.time.seconds(),
+        creator: Some(info.sender.clone()),
+        end_timestamp,
+        retirement: None,
     };
     pool.incentives.push(incentive);
 
-    POOLS.save(deps.storage, &pool_key_db, &pool)?;
+    state::save_pool(deps.storage, &pool_key_db, &pool)?;
 
-    Ok(Response::new().add_attributes(vec![
+    let mut msgs = vec![];
+    deposit_incentive(
+        &mut msgs,
+        &info,
+        received.as_ref(),
+        &config.incentives_fund_manager,
+        &reward_token,
+        total_reward,
+    )?;
+
+    Ok(Response::new().add_messages(msgs).add_attributes(vec![
         ("action", "create_incentive"),
         ("pool", &pool_key.to_string()),
         ("record_id", &id.to_string()),
+        ("creator", info.sender.as_str()),
         ("reward_token", &reward_token.denom()),
-        ("total_reward", &remaining.to_string()),
+        ("total_reward", &total_reward.to_string()),
         ("reward_per_sec", &reward_per_sec.to_string()),
+        ("start_timestamp", &start_timestamp.to_string()),
         (
-            "start_timestamp",
-            &start_timestamp
-                .unwrap_or(env.block.time.seconds())
-                .to_string(),
+            "end_timestamp",
+            &end_timestamp.unwrap_or_default().to_string(),
         ),
     ]))
 }
 
-// only owner can execute
+// programs created before they were funded are managed by the admin
+// anyone can pick the reward token of a program, so a failing payout is caught in reply
+// instead of reverting the withdrawal or retirement it comes with
+fn send_incentive(fund_manager: &Addr, asset: Asset, receiver: Addr) -> StdResult<SubMsg> {
+    Ok(SubMsg::reply_on_error(
+        wasm_execute(
+            fund_manager,
+            &incentives_fund_manager::ExecuteMsg::SendFund { asset, receiver },
+            vec![],
+        )?,
+        INCENTIVE_REPLY_ID,
+    ))
+}
+
+fn incentive_manager(storage: &dyn Storage, record: &IncentiveRecord) -> StdResult<Addr> {
+    match &record.creator {
+        Some(creator) => Ok(creator.clone()),
+        None => Ok(CONFIG.load(storage)?.admin),
+    }
+}
+
+// loads the pool and the running incentive program managed by the sender
+fn load_creator_incentive(
+    storage: &dyn Storage,
+    sender: &Addr,
+    pool_key: &PoolKey,
+    record_id: u64,
+) -> Result<(Pool, usize), ContractError> {
+    let pool = POOLS.load(storage, &pool_key.key())?;
+    let index = pool
+        .incentives
+        .iter()
+        .position(|i| i.id == record_id)
+        .ok_or(ContractError::IncentiveNotFound)?;
+    let record = &pool.incentives[index];
+    if sender.ne(&incentive_manager(storage, record)?) {
+        return Err(ContractError::Unauthorized {});
+    }
+    if record.is_retired() {
+        return Err(ContractError::IncentiveRetired);
+    }
+
+    Ok((pool, index))
+}
+
+/// Deposits more rewards into an incentive program, only its creator can top it up.
+///
+/// # Parameters
+/// - `received`: cw20 tokens already sent to the contract through a hook.
+/// - `pool_key`: a unique key that identifies the specified pool.
+/// - `record_id`: the id of the incentive program.
+/// - `amount`: the amount deposited, native coins must be sent with the message.
+///
+/// # Errors
+/// - Fails if the program does not exist.
+/// - Fails if the caller is not the creator of the program.
+/// - Fails if the reward is not deposited.
+pub fn top_up_incentive(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    received: Option<Asset>,
+    pool_key: PoolKey,
+    record_id: u64,
+    amount: TokenAmount,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    let (mut pool, index) =
+        load_creator_incentive(deps.storage, &info.sender, &pool_key, record_id)?;
+    pool.update_global_incentives(env.block.time.seconds())?;
+
+    let record = &mut pool.incentives[index];
+    record.remaining = record.remaining.checked_add(amount)?;
+    let reward_token = record.reward_token.clone();
+    let remaining = record.remaining;
+
+    state::save_pool(deps.storage, &pool_key.key(), &pool)?;
+
+    let mut msgs = vec![];
+    deposit_incentive(
+        &mut msgs,
+        &info,
+        received.as_ref(),
+        &config.incentives_fund_manager,
+        &reward_token,
+        amount,
+    )?;
+
+    Ok(Response::new().add_messages(msgs).add_attributes(vec![
+        ("action", "top_up_incentive"),
+        ("pool", &pool_key.to_string()),
+        ("record_id", &record_id.to_string()),
+        ("amount", &amount.to_string()),
+        ("remaining_reward", &remaining.to_string()),
+    ]))
+}
+
+/// Updates an incentive program, only its creator can update it.
+///
+/// # Parameters
+/// - `pool_key`: a unique key that identifies the specified pool.
+/// - `record_id`: the id of the incentive program.
+/// - `remaining_reward`: lowers the rewards left, the difference is refunded to the creator.
+/// - `start_timestamp`: when the emission starts.
+/// - `reward_per_sec`: the amount emitted every second.
+///
+/// # Errors
+/// - Fails if the program does not exist.
+/// - Fails if the caller is not the creator of the program.
+/// - Fails if `remaining_reward` is above the rewards left, `TopUpIncentive` adds rewards.
+/// - Fails if `reward_per_sec` is zero.
 #[allow(clippy::too_many_arguments)]
 pub fn update_incentive(
     deps: DepsMut,
@@ -1230,54 +2934,232 @@ pub fn update_incentive(
     start_timestamp: Option<u64>,
     reward_per_sec: Option<TokenAmount>,
 ) -> Result<Response, ContractError> {
+    if reward_per_sec.map_or(false, |r| r.is_zero()) {
+        return Err(ContractError::AmountIsZero);
+    }
     let config = CONFIG.load(deps.storage)?;
-    if info.sender != config.admin {
-        return Err(ContractError::Unauthorized {});
+    let (mut pool, index) =
+        load_creator_incentive(deps.storage, &info.sender, &pool_key, record_id)?;
+    pool.update_global_incentives(env.block.time.seconds())?;
+
+    let mut sub_msgs = vec![];
+    let record = &mut pool.incentives[index];
+    if let Some(remaining_reward) = remaining_reward {
+        let refund = record.remaining.checked_sub(remaining_reward)?;
+        record.remaining = remaining_reward;
+        // programs created before they were funded have nothing to refund
+        if let Some(creator) = &record.creator {
+            if !refund.is_zero() {
+                sub_msgs.push(send_incentive(
+                    &config.incentives_fund_manager,
+                    Asset::new(record.reward_token.clone(), refund.into()),
+                    creator.clone(),
+                )?);
+            }
+        }
+    }
+    if let Some(start_timestamp) = start_timestamp {
+        record.start_timestamp = start_timestamp;
+    }
+    if let Some(reward_per_sec) = reward_per_sec {
+        record.reward_per_sec = reward_per_sec;
     }
 
+    state::save_pool(deps.storage, &pool_key.key(), &pool)?;
+
+    Ok(Response::new()
+        .add_submessages(sub_msgs)
+        .add_attributes(vec![
+            ("action", "update_incentive"),
+            ("pool", &pool_key.to_string()),
+            ("record_id", &record_id.to_string()),
+            (
+                "remaining_reward",
+                &remaining_reward.unwrap_or_default().to_string(),
+            ),
+            (
+                "start_timestamp",
+                &start_timestamp.unwrap_or_default().to_string(),
+            ),
+            (
+                "reward_per_sec",
+                &reward_per_sec.unwrap_or_default().to_string(),
+            ),
+        ]))
+}
+
+/// Retires an incentive program. The rewards left are refunded to its creator and the record
+/// is pruned from the pool once every open position has settled its rewards. The creator and the
+/// admin can retire a program at any time, so programs squatting the slots of a pool can be
+/// cleared, anyone can retire a finished one.
+///
+/// # Parameters
+/// - `pool_key`: a unique key that identifies the specified pool.
+/// - `record_id`: the id of the incentive program.
+///
+/// # Errors
+/// - Fails if the program does not exist or is already retired.
+/// - Fails if the caller is neither the creator nor the admin of a program that is still emitting.
+pub fn retire_incentive(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    pool_key: PoolKey,
+    record_id: u64,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
     let pool_key_db = pool_key.key();
     let mut pool = POOLS.load(deps.storage, &pool_key_db)?;
     pool.update_global_incentives(env.block.time.seconds())?;
 
-    if let Some(record) = pool.incentives.iter_mut().find(|i| i.id == record_id) {
-        if let Some(remaining_reward) = remaining_reward {
-            record.remaining = remaining_reward;
-        }
-        if let Some(start_timestamp) = start_timestamp {
-            record.start_timestamp = start_timestamp;
-        }
-        if let Some(reward_per_sec) = reward_per_sec {
-            record.reward_per_sec = reward_per_sec;
+    let retirement = IncentiveRetirement {
+        tick_index: pool.current_tick_index,
+        last_token_id: state::last_token_id(deps.storage)?,
+        unsettled_positions: pool.position_count,
+    };
+    let record = pool
+        .incentives
+        .iter_mut()
+        .find(|i| i.id == record_id)
+        .ok_or(ContractError::IncentiveNotFound)?;
+    if record.is_retired() {
+        return Err(ContractError::IncentiveRetired);
+    }
+    if info.sender.ne(&incentive_manager(deps.storage, record)?)
+        && info.sender.ne(&config.admin)
+        && !record.is_finished(env.block.time.seconds())
+    {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    let refund = record.remaining;
+    record.remaining = TokenAmount::new(0);
+    record.retirement = Some(retirement);
+
+    let mut sub_msgs = vec![];
+    // programs created before they were funded have nothing to refund
+    if let Some(creator) = &record.creator {
+        if !refund.is_zero() {
+            sub_msgs.push(send_incentive(
+                &config.incentives_fund_manager,
+                Asset::new(record.reward_token.clone(), refund.into()),
+                creator.clone(),
+            )?);
         }
     }
 
-    POOLS.save(deps.storage, &pool_key_db, &pool)?;
+    // without positions there is nothing to settle
+    pool.prune_incentives();
+    state::save_pool(deps.storage, &pool_key_db, &pool)?;
+
+    Ok(Response::new()
+        .add_submessages(sub_msgs)
+        .add_attributes(vec![
+            ("action", "retire_incentive"),
+            ("pool", &pool_key.to_string()),
+            ("record_id", &record_id.to_string()),
+            ("refund", &refund.to_string()),
+        ]))
+}
+
+// only owner can execute
+pub fn update_pool_status(
+    deps: DepsMut,
+    info: MessageInfo,
+    pool_key: PoolKey,
+    status: Option<PoolStatus>,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    if info.sender != config.admin {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    let pool_key_db = pool_key.key();
+    let mut pool = POOLS.load(deps.storage, &pool_key_db)?;
+    pool.status = status.clone();
+
+    state::save_pool(deps.storage, &pool_key_db, &pool)?;
 
     Ok(Response::new().add_attributes(vec![
-        ("action", "update_incentive"),
+        ("action", "update_pool_status"),
         ("pool", &pool_key.to_string()),
-        ("record_id", &record_id.to_string()),
         (
-            "remaining_reward",
-            &remaining_reward.unwrap_or_default().to_string(),
+            "pool_status",
+            &format!("{:?}", &status.unwrap_or(PoolStatus::Opening)),
         ),
+    ]))
+}
+
+/// Increases the number of observations the pool oracle can store.
+/// The ring buffer grows lazily, once the current last slot has been written.
+///
+/// # Parameters
+/// - `pool_key`: A unique key that identifies the specified pool.
+/// - `observation_cardinality_next`: The desired minimum number of observations to store.
+///
+/// # Events
+/// - On successful update, emits an `Increase Observation Cardinality Next` event with the old and new values.
+///
+/// # Errors
+/// - Fails if the pool does not exist.
+/// - Fails if the number of observations is above `MAX_OBSERVATION_CARDINALITY`.
+pub fn increase_observation_cardinality_next(
+    deps: DepsMut,
+    env: Env,
+    pool_key: PoolKey,
+    observation_cardinality_next: u16,
+) -> Result<Response, ContractError> {
+    if observation_cardinality_next > MAX_OBSERVATION_CARDINALITY {
+        return Err(ContractError::InvalidObservationCardinality {});
+    }
+
+    let pool_key_db = pool_key.key();
+    let mut pool = POOLS.load(deps.storage, &pool_key_db)?;
+
+    // pools created before the oracle existed start recording from now on
+    write_observation(deps.storage, &pool_key, &mut pool, env.block.time.seconds())?;
+
+    let observation_cardinality_next_old = pool.observation_cardinality_next;
+    if observation_cardinality_next > observation_cardinality_next_old {
+        pool.observation_cardinality_next = observation_cardinality_next;
+    }
+
+    state::save_pool(deps.storage, &pool_key_db, &pool)?;
+
+    Ok(Response::new().add_attributes(vec![
+        ("action", "increase_observation_cardinality_next"),
+        ("pool", &pool_key.to_string()),
         (
-            "start_timestamp",
-            &start_timestamp.unwrap_or_default().to_string(),
+            "observation_cardinality_next_old",
+            &observation_cardinality_next_old.to_string(),
         ),
         (
-            "reward_per_sec",
-            &reward_per_sec.unwrap_or_default().to_string(),
+            "observation_cardinality_next_new",
+            &pool.observation_cardinality_next.to_string(),
         ),
     ]))
 }
 
-// only owner can execute
-pub fn update_pool_status(
+/// Enables, updates or disables the dynamic fee of the pool.
+/// The volatility window of a new config starts from the current tick.
+///
+/// # Parameters
+/// - `pool_key`: A unique key that identifies the specified pool.
+/// - `dynamic_fee`: The base fee, max fee and window of the dynamic fee, none to use the fee of the fee tier.
+///
+/// # Events
+/// - On successful update, emits an `Update Dynamic Fee` event with the new fees.
+///
+/// # Errors
+/// - Reverts the call when the caller is an unauthorized user.
+/// - Fails if the pool does not exist.
+/// - Fails if the base fee is above the max fee, the max fee is above 100% or the window is empty.
+pub fn update_dynamic_fee(
     deps: DepsMut,
+    env: Env,
     info: MessageInfo,
     pool_key: PoolKey,
-    status: Option<PoolStatus>,
+    dynamic_fee: Option<DynamicFeeConfig>,
 ) -> Result<Response, ContractError> {
     let config = CONFIG.load(deps.storage)?;
     if info.sender != config.admin {
@@ -1286,16 +3168,27 @@ pub fn update_pool_status(
 
     let pool_key_db = pool_key.key();
     let mut pool = POOLS.load(deps.storage, &pool_key_db)?;
-    pool.status = status.clone();
+    pool.dynamic_fee = dynamic_fee
+        .map(|config| DynamicFee::new(config, pool.current_tick_index, env.block.time.seconds()))
+        .transpose()?;
+
+    state::save_pool(deps.storage, &pool_key_db, &pool)?;
 
-    POOLS.save(deps.storage, &pool_key_db, &pool)?;
+    let (base_fee, max_fee) = dynamic_fee
+        .map(|config| (config.base_fee, config.max_fee))
+        .unwrap_or((pool_key.fee_tier.fee, pool_key.fee_tier.fee));
 
     Ok(Response::new().add_attributes(vec![
-        ("action", "update_pool_status"),
+        ("action", "update_dynamic_fee"),
         ("pool", &pool_key.to_string()),
+        ("base_fee", &base_fee.get().to_string()),
+        ("max_fee", &max_fee.get().to_string()),
         (
-            "pool_status",
-            &format!("{:?}", &status.unwrap_or(PoolStatus::Opening)),
+            "window",
+            &dynamic_fee
+                .map(|config| config.window)
+                .unwrap_or_default()
+                .to_string(),
         ),
     ]))
 }
@@ -1,15 +1,25 @@
-use cosmwasm_std::{Addr, Binary, Deps, Env, Order, StdResult, Uint64};
+use std::collections::{btree_map::Entry, BTreeMap};
+
+use cosmwasm_std::{Addr, Binary, Deps, Env, Order, StdError, StdResult, Uint64};
+use cw20::Expiration;
 use cw_storage_plus::Bound;
+use decimal::Decimal;
 use oraiswap_v3_common::{
     asset::Asset,
     error::ContractError,
     interface::{
-        AllNftInfoResponse, Approval, ApprovedForAllResponse, NftInfoResponse, NumTokensResponse,
-        OwnerOfResponse, PoolWithPoolKey, PositionTick, QuoteResult, SwapHop, TokensResponse,
+        AllNftInfoResponse, Approval, ApprovalResponse, ApprovalsResponse, ApprovedForAllResponse,
+        ContractInfoResponse, IncentiveStatus, MinterResponse, NftInfoResponse, NumTokensResponse,
+        ObserveResponse, OwnerOfResponse, PoolIncentive, PoolWithPoolKey, PositionMetadata,
+        PositionPendingIncentives, PositionTick, PositionTokensResponse, PositionWithIndex,
+        QuoteResult, SnapshotCumulativesInsideResponse, SwapHop, TokensResponse, Trait,
     },
     math::{
         percentage::Percentage,
-        sqrt_price::{get_max_tick, get_min_tick, SqrtPrice},
+        seconds_per_liquidity::calculate_seconds_per_liquidity_inside,
+        sqrt_price::{
+            calculate_sqrt_price, get_max_tick, get_min_tick, sqrt_price_to_price_string, SqrtPrice,
+        },
         token_amount::TokenAmount,
     },
     storage::{
@@ -19,9 +29,9 @@ use oraiswap_v3_common::{
     },
 };
 
-use crate::state::{self, CONFIG, MAX_LIMIT, POSITIONS};
+use crate::state::{self, CONFIG, MAX_LIMIT, NFT_NAME, NFT_SYMBOL, POSITIONS};
 
-use super::{calculate_swap, route, tickmap_slice, TimeStampExt};
+use super::{calculate_swap, observe_single, route, route_exact_out, tickmap_slice, TimeStampExt};
 
 /// Retrieves the admin of contract.
 pub fn query_admin(deps: Deps) -> Result<Addr, ContractError> {
@@ -53,6 +63,18 @@ pub fn get_position(deps: Deps, owner_id: Addr, index: u32) -> Result<Position,
     state::get_position(deps.storage, &owner_id, index)
 }
 
+/// Retrieves information about a single position by its token id.
+///
+/// # Parameters
+/// - `token_id`: The token id of the position, it does not change while the position exists.
+///
+/// # Errors
+/// - Fails if position cannot be found
+pub fn get_position_by_token_id(deps: Deps, token_id: u64) -> Result<Position, ContractError> {
+    let (owner_id, index) = state::get_position_owner_and_index(deps.storage, token_id)?;
+    state::get_position(deps.storage, &owner_id, index)
+}
+
 // /// Retrieves a vector containing all positions held by the user.
 // ///
 // /// # Parameters
@@ -66,6 +88,21 @@ pub fn get_positions(
     state::get_all_positions(deps.storage, &owner_id, limit, offset)
 }
 
+/// Retrieves the limit orders held by the user, filled or not, with their position indexes.
+///
+/// # Parameters
+/// - `owner`: An `Addr` identifying the user who owns the orders.
+/// - `start_after`: The position index to list the orders after.
+/// - `limit`: The maximum number of orders to return, capped at `MAX_LIMIT`.
+pub fn get_limit_orders(
+    deps: Deps,
+    owner: Addr,
+    start_after: Option<u32>,
+    limit: Option<u32>,
+) -> Result<Vec<PositionWithIndex>, ContractError> {
+    state::get_limit_orders(deps.storage, &owner, start_after, limit)
+}
+
 /// Query of whether the fee tier exists.
 ///
 /// # Parameters
@@ -407,6 +444,7 @@ pub fn quote(
         amount_out: calculate_swap_result.amount_out,
         target_sqrt_price: calculate_swap_result.pool.sqrt_price,
         ticks: calculate_swap_result.ticks,
+        fee_rate: calculate_swap_result.fee_rate,
     })
 }
 
@@ -430,6 +468,26 @@ pub fn quote_route(
     Ok(amount_out)
 }
 
+/// Simulates multiple swaps for an exact amount of the last token without its execution.
+///
+/// # Parameters
+/// - `amount_out`: The amount of the last token that the user wants to receive.
+/// - `swaps`: A vector containing all parameters needed to identify separate swap steps.
+///
+/// # Errors
+/// - Fails if the user attempts to perform a swap with zero amounts.
+/// - Fails if a pool cannot provide the amount out.
+/// - Fails if pool does not exist
+pub fn quote_route_exact_out(
+    deps: Deps,
+    env: Env,
+    amount_out: TokenAmount,
+    swaps: Vec<SwapHop>,
+) -> Result<TokenAmount, ContractError> {
+    let amount_in = route_exact_out(deps.storage, env, amount_out, swaps)?;
+    Ok(amount_in)
+}
+
 pub fn query_owner_of(
     deps: Deps,
     env: Env,
@@ -475,12 +533,122 @@ pub fn query_all_approvals(
     Ok(ApprovedForAllResponse { operators: res? })
 }
 
+pub fn query_approval(
+    deps: Deps,
+    env: Env,
+    token_id: u64,
+    spender: Addr,
+    include_expired: bool,
+) -> Result<ApprovalResponse, ContractError> {
+    let (owner, index) = state::get_position_owner_and_index(deps.storage, token_id)?;
+    // the owner can always send its token
+    if owner == spender {
+        return Ok(ApprovalResponse {
+            approval: Approval {
+                spender,
+                expires: Expiration::Never {},
+            },
+        });
+    }
+
+    let pos = state::get_position(deps.storage, &owner, index)?;
+    let approval = pos
+        .approvals
+        .into_iter()
+        .find(|apr| apr.spender == spender)
+        .filter(|apr| include_expired || !apr.expires.is_expired(&env.block))
+        .ok_or_else(|| StdError::not_found("Approval"))?;
+
+    Ok(ApprovalResponse { approval })
+}
+
+pub fn query_approvals(
+    deps: Deps,
+    env: Env,
+    token_id: u64,
+    include_expired: bool,
+) -> Result<ApprovalsResponse, ContractError> {
+    let (owner, index) = state::get_position_owner_and_index(deps.storage, token_id)?;
+    let mut pos = state::get_position(deps.storage, &owner, index)?;
+    pos.approvals
+        .retain(|apr| include_expired || !apr.expires.is_expired(&env.block));
+
+    Ok(ApprovalsResponse {
+        approvals: pos.approvals,
+    })
+}
+
+// decimal string without the trailing zeros of its fixed scale
+fn trim_decimal(value: String) -> String {
+    if !value.contains('.') {
+        return value;
+    }
+    value
+        .trim_end_matches('0')
+        .trim_end_matches('.')
+        .to_string()
+}
+
+// the token uri and cw721 metadata of a position, priced against the current pool state
+fn position_nft_info(deps: Deps, position: Position) -> Result<NftInfoResponse, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    let pool = state::get_pool(deps.storage, &position.pool_key)?;
+    let pool_key = &position.pool_key;
+
+    let fee = trim_decimal(Percentage::new(pool_key.fee_tier.fee.get() * 100).to_string());
+    let lower_price = sqrt_price_to_price_string(calculate_sqrt_price(position.lower_tick_index)?);
+    let upper_price = sqrt_price_to_price_string(calculate_sqrt_price(position.upper_tick_index)?);
+    let in_range = position.lower_tick_index <= pool.current_tick_index
+        && pool.current_tick_index < position.upper_tick_index;
+
+    let attributes = [
+        ("token_x", pool_key.token_x.clone()),
+        ("token_y", pool_key.token_y.clone()),
+        ("fee", format!("{}%", fee)),
+        ("tick_spacing", pool_key.fee_tier.tick_spacing.to_string()),
+        ("lower_tick", position.lower_tick_index.to_string()),
+        ("upper_tick", position.upper_tick_index.to_string()),
+        ("lower_price", lower_price.clone()),
+        ("upper_price", upper_price.clone()),
+        ("liquidity", trim_decimal(position.liquidity.to_string())),
+        ("in_range", in_range.to_string()),
+    ]
+    .into_iter()
+    .map(|(trait_type, value)| Trait {
+        display_type: None,
+        trait_type: trait_type.to_string(),
+        value,
+    })
+    .collect();
+
+    Ok(NftInfoResponse {
+        token_uri: config
+            .token_uri
+            .map(|base| format!("{}{}", base, position.token_id)),
+        extension: PositionMetadata {
+            name: format!("Oraiswap V3 Position #{}", position.token_id),
+            description: format!(
+                "Liquidity in the {}/{} {}% pool between the prices {} and {} of {} in {}",
+                pool_key.token_x,
+                pool_key.token_y,
+                fee,
+                lower_price,
+                upper_price,
+                pool_key.token_x,
+                pool_key.token_y
+            ),
+            attributes,
+            position,
+        },
+    })
+}
+
 pub fn query_nft_info(deps: Deps, token_id: u64) -> Result<NftInfoResponse, ContractError> {
     let (owner_raw, index) = state::POSITION_KEYS_BY_TOKEN_ID.load(deps.storage, token_id)?;
     let mut position_key = owner_raw.to_vec();
     position_key.extend_from_slice(&index.to_be_bytes());
     let pos = state::get_position_by_key(deps.storage, &position_key)?;
-    Ok(NftInfoResponse { extension: pos })
+    position_nft_info(deps, pos)
 }
 
 pub fn query_all_nft_info(
@@ -499,17 +667,21 @@ pub fn query_all_nft_info(
             owner,
             approvals: pos.approvals.clone(),
         },
-        info: NftInfoResponse { extension: pos },
+        info: position_nft_info(deps, pos)?,
     })
 }
 
 pub fn query_tokens(
     deps: Deps,
     owner: Addr,
-    start_after: Option<u32>,
+    start_after: Option<u64>,
     limit: Option<u32>,
 ) -> Result<TokensResponse, ContractError> {
-    let tokens = state::get_all_position_keys(deps.storage, &owner, limit, start_after)
+    let offset = start_after
+        .map(|token_id| state::get_position_index(deps.storage, &owner, token_id))
+        .transpose()?
+        .map(|index| index + 1);
+    let tokens = state::get_all_position_keys(deps.storage, &owner, limit, offset)
         .into_iter()
         .map(|key| {
             let pos = state::get_position_by_key(deps.storage, &key)?;
@@ -520,6 +692,23 @@ pub fn query_tokens(
     Ok(TokensResponse { tokens })
 }
 
+pub fn query_position_tokens(
+    deps: Deps,
+    owner: Addr,
+    start_after: Option<u32>,
+    limit: Option<u32>,
+) -> Result<PositionTokensResponse, ContractError> {
+    let tokens = state::get_all_position_keys(deps.storage, &owner, limit, start_after)
+        .into_iter()
+        .map(|key| {
+            let pos = state::get_position_by_key(deps.storage, &key)?;
+            Ok(pos.token_id)
+        })
+        .collect::<StdResult<_>>()?;
+
+    Ok(PositionTokensResponse { tokens })
+}
+
 pub fn query_all_tokens(
     deps: Deps,
     start_after: Option<u64>,
@@ -544,6 +733,17 @@ pub fn query_num_tokens(deps: Deps) -> Result<NumTokensResponse, ContractError>
     Ok(NumTokensResponse { count })
 }
 
+pub fn query_contract_info() -> ContractInfoResponse {
+    ContractInfoResponse {
+        name: NFT_NAME.to_string(),
+        symbol: NFT_SYMBOL.to_string(),
+    }
+}
+
+pub fn query_minter() -> MinterResponse {
+    MinterResponse { minter: None }
+}
+
 /// Retrieves incentives information of a single position.
 ///
 /// # Parameters
@@ -571,6 +771,107 @@ pub fn query_position_incentives(
     Ok(incentives)
 }
 
+/// Retrieves incentives information of a single position by its token id.
+///
+/// # Parameters
+/// - `token_id`: The token id of the position.
+///
+/// # Errors
+/// - Fails if position cannot be found
+pub fn query_position_incentives_by_token_id(
+    deps: Deps,
+    env: Env,
+    token_id: u64,
+) -> Result<Vec<Asset>, ContractError> {
+    let (owner_id, index) = state::get_position_owner_and_index(deps.storage, token_id)?;
+    query_position_incentives(deps, env, owner_id, index)
+}
+
+/// Retrieves the incentive programs of a pool, updated to the current block time.
+///
+/// # Parameters
+/// - `pool_key`: A unique key that identifies the specified pool.
+///
+/// # Errors
+/// - Fails if pool cannot be found
+pub fn query_pool_incentives(
+    deps: Deps,
+    env: Env,
+    pool_key: PoolKey,
+) -> Result<Vec<PoolIncentive>, ContractError> {
+    let mut pool = state::get_pool(deps.storage, &pool_key)?;
+    let current_timestamp = env.block.time.seconds();
+    pool.update_global_incentives(current_timestamp)?;
+
+    Ok(pool
+        .incentives
+        .into_iter()
+        .map(|incentive| {
+            let status = if incentive.is_retired() {
+                IncentiveStatus::Retired
+            } else if incentive.is_finished(current_timestamp) {
+                IncentiveStatus::Finished
+            } else if current_timestamp < incentive.start_timestamp {
+                IncentiveStatus::Upcoming
+            } else {
+                IncentiveStatus::Active
+            };
+            PoolIncentive { incentive, status }
+        })
+        .collect())
+}
+
+/// Retrieves the pending incentives of the positions of an owner at the current block time.
+/// Each pool is updated once, no matter how many positions it holds.
+///
+/// # Parameters
+/// - `owner`: An `Addr` identifying the user who owns the positions.
+/// - `start_after`: The index of the last position returned by the previous page.
+/// - `limit`: The maximum number of positions to return.
+///
+/// # Errors
+/// - Fails if a pool or a tick of the positions cannot be found
+pub fn query_pending_incentives(
+    deps: Deps,
+    env: Env,
+    owner: Addr,
+    start_after: Option<u32>,
+    limit: Option<u32>,
+) -> Result<Vec<PositionPendingIncentives>, ContractError> {
+    let current_timestamp = env.block.time.seconds();
+    let offset = start_after.map_or(0, |index| index + 1);
+    let limit = limit.unwrap_or(MAX_LIMIT).min(MAX_LIMIT);
+    let positions = state::get_all_positions(deps.storage, &owner, Some(limit), Some(offset))?;
+
+    let mut pools = BTreeMap::new();
+    positions
+        .into_iter()
+        .zip(offset..)
+        .map(|(mut position, index)| {
+            let pool = match pools.entry(position.pool_key.key()) {
+                Entry::Occupied(entry) => entry.into_mut(),
+                Entry::Vacant(entry) => {
+                    let mut pool = state::get_pool(deps.storage, &position.pool_key)?;
+                    pool.update_global_incentives(current_timestamp)?;
+                    entry.insert(pool)
+                }
+            };
+            let lower_tick =
+                state::get_tick(deps.storage, &position.pool_key, position.lower_tick_index)?;
+            let upper_tick =
+                state::get_tick(deps.storage, &position.pool_key, position.upper_tick_index)?;
+            position.update_incentives(pool, &upper_tick, &lower_tick)?;
+            let incentives = position.claim_incentives(pool, &upper_tick, &lower_tick)?;
+
+            Ok(PositionPendingIncentives {
+                index,
+                pool_key: position.pool_key,
+                incentives,
+            })
+        })
+        .collect()
+}
+
 pub fn query_all_positions(
     deps: Deps,
     limit: Option<u32>,
@@ -585,3 +886,62 @@ pub fn query_all_positions(
         .map(|(_, position)| position)
         .collect::<Vec<Position>>())
 }
+
+/// Retrieves the seconds per liquidity accumulated inside the given tick range as of the current block time.
+/// Multiplied by the liquidity of a position, the difference between two snapshots is the time the
+/// position spent in range.
+pub fn snapshot_cumulatives_inside(
+    deps: Deps,
+    env: Env,
+    pool_key: PoolKey,
+    lower_tick: i32,
+    upper_tick: i32,
+) -> Result<SnapshotCumulativesInsideResponse, ContractError> {
+    if lower_tick >= upper_tick {
+        return Err(ContractError::InvalidTickIndex {});
+    }
+
+    let mut pool = state::get_pool(deps.storage, &pool_key)?;
+    let lower_tick = state::get_tick(deps.storage, &pool_key, lower_tick)?;
+    let upper_tick = state::get_tick(deps.storage, &pool_key, upper_tick)?;
+    pool.update_seconds_per_liquidity_global(env.block.time.millis())?;
+
+    let seconds_per_liquidity_inside = calculate_seconds_per_liquidity_inside(
+        lower_tick.index,
+        upper_tick.index,
+        pool.current_tick_index,
+        lower_tick.seconds_per_liquidity_outside,
+        upper_tick.seconds_per_liquidity_outside,
+        pool.seconds_per_liquidity_global,
+    )?;
+
+    Ok(SnapshotCumulativesInsideResponse {
+        seconds_per_liquidity_inside,
+    })
+}
+
+/// Retrieves the oracle cumulatives of a pool as of each `seconds_ago` from the current block time.
+/// Averages between two results give the time-weighted tick and in-range liquidity.
+pub fn observe(
+    deps: Deps,
+    env: Env,
+    pool_key: PoolKey,
+    seconds_agos: Vec<u64>,
+) -> Result<ObserveResponse, ContractError> {
+    let pool = state::get_pool(deps.storage, &pool_key)?;
+    let block_timestamp = env.block.time.seconds();
+
+    let mut tick_cumulatives = Vec::with_capacity(seconds_agos.len());
+    let mut seconds_per_liquidity_cumulatives = Vec::with_capacity(seconds_agos.len());
+    for seconds_ago in seconds_agos {
+        let observation =
+            observe_single(deps.storage, &pool_key, &pool, block_timestamp, seconds_ago)?;
+        tick_cumulatives.push(observation.tick_cumulative);
+        seconds_per_liquidity_cumulatives.push(observation.seconds_per_liquidity_cumulative);
+    }
+
+    Ok(ObserveResponse {
+        tick_cumulatives,
+        seconds_per_liquidity_cumulatives,
+    })
+}
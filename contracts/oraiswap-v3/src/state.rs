@@ -3,14 +3,16 @@ use cw20::Expiration;
 use cw_storage_plus::{Bound, Item, Map};
 use oraiswap_v3_common::{
     error::ContractError,
-    interface::PoolWithPoolKey,
+    interface::{PoolWithPoolKey, PositionWithIndex},
     math::{
+        liquidity::Liquidity,
         sqrt_price::{calculate_sqrt_price, SqrtPrice},
         MAX_TICK,
     },
     storage::{
         flip_bit_at_position, get_bit_at_position, get_search_limit, incentive::IncentiveRecord,
-        tick_to_position, Config, Pool, PoolKey, Position, Tick, CHUNK_SIZE,
+        tick_to_position, Config, FlashLoan, Observation, Pool, PoolKey, Position, Tick,
+        CHUNK_SIZE,
     },
 };
 
@@ -27,6 +29,10 @@ pub const POSITIONS: Map<&[u8], Position> = Map::new("positions");
 pub const TICKS: Map<&[u8], Tick> = Map::new("ticks");
 
 pub const BITMAP: Map<&[u8], u64> = Map::new("bitmap");
+
+pub const OBSERVATIONS: Map<&[u8], Observation> = Map::new("observations");
+// token ids of the open limit orders filled by crossing a tick
+pub const LIMIT_ORDERS: Map<&[u8], Vec<u64>> = Map::new("limit_orders");
 // for store global id of postion as token id
 pub const TOKEN_ID: Item<u64> = Item::new("token_id");
 // for mapping token_id => position key(account + index)
@@ -36,8 +42,28 @@ pub const OPERATORS: Map<(&[u8], &[u8]), Expiration> = Map::new("operators");
 
 pub const INCENTIVE_RECORD: Map<u64, IncentiveRecord> = Map::new("incentive_record");
 
+// flash loan waiting for its repayment check in reply
+pub const FLASH_LOAN: Item<FlashLoan> = Item::new("flash_loan");
+
 pub const MAX_LIMIT: u32 = 100;
 
+// most observations the oracle of a pool can store
+pub const MAX_OBSERVATION_CARDINALITY: u16 = 1000;
+
+// a swap crossing a tick fills all of its limit orders, so they are bounded in number and size,
+// and a single owner cannot take all the slots of a tick
+pub const MAX_LIMIT_ORDERS_PER_TICK: usize = 50;
+pub const MAX_LIMIT_ORDERS_PER_OWNER_PER_TICK: usize = 5;
+pub const MIN_LIMIT_ORDER_LIQUIDITY: Liquidity = Liquidity(1_000_000_000_000);
+
+// every action on a pool updates all of its incentive programs, so anyone creating one is bounded
+pub const MAX_INCENTIVES_PER_POOL: usize = 10;
+pub const MIN_INCENTIVE_DURATION: u64 = 3600;
+
+// cw721 name and symbol of the position NFTs
+pub const NFT_NAME: &str = "Oraiswap V3 Positions";
+pub const NFT_SYMBOL: &str = "ORAIX-V3-POS";
+
 pub fn num_tokens(storage: &dyn Storage) -> StdResult<u64> {
     Ok(TOKEN_COUNT.may_load(storage)?.unwrap_or_default())
 }
@@ -69,6 +95,17 @@ pub fn get_pool(store: &dyn Storage, pool_key: &PoolKey) -> Result<Pool, Contrac
     Ok(pool)
 }
 
+/// Saves a pool, the pool of a pending flash loan is locked until the loan is repaid.
+pub fn save_pool(store: &mut dyn Storage, db_key: &[u8], pool: &Pool) -> Result<(), ContractError> {
+    if let Some(flash_loan) = FLASH_LOAN.may_load(store)? {
+        if flash_loan.pool_key.key().eq(db_key) {
+            return Err(ContractError::FlashLoanInProgress {});
+        }
+    }
+    POOLS.save(store, db_key, pool)?;
+    Ok(())
+}
+
 pub fn get_pools(
     store: &dyn Storage,
     limit: Option<u32>,
@@ -161,6 +198,86 @@ pub fn get_tick(
     Ok(tick)
 }
 
+pub fn observation_key(pool_key: &PoolKey, index: u16) -> Vec<u8> {
+    let mut db_key = pool_key.key();
+    db_key.extend_from_slice(&index.to_be_bytes());
+    db_key
+}
+
+/// Returns the observation at `index`, slots that were never written are uninitialized.
+pub fn get_observation(store: &dyn Storage, pool_key: &PoolKey, index: u16) -> Observation {
+    OBSERVATIONS
+        .may_load(store, &observation_key(pool_key, index))
+        .ok()
+        .flatten()
+        .unwrap_or_default()
+}
+
+pub fn save_observation(
+    store: &mut dyn Storage,
+    pool_key: &PoolKey,
+    index: u16,
+    observation: &Observation,
+) -> Result<(), ContractError> {
+    OBSERVATIONS.save(store, &observation_key(pool_key, index), observation)?;
+    Ok(())
+}
+
+pub fn add_limit_order(
+    store: &mut dyn Storage,
+    pool_key: &PoolKey,
+    tick_index: i32,
+    owner: &Addr,
+    token_id: u64,
+) -> Result<(), ContractError> {
+    let db_key = tick_key(pool_key, tick_index);
+    let mut token_ids = LIMIT_ORDERS.may_load(store, &db_key)?.unwrap_or_default();
+    if token_ids.len() >= MAX_LIMIT_ORDERS_PER_TICK {
+        return Err(ContractError::TooManyLimitOrders {});
+    }
+    let mut owned = 0;
+    for id in &token_ids {
+        if get_position_owner_and_index(store, *id)?.0.eq(owner) {
+            owned += 1;
+        }
+    }
+    if owned >= MAX_LIMIT_ORDERS_PER_OWNER_PER_TICK {
+        return Err(ContractError::TooManyLimitOrders {});
+    }
+    token_ids.push(token_id);
+    LIMIT_ORDERS.save(store, &db_key, &token_ids)?;
+    Ok(())
+}
+
+pub fn remove_limit_order(
+    store: &mut dyn Storage,
+    pool_key: &PoolKey,
+    tick_index: i32,
+    token_id: u64,
+) -> Result<(), ContractError> {
+    let db_key = tick_key(pool_key, tick_index);
+    let mut token_ids = LIMIT_ORDERS.may_load(store, &db_key)?.unwrap_or_default();
+    token_ids.retain(|id| *id != token_id);
+    if token_ids.is_empty() {
+        LIMIT_ORDERS.remove(store, &db_key);
+    } else {
+        LIMIT_ORDERS.save(store, &db_key, &token_ids)?;
+    }
+    Ok(())
+}
+
+/// Removes and returns the limit orders waiting for `tick_index` to be crossed.
+pub fn take_limit_orders(
+    store: &mut dyn Storage,
+    pool_key: &PoolKey,
+    tick_index: i32,
+) -> Result<Vec<u64>, ContractError> {
+    let db_key = tick_key(pool_key, tick_index);
+    let token_ids = LIMIT_ORDERS.may_load(store, &db_key)?.unwrap_or_default();
+    LIMIT_ORDERS.remove(store, &db_key);
+    Ok(token_ids)
+}
+
 pub fn position_key(account_id: &Addr, index: u32) -> Vec<u8> {
     let mut db_key = account_id.as_bytes().to_vec();
     db_key.extend_from_slice(&index.to_be_bytes());
@@ -240,6 +357,31 @@ pub fn get_position(
     get_position_by_key(store, &db_key)
 }
 
+/// Returns the owner of the position minted as `token_id` and its current index
+pub fn get_position_owner_and_index(
+    store: &dyn Storage,
+    token_id: u64,
+) -> Result<(Addr, u32), ContractError> {
+    let (owner_raw, index) = POSITION_KEYS_BY_TOKEN_ID.load(store, token_id)?;
+    let owner = Addr::unchecked(String::from_utf8(owner_raw)?);
+
+    Ok((owner, index))
+}
+
+/// Returns the current index of the position minted as `token_id`, it must be owned by `account_id`
+pub fn get_position_index(
+    store: &dyn Storage,
+    account_id: &Addr,
+    token_id: u64,
+) -> Result<u32, ContractError> {
+    let (owner, index) = get_position_owner_and_index(store, token_id)?;
+    if owner != *account_id {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    Ok(index)
+}
+
 pub fn get_position_by_key(store: &dyn Storage, db_key: &[u8]) -> Result<Position, ContractError> {
     let position = POSITIONS.load(store, db_key)?;
 
@@ -281,6 +423,37 @@ pub fn get_all_position_keys(
         .collect()
 }
 
+/// Returns the limit orders of `account_id` with their position indexes, at most `MAX_LIMIT` of them
+/// starting after the position index `start_after`.
+pub fn get_limit_orders(
+    store: &dyn Storage,
+    account_id: &Addr,
+    start_after: Option<u32>,
+    limit: Option<u32>,
+) -> Result<Vec<PositionWithIndex>, ContractError> {
+    let from_idx = start_after.map_or(0, |index| index.saturating_add(1));
+    let limit = limit.unwrap_or(MAX_LIMIT).min(MAX_LIMIT) as usize;
+
+    let min = Some(Bound::InclusiveRaw(position_key(account_id, from_idx)));
+    let max = Some(Bound::ExclusiveRaw(position_key(
+        account_id,
+        get_position_length(store, account_id),
+    )));
+
+    let orders = POSITIONS
+        .range_raw(store, min, max, Order::Ascending)
+        .zip(from_idx..)
+        .filter_map(|(item, index)| match item {
+            Ok((_, position)) if position.limit_order.is_none() => None,
+            Ok((_, position)) => Some(Ok(PositionWithIndex { index, position })),
+            Err(err) => Some(Err(err)),
+        })
+        .take(limit)
+        .collect::<StdResult<Vec<_>>>()?;
+
+    Ok(orders)
+}
+
 pub fn get_position_length(store: &dyn Storage, account_id: &Addr) -> u32 {
     POSITIONS_LENGTH
         .load(store, account_id.as_bytes())
@@ -1,4 +1,4 @@
-use cosmwasm_std::coins;
+use cosmwasm_std::{coins, Addr};
 use decimal::{Decimal, Factories};
 use oraiswap_v3_common::{
     asset::AssetInfo,
@@ -7,6 +7,7 @@ use oraiswap_v3_common::{
         percentage::Percentage,
         sqrt_price::{self, calculate_sqrt_price, SqrtPrice},
         token_amount::TokenAmount,
+        MAX_SQRT_PRICE, MIN_SQRT_PRICE,
     },
     storage::{FeeTier, PoolKey},
 };
@@ -146,7 +147,7 @@ fn claim_both_fee_and_incentives() {
     let reward_token_3 = AssetInfo::Token {
         contract_addr: token_b.clone(),
     };
-    let total_reward = Some(TokenAmount::from_integer(1000000000));
+    let total_reward = TokenAmount::from_integer(1000000000);
     let reward_per_sec = TokenAmount(100);
     let start_timestamp: Option<u64> = None;
     let liquidity = Liquidity::from_integer(1000000);
@@ -262,3 +263,150 @@ fn claim_both_fee_and_incentives() {
     assert_eq!(position.fee_growth_inside_x, pool.fee_growth_global_x);
     assert_eq!(position.tokens_owed_x, TokenAmount(0));
 }
+
+// three positions of alice, two of them sharing their ticks, with fees of both tokens and
+// incentives to claim
+fn init_claim_all(app: &mut MockApp, alice: &str, bob: &str) -> (Addr, [Addr; 3], PoolKey) {
+    let initial_amount = 10u128.pow(10);
+    let dex = create_dex!(app, Percentage::new(0), alice);
+    let (token_x, token_y, token_z) =
+        create_3_tokens!(app, initial_amount, initial_amount, initial_amount, alice);
+    let fee_tier = FeeTier::new(Percentage::from_scale(6, 3), 1).unwrap();
+    add_fee_tier!(app, dex, fee_tier, alice).unwrap();
+    create_pool!(
+        app,
+        dex,
+        token_x,
+        token_y,
+        fee_tier,
+        calculate_sqrt_price(0).unwrap(),
+        0,
+        alice
+    )
+    .unwrap();
+    let pool_key = PoolKey::new(token_x.to_string(), token_y.to_string(), fee_tier).unwrap();
+    create_incentive!(
+        app,
+        dex,
+        pool_key,
+        AssetInfo::Token {
+            contract_addr: token_z.clone()
+        },
+        TokenAmount(1000000),
+        TokenAmount(100),
+        None,
+        alice
+    )
+    .unwrap();
+
+    approve!(app, token_x, dex, initial_amount, alice).unwrap();
+    approve!(app, token_y, dex, initial_amount, alice).unwrap();
+    for (lower_tick, upper_tick) in [(-20, 20), (-20, 20), (-10, 30)] {
+        create_position!(
+            app,
+            dex,
+            pool_key,
+            lower_tick,
+            upper_tick,
+            Liquidity::from_integer(100_000_000),
+            SqrtPrice::new(0),
+            SqrtPrice::max_instance(),
+            alice
+        )
+        .unwrap();
+    }
+
+    for (token, x_to_y, sqrt_price_limit) in [
+        (&token_x, true, MIN_SQRT_PRICE),
+        (&token_y, false, MAX_SQRT_PRICE),
+    ] {
+        mint!(app, token, bob, 10000, alice).unwrap();
+        approve!(app, token, dex, 10000, bob).unwrap();
+        swap!(
+            app,
+            dex,
+            pool_key,
+            x_to_y,
+            TokenAmount(10000),
+            true,
+            SqrtPrice::new(sqrt_price_limit),
+            bob
+        )
+        .unwrap();
+    }
+    app.increase_time(1000);
+
+    (dex, [token_x, token_y, token_z], pool_key)
+}
+
+#[test]
+fn test_claim_all() {
+    let balances = |app: &MockApp, tokens: &[Addr; 3], owner: &str| {
+        tokens
+            .iter()
+            .map(|token| balance_of!(app, token, owner))
+            .collect::<Vec<_>>()
+    };
+
+    // claiming the positions one by one
+    let (mut app, accounts) = MockApp::new(&[
+        ("alice", &coins(100_000_000_000, FEE_DENOM)),
+        ("bob", &coins(100_000_000_000, FEE_DENOM)),
+    ]);
+    let alice = &accounts[0];
+    let bob = &accounts[1];
+    let (dex, tokens, _) = init_claim_all(&mut app, alice, bob);
+    let before = balances(&app, &tokens, alice);
+    for index in 0..3 {
+        claim_fee!(app, dex, index, alice).unwrap();
+    }
+    let expected_fees = balances(&app, &tokens, alice)[..2]
+        .iter()
+        .zip(before)
+        .map(|(after, before)| after - before)
+        .collect::<Vec<_>>();
+    assert!(expected_fees.iter().all(|amount| *amount > 0));
+
+    // claiming all of them at once gives the same fees
+    let (mut app, accounts) = MockApp::new(&[
+        ("alice", &coins(100_000_000_000, FEE_DENOM)),
+        ("bob", &coins(100_000_000_000, FEE_DENOM)),
+    ]);
+    let alice = &accounts[0];
+    let bob = &accounts[1];
+    let (dex, tokens, pool_key) = init_claim_all(&mut app, alice, bob);
+    let incentives_fund_manager = app.get_incentives_fund_manager(dex.as_str()).unwrap();
+    let before = balances(&app, &tokens, alice);
+    let before_fund_manager = balance_of!(app, tokens[2], incentives_fund_manager);
+
+    // positions of another pool are left out
+    let other_pool_key = PoolKey::new(
+        tokens[0].to_string(),
+        tokens[2].to_string(),
+        pool_key.fee_tier,
+    )
+    .unwrap();
+    claim_all!(app, dex, None, Some(other_pool_key), alice).unwrap();
+    assert_eq!(balances(&app, &tokens, alice), before);
+
+    claim_all!(app, dex, Some(vec![2, 0, 2, 1]), Some(pool_key), alice).unwrap();
+    let claimed = balances(&app, &tokens, alice)
+        .iter()
+        .zip(before)
+        .map(|(after, before)| after - before)
+        .collect::<Vec<_>>();
+    assert_eq!(claimed[..2], expected_fees);
+    assert!(claimed[2] > 0);
+    assert_eq!(
+        before_fund_manager - balance_of!(app, tokens[2], incentives_fund_manager),
+        claimed[2]
+    );
+    for index in 0..3 {
+        let position = get_position!(app, dex, index, alice).unwrap();
+        assert_eq!(position.tokens_owed_x, TokenAmount(0));
+        assert_eq!(position.tokens_owed_y, TokenAmount(0));
+    }
+
+    let error = claim_all!(app, dex, Some(vec![3]), None, alice).unwrap_err();
+    assert!(error.root_cause().to_string().contains("not found"));
+}
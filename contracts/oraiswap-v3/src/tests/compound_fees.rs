@@ -0,0 +1,190 @@
+use cosmwasm_std::coins;
+use decimal::{Decimal, Factories};
+use oraiswap_v3_common::{
+    error::ContractError,
+    math::{
+        liquidity::Liquidity, percentage::Percentage, sqrt_price::calculate_sqrt_price,
+        sqrt_price::SqrtPrice, token_amount::TokenAmount, MAX_SQRT_PRICE, MIN_SQRT_PRICE,
+    },
+    storage::{FeeTier, PoolKey},
+};
+
+use crate::tests::helper::{macros::*, MockApp, FEE_DENOM};
+
+#[test]
+fn test_compound_fees() {
+    let (mut app, accounts) = MockApp::new(&[
+        ("alice", &coins(100_000_000_000, FEE_DENOM)),
+        ("bob", &coins(100_000_000_000, FEE_DENOM)),
+    ]);
+    let alice = &accounts[0];
+    let bob = &accounts[1];
+    let initial_amount = 10u128.pow(10);
+    let dex = create_dex!(app, Percentage::new(0), alice);
+    let (token_x, token_y) = create_tokens!(app, initial_amount, initial_amount, alice);
+
+    let fee_tier = FeeTier::new(Percentage::from_scale(1, 2), 1).unwrap();
+    add_fee_tier!(app, dex, fee_tier, alice).unwrap();
+    create_pool!(
+        app,
+        dex,
+        token_x,
+        token_y,
+        fee_tier,
+        calculate_sqrt_price(0).unwrap(),
+        0,
+        alice
+    )
+    .unwrap();
+    let pool_key = PoolKey::new(token_x.to_string(), token_y.to_string(), fee_tier).unwrap();
+    approve!(app, token_x, dex, initial_amount, alice).unwrap();
+    approve!(app, token_y, dex, initial_amount, alice).unwrap();
+    create_position!(
+        app,
+        dex,
+        pool_key,
+        -100,
+        100,
+        Liquidity::from_integer(100_000_000),
+        SqrtPrice::new(MIN_SQRT_PRICE),
+        SqrtPrice::new(MAX_SQRT_PRICE),
+        alice
+    )
+    .unwrap();
+
+    // fees are collected in token x only, half of them has to be swapped
+    mint!(app, token_x, bob, 100000, alice).unwrap();
+    approve!(app, token_x, dex, 100000, bob).unwrap();
+    swap!(
+        app,
+        dex,
+        pool_key,
+        true,
+        TokenAmount(100000),
+        true,
+        SqrtPrice::new(MIN_SQRT_PRICE),
+        bob
+    )
+    .unwrap();
+
+    // the internal swap pays the pool fee
+    let error = compound_fees!(
+        app,
+        dex,
+        0,
+        Percentage::new(0),
+        SqrtPrice::new(MIN_SQRT_PRICE),
+        SqrtPrice::new(MAX_SQRT_PRICE),
+        alice
+    )
+    .unwrap_err();
+    assert!(error
+        .root_cause()
+        .to_string()
+        .contains(&ContractError::AmountUnderMinimumAmountOut.to_string()));
+
+    // the price is out of the limits of the caller
+    let pool = get_pool!(app, dex, token_x, token_y, fee_tier).unwrap();
+    let error = compound_fees!(
+        app,
+        dex,
+        0,
+        Percentage::from_scale(5, 2),
+        SqrtPrice::new(MIN_SQRT_PRICE),
+        pool.sqrt_price - SqrtPrice::new(1),
+        alice
+    )
+    .unwrap_err();
+    assert!(error
+        .root_cause()
+        .to_string()
+        .contains(&ContractError::PriceLimitReached.to_string()));
+
+    // the internal swap pushes the price below the limits of the caller
+    let error = compound_fees!(
+        app,
+        dex,
+        0,
+        Percentage::from_scale(5, 2),
+        pool.sqrt_price,
+        SqrtPrice::new(MAX_SQRT_PRICE),
+        alice
+    )
+    .unwrap_err();
+    assert!(error
+        .root_cause()
+        .to_string()
+        .contains(&ContractError::PriceLimitReached.to_string()));
+
+    let position_before = get_position!(app, dex, 0, alice).unwrap();
+    let alice_balances = (
+        balance_of!(app, token_x, alice),
+        balance_of!(app, token_y, alice),
+    );
+    let dex_balances = (
+        balance_of!(app, token_x, dex),
+        balance_of!(app, token_y, dex),
+    );
+    compound_fees!(
+        app,
+        dex,
+        0,
+        Percentage::from_scale(5, 2),
+        SqrtPrice::new(MIN_SQRT_PRICE),
+        SqrtPrice::new(MAX_SQRT_PRICE),
+        alice
+    )
+    .unwrap();
+
+    // the fees become liquidity without any transfer
+    let position = get_position!(app, dex, 0, alice).unwrap();
+    assert!(position.liquidity > position_before.liquidity);
+    assert_eq!(
+        (
+            balance_of!(app, token_x, alice),
+            balance_of!(app, token_y, alice)
+        ),
+        alice_balances
+    );
+    assert_eq!(
+        (
+            balance_of!(app, token_x, dex),
+            balance_of!(app, token_y, dex)
+        ),
+        dex_balances
+    );
+    let pool = get_pool!(app, dex, token_x, token_y, fee_tier).unwrap();
+    assert_eq!(pool.liquidity, position.liquidity);
+
+    // only dust is left owed to the position
+    assert!(position.tokens_owed_x <= TokenAmount(20));
+    assert!(position.tokens_owed_y <= TokenAmount(20));
+
+    // a position without fees has nothing to compound
+    create_position!(
+        app,
+        dex,
+        pool_key,
+        -10,
+        10,
+        Liquidity::from_integer(1000),
+        SqrtPrice::new(MIN_SQRT_PRICE),
+        SqrtPrice::new(MAX_SQRT_PRICE),
+        alice
+    )
+    .unwrap();
+    let error = compound_fees!(
+        app,
+        dex,
+        1,
+        Percentage::from_scale(5, 2),
+        SqrtPrice::new(MIN_SQRT_PRICE),
+        SqrtPrice::new(MAX_SQRT_PRICE),
+        alice
+    )
+    .unwrap_err();
+    assert!(error
+        .root_cause()
+        .to_string()
+        .contains(&ContractError::AmountIsZero.to_string()));
+}
@@ -0,0 +1,246 @@
+use cosmwasm_std::coins;
+use decimal::{Decimal, Factories};
+use oraiswap_v3_common::{
+    error::ContractError,
+    math::{
+        liquidity::Liquidity,
+        percentage::Percentage,
+        sqrt_price::{calculate_sqrt_price, SqrtPrice},
+        token_amount::TokenAmount,
+    },
+    storage::{FeeTier, PoolKey},
+};
+
+use crate::tests::helper::{macros::*, MockApp, FEE_DENOM};
+
+#[test]
+fn test_decrease_liquidity() {
+    let (mut app, accounts) = MockApp::new(&[
+        ("alice", &coins(100_000_000_000, FEE_DENOM)),
+        ("bob", &coins(100_000_000_000, FEE_DENOM)),
+    ]);
+    let alice = &accounts[0];
+    let bob = &accounts[1];
+    let (dex, token_x, token_y) = init_dex_and_tokens!(app, alice);
+    init_basic_pool!(app, dex, token_x, token_y, alice);
+    init_basic_position!(app, dex, token_x, token_y, alice);
+    init_basic_swap!(app, dex, token_x, token_y, alice, bob);
+
+    let fee_tier = FeeTier::new(Percentage::from_scale(6, 3), 10).unwrap();
+
+    let position_before = get_position!(app, dex, 0, alice).unwrap();
+    let pool_before = get_pool!(app, dex, token_x, token_y, fee_tier).unwrap();
+    let user_amount_x_before = balance_of!(app, token_x, alice);
+    let user_amount_y_before = balance_of!(app, token_y, alice);
+    let dex_amount_x_before = balance_of!(app, token_x, dex);
+    let dex_amount_y_before = balance_of!(app, token_y, dex);
+
+    let liquidity_delta = Liquidity::from_integer(500000);
+    decrease_liquidity!(
+        app,
+        dex,
+        0,
+        liquidity_delta,
+        SqrtPrice::new(0),
+        SqrtPrice::max_instance(),
+        alice
+    )
+    .unwrap();
+
+    let position_after = get_position!(app, dex, 0, alice).unwrap();
+    let pool_after = get_pool!(app, dex, token_x, token_y, fee_tier).unwrap();
+    let user_amount_x_after = balance_of!(app, token_x, alice);
+    let user_amount_y_after = balance_of!(app, token_y, alice);
+    let dex_amount_x_after = balance_of!(app, token_x, dex);
+    let dex_amount_y_after = balance_of!(app, token_y, dex);
+
+    // position and nft are kept
+    assert_eq!(position_after.token_id, position_before.token_id);
+    assert_eq!(
+        position_after.liquidity,
+        position_before.liquidity - liquidity_delta
+    );
+    assert_eq!(pool_after.liquidity, pool_before.liquidity - liquidity_delta);
+    assert_eq!(app.get_all_positions(dex.as_str(), alice).unwrap().len(), 1);
+
+    // owed fees are paid together with the withdrawn liquidity
+    assert_eq!(position_after.tokens_owed_x, TokenAmount(0));
+    assert_eq!(position_after.tokens_owed_y, TokenAmount(0));
+    assert_eq!(
+        position_after.fee_growth_inside_x,
+        pool_after.fee_growth_global_x
+    );
+    let expected_fee_x = 5;
+    assert!(user_amount_x_after - user_amount_x_before > expected_fee_x);
+    assert_eq!(
+        user_amount_x_after - user_amount_x_before,
+        dex_amount_x_before - dex_amount_x_after
+    );
+    assert_eq!(
+        user_amount_y_after - user_amount_y_before,
+        dex_amount_y_before - dex_amount_y_after
+    );
+
+    // the rest of the position can still be removed
+    remove_position!(app, dex, 0, alice).unwrap();
+    assert_eq!(app.get_all_positions(dex.as_str(), alice).unwrap().len(), 0);
+}
+
+#[test]
+fn test_decrease_liquidity_keeps_incentives() {
+    let (mut app, accounts) = MockApp::new(&[("alice", &coins(100_000_000_000, FEE_DENOM))]);
+    let alice = &accounts[0];
+    let dex = create_dex!(app, Percentage::new(0), alice);
+    let (token_x, token_y, token_z) = create_3_tokens!(app, 500, 500, 36000, alice);
+
+    let fee_tier = FeeTier::new(Percentage::new(0), 1).unwrap();
+    add_fee_tier!(app, dex, fee_tier, alice).unwrap();
+    let init_tick = 0;
+    let init_sqrt_price = calculate_sqrt_price(init_tick).unwrap();
+    create_pool!(
+        app,
+        dex,
+        token_x,
+        token_y,
+        fee_tier,
+        init_sqrt_price,
+        init_tick,
+        alice
+    )
+    .unwrap();
+    let pool_key = PoolKey::new(token_x.to_string(), token_y.to_string(), fee_tier).unwrap();
+
+    // create incentive
+    let reward_token = oraiswap_v3_common::asset::AssetInfo::Token {
+        contract_addr: token_z.clone(),
+    };
+    create_incentive!(
+        app,
+        dex,
+        pool_key,
+        reward_token.clone(),
+        TokenAmount(36000),
+        TokenAmount(10),
+        None,
+        alice
+    )
+    .unwrap();
+
+    approve!(app, token_x, dex, 500, alice).unwrap();
+    approve!(app, token_y, dex, 500, alice).unwrap();
+    create_position!(
+        app,
+        dex,
+        pool_key,
+        -10,
+        10,
+        Liquidity::from_integer(1000),
+        SqrtPrice::new(0),
+        SqrtPrice::max_instance(),
+        alice
+    )
+    .unwrap();
+
+    app.increase_time(10);
+
+    let reward_before = balance_of!(app, token_z, alice);
+    decrease_liquidity!(
+        app,
+        dex,
+        0,
+        Liquidity::from_integer(500),
+        SqrtPrice::new(0),
+        SqrtPrice::max_instance(),
+        alice
+    )
+    .unwrap();
+
+    // incentives are not paid, they stay pending on the position
+    assert_eq!(balance_of!(app, token_z, alice), reward_before);
+    let position = get_position!(app, dex, 0, alice).unwrap();
+    assert_eq!(position.incentives.len(), 1);
+    assert!(!position.incentives[0].pending_rewards.is_zero());
+
+    let pending = get_position_incentives!(app, dex, 0, alice).unwrap();
+    claim_incentives!(app, dex, 0, alice).unwrap();
+    assert_eq!(
+        balance_of!(app, token_z, alice) - reward_before,
+        pending[0].amount.u128()
+    );
+}
+
+#[test]
+fn test_decrease_liquidity_errors() {
+    let (mut app, accounts) = MockApp::new(&[
+        ("alice", &coins(100_000_000_000, FEE_DENOM)),
+        ("bob", &coins(100_000_000_000, FEE_DENOM)),
+    ]);
+    let alice = &accounts[0];
+    let bob = &accounts[1];
+    let (dex, token_x, token_y) = init_dex_and_tokens!(app, alice);
+    init_basic_pool!(app, dex, token_x, token_y, alice);
+    init_basic_position!(app, dex, token_x, token_y, alice);
+
+    let position = get_position!(app, dex, 0, alice).unwrap();
+
+    // zero liquidity
+    let error = decrease_liquidity!(
+        app,
+        dex,
+        0,
+        Liquidity::new(0),
+        SqrtPrice::new(0),
+        SqrtPrice::max_instance(),
+        alice
+    )
+    .unwrap_err();
+    assert!(error
+        .root_cause()
+        .to_string()
+        .contains(&ContractError::InsufficientLiquidity {}.to_string()));
+
+    // the whole liquidity must be withdrawn with RemovePosition
+    let error = decrease_liquidity!(
+        app,
+        dex,
+        0,
+        position.liquidity,
+        SqrtPrice::new(0),
+        SqrtPrice::max_instance(),
+        alice
+    )
+    .unwrap_err();
+    assert!(error
+        .root_cause()
+        .to_string()
+        .contains(&ContractError::InsufficientLiquidity {}.to_string()));
+
+    // price out of slippage limits
+    let error = decrease_liquidity!(
+        app,
+        dex,
+        0,
+        Liquidity::from_integer(1),
+        SqrtPrice::max_instance(),
+        SqrtPrice::max_instance(),
+        alice
+    )
+    .unwrap_err();
+    assert!(error
+        .root_cause()
+        .to_string()
+        .contains(&ContractError::PriceLimitReached {}.to_string()));
+
+    // not owner
+    let error = decrease_liquidity!(
+        app,
+        dex,
+        0,
+        Liquidity::from_integer(1),
+        SqrtPrice::new(0),
+        SqrtPrice::max_instance(),
+        bob
+    )
+    .unwrap_err();
+    assert!(error.root_cause().to_string().contains("not found"));
+}
@@ -0,0 +1,156 @@
+use cosmwasm_std::coins;
+use decimal::{Decimal, Factories};
+use oraiswap_v3_common::{
+    error::ContractError,
+    math::{
+        liquidity::Liquidity, percentage::Percentage, sqrt_price::SqrtPrice,
+        token_amount::TokenAmount, MAX_SQRT_PRICE, MIN_SQRT_PRICE,
+    },
+    storage::{DynamicFeeConfig, FeeTier, PoolKey},
+};
+
+use crate::tests::helper::{macros::*, MockApp, FEE_DENOM};
+
+#[test]
+fn test_dynamic_fee() {
+    let (mut app, accounts) = MockApp::new(&[
+        ("alice", &coins(100_000_000_000, FEE_DENOM)),
+        ("bob", &coins(100_000_000_000, FEE_DENOM)),
+    ]);
+    let alice = &accounts[0];
+    let bob = &accounts[1];
+    let (dex, token_x, token_y) = init_dex_and_tokens!(app, alice);
+    init_basic_pool!(app, dex, token_x, token_y, alice);
+
+    let fee_tier = FeeTier::new(Percentage::from_scale(6, 3), 10).unwrap();
+    let pool_key = PoolKey::new(token_x.to_string(), token_y.to_string(), fee_tier).unwrap();
+    let min_sqrt_price = SqrtPrice::new(MIN_SQRT_PRICE);
+    let max_sqrt_price = SqrtPrice::new(MAX_SQRT_PRICE);
+
+    approve!(app, token_x, dex, 10u128.pow(10), alice).unwrap();
+    approve!(app, token_y, dex, 10u128.pow(10), alice).unwrap();
+    create_position!(
+        app,
+        dex,
+        pool_key,
+        -1000,
+        1000,
+        Liquidity::from_integer(100000000),
+        min_sqrt_price,
+        max_sqrt_price,
+        alice
+    )
+    .unwrap();
+
+    let quote = quote!(
+        app,
+        dex,
+        pool_key,
+        true,
+        TokenAmount(1000),
+        true,
+        min_sqrt_price
+    )
+    .unwrap();
+    assert_eq!(quote.fee_rate, fee_tier.fee);
+
+    let config = DynamicFeeConfig {
+        base_fee: Percentage::from_scale(1, 3),
+        max_fee: Percentage::from_scale(1, 2),
+        window: 60,
+    };
+
+    // only the admin can set it
+    let error = update_dynamic_fee!(app, dex, pool_key, Some(config), bob).unwrap_err();
+    assert!(error
+        .root_cause()
+        .to_string()
+        .contains(&ContractError::Unauthorized {}.to_string()));
+
+    let error = update_dynamic_fee!(
+        app,
+        dex,
+        pool_key,
+        Some(DynamicFeeConfig {
+            base_fee: Percentage::from_scale(2, 2),
+            ..config
+        }),
+        alice
+    )
+    .unwrap_err();
+    assert!(error
+        .root_cause()
+        .to_string()
+        .contains(&ContractError::InvalidDynamicFee {}.to_string()));
+
+    update_dynamic_fee!(app, dex, pool_key, Some(config), alice).unwrap();
+    let quote = quote!(
+        app,
+        dex,
+        pool_key,
+        true,
+        TokenAmount(1000),
+        true,
+        min_sqrt_price
+    )
+    .unwrap();
+    assert_eq!(quote.fee_rate, config.base_fee);
+
+    // the fee grows with the distance from the tick the window started at
+    swap!(
+        app,
+        dex,
+        pool_key,
+        false,
+        TokenAmount(80000),
+        true,
+        max_sqrt_price,
+        alice
+    )
+    .unwrap();
+    let pool = get_pool!(app, dex, token_x, token_y, fee_tier).unwrap();
+    assert!(pool.current_tick_index >= 10);
+    let moves = pool.current_tick_index as u64 / 10;
+    let quote = quote!(
+        app,
+        dex,
+        pool_key,
+        true,
+        TokenAmount(1000),
+        true,
+        min_sqrt_price
+    )
+    .unwrap();
+    assert_eq!(
+        quote.fee_rate,
+        Percentage::new(config.base_fee.get() * (moves + 1))
+    );
+
+    // a new window starts from the current tick
+    app.increase_time(config.window);
+    let quote = quote!(
+        app,
+        dex,
+        pool_key,
+        true,
+        TokenAmount(1000),
+        true,
+        min_sqrt_price
+    )
+    .unwrap();
+    assert_eq!(quote.fee_rate, config.base_fee);
+
+    // back to the fee of the fee tier
+    update_dynamic_fee!(app, dex, pool_key, None, alice).unwrap();
+    let quote = quote!(
+        app,
+        dex,
+        pool_key,
+        true,
+        TokenAmount(1000),
+        true,
+        min_sqrt_price
+    )
+    .unwrap();
+    assert_eq!(quote.fee_rate, fee_tier.fee);
+}
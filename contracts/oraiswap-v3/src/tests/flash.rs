@@ -0,0 +1,545 @@
+use cosmwasm_schema::cw_serde;
+use cosmwasm_std::{
+    coins, from_json, to_json_binary, wasm_execute, Addr, Binary, Deps, DepsMut, Empty, Env,
+    MessageInfo, Response, StdError, StdResult, Uint128,
+};
+use cosmwasm_testing_util::ContractWrapper;
+use cw20::Cw20ExecuteMsg;
+use decimal::{Decimal, Factories};
+use oraiswap_v3_common::{
+    asset::AssetInfo,
+    error::ContractError,
+    interface::FlashReceiverHandleMsg,
+    math::{
+        fee_growth::FeeGrowth, liquidity::Liquidity, percentage::Percentage, sqrt_price::SqrtPrice,
+        token_amount::TokenAmount, MIN_SQRT_PRICE,
+    },
+    oraiswap_v3_msg::{Cw20HookMsg, ExecuteMsg},
+    storage::{FeeTier, PoolKey, PoolStatus},
+};
+
+use crate::tests::helper::{macros::*, MockApp, FEE_DENOM};
+
+#[cw_serde]
+enum BorrowerMsg {
+    Repay {
+        amount_x: Uint128,
+        amount_y: Uint128,
+    },
+    Transfer {
+        amount_x: Uint128,
+        amount_y: Uint128,
+    },
+    Reenter {
+        msg: ExecuteMsg,
+        amount_x: Uint128,
+        amount_y: Uint128,
+    },
+}
+
+fn borrower_instantiate(
+    _deps: DepsMut,
+    _env: Env,
+    _info: MessageInfo,
+    _msg: Empty,
+) -> StdResult<Response> {
+    Ok(Response::default())
+}
+
+fn borrower_execute(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    msg: FlashReceiverHandleMsg,
+) -> Result<Response, ContractError> {
+    let FlashReceiverHandleMsg::FlashCallback(callback) = msg;
+    let mut msgs = vec![];
+    let (amount_x, amount_y) = match from_json(&callback.msg)? {
+        BorrowerMsg::Repay { amount_x, amount_y } => (amount_x, amount_y),
+        BorrowerMsg::Transfer { amount_x, amount_y } => {
+            // sent without `FlashRepay`, this does not count as a repayment
+            for (denom, amount) in [
+                (&callback.pool_key.token_x, amount_x),
+                (&callback.pool_key.token_y, amount_y),
+            ] {
+                if !amount.is_zero() {
+                    AssetInfo::from_denom(deps.api, denom).transfer(
+                        &mut msgs,
+                        info.sender.to_string(),
+                        amount,
+                    )?;
+                }
+            }
+            return Ok(Response::new().add_messages(msgs));
+        }
+        BorrowerMsg::Reenter {
+            msg,
+            amount_x,
+            amount_y,
+        } => {
+            msgs.push(wasm_execute(info.sender.clone(), &msg, vec![])?.into());
+            (amount_x, amount_y)
+        }
+    };
+    for (denom, amount) in [
+        (&callback.pool_key.token_x, amount_x),
+        (&callback.pool_key.token_y, amount_y),
+    ] {
+        if !amount.is_zero() {
+            msgs.push(
+                wasm_execute(
+                    denom,
+                    &Cw20ExecuteMsg::Send {
+                        contract: info.sender.to_string(),
+                        amount,
+                        msg: to_json_binary(&Cw20HookMsg::FlashRepay {})?,
+                    },
+                    vec![],
+                )?
+                .into(),
+            );
+        }
+    }
+    Ok(Response::new().add_messages(msgs))
+}
+
+fn borrower_query(_deps: Deps, _env: Env, _msg: Empty) -> StdResult<Binary> {
+    Err(StdError::generic_err("unsupported"))
+}
+
+fn create_borrower(app: &mut MockApp, owner: &str) -> Addr {
+    let code_id = app.upload(Box::new(ContractWrapper::new_with_empty(
+        borrower_execute,
+        borrower_instantiate,
+        borrower_query,
+    )));
+    app.instantiate(code_id, Addr::unchecked(owner), &Empty {}, &[], "borrower")
+        .unwrap()
+}
+
+fn repay(amount_x: u128, amount_y: u128) -> Binary {
+    to_json_binary(&BorrowerMsg::Repay {
+        amount_x: amount_x.into(),
+        amount_y: amount_y.into(),
+    })
+    .unwrap()
+}
+
+#[test]
+fn test_flash() {
+    let (mut app, accounts) = MockApp::new(&[("alice", &coins(100_000_000_000, FEE_DENOM))]);
+    let alice = &accounts[0];
+    let (dex, token_x, token_y) = init_dex_and_tokens!(app, alice);
+    init_basic_pool!(app, dex, token_x, token_y, alice);
+    init_basic_position!(app, dex, token_x, token_y, alice);
+
+    let fee_tier = FeeTier::new(Percentage::from_scale(6, 3), 10).unwrap();
+    let pool_key = PoolKey::new(token_x.to_string(), token_y.to_string(), fee_tier).unwrap();
+
+    let borrower = create_borrower(&mut app, alice);
+    let borrower_raw = &borrower.to_string();
+    mint!(app, token_x, borrower_raw, 10, alice).unwrap();
+    mint!(app, token_y, borrower_raw, 10, alice).unwrap();
+
+    let pool_before = get_pool!(app, dex, token_x, token_y, fee_tier).unwrap();
+    let dex_x_before = balance_of!(app, token_x, dex);
+    let dex_y_before = balance_of!(app, token_y, dex);
+
+    // 0.6% fee rounded up
+    let (fee_x, fee_y) = (3, 6);
+    flash!(
+        app,
+        dex,
+        pool_key,
+        TokenAmount(400),
+        TokenAmount(900),
+        borrower,
+        repay(400 + fee_x, 900 + fee_y),
+        alice
+    )
+    .unwrap();
+
+    assert_eq!(balance_of!(app, token_x, dex), dex_x_before + fee_x);
+    assert_eq!(balance_of!(app, token_y, dex), dex_y_before + fee_y);
+    assert_eq!(balance_of!(app, token_x, borrower), 10 - fee_x);
+    assert_eq!(balance_of!(app, token_y, borrower), 10 - fee_y);
+
+    // the fees are shared between the liquidity providers and the protocol
+    let pool_after = get_pool!(app, dex, token_x, token_y, fee_tier).unwrap();
+    assert_eq!(
+        pool_after.fee_protocol_token_x,
+        pool_before.fee_protocol_token_x + TokenAmount(1)
+    );
+    assert_eq!(
+        pool_after.fee_protocol_token_y,
+        pool_before.fee_protocol_token_y + TokenAmount(1)
+    );
+    assert_eq!(
+        pool_after.fee_growth_global_x,
+        pool_before.fee_growth_global_x.unchecked_add(
+            FeeGrowth::from_fee(pool_after.liquidity, TokenAmount(fee_x - 1)).unwrap()
+        )
+    );
+    assert_eq!(
+        pool_after.fee_growth_global_y,
+        pool_before.fee_growth_global_y.unchecked_add(
+            FeeGrowth::from_fee(pool_after.liquidity, TokenAmount(fee_y - 1)).unwrap()
+        )
+    );
+
+    // the contract is unlocked again
+    let user_x_before = balance_of!(app, token_x, alice);
+    claim_fee!(app, dex, 0, alice).unwrap();
+    assert!(balance_of!(app, token_x, alice) > user_x_before);
+}
+
+#[test]
+fn test_flash_across_initialized_tick() {
+    let (mut app, accounts) = MockApp::new(&[("alice", &coins(100_000_000_000, FEE_DENOM))]);
+    let alice = &accounts[0];
+    let (dex, token_x, token_y) = init_dex_and_tokens!(app, alice);
+    init_basic_pool!(app, dex, token_x, token_y, alice);
+    init_basic_position!(app, dex, token_x, token_y, alice);
+
+    let fee_tier = FeeTier::new(Percentage::from_scale(6, 3), 10).unwrap();
+    let pool_key = PoolKey::new(token_x.to_string(), token_y.to_string(), fee_tier).unwrap();
+
+    // a wider position holds the tokens past the upper tick of the basic one
+    approve!(app, token_x, dex, 10u128.pow(10), alice).unwrap();
+    approve!(app, token_y, dex, 10u128.pow(10), alice).unwrap();
+    create_position!(
+        app,
+        dex,
+        pool_key,
+        -100,
+        100,
+        Liquidity::from_integer(1000000),
+        SqrtPrice::new(0),
+        SqrtPrice::max_instance(),
+        alice
+    )
+    .unwrap();
+
+    let borrower = create_borrower(&mut app, alice);
+    let borrower_raw = &borrower.to_string();
+    mint!(app, token_x, borrower_raw, 20, alice).unwrap();
+
+    // paying out 2000 of token x crosses the upper tick of the basic position
+    app.increase_time(100);
+    let dex_x_before = balance_of!(app, token_x, dex);
+    flash!(
+        app,
+        dex,
+        pool_key,
+        TokenAmount(2000),
+        TokenAmount(0),
+        borrower,
+        repay(2012, 0),
+        alice
+    )
+    .unwrap();
+    assert_eq!(balance_of!(app, token_x, dex), dex_x_before + 12);
+}
+
+#[test]
+fn test_flash_not_repaid() {
+    let (mut app, accounts) = MockApp::new(&[("alice", &coins(100_000_000_000, FEE_DENOM))]);
+    let alice = &accounts[0];
+    let (dex, token_x, token_y) = init_dex_and_tokens!(app, alice);
+    init_basic_pool!(app, dex, token_x, token_y, alice);
+    init_basic_position!(app, dex, token_x, token_y, alice);
+
+    let fee_tier = FeeTier::new(Percentage::from_scale(6, 3), 10).unwrap();
+    let pool_key = PoolKey::new(token_x.to_string(), token_y.to_string(), fee_tier).unwrap();
+
+    let borrower = create_borrower(&mut app, alice);
+    let borrower_raw = &borrower.to_string();
+    mint!(app, token_x, borrower_raw, 10, alice).unwrap();
+
+    let dex_x_before = balance_of!(app, token_x, dex);
+
+    // fee is missing
+    let error = flash!(
+        app,
+        dex,
+        pool_key,
+        TokenAmount(400),
+        TokenAmount(0),
+        borrower,
+        repay(400, 0),
+        alice
+    )
+    .unwrap_err();
+    assert!(error
+        .root_cause()
+        .to_string()
+        .contains(&ContractError::FlashLoanNotRepaid {}.to_string()));
+
+    // tokens sent back without `FlashRepay` are not a repayment
+    let error = flash!(
+        app,
+        dex,
+        pool_key,
+        TokenAmount(400),
+        TokenAmount(0),
+        borrower,
+        to_json_binary(&BorrowerMsg::Transfer {
+            amount_x: Uint128::new(403),
+            amount_y: Uint128::zero(),
+        })
+        .unwrap(),
+        alice
+    )
+    .unwrap_err();
+    assert!(error
+        .root_cause()
+        .to_string()
+        .contains(&ContractError::FlashLoanNotRepaid {}.to_string()));
+
+    // nothing is repaid
+    let error = flash!(
+        app,
+        dex,
+        pool_key,
+        TokenAmount(400),
+        TokenAmount(0),
+        borrower,
+        repay(0, 0),
+        alice
+    )
+    .unwrap_err();
+    assert!(error
+        .root_cause()
+        .to_string()
+        .contains(&ContractError::FlashLoanNotRepaid {}.to_string()));
+
+    assert_eq!(balance_of!(app, token_x, dex), dex_x_before);
+    assert_eq!(balance_of!(app, token_x, borrower), 10);
+}
+
+#[test]
+fn test_flash_cannot_reenter() {
+    let (mut app, accounts) = MockApp::new(&[("alice", &coins(100_000_000_000, FEE_DENOM))]);
+    let alice = &accounts[0];
+    let (dex, token_x, token_y) = init_dex_and_tokens!(app, alice);
+    init_basic_pool!(app, dex, token_x, token_y, alice);
+    init_basic_position!(app, dex, token_x, token_y, alice);
+
+    let fee_tier = FeeTier::new(Percentage::from_scale(6, 3), 10).unwrap();
+    let pool_key = PoolKey::new(token_x.to_string(), token_y.to_string(), fee_tier).unwrap();
+
+    let borrower = create_borrower(&mut app, alice);
+
+    // the borrowed pool is locked until the loan is repaid
+    let msg = to_json_binary(&BorrowerMsg::Reenter {
+        msg: ExecuteMsg::Swap {
+            pool_key: pool_key.clone(),
+            x_to_y: true,
+            amount: TokenAmount(100),
+            by_amount_in: true,
+            sqrt_price_limit: SqrtPrice::new(MIN_SQRT_PRICE),
+        },
+        amount_x: Uint128::new(101),
+        amount_y: Uint128::zero(),
+    })
+    .unwrap();
+    let error = flash!(
+        app,
+        dex,
+        pool_key,
+        TokenAmount(100),
+        TokenAmount(0),
+        borrower,
+        msg,
+        alice
+    )
+    .unwrap_err();
+    assert!(error
+        .root_cause()
+        .to_string()
+        .contains(&ContractError::FlashLoanInProgress {}.to_string()));
+}
+
+#[test]
+fn test_flash_other_pool_usable() {
+    let (mut app, accounts) = MockApp::new(&[("alice", &coins(100_000_000_000, FEE_DENOM))]);
+    let alice = &accounts[0];
+    let (dex, token_x, token_y) = init_dex_and_tokens!(app, alice);
+    init_basic_pool!(app, dex, token_x, token_y, alice);
+    init_basic_position!(app, dex, token_x, token_y, alice);
+
+    let fee_tier = FeeTier::new(Percentage::from_scale(6, 3), 10).unwrap();
+    let pool_key = PoolKey::new(token_x.to_string(), token_y.to_string(), fee_tier).unwrap();
+
+    // another pool of the same tokens
+    let other_fee_tier = FeeTier::new(Percentage::from_scale(1, 2), 10).unwrap();
+    let other_pool_key =
+        PoolKey::new(token_x.to_string(), token_y.to_string(), other_fee_tier).unwrap();
+    add_fee_tier!(app, dex, other_fee_tier, alice).unwrap();
+    create_pool!(
+        app,
+        dex,
+        token_x,
+        token_y,
+        other_fee_tier,
+        SqrtPrice::from_integer(1),
+        0,
+        alice
+    )
+    .unwrap();
+    create_position!(
+        app,
+        dex,
+        other_pool_key,
+        -100,
+        100,
+        Liquidity::from_integer(1000000),
+        SqrtPrice::new(0),
+        SqrtPrice::max_instance(),
+        alice
+    )
+    .unwrap();
+
+    let borrower = create_borrower(&mut app, alice);
+    let borrower_raw = &borrower.to_string();
+    mint!(app, token_x, borrower_raw, 10, alice).unwrap();
+    approve!(app, token_x, dex, 100, borrower_raw).unwrap();
+
+    // the borrowed tokens are swapped in the other pool while the loan is pending
+    let other_pool_before = get_pool!(app, dex, token_x, token_y, other_fee_tier).unwrap();
+    let dex_x_before = balance_of!(app, token_x, dex);
+    let msg = to_json_binary(&BorrowerMsg::Reenter {
+        msg: ExecuteMsg::Swap {
+            pool_key: other_pool_key.clone(),
+            x_to_y: true,
+            amount: TokenAmount(100),
+            by_amount_in: true,
+            sqrt_price_limit: SqrtPrice::new(MIN_SQRT_PRICE),
+        },
+        amount_x: Uint128::new(101),
+        amount_y: Uint128::zero(),
+    })
+    .unwrap();
+
+    // the swap does not count as a repayment
+    let error = flash!(
+        app,
+        dex,
+        pool_key,
+        TokenAmount(100),
+        TokenAmount(0),
+        borrower,
+        msg,
+        alice
+    )
+    .unwrap_err();
+    assert!(error.root_cause().to_string().contains("Cannot Sub"));
+
+    mint!(app, token_x, borrower_raw, 100, alice).unwrap();
+    flash!(
+        app,
+        dex,
+        pool_key,
+        TokenAmount(100),
+        TokenAmount(0),
+        borrower,
+        msg,
+        alice
+    )
+    .unwrap();
+
+    let other_pool_after = get_pool!(app, dex, token_x, token_y, other_fee_tier).unwrap();
+    assert!(other_pool_after.sqrt_price < other_pool_before.sqrt_price);
+    assert_eq!(balance_of!(app, token_x, dex), dex_x_before + 100 + 1);
+    assert_eq!(balance_of!(app, token_x, borrower), 10 + 100 - 100 - 1);
+    assert!(balance_of!(app, token_y, borrower) > 0);
+}
+
+#[test]
+fn test_flash_errors() {
+    let (mut app, accounts) = MockApp::new(&[("alice", &coins(100_000_000_000, FEE_DENOM))]);
+    let alice = &accounts[0];
+    let (dex, token_x, token_y) = init_dex_and_tokens!(app, alice);
+    init_basic_pool!(app, dex, token_x, token_y, alice);
+
+    let fee_tier = FeeTier::new(Percentage::from_scale(6, 3), 10).unwrap();
+    let pool_key = PoolKey::new(token_x.to_string(), token_y.to_string(), fee_tier).unwrap();
+
+    let borrower = create_borrower(&mut app, alice);
+
+    // no liquidity to earn the fees
+    let error = flash!(
+        app,
+        dex,
+        pool_key,
+        TokenAmount(1),
+        TokenAmount(0),
+        borrower,
+        repay(2, 0),
+        alice
+    )
+    .unwrap_err();
+    assert!(error
+        .root_cause()
+        .to_string()
+        .contains(&ContractError::InsufficientLiquidity {}.to_string()));
+
+    init_basic_position!(app, dex, token_x, token_y, alice);
+
+    // zero amounts
+    let error = flash!(
+        app,
+        dex,
+        pool_key,
+        TokenAmount(0),
+        TokenAmount(0),
+        borrower,
+        repay(0, 0),
+        alice
+    )
+    .unwrap_err();
+    assert!(error
+        .root_cause()
+        .to_string()
+        .contains(&ContractError::AmountIsZero {}.to_string()));
+
+    // paused pool
+    app.update_pool_status(alice, dex.as_str(), &pool_key, Some(PoolStatus::Paused))
+        .unwrap();
+    let error = flash!(
+        app,
+        dex,
+        pool_key,
+        TokenAmount(1),
+        TokenAmount(0),
+        borrower,
+        repay(2, 0),
+        alice
+    )
+    .unwrap_err();
+    assert!(error
+        .root_cause()
+        .to_string()
+        .contains(&ContractError::PoolPaused {}.to_string()));
+
+    // more than the liquidity of the pool holds, even though the contract holds it
+    app.update_pool_status(alice, dex.as_str(), &pool_key, Some(PoolStatus::Opening))
+        .unwrap();
+    let dex_raw = &dex.to_string();
+    mint!(app, token_x, dex_raw, 1_000_000, alice).unwrap();
+    let error = flash!(
+        app,
+        dex,
+        pool_key,
+        TokenAmount(1000),
+        TokenAmount(0),
+        borrower,
+        repay(1010, 0),
+        alice
+    )
+    .unwrap_err();
+    assert!(error
+        .root_cause()
+        .to_string()
+        .contains(&ContractError::InsufficientLiquidity {}.to_string()));
+}
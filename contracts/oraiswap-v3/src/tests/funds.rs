@@ -0,0 +1,307 @@
+use cosmwasm_std::{coin, coins, Addr};
+use decimal::{Decimal, Factories};
+use oraiswap_v3_common::{
+    error::ContractError,
+    interface::SwapHop,
+    math::{
+        liquidity::Liquidity, percentage::Percentage, sqrt_price::calculate_sqrt_price,
+        sqrt_price::SqrtPrice, token_amount::TokenAmount, MAX_SQRT_PRICE, MIN_SQRT_PRICE,
+    },
+    oraiswap_v3_msg::ExecuteMsg,
+    storage::{FeeTier, PoolKey},
+};
+
+use crate::tests::helper::{macros::*, MockApp, FEE_DENOM};
+
+const NATIVE_DENOM: &str = "usdt";
+
+fn native_balance(app: &MockApp, address: &str) -> u128 {
+    app.query_balance(Addr::unchecked(address), NATIVE_DENOM.to_string())
+        .unwrap()
+        .u128()
+}
+
+fn create_position_msg(pool_key: &PoolKey, lower_tick: i32, upper_tick: i32) -> ExecuteMsg {
+    ExecuteMsg::CreatePosition {
+        pool_key: pool_key.clone(),
+        lower_tick,
+        upper_tick,
+        liquidity_delta: Liquidity::from_integer(1_000_000_000),
+        slippage_limit_lower: SqrtPrice::new(MIN_SQRT_PRICE),
+        slippage_limit_upper: SqrtPrice::new(MAX_SQRT_PRICE),
+        limit_order: None,
+    }
+}
+
+// pool between the native token and a cw20 token with liquidity around the current tick
+fn init_mixed_pool(app: &mut MockApp, alice: &str, token: &Addr) -> (Addr, PoolKey) {
+    let dex = create_dex!(app, Percentage::from_scale(1, 2), alice);
+    let fee_tier = FeeTier::new(Percentage::from_scale(6, 3), 10).unwrap();
+    add_fee_tier!(app, dex, fee_tier, alice).unwrap();
+    create_pool!(
+        app,
+        dex,
+        token,
+        NATIVE_DENOM.to_string(),
+        fee_tier,
+        calculate_sqrt_price(0).unwrap(),
+        0,
+        alice
+    )
+    .unwrap();
+    approve!(app, token, dex, 10u128.pow(10), alice).unwrap();
+
+    let pool_key = PoolKey::new(token.to_string(), NATIVE_DENOM.to_string(), fee_tier).unwrap();
+    app.execute(
+        Addr::unchecked(alice),
+        dex.clone(),
+        &create_position_msg(&pool_key, -1000, 1000),
+        &coins(10u128.pow(9), NATIVE_DENOM),
+    )
+    .unwrap();
+
+    (dex, pool_key)
+}
+
+#[test]
+fn test_create_position_native_funds() {
+    let (mut app, accounts) = MockApp::new(&[(
+        "alice",
+        &[
+            coin(100_000_000_000, FEE_DENOM),
+            coin(100_000_000_000, NATIVE_DENOM),
+        ],
+    )]);
+    let alice = &accounts[0];
+    let token = app.create_token(alice, "tokenx", 10u128.pow(10));
+    let (dex, pool_key) = init_mixed_pool(&mut app, alice, &token);
+    let native_is_x = pool_key.token_x == NATIVE_DENOM;
+
+    // the excess of the native token is refunded
+    let dex_native = native_balance(&app, dex.as_str());
+    let alice_native = native_balance(&app, alice);
+    assert!(dex_native > 0);
+    assert_eq!(alice_native, 100_000_000_000 - dex_native);
+
+    // no native funds
+    let error = app
+        .execute(
+            Addr::unchecked(alice),
+            dex.clone(),
+            &create_position_msg(&pool_key, -100, 100),
+            &[],
+        )
+        .unwrap_err();
+    assert!(error
+        .root_cause()
+        .to_string()
+        .contains("require minimum amount"));
+
+    // denom that is not part of the pool
+    let error = app
+        .execute(
+            Addr::unchecked(alice),
+            dex.clone(),
+            &create_position_msg(&pool_key, -100, 100),
+            &[coin(1, FEE_DENOM), coin(10u128.pow(9), NATIVE_DENOM)],
+        )
+        .unwrap_err();
+    assert!(error.root_cause().to_string().contains(
+        &ContractError::UnexpectedFunds {
+            denom: FEE_DENOM.to_string()
+        }
+        .to_string()
+    ));
+
+    // only the cw20 token is needed, the native funds are refunded in full
+    let (lower_tick, upper_tick) = if native_is_x {
+        (-200, -100)
+    } else {
+        (100, 200)
+    };
+    let token_before = balance_of!(app, token, alice);
+    app.execute(
+        Addr::unchecked(alice),
+        dex.clone(),
+        &create_position_msg(&pool_key, lower_tick, upper_tick),
+        &coins(1000, NATIVE_DENOM),
+    )
+    .unwrap();
+    assert!(balance_of!(app, token, alice) < token_before);
+    assert_eq!(native_balance(&app, alice), alice_native);
+    assert_eq!(native_balance(&app, dex.as_str()), dex_native);
+}
+
+#[test]
+fn test_swap_native_funds() {
+    let (mut app, accounts) = MockApp::new(&[(
+        "alice",
+        &[
+            coin(100_000_000_000, FEE_DENOM),
+            coin(100_000_000_000, NATIVE_DENOM),
+        ],
+    )]);
+    let alice = &accounts[0];
+    let token = app.create_token(alice, "tokenx", 10u128.pow(10));
+    let (dex, pool_key) = init_mixed_pool(&mut app, alice, &token);
+    let native_is_x = pool_key.token_x == NATIVE_DENOM;
+
+    let swap_msg = |x_to_y: bool| ExecuteMsg::Swap {
+        pool_key: pool_key.clone(),
+        x_to_y,
+        amount: TokenAmount(1000),
+        by_amount_in: true,
+        sqrt_price_limit: if x_to_y {
+            SqrtPrice::new(MIN_SQRT_PRICE)
+        } else {
+            SqrtPrice::new(MAX_SQRT_PRICE)
+        },
+    };
+
+    // native in, the excess is refunded
+    let dex_native = native_balance(&app, dex.as_str());
+    let alice_native = native_balance(&app, alice);
+    let token_before = balance_of!(app, token, alice);
+    app.execute(
+        Addr::unchecked(alice),
+        dex.clone(),
+        &swap_msg(native_is_x),
+        &coins(1500, NATIVE_DENOM),
+    )
+    .unwrap();
+    assert_eq!(native_balance(&app, alice), alice_native - 1000);
+    assert_eq!(native_balance(&app, dex.as_str()), dex_native + 1000);
+    assert!(balance_of!(app, token, alice) > token_before);
+
+    let error = app
+        .execute(
+            Addr::unchecked(alice),
+            dex.clone(),
+            &swap_msg(native_is_x),
+            &coins(999, NATIVE_DENOM),
+        )
+        .unwrap_err();
+    assert!(error
+        .root_cause()
+        .to_string()
+        .contains("require minimum amount"));
+
+    // cw20 in, native coins are not taken
+    let error = app
+        .execute(
+            Addr::unchecked(alice),
+            dex.clone(),
+            &swap_msg(!native_is_x),
+            &coins(1000, NATIVE_DENOM),
+        )
+        .unwrap_err();
+    assert!(error.root_cause().to_string().contains(
+        &ContractError::UnexpectedFunds {
+            denom: NATIVE_DENOM.to_string()
+        }
+        .to_string()
+    ));
+
+    let alice_native = native_balance(&app, alice);
+    app.execute(
+        Addr::unchecked(alice),
+        dex.clone(),
+        &swap_msg(!native_is_x),
+        &[],
+    )
+    .unwrap();
+    assert!(native_balance(&app, alice) > alice_native);
+}
+
+#[test]
+fn test_swap_route_native_funds() {
+    let (mut app, accounts) = MockApp::new(&[(
+        "alice",
+        &[
+            coin(100_000_000_000, FEE_DENOM),
+            coin(100_000_000_000, NATIVE_DENOM),
+        ],
+    )]);
+    let alice = &accounts[0];
+    let (token_a, token_b) = create_tokens!(app, 10u128.pow(10), alice);
+    let (dex, pool_key) = init_mixed_pool(&mut app, alice, &token_a);
+
+    let fee_tier = pool_key.fee_tier;
+    create_pool!(
+        app,
+        dex,
+        token_a,
+        token_b,
+        fee_tier,
+        calculate_sqrt_price(0).unwrap(),
+        0,
+        alice
+    )
+    .unwrap();
+    approve!(app, token_b, dex, 10u128.pow(10), alice).unwrap();
+    let pool_key_ab = PoolKey::new(token_a.to_string(), token_b.to_string(), fee_tier).unwrap();
+    create_position!(
+        app,
+        dex,
+        pool_key_ab,
+        -1000,
+        1000,
+        Liquidity::from_integer(1_000_000_000),
+        SqrtPrice::new(MIN_SQRT_PRICE),
+        SqrtPrice::new(MAX_SQRT_PRICE),
+        alice
+    )
+    .unwrap();
+
+    // native -> token a -> token b
+    let swaps = vec![
+        SwapHop {
+            pool_key: pool_key.clone(),
+            x_to_y: pool_key.token_x == NATIVE_DENOM,
+        },
+        SwapHop {
+            pool_key: pool_key_ab.clone(),
+            x_to_y: pool_key_ab.token_x == token_a.as_str(),
+        },
+    ];
+    let msg = ExecuteMsg::SwapRoute {
+        amount_in: TokenAmount(1000),
+        expected_amount_out: TokenAmount(900),
+        slippage: Percentage::from_scale(1, 1),
+        swaps,
+    };
+
+    let dex_native = native_balance(&app, dex.as_str());
+    let alice_native = native_balance(&app, alice);
+    let alice_a = balance_of!(app, token_a, alice);
+    let alice_b = balance_of!(app, token_b, alice);
+
+    let error = app
+        .execute(
+            Addr::unchecked(alice),
+            dex.clone(),
+            &msg,
+            &[coin(1, FEE_DENOM), coin(1000, NATIVE_DENOM)],
+        )
+        .unwrap_err();
+    assert!(error.root_cause().to_string().contains(
+        &ContractError::UnexpectedFunds {
+            denom: FEE_DENOM.to_string()
+        }
+        .to_string()
+    ));
+
+    app.execute(
+        Addr::unchecked(alice),
+        dex.clone(),
+        &msg,
+        &coins(1200, NATIVE_DENOM),
+    )
+    .unwrap();
+
+    // only the first token is taken and the intermediate token never leaves the contract
+    assert_eq!(native_balance(&app, alice), alice_native - 1000);
+    assert_eq!(native_balance(&app, dex.as_str()), dex_native + 1000);
+    assert_eq!(balance_of!(app, token_a, alice), alice_a);
+    assert!(balance_of!(app, token_b, alice) > alice_b);
+}
@@ -300,7 +300,8 @@ fn test_query_all_positions() {
                 tokens_owed_y: TokenAmount(0),
                 approvals: vec![],
                 token_id: 1,
-                incentives: vec![]
+                incentives: vec![],
+                limit_order: None
             },
             Position {
                 pool_key: PoolKey {
@@ -321,7 +322,8 @@ fn test_query_all_positions() {
                 tokens_owed_y: TokenAmount(0),
                 token_id: 2,
                 approvals: vec![],
-                incentives: vec![]
+                incentives: vec![],
+                limit_order: None
             }
         ]
     )
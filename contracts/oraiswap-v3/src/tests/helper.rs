@@ -1,17 +1,20 @@
-use cosmwasm_std::{Addr, Binary, Coin, Event, StdResult, Uint64};
+use cosmwasm_std::{to_json_binary, Addr, Binary, Coin, Event, StdResult, Uint64};
 use cosmwasm_testing_util::{ExecuteResponse, MockResult};
 
 use cosmwasm_testing_util::ContractWrapper;
 use derive_more::{Deref, DerefMut};
 use oraiswap_v3_common::asset::{Asset, AssetInfo};
-use oraiswap_v3_common::interface::{PoolWithPoolKey, QuoteResult, SwapHop};
+use oraiswap_v3_common::interface::{
+    ObserveResponse, PoolIncentive, PoolWithPoolKey, PositionPendingIncentives, PositionWithIndex,
+    QuoteResult, SnapshotCumulativesInsideResponse, SwapHop,
+};
 use oraiswap_v3_common::math::liquidity::Liquidity;
 use oraiswap_v3_common::math::percentage::Percentage;
 use oraiswap_v3_common::math::sqrt_price::SqrtPrice;
 use oraiswap_v3_common::math::token_amount::TokenAmount;
 use oraiswap_v3_common::oraiswap_v3_msg;
 use oraiswap_v3_common::storage::{
-    FeeTier, LiquidityTick, Pool, PoolKey, PoolStatus, Position, Tick,
+    DynamicFeeConfig, FeeTier, LiquidityTick, Pool, PoolKey, PoolStatus, Position, Tick,
 };
 
 use crate::state::MAX_LIMIT;
@@ -46,7 +49,8 @@ impl MockApp {
                     crate::contract::execute,
                     crate::contract::instantiate,
                     crate::contract::query,
-                ),
+                )
+                .with_reply_empty(crate::contract::reply),
             ));
             incentives_id = app.upload(Box::new(ContractWrapper::new_with_empty(
                 incentives_fund_manager::contract::execute,
@@ -239,6 +243,7 @@ impl MockApp {
                 liquidity_delta,
                 slippage_limit_lower,
                 slippage_limit_upper,
+                limit_order: None,
             },
             &[],
         )
@@ -262,6 +267,48 @@ impl MockApp {
         )
     }
 
+    #[allow(clippy::too_many_arguments)]
+    pub fn create_limit_order(
+        &mut self,
+        sender: &str,
+        dex: &str,
+        pool_key: &PoolKey,
+        lower_tick: i32,
+        upper_tick: i32,
+        liquidity_delta: Liquidity,
+        slippage_limit_lower: SqrtPrice,
+        slippage_limit_upper: SqrtPrice,
+    ) -> MockResult<ExecuteResponse> {
+        self.execute(
+            Addr::unchecked(sender),
+            Addr::unchecked(dex),
+            &oraiswap_v3_msg::ExecuteMsg::CreatePosition {
+                pool_key: pool_key.clone(),
+                lower_tick,
+                upper_tick,
+                liquidity_delta,
+                slippage_limit_lower,
+                slippage_limit_upper,
+                limit_order: Some(true),
+            },
+            &[],
+        )
+    }
+
+    pub fn claim_filled_order(
+        &mut self,
+        sender: &str,
+        dex: &str,
+        index: u32,
+    ) -> MockResult<ExecuteResponse> {
+        self.execute(
+            Addr::unchecked(sender),
+            Addr::unchecked(dex),
+            &oraiswap_v3_msg::ExecuteMsg::ClaimFilledOrder { index },
+            &[],
+        )
+    }
+
     pub fn remove_position(
         &mut self,
         sender: &str,
@@ -276,6 +323,68 @@ impl MockApp {
         )
     }
 
+    pub fn remove_position_by_token_id(
+        &mut self,
+        sender: &str,
+        dex: &str,
+        token_id: u64,
+        recipient: Option<&str>,
+    ) -> MockResult<ExecuteResponse> {
+        self.execute(
+            Addr::unchecked(sender),
+            Addr::unchecked(dex),
+            &oraiswap_v3_msg::ExecuteMsg::RemovePositionByTokenId {
+                token_id,
+                recipient: recipient.map(Addr::unchecked),
+            },
+            &[],
+        )
+    }
+
+    pub fn increase_liquidity(
+        &mut self,
+        sender: &str,
+        dex: &str,
+        index: u32,
+        liquidity_delta: Liquidity,
+        slippage_limit_lower: SqrtPrice,
+        slippage_limit_upper: SqrtPrice,
+    ) -> MockResult<ExecuteResponse> {
+        self.execute(
+            Addr::unchecked(sender),
+            Addr::unchecked(dex),
+            &oraiswap_v3_msg::ExecuteMsg::IncreaseLiquidity {
+                index,
+                liquidity_delta,
+                slippage_limit_lower,
+                slippage_limit_upper,
+            },
+            &[],
+        )
+    }
+
+    pub fn decrease_liquidity(
+        &mut self,
+        sender: &str,
+        dex: &str,
+        index: u32,
+        liquidity_delta: Liquidity,
+        slippage_limit_lower: SqrtPrice,
+        slippage_limit_upper: SqrtPrice,
+    ) -> MockResult<ExecuteResponse> {
+        self.execute(
+            Addr::unchecked(sender),
+            Addr::unchecked(dex),
+            &oraiswap_v3_msg::ExecuteMsg::DecreaseLiquidity {
+                index,
+                liquidity_delta,
+                slippage_limit_lower,
+                slippage_limit_upper,
+            },
+            &[],
+        )
+    }
+
     pub fn swap_route(
         &mut self,
         sender: &str,
@@ -298,6 +407,71 @@ impl MockApp {
         )
     }
 
+    pub fn swap_route_exact_out(
+        &mut self,
+        sender: &str,
+        dex: &str,
+        amount_out: TokenAmount,
+        max_amount_in: TokenAmount,
+        swaps: Vec<SwapHop>,
+    ) -> MockResult<ExecuteResponse> {
+        self.execute(
+            Addr::unchecked(sender),
+            Addr::unchecked(dex),
+            &oraiswap_v3_msg::ExecuteMsg::SwapRouteExactOut {
+                amount_out,
+                max_amount_in,
+                swaps,
+            },
+            &[],
+        )
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    pub fn flash(
+        &mut self,
+        sender: &str,
+        dex: &str,
+        pool_key: &PoolKey,
+        amount_x: TokenAmount,
+        amount_y: TokenAmount,
+        recipient: &str,
+        msg: Binary,
+    ) -> MockResult<ExecuteResponse> {
+        self.execute(
+            Addr::unchecked(sender),
+            Addr::unchecked(dex),
+            &oraiswap_v3_msg::ExecuteMsg::Flash {
+                pool_key: pool_key.clone(),
+                amount_x,
+                amount_y,
+                recipient: Addr::unchecked(recipient),
+                msg,
+            },
+            &[],
+        )
+    }
+
+    pub fn send_with_hook(
+        &mut self,
+        sender: &str,
+        token: &str,
+        dex: &str,
+        amount: u128,
+        hook: &oraiswap_v3_msg::Cw20HookMsg,
+    ) -> MockResult<ExecuteResponse> {
+        self.execute(
+            Addr::unchecked(sender),
+            Addr::unchecked(token),
+            &cw20::Cw20ExecuteMsg::Send {
+                contract: dex.to_string(),
+                amount: amount.into(),
+                msg: to_json_binary(hook).unwrap(),
+            },
+            &[],
+        )
+    }
+
     pub fn swap(
         &mut self,
         sender: &str,
@@ -336,6 +510,24 @@ impl MockApp {
         )
     }
 
+    pub fn claim_fee_by_token_id(
+        &mut self,
+        sender: &str,
+        dex: &str,
+        token_id: u64,
+        recipient: Option<&str>,
+    ) -> MockResult<ExecuteResponse> {
+        self.execute(
+            Addr::unchecked(sender),
+            Addr::unchecked(dex),
+            &oraiswap_v3_msg::ExecuteMsg::ClaimFeeByTokenId {
+                token_id,
+                recipient: recipient.map(Addr::unchecked),
+            },
+            &[],
+        )
+    }
+
     pub fn claim_incentives(
         &mut self,
         sender: &str,
@@ -350,6 +542,71 @@ impl MockApp {
         )
     }
 
+    #[allow(clippy::too_many_arguments)]
+    pub fn compound_fees(
+        &mut self,
+        sender: &str,
+        dex: &str,
+        index: u32,
+        max_slippage: Percentage,
+        slippage_limit_lower: SqrtPrice,
+        slippage_limit_upper: SqrtPrice,
+    ) -> MockResult<ExecuteResponse> {
+        self.execute(
+            Addr::unchecked(sender),
+            Addr::unchecked(dex),
+            &oraiswap_v3_msg::ExecuteMsg::CompoundFees {
+                index,
+                max_slippage,
+                slippage_limit_lower,
+                slippage_limit_upper,
+            },
+            &[],
+        )
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    pub fn rebalance(
+        &mut self,
+        sender: &str,
+        dex: &str,
+        index: u32,
+        new_lower_tick: i32,
+        new_upper_tick: i32,
+        slippage: Percentage,
+        slippage_limit_lower: SqrtPrice,
+        slippage_limit_upper: SqrtPrice,
+    ) -> MockResult<ExecuteResponse> {
+        self.execute(
+            Addr::unchecked(sender),
+            Addr::unchecked(dex),
+            &oraiswap_v3_msg::ExecuteMsg::Rebalance {
+                index,
+                new_lower_tick,
+                new_upper_tick,
+                slippage,
+                slippage_limit_lower,
+                slippage_limit_upper,
+            },
+            &[],
+        )
+    }
+
+    pub fn claim_all(
+        &mut self,
+        sender: &str,
+        dex: &str,
+        indexes: Option<Vec<u32>>,
+        pool_key: Option<PoolKey>,
+    ) -> MockResult<ExecuteResponse> {
+        self.execute(
+            Addr::unchecked(sender),
+            Addr::unchecked(dex),
+            &oraiswap_v3_msg::ExecuteMsg::ClaimAll { indexes, pool_key },
+            &[],
+        )
+    }
+
     pub fn quote_route(
         &mut self,
         dex: &str,
@@ -362,6 +619,18 @@ impl MockApp {
         )
     }
 
+    pub fn quote_route_exact_out(
+        &mut self,
+        dex: &str,
+        amount_out: TokenAmount,
+        swaps: Vec<SwapHop>,
+    ) -> StdResult<TokenAmount> {
+        self.query(
+            Addr::unchecked(dex),
+            &oraiswap_v3_msg::QueryMsg::QuoteRouteExactOut { amount_out, swaps },
+        )
+    }
+
     pub fn quote(
         &mut self,
         dex: &str,
@@ -437,6 +706,13 @@ impl MockApp {
         )
     }
 
+    pub fn get_position_by_token_id(&self, dex: &str, token_id: u64) -> StdResult<Position> {
+        self.query(
+            Addr::unchecked(dex),
+            &oraiswap_v3_msg::QueryMsg::PositionByTokenId { token_id },
+        )
+    }
+
     pub fn get_position_incentives(
         &self,
         dex: &str,
@@ -452,6 +728,36 @@ impl MockApp {
         )
     }
 
+    pub fn get_pool_incentives(
+        &self,
+        dex: &str,
+        pool_key: &PoolKey,
+    ) -> StdResult<Vec<PoolIncentive>> {
+        self.query(
+            Addr::unchecked(dex),
+            &oraiswap_v3_msg::QueryMsg::PoolIncentives {
+                pool_key: pool_key.clone(),
+            },
+        )
+    }
+
+    pub fn get_pending_incentives(
+        &self,
+        dex: &str,
+        owner: &str,
+        start_after: Option<u32>,
+        limit: Option<u32>,
+    ) -> StdResult<Vec<PositionPendingIncentives>> {
+        self.query(
+            Addr::unchecked(dex),
+            &oraiswap_v3_msg::QueryMsg::PendingIncentives {
+                owner: Addr::unchecked(owner),
+                start_after,
+                limit,
+            },
+        )
+    }
+
     pub fn get_all_positions(&self, dex: &str, owner_id: &str) -> StdResult<Vec<Position>> {
         self.query(
             Addr::unchecked(dex),
@@ -463,6 +769,23 @@ impl MockApp {
         )
     }
 
+    pub fn get_limit_orders(
+        &self,
+        dex: &str,
+        owner: &str,
+        start_after: Option<u32>,
+        limit: Option<u32>,
+    ) -> StdResult<Vec<PositionWithIndex>> {
+        self.query(
+            Addr::unchecked(dex),
+            &oraiswap_v3_msg::QueryMsg::LimitOrders {
+                owner: Addr::unchecked(owner),
+                start_after,
+                limit,
+            },
+        )
+    }
+
     pub fn fee_tier_exist(&self, dex: &str, fee_tier: FeeTier) -> StdResult<bool> {
         self.query(
             Addr::unchecked(dex),
@@ -495,29 +818,125 @@ impl MockApp {
         )
     }
 
+    #[allow(clippy::too_many_arguments)]
     pub fn create_incentive(
         &mut self,
         sender: &str,
         dex: &str,
         pool_key: &PoolKey,
         reward_token: AssetInfo,
-        total_reward: Option<TokenAmount>,
+        total_reward: TokenAmount,
         reward_per_sec: TokenAmount,
         start_timestamp: Option<u64>,
+    ) -> MockResult<ExecuteResponse> {
+        match reward_token {
+            // cw20 rewards are deposited through the hook so no allowance is needed
+            AssetInfo::Token { contract_addr } => self.send_with_hook(
+                sender,
+                contract_addr.as_str(),
+                dex,
+                total_reward.get(),
+                &oraiswap_v3_msg::Cw20HookMsg::CreateIncentive {
+                    pool_key: pool_key.clone(),
+                    reward_per_sec,
+                    start_timestamp,
+                    end_timestamp: None,
+                },
+            ),
+            AssetInfo::NativeToken { denom } => self.execute(
+                Addr::unchecked(sender),
+                Addr::unchecked(dex),
+                &oraiswap_v3_msg::ExecuteMsg::CreateIncentive {
+                    pool_key: pool_key.clone(),
+                    reward_token: AssetInfo::NativeToken {
+                        denom: denom.clone(),
+                    },
+                    total_reward,
+                    reward_per_sec,
+                    start_timestamp,
+                    end_timestamp: None,
+                },
+                &[Coin::new(total_reward.get(), denom)],
+            ),
+        }
+    }
+
+    pub fn top_up_incentive(
+        &mut self,
+        sender: &str,
+        dex: &str,
+        pool_key: &PoolKey,
+        incentive_id: u64,
+        reward_token: AssetInfo,
+        amount: TokenAmount,
+    ) -> MockResult<ExecuteResponse> {
+        match reward_token {
+            AssetInfo::Token { contract_addr } => self.send_with_hook(
+                sender,
+                contract_addr.as_str(),
+                dex,
+                amount.get(),
+                &oraiswap_v3_msg::Cw20HookMsg::TopUpIncentive {
+                    pool_key: pool_key.clone(),
+                    incentive_id,
+                },
+            ),
+            AssetInfo::NativeToken { denom } => self.execute(
+                Addr::unchecked(sender),
+                Addr::unchecked(dex),
+                &oraiswap_v3_msg::ExecuteMsg::TopUpIncentive {
+                    pool_key: pool_key.clone(),
+                    incentive_id,
+                    amount,
+                },
+                &[Coin::new(amount.get(), denom)],
+            ),
+        }
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    pub fn update_incentive(
+        &mut self,
+        sender: &str,
+        dex: &str,
+        pool_key: &PoolKey,
+        incentive_id: u64,
+        remaining_reward: Option<TokenAmount>,
+        start_timestamp: Option<u64>,
+        reward_per_sec: Option<TokenAmount>,
     ) -> MockResult<ExecuteResponse> {
         self.execute(
             Addr::unchecked(sender),
             Addr::unchecked(dex),
-            &oraiswap_v3_msg::ExecuteMsg::CreateIncentive {
+            &oraiswap_v3_msg::ExecuteMsg::UpdateIncentive {
                 pool_key: pool_key.clone(),
-                reward_token,
-                total_reward,
-                reward_per_sec,
+                incentive_id,
+                remaining_reward,
                 start_timestamp,
+                reward_per_sec,
             },
             &[],
         )
     }
+
+    pub fn retire_incentive(
+        &mut self,
+        sender: &str,
+        dex: &str,
+        pool_key: &PoolKey,
+        incentive_id: u64,
+    ) -> MockResult<ExecuteResponse> {
+        self.execute(
+            Addr::unchecked(sender),
+            Addr::unchecked(dex),
+            &oraiswap_v3_msg::ExecuteMsg::RetireIncentive {
+                pool_key: pool_key.clone(),
+                incentive_id,
+            },
+            &[],
+        )
+    }
+
     pub fn query_all_positions(
         &self,
         dex: &str,
@@ -568,6 +987,74 @@ impl MockApp {
             &[],
         )
     }
+
+    pub fn increase_observation_cardinality_next(
+        &mut self,
+        sender: &str,
+        dex: &str,
+        pool_key: &PoolKey,
+        observation_cardinality_next: u16,
+    ) -> MockResult<ExecuteResponse> {
+        self.execute(
+            Addr::unchecked(sender),
+            Addr::unchecked(dex),
+            &oraiswap_v3_msg::ExecuteMsg::IncreaseObservationCardinalityNext {
+                pool_key: pool_key.clone(),
+                observation_cardinality_next,
+            },
+            &[],
+        )
+    }
+
+    pub fn update_dynamic_fee(
+        &mut self,
+        sender: &str,
+        dex: &str,
+        pool_key: &PoolKey,
+        dynamic_fee: Option<DynamicFeeConfig>,
+    ) -> MockResult<ExecuteResponse> {
+        self.execute(
+            Addr::unchecked(sender),
+            Addr::unchecked(dex),
+            &oraiswap_v3_msg::ExecuteMsg::UpdateDynamicFee {
+                pool_key: pool_key.clone(),
+                dynamic_fee,
+            },
+            &[],
+        )
+    }
+
+    pub fn snapshot_cumulatives_inside(
+        &self,
+        dex: &str,
+        pool_key: &PoolKey,
+        lower_tick: i32,
+        upper_tick: i32,
+    ) -> StdResult<SnapshotCumulativesInsideResponse> {
+        self.query(
+            Addr::unchecked(dex),
+            &oraiswap_v3_msg::QueryMsg::SnapshotCumulativesInside {
+                pool_key: pool_key.clone(),
+                lower_tick,
+                upper_tick,
+            },
+        )
+    }
+
+    pub fn observe(
+        &self,
+        dex: &str,
+        pool_key: &PoolKey,
+        seconds_agos: Vec<u64>,
+    ) -> StdResult<ObserveResponse> {
+        self.query(
+            Addr::unchecked(dex),
+            &oraiswap_v3_msg::QueryMsg::Observe {
+                pool_key: pool_key.clone(),
+                seconds_agos,
+            },
+        )
+    }
 }
 
 pub fn extract_amount(events: &[Event], key: &str) -> Option<TokenAmount> {
@@ -710,6 +1197,42 @@ pub mod macros {
     }
     pub(crate) use create_incentive;
 
+    macro_rules! top_up_incentive {
+        ($app:ident, $dex_address:expr, $pool_key:expr, $incentive_id:expr, $reward_token:expr, $amount:expr, $caller:tt) => {{
+            $app.top_up_incentive(
+                $caller,
+                $dex_address.as_str(),
+                &$pool_key,
+                $incentive_id,
+                $reward_token,
+                $amount,
+            )
+        }};
+    }
+    pub(crate) use top_up_incentive;
+
+    macro_rules! update_incentive {
+        ($app:ident, $dex_address:expr, $pool_key:expr, $incentive_id:expr, $remaining_reward:expr, $start_timestamp:expr, $reward_per_sec:expr, $caller:tt) => {{
+            $app.update_incentive(
+                $caller,
+                $dex_address.as_str(),
+                &$pool_key,
+                $incentive_id,
+                $remaining_reward,
+                $start_timestamp,
+                $reward_per_sec,
+            )
+        }};
+    }
+    pub(crate) use update_incentive;
+
+    macro_rules! retire_incentive {
+        ($app:ident, $dex_address:expr, $pool_key:expr, $incentive_id:expr, $caller:tt) => {{
+            $app.retire_incentive($caller, $dex_address.as_str(), &$pool_key, $incentive_id)
+        }};
+    }
+    pub(crate) use retire_incentive;
+
     macro_rules! create_position {
         ($app:ident, $dex_address:expr, $pool_key:expr, $lower_tick:expr, $upper_tick:expr, $liquidity_delta:expr, $slippage_limit_lower:expr, $slippage_limit_upper:expr, $caller:tt) => {{
             $app.create_position(
@@ -726,6 +1249,39 @@ pub mod macros {
     }
     pub(crate) use create_position;
 
+    macro_rules! create_limit_order {
+        ($app:ident, $dex_address:expr, $pool_key:expr, $lower_tick:expr, $upper_tick:expr, $liquidity_delta:expr, $slippage_limit_lower:expr, $slippage_limit_upper:expr, $caller:tt) => {{
+            $app.create_limit_order(
+                $caller,
+                $dex_address.as_str(),
+                &$pool_key,
+                $lower_tick,
+                $upper_tick,
+                $liquidity_delta,
+                $slippage_limit_lower,
+                $slippage_limit_upper,
+            )
+        }};
+    }
+    pub(crate) use create_limit_order;
+
+    macro_rules! claim_filled_order {
+        ($app:ident, $dex_address:expr, $index:expr, $caller:tt) => {{
+            $app.claim_filled_order($caller, $dex_address.as_str(), $index)
+        }};
+    }
+    pub(crate) use claim_filled_order;
+
+    macro_rules! get_limit_orders {
+        ($app:ident, $dex_address:expr, $owner:tt) => {{
+            $app.get_limit_orders($dex_address.as_str(), $owner, None, None)
+        }};
+        ($app:ident, $dex_address:expr, $owner:tt, $start_after:expr, $limit:expr) => {{
+            $app.get_limit_orders($dex_address.as_str(), $owner, $start_after, $limit)
+        }};
+    }
+    pub(crate) use get_limit_orders;
+
     macro_rules! remove_position {
         ($app:ident,  $dex_address:expr, $index:expr, $caller:tt) => {{
             $app.remove_position($caller, $dex_address.as_str(), $index)
@@ -733,6 +1289,41 @@ pub mod macros {
     }
     pub(crate) use remove_position;
 
+    macro_rules! remove_position_by_token_id {
+        ($app:ident, $dex_address:expr, $token_id:expr, $recipient:expr, $caller:tt) => {{
+            $app.remove_position_by_token_id($caller, $dex_address.as_str(), $token_id, $recipient)
+        }};
+    }
+    pub(crate) use remove_position_by_token_id;
+
+    macro_rules! increase_liquidity {
+        ($app:ident, $dex_address:expr, $index:expr, $liquidity_delta:expr, $slippage_limit_lower:expr, $slippage_limit_upper:expr, $caller:tt) => {{
+            $app.increase_liquidity(
+                $caller,
+                $dex_address.as_str(),
+                $index,
+                $liquidity_delta,
+                $slippage_limit_lower,
+                $slippage_limit_upper,
+            )
+        }};
+    }
+    pub(crate) use increase_liquidity;
+
+    macro_rules! decrease_liquidity {
+        ($app:ident, $dex_address:expr, $index:expr, $liquidity_delta:expr, $slippage_limit_lower:expr, $slippage_limit_upper:expr, $caller:tt) => {{
+            $app.decrease_liquidity(
+                $caller,
+                $dex_address.as_str(),
+                $index,
+                $liquidity_delta,
+                $slippage_limit_lower,
+                $slippage_limit_upper,
+            )
+        }};
+    }
+    pub(crate) use decrease_liquidity;
+
     macro_rules! get_pool {
         ($app:ident, $dex_address:expr, $token_0:expr, $token_1:expr, $fee_tier:expr) => {{
             $app.get_pool(
@@ -752,6 +1343,13 @@ pub mod macros {
     }
     pub(crate) use get_position;
 
+    macro_rules! get_position_by_token_id {
+        ($app:ident, $dex_address:expr, $token_id:expr) => {{
+            $app.get_position_by_token_id($dex_address.as_str(), $token_id)
+        }};
+    }
+    pub(crate) use get_position_by_token_id;
+
     macro_rules! get_position_incentives {
         ($app:ident, $dex_address:expr, $index:expr, $owner:tt) => {{
             $app.get_position_incentives($dex_address.as_str(), $owner, $index)
@@ -759,6 +1357,20 @@ pub mod macros {
     }
     pub(crate) use get_position_incentives;
 
+    macro_rules! get_pool_incentives {
+        ($app:ident, $dex_address:expr, $pool_key:expr) => {{
+            $app.get_pool_incentives($dex_address.as_str(), &$pool_key)
+        }};
+    }
+    pub(crate) use get_pool_incentives;
+
+    macro_rules! get_pending_incentives {
+        ($app:ident, $dex_address:expr, $start_after:expr, $limit:expr, $owner:tt) => {{
+            $app.get_pending_incentives($dex_address.as_str(), $owner, $start_after, $limit)
+        }};
+    }
+    pub(crate) use get_pending_incentives;
+
     macro_rules! get_tick {
         ($app:ident, $dex_address:expr, $key:expr, $index:expr) => {{
             $app.get_tick($dex_address.as_str(), &$key, $index)
@@ -825,6 +1437,34 @@ pub mod macros {
     }
     pub(crate) use swap;
 
+    macro_rules! flash {
+        ($app:ident, $dex_address:expr, $pool_key:expr, $amount_x:expr, $amount_y:expr, $recipient:expr, $msg:expr, $caller:tt) => {{
+            $app.flash(
+                $caller,
+                $dex_address.as_str(),
+                &$pool_key,
+                $amount_x,
+                $amount_y,
+                $recipient.as_str(),
+                $msg,
+            )
+        }};
+    }
+    pub(crate) use flash;
+
+    macro_rules! send_with_hook {
+        ($app:ident, $token_address:expr, $dex_address:expr, $amount:expr, $hook:expr, $caller:tt) => {{
+            $app.send_with_hook(
+                $caller,
+                $token_address.as_str(),
+                $dex_address.as_str(),
+                $amount,
+                &$hook,
+            )
+        }};
+    }
+    pub(crate) use send_with_hook;
+
     macro_rules! quote_route {
         ($app:ident, $dex_address:expr, $amount_in:expr, $swaps:expr) => {{
             $app.quote_route($dex_address.as_str(), $amount_in, $swaps)
@@ -846,6 +1486,26 @@ pub mod macros {
     }
     pub(crate) use swap_route;
 
+    macro_rules! quote_route_exact_out {
+        ($app:ident, $dex_address:expr, $amount_out:expr, $swaps:expr) => {{
+            $app.quote_route_exact_out($dex_address.as_str(), $amount_out, $swaps)
+        }};
+    }
+    pub(crate) use quote_route_exact_out;
+
+    macro_rules! swap_route_exact_out {
+        ($app:ident, $dex_address:expr, $amount_out:expr, $max_amount_in:expr, $swaps:expr, $caller:tt) => {{
+            $app.swap_route_exact_out(
+                $caller,
+                $dex_address.as_str(),
+                $amount_out,
+                $max_amount_in,
+                $swaps,
+            )
+        }};
+    }
+    pub(crate) use swap_route_exact_out;
+
     macro_rules! claim_fee {
         ($app:ident, $dex_address:expr, $index:expr, $caller:tt) => {{
             $app.claim_fee($caller, $dex_address.as_str(), $index)
@@ -853,6 +1513,50 @@ pub mod macros {
     }
     pub(crate) use claim_fee;
 
+    macro_rules! claim_fee_by_token_id {
+        ($app:ident, $dex_address:expr, $token_id:expr, $recipient:expr, $caller:tt) => {{
+            $app.claim_fee_by_token_id($caller, $dex_address.as_str(), $token_id, $recipient)
+        }};
+    }
+    pub(crate) use claim_fee_by_token_id;
+
+    macro_rules! claim_all {
+        ($app:ident, $dex_address:expr, $indexes:expr, $pool_key:expr, $caller:tt) => {{
+            $app.claim_all($caller, $dex_address.as_str(), $indexes, $pool_key)
+        }};
+    }
+    pub(crate) use claim_all;
+
+    macro_rules! compound_fees {
+        ($app:ident, $dex_address:expr, $index:expr, $max_slippage:expr, $slippage_limit_lower:expr, $slippage_limit_upper:expr, $caller:tt) => {{
+            $app.compound_fees(
+                $caller,
+                $dex_address.as_str(),
+                $index,
+                $max_slippage,
+                $slippage_limit_lower,
+                $slippage_limit_upper,
+            )
+        }};
+    }
+    pub(crate) use compound_fees;
+
+    macro_rules! rebalance {
+        ($app:ident, $dex_address:expr, $index:expr, $new_lower_tick:expr, $new_upper_tick:expr, $slippage:expr, $slippage_limit_lower:expr, $slippage_limit_upper:expr, $caller:tt) => {{
+            $app.rebalance(
+                $caller,
+                $dex_address.as_str(),
+                $index,
+                $new_lower_tick,
+                $new_upper_tick,
+                $slippage,
+                $slippage_limit_lower,
+                $slippage_limit_upper,
+            )
+        }};
+    }
+    pub(crate) use rebalance;
+
     macro_rules! claim_incentives {
         ($app:ident, $dex_address:expr, $index:expr, $caller:tt) => {{
             $app.claim_incentives($caller, $dex_address.as_str(), $index)
@@ -1719,6 +2423,44 @@ pub mod macros {
         }};
     }
     pub(crate) use positions_equals;
+
+    macro_rules! increase_observation_cardinality_next {
+        ($app:ident, $dex_address:expr, $pool_key:expr, $observation_cardinality_next:expr, $caller:tt) => {{
+            $app.increase_observation_cardinality_next(
+                $caller,
+                $dex_address.as_str(),
+                &$pool_key,
+                $observation_cardinality_next,
+            )
+        }};
+    }
+    pub(crate) use increase_observation_cardinality_next;
+
+    macro_rules! update_dynamic_fee {
+        ($app:ident, $dex_address:expr, $pool_key:expr, $dynamic_fee:expr, $caller:tt) => {{
+            $app.update_dynamic_fee($caller, $dex_address.as_str(), &$pool_key, $dynamic_fee)
+        }};
+    }
+    pub(crate) use update_dynamic_fee;
+
+    macro_rules! snapshot_cumulatives_inside {
+        ($app:ident, $dex_address:expr, $pool_key:expr, $lower_tick:expr, $upper_tick:expr) => {{
+            $app.snapshot_cumulatives_inside(
+                $dex_address.as_str(),
+                &$pool_key,
+                $lower_tick,
+                $upper_tick,
+            )
+        }};
+    }
+    pub(crate) use snapshot_cumulatives_inside;
+
+    macro_rules! observe {
+        ($app:ident, $dex_address:expr, $pool_key:expr, $seconds_agos:expr) => {{
+            $app.observe($dex_address.as_str(), &$pool_key, $seconds_agos)
+        }};
+    }
+    pub(crate) use observe;
 }
 
 #[cfg(test)]
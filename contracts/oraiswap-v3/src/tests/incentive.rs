@@ -1,11 +1,20 @@
-use cosmwasm_std::{coins, Addr, Uint128};
+use cosmwasm_schema::cw_serde;
+use cosmwasm_std::{
+    coins, Addr, Binary, Deps, DepsMut, Empty, Env, MessageInfo, Response, StdError, StdResult,
+    Uint128,
+};
+use cosmwasm_testing_util::ContractWrapper;
+use cw20::Cw20ReceiveMsg;
+use cw_storage_plus::Item;
 use decimal::*;
 
+use crate::state::{MAX_INCENTIVES_PER_POOL, MIN_INCENTIVE_DURATION};
 use crate::tests::helper::{macros::*, subtract_assets, MockApp, FEE_DENOM};
 
 use oraiswap_v3_common::{
     asset::{Asset, AssetInfo},
     error::ContractError,
+    interface::IncentiveStatus,
     math::{
         fee_growth::FeeGrowth,
         liquidity::Liquidity,
@@ -14,6 +23,7 @@ use oraiswap_v3_common::{
         token_amount::TokenAmount,
         MAX_SQRT_PRICE, MIN_SQRT_PRICE,
     },
+    oraiswap_v3_msg::ExecuteMsg,
     storage::{
         incentive::{IncentiveRecord, PositionIncentives},
         FeeTier, PoolKey,
@@ -55,7 +65,7 @@ pub fn test_create_incentive() {
     let reward_token = AssetInfo::NativeToken {
         denom: "orai".to_string(),
     };
-    let total_reward = Some(TokenAmount(1000000000));
+    let total_reward = TokenAmount(1000000000);
     let reward_per_sec = TokenAmount(100);
     let start_timestamp: Option<u64> = None;
 
@@ -79,10 +89,13 @@ pub fn test_create_incentive() {
             id: 0,
             reward_per_sec,
             reward_token: reward_token.clone(),
-            remaining: total_reward.unwrap(),
+            remaining: total_reward,
             start_timestamp: pool.incentives[0].start_timestamp,
             incentive_growth_global: FeeGrowth(0),
-            last_updated: pool.incentives[0].last_updated
+            last_updated: pool.incentives[0].last_updated,
+            creator: Some(Addr::unchecked(alice)),
+            end_timestamp: None,
+            retirement: None
         }]
     );
 
@@ -107,86 +120,183 @@ pub fn test_create_incentive() {
                 id: 0,
                 reward_per_sec,
                 reward_token: reward_token.clone(),
-                remaining: total_reward.unwrap(),
+                remaining: total_reward,
                 start_timestamp: pool.incentives[0].start_timestamp,
                 incentive_growth_global: FeeGrowth(0),
-                last_updated: pool.incentives[0].last_updated
+                last_updated: pool.incentives[0].last_updated,
+                creator: Some(Addr::unchecked(alice)),
+                end_timestamp: None,
+                retirement: None
             },
             IncentiveRecord {
                 id: 1,
                 reward_per_sec,
                 reward_token: reward_token.clone(),
-                remaining: total_reward.unwrap(),
+                remaining: total_reward,
                 start_timestamp: pool.incentives[1].start_timestamp,
                 incentive_growth_global: FeeGrowth(0),
-                last_updated: pool.incentives[1].last_updated
+                last_updated: pool.incentives[1].last_updated,
+                creator: Some(Addr::unchecked(alice)),
+                end_timestamp: None,
+                retirement: None
             }
         ]
     );
 
-    // create incentive with no total reward -> fallback to max:u128
+    // anyone can create an incentive by depositing its reward
     create_incentive!(
         app,
         dex,
         pool_key,
         reward_token.clone(),
-        None,
+        total_reward,
         reward_per_sec,
         start_timestamp,
-        alice
+        bob
     )
     .unwrap();
     let pool = get_pool!(app, dex, token_x, token_y, fee_tier).unwrap();
+    assert_eq!(pool.incentives.len(), 3);
+    assert_eq!(pool.incentives[2].creator, Some(Addr::unchecked(bob)));
 
+    // the rewards are held by the incentives fund manager
+    let incentives_addr = app.get_incentives_fund_manager(dex.as_str()).unwrap();
     assert_eq!(
-        pool.incentives,
-        vec![
-            IncentiveRecord {
-                id: 0,
-                reward_per_sec,
-                reward_token: reward_token.clone(),
-                remaining: total_reward.unwrap(),
-                start_timestamp: pool.incentives[0].start_timestamp,
-                incentive_growth_global: FeeGrowth(0),
-                last_updated: pool.incentives[0].last_updated
-            },
-            IncentiveRecord {
-                id: 1,
-                reward_per_sec,
-                reward_token: reward_token.clone(),
-                remaining: total_reward.unwrap(),
-                start_timestamp: pool.incentives[1].start_timestamp,
-                incentive_growth_global: FeeGrowth(0),
-                last_updated: pool.incentives[1].last_updated
-            },
-            IncentiveRecord {
-                id: 2,
-                reward_per_sec,
-                reward_token: reward_token.clone(),
-                remaining: TokenAmount(u128::MAX),
-                start_timestamp: pool.incentives[2].start_timestamp,
-                incentive_growth_global: FeeGrowth(0),
-                last_updated: pool.incentives[2].start_timestamp
-            }
-        ]
+        app.query_balance(incentives_addr, FEE_DENOM.to_string())
+            .unwrap()
+            .u128(),
+        3 * total_reward.get()
     );
 
-    // create fail, unauthorized
+    // create fail, nothing to deposit
+    let error = create_incentive!(
+        app,
+        dex,
+        pool_key,
+        reward_token.clone(),
+        TokenAmount(0),
+        reward_per_sec,
+        start_timestamp,
+        bob
+    )
+    .unwrap_err();
+    assert!(error
+        .root_cause()
+        .to_string()
+        .contains(&ContractError::AmountIsZero.to_string()));
+
+    // create fail, nothing is emitted
     let error = create_incentive!(
         app,
         dex,
         pool_key,
         reward_token.clone(),
         total_reward,
+        TokenAmount(0),
+        start_timestamp,
+        bob
+    )
+    .unwrap_err();
+    assert!(error
+        .root_cause()
+        .to_string()
+        .contains(&ContractError::AmountIsZero.to_string()));
+
+    // create fail, the reward runs out too soon
+    let error = create_incentive!(
+        app,
+        dex,
+        pool_key,
+        reward_token.clone(),
+        TokenAmount(MIN_INCENTIVE_DURATION as u128 * 100 - 1),
         reward_per_sec,
         start_timestamp,
         bob
     )
     .unwrap_err();
+    assert!(error
+        .root_cause()
+        .to_string()
+        .contains(&ContractError::IncentiveTooShort.to_string()));
+
+    // create fail, the pool is full
+    for _ in 3..MAX_INCENTIVES_PER_POOL {
+        create_incentive!(
+            app,
+            dex,
+            pool_key,
+            reward_token.clone(),
+            total_reward,
+            reward_per_sec,
+            start_timestamp,
+            bob
+        )
+        .unwrap();
+    }
+    let error = create_incentive!(
+        app,
+        dex,
+        pool_key,
+        reward_token.clone(),
+        total_reward,
+        reward_per_sec,
+        start_timestamp,
+        bob
+    )
+    .unwrap_err();
+    assert!(error
+        .root_cause()
+        .to_string()
+        .contains(&ContractError::TooManyIncentives.to_string()));
+
+    // retired programs do not count
+    retire_incentive!(app, dex, pool_key, 2, bob).unwrap();
+    create_incentive!(
+        app,
+        dex,
+        pool_key,
+        reward_token.clone(),
+        total_reward,
+        reward_per_sec,
+        start_timestamp,
+        bob
+    )
+    .unwrap();
+    let pool = get_pool!(app, dex, token_x, token_y, fee_tier).unwrap();
+    assert_eq!(
+        pool.incentives.iter().filter(|i| !i.is_retired()).count(),
+        MAX_INCENTIVES_PER_POOL
+    );
+
+    // the admin can retire any running program, so the pool cannot be squatted
+    let error = retire_incentive!(app, dex, pool_key, 0, bob).unwrap_err();
     assert!(error
         .root_cause()
         .to_string()
         .contains(&ContractError::Unauthorized {}.to_string()));
+    retire_incentive!(app, dex, pool_key, 3, alice).unwrap();
+    let pool = get_pool!(app, dex, token_x, token_y, fee_tier).unwrap();
+    assert_eq!(
+        pool.incentives.iter().filter(|i| !i.is_retired()).count(),
+        MAX_INCENTIVES_PER_POOL - 1
+    );
+
+    // a duration above u64::MAX seconds is not truncated
+    let total_reward = 1u128 << 64;
+    mint!(app, token_x, bob, total_reward, alice).unwrap();
+    create_incentive!(
+        app,
+        dex,
+        pool_key,
+        AssetInfo::Token {
+            contract_addr: token_x.clone()
+        },
+        TokenAmount(total_reward),
+        TokenAmount(1),
+        start_timestamp,
+        bob
+    )
+    .unwrap();
 }
 
 #[test]
@@ -220,7 +330,7 @@ pub fn test_single_incentive_with_single_position() {
     let reward_token = AssetInfo::NativeToken {
         denom: "orai".to_string(),
     };
-    let total_reward = Some(TokenAmount(1000000));
+    let total_reward = TokenAmount(1000000);
     let reward_per_sec = TokenAmount(100);
     let start_timestamp: Option<u64> = None;
     create_incentive!(
@@ -262,7 +372,8 @@ pub fn test_single_incentive_with_single_position() {
         vec![PositionIncentives {
             incentive_id: 0,
             pending_rewards: TokenAmount(0),
-            incentive_growth_inside: FeeGrowth(0)
+            incentive_growth_inside: FeeGrowth(0),
+            settled: false
         }]
     );
 
@@ -326,9 +437,9 @@ pub fn test_multi_incentives_with_single_position() {
         denom: "orai".to_string(),
     };
     let reward_token_2 = AssetInfo::Token {
-        contract_addr: Addr::unchecked("usdt"),
+        contract_addr: app.create_token(alice, "usdt", 1000000000),
     };
-    let total_reward = Some(TokenAmount(1000000));
+    let total_reward = TokenAmount(1000000);
     let reward_per_sec = TokenAmount(100);
     let start_timestamp: Option<u64> = None;
     create_incentive!(
@@ -347,7 +458,7 @@ pub fn test_multi_incentives_with_single_position() {
         dex,
         pool_key,
         reward_token_2.clone(),
-        Some(TokenAmount(1000000000)),
+        TokenAmount(1000000000),
         TokenAmount(200),
         start_timestamp,
         alice
@@ -382,12 +493,14 @@ pub fn test_multi_incentives_with_single_position() {
             PositionIncentives {
                 incentive_id: 0,
                 pending_rewards: TokenAmount(0),
-                incentive_growth_inside: FeeGrowth(0)
+                incentive_growth_inside: FeeGrowth(0),
+                settled: false
             },
             PositionIncentives {
                 incentive_id: 1,
                 pending_rewards: TokenAmount(0),
-                incentive_growth_inside: FeeGrowth(0)
+                incentive_growth_inside: FeeGrowth(0),
+                settled: false
             }
         ]
     );
@@ -474,9 +587,9 @@ pub fn test_multi_incentives_with_multi_positions() {
         denom: "orai".to_string(),
     };
     let reward_token_2 = AssetInfo::Token {
-        contract_addr: Addr::unchecked("usdt"),
+        contract_addr: app.create_token(alice, "usdt", 1000000000),
     };
-    let total_reward = Some(TokenAmount(1000000));
+    let total_reward = TokenAmount(1000000);
     let reward_per_sec = TokenAmount(100);
     let start_timestamp: Option<u64> = None;
     create_incentive!(
@@ -495,7 +608,7 @@ pub fn test_multi_incentives_with_multi_positions() {
         dex,
         pool_key,
         reward_token_2.clone(),
-        Some(TokenAmount(1000000000)),
+        TokenAmount(1000000000),
         TokenAmount(200),
         start_timestamp,
         alice
@@ -634,7 +747,7 @@ pub fn test_incentive_with_position_cross_out_of_range() {
     let reward_token = AssetInfo::Token {
         contract_addr: token_z.clone(),
     };
-    let total_reward = Some(TokenAmount(1000000000));
+    let total_reward = TokenAmount(1000000000);
     let reward_per_sec = TokenAmount(100);
     let start_timestamp: Option<u64> = None;
     let liquidity = Liquidity::from_integer(1000000);
@@ -851,7 +964,7 @@ pub fn test_remove_position() {
     let reward_token = AssetInfo::Token {
         contract_addr: token_z.clone(),
     };
-    let total_reward = Some(TokenAmount(1000000000));
+    let total_reward = TokenAmount(1000000000);
     let reward_per_sec = TokenAmount(100);
     let start_timestamp: Option<u64> = None;
     let liquidity = Liquidity::from_integer(1000000);
@@ -948,7 +1061,7 @@ pub fn incentive_stress_test() {
     let reward_token = AssetInfo::Token {
         contract_addr: token_z.clone(),
     };
-    let total_reward = Some(TokenAmount(1000000000));
+    let total_reward = TokenAmount(1000000000);
     let start_timestamp: Option<u64> = None;
 
     let rps: Vec<TokenAmount> = vec![
@@ -1087,7 +1200,7 @@ pub fn test_claim_incentive_with_single_position() {
     let reward_token = AssetInfo::Token {
         contract_addr: token_z.clone(),
     };
-    let total_reward = Some(TokenAmount::from_integer(1000000000));
+    let total_reward = TokenAmount::from_integer(1000000000);
     let reward_per_sec = TokenAmount(100);
     let start_timestamp: Option<u64> = None;
     let liquidity = Liquidity::from_integer(1000000);
@@ -1186,7 +1299,7 @@ pub fn test_claim_incentive_with_multi_position() {
     let reward_token = AssetInfo::Token {
         contract_addr: token_z.clone(),
     };
-    let total_reward = Some(TokenAmount::from_integer(1000000000));
+    let total_reward = TokenAmount::from_integer(1000000000);
     let reward_per_sec = TokenAmount(100);
     let start_timestamp: Option<u64> = None;
 
@@ -1300,7 +1413,7 @@ pub fn test_update_incentive_with_tick_move_left_to_right() {
     let reward_token = AssetInfo::Token {
         contract_addr: token_z.clone(),
     };
-    let total_reward = Some(TokenAmount(1000000000));
+    let total_reward = TokenAmount(1000000000);
     let reward_per_sec = TokenAmount(100);
     let start_timestamp: Option<u64> = None;
     let liquidity = Liquidity::from_integer(1000000);
@@ -1461,7 +1574,7 @@ pub fn test_update_incentive_with_tick_move_right_to_left() {
     let reward_token = AssetInfo::Token {
         contract_addr: token_z.clone(),
     };
-    let total_reward = Some(TokenAmount(1000000000));
+    let total_reward = TokenAmount(1000000000);
     let reward_per_sec = TokenAmount(100);
     let start_timestamp: Option<u64> = None;
     let liquidity = Liquidity::from_integer(1000000);
@@ -1580,3 +1693,547 @@ pub fn test_update_incentive_with_tick_move_right_to_left() {
         }]
     );
 }
+
+#[test]
+pub fn test_incentive_managed_by_creator() {
+    let (mut app, accounts) = MockApp::new(&[
+        ("alice", &coins(100_000_000_000, FEE_DENOM)),
+        ("bob", &coins(100_000_000_000, FEE_DENOM)),
+    ]);
+    let alice = &accounts[0];
+    let bob = &accounts[1];
+    let dex = create_dex!(app, Percentage::new(0), alice);
+    let (token_x, token_y, token_z) = create_3_tokens!(app, 500, 500, 500, alice);
+    let incentives_addr = app.get_incentives_fund_manager(dex.as_str()).unwrap();
+
+    let fee_tier = FeeTier::new(Percentage::new(0), 1).unwrap();
+    add_fee_tier!(app, dex, fee_tier, alice).unwrap();
+    create_pool!(
+        app,
+        dex,
+        token_x,
+        token_y,
+        fee_tier,
+        calculate_sqrt_price(0).unwrap(),
+        0,
+        alice
+    )
+    .unwrap();
+    let pool_key = PoolKey::new(token_x.to_string(), token_y.to_string(), fee_tier).unwrap();
+
+    mint!(app, token_z, bob, 200000, alice).unwrap();
+    let reward_token = AssetInfo::Token {
+        contract_addr: token_z.clone(),
+    };
+    let msg = ExecuteMsg::CreateIncentive {
+        pool_key: pool_key.clone(),
+        reward_token: reward_token.clone(),
+        total_reward: TokenAmount(100000),
+        reward_per_sec: TokenAmount(10),
+        start_timestamp: None,
+        end_timestamp: None,
+    };
+
+    // the reward is pulled from the creator
+    let error = app
+        .execute(Addr::unchecked(bob), dex.clone(), &msg, &[])
+        .unwrap_err();
+    assert!(error.root_cause().to_string().contains("allowance"));
+
+    approve!(app, token_z, dex, 100000, bob).unwrap();
+    app.execute(Addr::unchecked(bob), dex.clone(), &msg, &[])
+        .unwrap();
+    assert_eq!(balance_of!(app, token_z, bob), 100000);
+    assert_eq!(balance_of!(app, token_z, incentives_addr), 100000);
+
+    // only the creator manages the program, the admin can only retire it
+    let error = top_up_incentive!(
+        app,
+        dex,
+        pool_key,
+        0,
+        reward_token.clone(),
+        TokenAmount(50000),
+        alice
+    )
+    .unwrap_err();
+    assert!(error
+        .root_cause()
+        .to_string()
+        .contains(&ContractError::Unauthorized {}.to_string()));
+    let error = update_incentive!(
+        app,
+        dex,
+        pool_key,
+        0,
+        Some(TokenAmount(0)),
+        None,
+        None,
+        alice
+    )
+    .unwrap_err();
+    assert!(error
+        .root_cause()
+        .to_string()
+        .contains(&ContractError::Unauthorized {}.to_string()));
+    let error = update_incentive!(app, dex, pool_key, 0, None, None, Some(TokenAmount(0)), bob)
+        .unwrap_err();
+    assert!(error
+        .root_cause()
+        .to_string()
+        .contains(&ContractError::AmountIsZero.to_string()));
+    let error = update_incentive!(app, dex, pool_key, 1, None, None, None, bob).unwrap_err();
+    assert!(error
+        .root_cause()
+        .to_string()
+        .contains(&ContractError::IncentiveNotFound.to_string()));
+
+    top_up_incentive!(
+        app,
+        dex,
+        pool_key,
+        0,
+        reward_token.clone(),
+        TokenAmount(50000),
+        bob
+    )
+    .unwrap();
+    let pool = get_pool!(app, dex, token_x, token_y, fee_tier).unwrap();
+    assert_eq!(pool.incentives[0].remaining, TokenAmount(150000));
+    assert_eq!(balance_of!(app, token_z, incentives_addr), 150000);
+
+    // rewards can not be added without a deposit
+    update_incentive!(
+        app,
+        dex,
+        pool_key,
+        0,
+        Some(TokenAmount(200000)),
+        None,
+        None,
+        bob
+    )
+    .unwrap_err();
+
+    // stopping the program refunds what is left
+    update_incentive!(app, dex, pool_key, 0, Some(TokenAmount(0)), None, None, bob).unwrap();
+    let pool = get_pool!(app, dex, token_x, token_y, fee_tier).unwrap();
+    assert_eq!(pool.incentives[0].remaining, TokenAmount(0));
+    assert_eq!(balance_of!(app, token_z, bob), 200000);
+    assert_eq!(balance_of!(app, token_z, incentives_addr), 0);
+}
+
+#[test]
+pub fn test_retire_incentive() {
+    let (mut app, accounts) = MockApp::new(&[
+        ("alice", &coins(100_000_000_000, FEE_DENOM)),
+        ("bob", &coins(100_000_000_000, FEE_DENOM)),
+        ("charlie", &coins(100_000_000_000, FEE_DENOM)),
+    ]);
+    let alice = &accounts[0];
+    let bob = &accounts[1];
+    let charlie = &accounts[2];
+    let dex = create_dex!(app, Percentage::new(0), alice);
+    let (token_x, token_y) = create_tokens!(app, 5000, 5000, alice);
+
+    let fee_tier = FeeTier::new(Percentage::new(0), 1).unwrap();
+    add_fee_tier!(app, dex, fee_tier, alice).unwrap();
+    create_pool!(
+        app,
+        dex,
+        token_x,
+        token_y,
+        fee_tier,
+        calculate_sqrt_price(0).unwrap(),
+        0,
+        alice
+    )
+    .unwrap();
+    let pool_key = PoolKey::new(token_x.to_string(), token_y.to_string(), fee_tier).unwrap();
+    approve!(app, token_x, dex, 5000, alice).unwrap();
+    approve!(app, token_y, dex, 5000, alice).unwrap();
+
+    let orai_balance = |app: &MockApp, address: &str| {
+        app.query_balance(Addr::unchecked(address), FEE_DENOM.to_string())
+            .unwrap()
+            .u128()
+    };
+
+    // bob funds a program ending in an hour
+    let total_reward = 1000000;
+    let mut msg = ExecuteMsg::CreateIncentive {
+        pool_key: pool_key.clone(),
+        reward_token: AssetInfo::NativeToken {
+            denom: FEE_DENOM.to_string(),
+        },
+        total_reward: TokenAmount(total_reward),
+        reward_per_sec: TokenAmount(100),
+        start_timestamp: None,
+        end_timestamp: Some(app.get_block_time().seconds()),
+    };
+    let error = app
+        .execute(
+            Addr::unchecked(bob),
+            dex.clone(),
+            &msg,
+            &coins(total_reward, FEE_DENOM),
+        )
+        .unwrap_err();
+    assert!(error
+        .root_cause()
+        .to_string()
+        .contains(&ContractError::InvalidEndTimestamp.to_string()));
+
+    if let ExecuteMsg::CreateIncentive { end_timestamp, .. } = &mut msg {
+        *end_timestamp = Some(app.get_block_time().seconds() + 3600);
+    }
+    app.execute(
+        Addr::unchecked(bob),
+        dex.clone(),
+        &msg,
+        &coins(total_reward, FEE_DENOM),
+    )
+    .unwrap();
+    let bob_balance = orai_balance(&app, bob);
+
+    create_position!(
+        app,
+        dex,
+        pool_key,
+        -10,
+        10,
+        Liquidity::new(1000),
+        SqrtPrice::new(0),
+        SqrtPrice::max_instance(),
+        alice
+    )
+    .unwrap();
+
+    // only the creator and the admin can retire a running program
+    app.increase_time(500);
+    let error = retire_incentive!(app, dex, pool_key, 0, charlie).unwrap_err();
+    assert!(error
+        .root_cause()
+        .to_string()
+        .contains(&ContractError::Unauthorized {}.to_string()));
+
+    // nothing is emitted after the end
+    app.increase_time(3600);
+    let pending = get_position_incentives!(app, dex, 0, alice).unwrap();
+    app.increase_time(1000);
+    assert_eq!(
+        get_position_incentives!(app, dex, 0, alice).unwrap(),
+        pending
+    );
+
+    // anyone can retire a finished program, the rest goes back to the creator
+    retire_incentive!(app, dex, pool_key, 0, charlie).unwrap();
+    assert_eq!(
+        orai_balance(&app, bob) - bob_balance,
+        total_reward - pending[0].amount.u128()
+    );
+    let error = retire_incentive!(app, dex, pool_key, 0, bob).unwrap_err();
+    assert!(error
+        .root_cause()
+        .to_string()
+        .contains(&ContractError::IncentiveRetired.to_string()));
+
+    // the record is kept until the open position settles
+    let pool = get_pool!(app, dex, token_x, token_y, fee_tier).unwrap();
+    assert!(pool.incentives[0].retirement.is_some());
+    assert_eq!(
+        get_position_incentives!(app, dex, 0, alice).unwrap(),
+        pending
+    );
+
+    // positions opened afterwards do not earn from it
+    create_position!(
+        app,
+        dex,
+        pool_key,
+        -20,
+        20,
+        Liquidity::new(1000),
+        SqrtPrice::new(0),
+        SqrtPrice::max_instance(),
+        alice
+    )
+    .unwrap();
+    assert_eq!(
+        get_position!(app, dex, 1, alice).unwrap().incentives,
+        vec![]
+    );
+
+    let alice_balance = orai_balance(&app, alice);
+    claim_incentives!(app, dex, 0, alice).unwrap();
+    assert_eq!(
+        orai_balance(&app, alice) - alice_balance,
+        pending[0].amount.u128()
+    );
+    let pool = get_pool!(app, dex, token_x, token_y, fee_tier).unwrap();
+    assert_eq!(pool.incentives, vec![]);
+    assert_eq!(
+        get_position!(app, dex, 0, alice).unwrap().incentives,
+        vec![]
+    );
+
+    // a pruned program is gone
+    let error = retire_incentive!(app, dex, pool_key, 0, bob).unwrap_err();
+    assert!(error
+        .root_cause()
+        .to_string()
+        .contains(&ContractError::IncentiveNotFound.to_string()));
+}
+
+#[test]
+pub fn test_incentive_queries() {
+    let (mut app, accounts) = MockApp::new(&[("alice", &coins(100_000_000_000, FEE_DENOM))]);
+    let alice = &accounts[0];
+    let dex = create_dex!(app, Percentage::new(0), alice);
+    let (token_x, token_y) = create_tokens!(app, 5000, 5000, alice);
+
+    let fee_tier = FeeTier::new(Percentage::new(0), 1).unwrap();
+    add_fee_tier!(app, dex, fee_tier, alice).unwrap();
+    create_pool!(
+        app,
+        dex,
+        token_x,
+        token_y,
+        fee_tier,
+        calculate_sqrt_price(0).unwrap(),
+        0,
+        alice
+    )
+    .unwrap();
+    let pool_key = PoolKey::new(token_x.to_string(), token_y.to_string(), fee_tier).unwrap();
+
+    let reward_token = AssetInfo::NativeToken {
+        denom: FEE_DENOM.to_string(),
+    };
+    let start_timestamp = app.get_block_time().seconds() + 1000;
+    for start_timestamp in [None, Some(start_timestamp)] {
+        create_incentive!(
+            app,
+            dex,
+            pool_key,
+            reward_token.clone(),
+            TokenAmount(1000000),
+            TokenAmount(100),
+            start_timestamp,
+            alice
+        )
+        .unwrap();
+    }
+
+    approve!(app, token_x, dex, 5000, alice).unwrap();
+    approve!(app, token_y, dex, 5000, alice).unwrap();
+    for (lower_tick, upper_tick) in [(-10, 10), (-20, 20), (100, 200)] {
+        create_position!(
+            app,
+            dex,
+            pool_key,
+            lower_tick,
+            upper_tick,
+            Liquidity::new(1000),
+            SqrtPrice::new(0),
+            SqrtPrice::max_instance(),
+            alice
+        )
+        .unwrap();
+    }
+
+    app.increase_time(1000);
+
+    // programs are updated to the current block time
+    let incentives = get_pool_incentives!(app, dex, pool_key).unwrap();
+    assert_eq!(
+        incentives
+            .iter()
+            .map(|incentive| incentive.status.clone())
+            .collect::<Vec<_>>(),
+        vec![IncentiveStatus::Active, IncentiveStatus::Upcoming]
+    );
+    assert_eq!(incentives[0].incentive.remaining, TokenAmount(900000));
+    assert_eq!(incentives[1].incentive.remaining, TokenAmount(1000000));
+    assert_eq!(incentives[0].incentive.reward_per_sec, TokenAmount(100));
+
+    // pending rewards match the single position query
+    let pending = get_pending_incentives!(app, dex, None, None, alice).unwrap();
+    assert_eq!(pending.len(), 3);
+    for (index, position) in pending.iter().enumerate() {
+        assert_eq!(position.index, index as u32);
+        assert_eq!(position.pool_key, pool_key);
+        assert_eq!(
+            position.incentives,
+            get_position_incentives!(app, dex, index as u32, alice).unwrap()
+        );
+    }
+    // out of range positions earn nothing
+    assert_eq!(pending[2].incentives, vec![]);
+    let total: u128 = pending
+        .iter()
+        .flat_map(|position| position.incentives.iter())
+        .map(|asset| asset.amount.u128())
+        .sum();
+    assert!(total > 0 && total <= 100000);
+
+    let page = get_pending_incentives!(app, dex, Some(0), Some(1), alice).unwrap();
+    assert_eq!(page, pending[1..2].to_vec());
+
+    // the first program runs out of rewards
+    app.increase_time(9000);
+    let incentives = get_pool_incentives!(app, dex, pool_key).unwrap();
+    assert_eq!(incentives[0].status, IncentiveStatus::Finished);
+    assert_eq!(incentives[0].incentive.remaining, TokenAmount(0));
+    assert_eq!(incentives[1].status, IncentiveStatus::Active);
+}
+
+// a reward token whose creator can turn its transfers off once a program is funded
+#[cw_serde]
+enum RewardTokenMsg {
+    Send {
+        contract: String,
+        amount: Uint128,
+        msg: Binary,
+    },
+    Transfer {
+        recipient: String,
+        amount: Uint128,
+    },
+    Block {},
+}
+
+const BLOCKED: Item<bool> = Item::new("blocked");
+
+fn reward_token_instantiate(
+    _deps: DepsMut,
+    _env: Env,
+    _info: MessageInfo,
+    _msg: Empty,
+) -> StdResult<Response> {
+    Ok(Response::default())
+}
+
+fn reward_token_execute(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    msg: RewardTokenMsg,
+) -> StdResult<Response> {
+    match msg {
+        RewardTokenMsg::Send {
+            contract,
+            amount,
+            msg,
+        } => Ok(Response::new().add_message(
+            Cw20ReceiveMsg {
+                sender: info.sender.to_string(),
+                amount,
+                msg,
+            }
+            .into_cosmos_msg(contract)?,
+        )),
+        RewardTokenMsg::Transfer { .. } => {
+            if BLOCKED.may_load(deps.storage)?.unwrap_or_default() {
+                return Err(StdError::generic_err("transfers are blocked"));
+            }
+            Ok(Response::default())
+        }
+        RewardTokenMsg::Block {} => {
+            BLOCKED.save(deps.storage, &true)?;
+            Ok(Response::default())
+        }
+    }
+}
+
+fn reward_token_query(_deps: Deps, _env: Env, _msg: Empty) -> StdResult<Binary> {
+    Err(StdError::generic_err("unsupported"))
+}
+
+#[test]
+pub fn test_incentive_reward_token_cannot_block_positions() {
+    let (mut app, accounts) = MockApp::new(&[
+        ("alice", &coins(100_000_000_000, FEE_DENOM)),
+        ("bob", &coins(100_000_000_000, FEE_DENOM)),
+    ]);
+    let alice = &accounts[0];
+    let bob = &accounts[1];
+    let dex = create_dex!(app, Percentage::new(0), alice);
+    let (token_x, token_y) = create_tokens!(app, 5000, 5000, alice);
+
+    let fee_tier = FeeTier::new(Percentage::new(0), 1).unwrap();
+    add_fee_tier!(app, dex, fee_tier, alice).unwrap();
+    create_pool!(
+        app,
+        dex,
+        token_x,
+        token_y,
+        fee_tier,
+        calculate_sqrt_price(0).unwrap(),
+        0,
+        alice
+    )
+    .unwrap();
+    let pool_key = PoolKey::new(token_x.to_string(), token_y.to_string(), fee_tier).unwrap();
+    approve!(app, token_x, dex, 5000, alice).unwrap();
+    approve!(app, token_y, dex, 5000, alice).unwrap();
+    create_position!(
+        app,
+        dex,
+        pool_key,
+        -10,
+        10,
+        Liquidity::new(1000),
+        SqrtPrice::new(0),
+        SqrtPrice::max_instance(),
+        alice
+    )
+    .unwrap();
+
+    // bob funds a program with his own token, then blocks its transfers
+    let code_id = app.upload(Box::new(ContractWrapper::new_with_empty(
+        reward_token_execute,
+        reward_token_instantiate,
+        reward_token_query,
+    )));
+    let reward_token = app
+        .instantiate(code_id, Addr::unchecked(bob), &Empty {}, &[], "reward")
+        .unwrap();
+    create_incentive!(
+        app,
+        dex,
+        pool_key,
+        AssetInfo::Token {
+            contract_addr: reward_token.clone()
+        },
+        TokenAmount(1000000),
+        TokenAmount(100),
+        None,
+        bob
+    )
+    .unwrap();
+    app.increase_time(100);
+    app.execute(
+        Addr::unchecked(bob),
+        reward_token,
+        &RewardTokenMsg::Block {},
+        &[],
+    )
+    .unwrap();
+
+    // the rewards cannot be paid, the position is still claimed and withdrawn
+    assert!(!get_position_incentives!(app, dex, 0, alice)
+        .unwrap()
+        .is_empty());
+    claim_fee!(app, dex, 0, alice).unwrap();
+    app.increase_time(100);
+    let balance_x = balance_of!(app, token_x, alice);
+    remove_position!(app, dex, 0, alice).unwrap();
+    assert!(balance_of!(app, token_x, alice) > balance_x);
+    assert!(get_all_positions!(app, dex, alice).is_empty());
+
+    // and the admin retires the program even though its refund cannot be paid either
+    retire_incentive!(app, dex, pool_key, 0, alice).unwrap();
+    let pool = get_pool!(app, dex, token_x, token_y, fee_tier).unwrap();
+    assert_eq!(pool.incentives, vec![]);
+}
@@ -0,0 +1,221 @@
+use cosmwasm_std::coins;
+use decimal::{Decimal, Factories};
+use oraiswap_v3_common::{
+    asset::AssetInfo,
+    error::ContractError,
+    math::{
+        liquidity::Liquidity,
+        percentage::Percentage,
+        sqrt_price::{calculate_sqrt_price, SqrtPrice},
+        token_amount::TokenAmount,
+    },
+    storage::{FeeTier, PoolKey},
+};
+
+use crate::tests::helper::{macros::*, MockApp, FEE_DENOM};
+
+#[test]
+fn test_increase_liquidity() {
+    let (mut app, accounts) = MockApp::new(&[
+        ("alice", &coins(100_000_000_000, FEE_DENOM)),
+        ("bob", &coins(100_000_000_000, FEE_DENOM)),
+    ]);
+    let alice = &accounts[0];
+    let bob = &accounts[1];
+    let (dex, token_x, token_y) = init_dex_and_tokens!(app, alice);
+    init_basic_pool!(app, dex, token_x, token_y, alice);
+    init_basic_position!(app, dex, token_x, token_y, alice);
+    init_basic_swap!(app, dex, token_x, token_y, alice, bob);
+
+    let fee_tier = FeeTier::new(Percentage::from_scale(6, 3), 10).unwrap();
+
+    let position_before = get_position!(app, dex, 0, alice).unwrap();
+    let pool_before = get_pool!(app, dex, token_x, token_y, fee_tier).unwrap();
+    let user_amount_x_before = balance_of!(app, token_x, alice);
+    let dex_amount_x_before = balance_of!(app, token_x, dex);
+
+    let liquidity_delta = Liquidity::from_integer(1000000);
+    increase_liquidity!(
+        app,
+        dex,
+        0,
+        liquidity_delta,
+        SqrtPrice::new(0),
+        SqrtPrice::max_instance(),
+        alice
+    )
+    .unwrap();
+
+    let position_after = get_position!(app, dex, 0, alice).unwrap();
+    let pool_after = get_pool!(app, dex, token_x, token_y, fee_tier).unwrap();
+    let user_amount_x_after = balance_of!(app, token_x, alice);
+    let dex_amount_x_after = balance_of!(app, token_x, dex);
+
+    // no new position nor token id
+    assert_eq!(position_after.token_id, position_before.token_id);
+    assert_eq!(app.get_all_positions(dex.as_str(), alice).unwrap().len(), 1);
+    assert_eq!(
+        position_after.liquidity,
+        position_before.liquidity + liquidity_delta
+    );
+    assert_eq!(pool_after.liquidity, pool_before.liquidity + liquidity_delta);
+    assert_eq!(
+        user_amount_x_before - user_amount_x_after,
+        dex_amount_x_after - dex_amount_x_before
+    );
+
+    // fees earned before the deposit are settled into the position
+    let expected_tokens_claimed = 5;
+    assert_eq!(
+        position_after.tokens_owed_x,
+        TokenAmount(expected_tokens_claimed)
+    );
+    assert_eq!(
+        position_after.fee_growth_inside_x,
+        pool_after.fee_growth_global_x
+    );
+
+    let user_amount_before_claim = balance_of!(app, token_x, alice);
+    claim_fee!(app, dex, 0, alice).unwrap();
+    let user_amount_after_claim = balance_of!(app, token_x, alice);
+    assert_eq!(
+        user_amount_after_claim - user_amount_before_claim,
+        expected_tokens_claimed
+    );
+}
+
+#[test]
+fn test_increase_liquidity_keeps_pending_incentives() {
+    let (mut app, accounts) = MockApp::new(&[("alice", &coins(100_000_000_000, FEE_DENOM))]);
+    let alice = &accounts[0];
+    let dex = create_dex!(app, Percentage::new(0), alice);
+    let (token_x, token_y, token_z) = create_3_tokens!(app, 500, 500, 36000, alice);
+
+    let fee_tier = FeeTier::new(Percentage::new(0), 1).unwrap();
+    add_fee_tier!(app, dex, fee_tier, alice).unwrap();
+    let init_tick = 0;
+    let init_sqrt_price = calculate_sqrt_price(init_tick).unwrap();
+    create_pool!(
+        app,
+        dex,
+        token_x,
+        token_y,
+        fee_tier,
+        init_sqrt_price,
+        init_tick,
+        alice
+    )
+    .unwrap();
+    let pool_key = PoolKey::new(token_x.to_string(), token_y.to_string(), fee_tier).unwrap();
+
+    let reward_token = AssetInfo::Token {
+        contract_addr: token_z.clone(),
+    };
+    create_incentive!(
+        app,
+        dex,
+        pool_key,
+        reward_token.clone(),
+        TokenAmount(36000),
+        TokenAmount(10),
+        None,
+        alice
+    )
+    .unwrap();
+
+    approve!(app, token_x, dex, 500, alice).unwrap();
+    approve!(app, token_y, dex, 500, alice).unwrap();
+    create_position!(
+        app,
+        dex,
+        pool_key,
+        -10,
+        10,
+        Liquidity::from_integer(1000),
+        SqrtPrice::new(0),
+        SqrtPrice::max_instance(),
+        alice
+    )
+    .unwrap();
+
+    app.increase_time(10);
+    let pending_before = get_position_incentives!(app, dex, 0, alice).unwrap();
+
+    increase_liquidity!(
+        app,
+        dex,
+        0,
+        Liquidity::from_integer(1000),
+        SqrtPrice::new(0),
+        SqrtPrice::max_instance(),
+        alice
+    )
+    .unwrap();
+
+    // rewards earned with the previous liquidity are not lost
+    let position = get_position!(app, dex, 0, alice).unwrap();
+    assert_eq!(
+        position.incentives[0].pending_rewards,
+        TokenAmount(pending_before[0].amount.u128())
+    );
+    let pending_after = get_position_incentives!(app, dex, 0, alice).unwrap();
+    assert_eq!(pending_after, pending_before);
+}
+
+#[test]
+fn test_increase_liquidity_errors() {
+    let (mut app, accounts) = MockApp::new(&[
+        ("alice", &coins(100_000_000_000, FEE_DENOM)),
+        ("bob", &coins(100_000_000_000, FEE_DENOM)),
+    ]);
+    let alice = &accounts[0];
+    let bob = &accounts[1];
+    let (dex, token_x, token_y) = init_dex_and_tokens!(app, alice);
+    init_basic_pool!(app, dex, token_x, token_y, alice);
+    init_basic_position!(app, dex, token_x, token_y, alice);
+
+    // zero liquidity
+    let error = increase_liquidity!(
+        app,
+        dex,
+        0,
+        Liquidity::new(0),
+        SqrtPrice::new(0),
+        SqrtPrice::max_instance(),
+        alice
+    )
+    .unwrap_err();
+    assert!(error
+        .root_cause()
+        .to_string()
+        .contains(&ContractError::InsufficientLiquidity {}.to_string()));
+
+    // price out of slippage limits
+    let error = increase_liquidity!(
+        app,
+        dex,
+        0,
+        Liquidity::from_integer(1),
+        SqrtPrice::new(0),
+        SqrtPrice::new(0),
+        alice
+    )
+    .unwrap_err();
+    assert!(error
+        .root_cause()
+        .to_string()
+        .contains(&ContractError::PriceLimitReached {}.to_string()));
+
+    // not owner
+    let error = increase_liquidity!(
+        app,
+        dex,
+        0,
+        Liquidity::from_integer(1),
+        SqrtPrice::new(0),
+        SqrtPrice::max_instance(),
+        bob
+    )
+    .unwrap_err();
+    assert!(error.root_cause().to_string().contains("not found"));
+}
@@ -0,0 +1,466 @@
+use cosmwasm_std::coins;
+use decimal::{Decimal, Factories};
+use oraiswap_v3_common::{
+    error::ContractError,
+    math::{
+        liquidity::Liquidity, percentage::Percentage, sqrt_price::SqrtPrice,
+        token_amount::TokenAmount, MAX_SQRT_PRICE, MIN_SQRT_PRICE,
+    },
+    storage::{FeeTier, LimitOrder, PoolKey},
+};
+
+use crate::state::{
+    MAX_LIMIT_ORDERS_PER_OWNER_PER_TICK, MAX_LIMIT_ORDERS_PER_TICK, MIN_LIMIT_ORDER_LIQUIDITY,
+};
+use crate::tests::helper::{macros::*, MockApp, FEE_DENOM};
+
+#[test]
+fn test_limit_order() {
+    let (mut app, accounts) = MockApp::new(&[
+        ("alice", &coins(100_000_000_000, FEE_DENOM)),
+        ("bob", &coins(100_000_000_000, FEE_DENOM)),
+    ]);
+    let alice = &accounts[0];
+    let bob = &accounts[1];
+    let (dex, token_x, token_y) = init_dex_and_tokens!(app, alice);
+    init_basic_pool!(app, dex, token_x, token_y, alice);
+
+    let fee_tier = FeeTier::new(Percentage::from_scale(6, 3), 10).unwrap();
+    let pool_key = PoolKey::new(token_x.to_string(), token_y.to_string(), fee_tier).unwrap();
+    let min_sqrt_price = SqrtPrice::new(MIN_SQRT_PRICE);
+    let max_sqrt_price = SqrtPrice::new(MAX_SQRT_PRICE);
+
+    approve!(app, token_x, dex, 10u128.pow(10), alice).unwrap();
+    approve!(app, token_y, dex, 10u128.pow(10), alice).unwrap();
+    let alice_liquidity = Liquidity::from_integer(100000000);
+    create_position!(
+        app,
+        dex,
+        pool_key,
+        -1000,
+        1000,
+        alice_liquidity,
+        min_sqrt_price,
+        max_sqrt_price,
+        alice
+    )
+    .unwrap();
+
+    mint!(app, token_x, bob, 1000, alice).unwrap();
+    approve!(app, token_x, dex, 1000, bob).unwrap();
+
+    // the range must not contain the current tick
+    let error = create_limit_order!(
+        app,
+        dex,
+        pool_key,
+        -10,
+        10,
+        Liquidity::from_integer(1000000),
+        min_sqrt_price,
+        max_sqrt_price,
+        bob
+    )
+    .unwrap_err();
+    assert!(error
+        .root_cause()
+        .to_string()
+        .contains(&ContractError::InvalidLimitOrder {}.to_string()));
+
+    // sells token x once the price goes above tick 20
+    create_limit_order!(
+        app,
+        dex,
+        pool_key,
+        10,
+        20,
+        Liquidity::from_integer(1000000),
+        min_sqrt_price,
+        max_sqrt_price,
+        bob
+    )
+    .unwrap();
+    let bob_x = balance_of!(app, token_x, bob);
+    let bob_y = balance_of!(app, token_y, bob);
+    assert!(bob_x < 1000);
+    assert_eq!(bob_y, 0);
+
+    let orders = get_limit_orders!(app, dex, bob).unwrap();
+    assert_eq!(orders.len(), 1);
+    assert_eq!(orders[0].index, 0);
+    assert_eq!(
+        orders[0].position.limit_order,
+        Some(LimitOrder {
+            tick_index: 20,
+            filled: false
+        })
+    );
+    assert!(get_limit_orders!(app, dex, alice).unwrap().is_empty());
+
+    let error = claim_filled_order!(app, dex, 0, bob).unwrap_err();
+    assert!(error
+        .root_cause()
+        .to_string()
+        .contains(&ContractError::LimitOrderNotFilled {}.to_string()));
+
+    // crossing the lower tick only puts the order in range
+    swap!(
+        app,
+        dex,
+        pool_key,
+        false,
+        TokenAmount(80000),
+        true,
+        max_sqrt_price,
+        alice
+    )
+    .unwrap();
+    let pool = get_pool!(app, dex, token_x, token_y, fee_tier).unwrap();
+    assert!(pool.current_tick_index >= 10 && pool.current_tick_index < 20);
+    assert_eq!(
+        pool.liquidity,
+        alice_liquidity + Liquidity::from_integer(1000000)
+    );
+    let orders = get_limit_orders!(app, dex, bob).unwrap();
+    assert!(!orders[0].position.limit_order.unwrap().filled);
+
+    // crossing the far tick fills the order
+    swap!(
+        app,
+        dex,
+        pool_key,
+        false,
+        TokenAmount(100000),
+        true,
+        max_sqrt_price,
+        alice
+    )
+    .unwrap();
+    let pool = get_pool!(app, dex, token_x, token_y, fee_tier).unwrap();
+    assert!(pool.current_tick_index >= 20);
+
+    let orders = get_limit_orders!(app, dex, bob).unwrap();
+    let order = &orders[0].position;
+    assert!(order.limit_order.unwrap().filled);
+    assert_eq!(order.liquidity, Liquidity::new(0));
+    assert_eq!(order.tokens_owed_x, TokenAmount(0));
+    // the sold token x plus the fees earned while in range
+    assert!(order.tokens_owed_y.get() > (1000 - bob_x));
+    let amount_y = order.tokens_owed_y;
+
+    // the ticks of the order are cleared
+    assert!(!is_tick_initialized!(app, dex, pool_key, 10));
+    assert!(!is_tick_initialized!(app, dex, pool_key, 20));
+
+    // going back through the range does not reopen the order
+    swap!(
+        app,
+        dex,
+        pool_key,
+        true,
+        TokenAmount(150000),
+        true,
+        min_sqrt_price,
+        alice
+    )
+    .unwrap();
+    let pool = get_pool!(app, dex, token_x, token_y, fee_tier).unwrap();
+    assert!(pool.current_tick_index < 10);
+    assert_eq!(pool.liquidity, alice_liquidity);
+
+    let error = claim_fee!(app, dex, 0, bob).unwrap_err();
+    assert!(error
+        .root_cause()
+        .to_string()
+        .contains(&ContractError::LimitOrderFilled {}.to_string()));
+
+    claim_filled_order!(app, dex, 0, bob).unwrap();
+    assert_eq!(balance_of!(app, token_x, bob), bob_x);
+    assert_eq!(balance_of!(app, token_y, bob), amount_y.get());
+    assert!(get_all_positions!(app, dex, bob).is_empty());
+    assert!(get_limit_orders!(app, dex, bob).unwrap().is_empty());
+
+    claim_filled_order!(app, dex, 0, bob).unwrap_err();
+}
+
+#[test]
+fn test_limit_order_remove() {
+    let (mut app, accounts) = MockApp::new(&[
+        ("alice", &coins(100_000_000_000, FEE_DENOM)),
+        ("bob", &coins(100_000_000_000, FEE_DENOM)),
+    ]);
+    let alice = &accounts[0];
+    let bob = &accounts[1];
+    let (dex, token_x, token_y) = init_dex_and_tokens!(app, alice);
+    init_basic_pool!(app, dex, token_x, token_y, alice);
+
+    let fee_tier = FeeTier::new(Percentage::from_scale(6, 3), 10).unwrap();
+    let pool_key = PoolKey::new(token_x.to_string(), token_y.to_string(), fee_tier).unwrap();
+    let min_sqrt_price = SqrtPrice::new(MIN_SQRT_PRICE);
+    let max_sqrt_price = SqrtPrice::new(MAX_SQRT_PRICE);
+
+    approve!(app, token_x, dex, 10u128.pow(10), alice).unwrap();
+    approve!(app, token_y, dex, 10u128.pow(10), alice).unwrap();
+    create_position!(
+        app,
+        dex,
+        pool_key,
+        -1000,
+        1000,
+        Liquidity::from_integer(100000000),
+        min_sqrt_price,
+        max_sqrt_price,
+        alice
+    )
+    .unwrap();
+    // keeps tick -40 initialized
+    create_position!(
+        app,
+        dex,
+        pool_key,
+        -40,
+        1000,
+        Liquidity::from_integer(1000000),
+        min_sqrt_price,
+        max_sqrt_price,
+        alice
+    )
+    .unwrap();
+
+    mint!(app, token_y, bob, 2000, alice).unwrap();
+    approve!(app, token_y, dex, 2000, bob).unwrap();
+
+    // both buy token x once the price goes below their lower tick
+    for (lower_tick, upper_tick) in [(-20, -10), (-40, -30)] {
+        create_limit_order!(
+            app,
+            dex,
+            pool_key,
+            lower_tick,
+            upper_tick,
+            Liquidity::from_integer(1000000),
+            min_sqrt_price,
+            max_sqrt_price,
+            bob
+        )
+        .unwrap();
+    }
+    let orders = get_limit_orders!(app, dex, bob).unwrap();
+    assert_eq!(orders[0].position.limit_order.unwrap().tick_index, -20);
+    assert_eq!(orders[1].position.limit_order.unwrap().tick_index, -40);
+
+    // a removed order is not filled anymore
+    remove_position!(app, dex, 1, bob).unwrap();
+    assert!(is_tick_initialized!(app, dex, pool_key, -40));
+    let bob_y = balance_of!(app, token_y, bob);
+
+    swap!(
+        app,
+        dex,
+        pool_key,
+        true,
+        TokenAmount(300000),
+        true,
+        min_sqrt_price,
+        alice
+    )
+    .unwrap();
+    let pool = get_pool!(app, dex, token_x, token_y, fee_tier).unwrap();
+    assert!(pool.current_tick_index < -40);
+
+    let orders = get_limit_orders!(app, dex, bob).unwrap();
+    assert_eq!(orders.len(), 1);
+    let order = orders[0].position.clone();
+    assert!(order.limit_order.unwrap().filled);
+    assert_eq!(order.tokens_owed_y, TokenAmount(0));
+    assert!(!order.tokens_owed_x.is_zero());
+
+    let error = increase_liquidity!(
+        app,
+        dex,
+        0,
+        Liquidity::from_integer(1),
+        min_sqrt_price,
+        max_sqrt_price,
+        bob
+    )
+    .unwrap_err();
+    assert!(error
+        .root_cause()
+        .to_string()
+        .contains(&ContractError::LimitOrderFilled {}.to_string()));
+
+    // removing a filled order claims it
+    remove_position!(app, dex, 0, bob).unwrap();
+    assert_eq!(balance_of!(app, token_x, bob), order.tokens_owed_x.get());
+    assert_eq!(balance_of!(app, token_y, bob), bob_y);
+    assert!(get_all_positions!(app, dex, bob).is_empty());
+}
+
+#[test]
+fn test_limit_order_limits() {
+    // enough owners to fill a tick
+    let owners = (1..MAX_LIMIT_ORDERS_PER_TICK / MAX_LIMIT_ORDERS_PER_OWNER_PER_TICK)
+        .map(|i| format!("owner{i}"))
+        .collect::<Vec<_>>();
+    let funds = coins(100_000_000_000, FEE_DENOM);
+    let mut init_balances = vec![("alice", funds.as_slice()), ("bob", funds.as_slice())];
+    init_balances.extend(
+        owners
+            .iter()
+            .map(|owner| (owner.as_str(), funds.as_slice())),
+    );
+    let (mut app, accounts) = MockApp::new(&init_balances);
+    let alice = &accounts[0];
+    let bob = &accounts[1];
+    let (dex, token_x, token_y) = init_dex_and_tokens!(app, alice);
+    init_basic_pool!(app, dex, token_x, token_y, alice);
+
+    let fee_tier = FeeTier::new(Percentage::from_scale(6, 3), 10).unwrap();
+    let pool_key = PoolKey::new(token_x.to_string(), token_y.to_string(), fee_tier).unwrap();
+    let min_sqrt_price = SqrtPrice::new(MIN_SQRT_PRICE);
+    let max_sqrt_price = SqrtPrice::new(MAX_SQRT_PRICE);
+
+    mint!(app, token_x, bob, 100000, alice).unwrap();
+    approve!(app, token_x, dex, 100000, bob).unwrap();
+
+    // dust orders are rejected
+    let error = create_limit_order!(
+        app,
+        dex,
+        pool_key,
+        10,
+        20,
+        MIN_LIMIT_ORDER_LIQUIDITY - Liquidity::new(1),
+        min_sqrt_price,
+        max_sqrt_price,
+        bob
+    )
+    .unwrap_err();
+    assert!(error
+        .root_cause()
+        .to_string()
+        .contains(&ContractError::LimitOrderTooSmall {}.to_string()));
+
+    // an owner holds a bounded number of orders on a tick
+    for _ in 0..MAX_LIMIT_ORDERS_PER_OWNER_PER_TICK {
+        create_limit_order!(
+            app,
+            dex,
+            pool_key,
+            10,
+            20,
+            MIN_LIMIT_ORDER_LIQUIDITY,
+            min_sqrt_price,
+            max_sqrt_price,
+            bob
+        )
+        .unwrap();
+    }
+    let error = create_limit_order!(
+        app,
+        dex,
+        pool_key,
+        10,
+        20,
+        MIN_LIMIT_ORDER_LIQUIDITY,
+        min_sqrt_price,
+        max_sqrt_price,
+        bob
+    )
+    .unwrap_err();
+    assert!(error
+        .root_cause()
+        .to_string()
+        .contains(&ContractError::TooManyLimitOrders {}.to_string()));
+
+    // and a tick holds a bounded number of orders
+    for owner in &accounts[2..] {
+        mint!(app, token_x, owner, 100000, alice).unwrap();
+        approve!(app, token_x, dex, 100000, owner).unwrap();
+        for _ in 0..MAX_LIMIT_ORDERS_PER_OWNER_PER_TICK {
+            create_limit_order!(
+                app,
+                dex,
+                pool_key,
+                10,
+                20,
+                MIN_LIMIT_ORDER_LIQUIDITY,
+                min_sqrt_price,
+                max_sqrt_price,
+                owner
+            )
+            .unwrap();
+        }
+    }
+    let error = create_limit_order!(
+        app,
+        dex,
+        pool_key,
+        10,
+        20,
+        MIN_LIMIT_ORDER_LIQUIDITY,
+        min_sqrt_price,
+        max_sqrt_price,
+        alice
+    )
+    .unwrap_err();
+    assert!(error
+        .root_cause()
+        .to_string()
+        .contains(&ContractError::TooManyLimitOrders {}.to_string()));
+
+    // other ticks still take orders
+    create_limit_order!(
+        app,
+        dex,
+        pool_key,
+        10,
+        30,
+        MIN_LIMIT_ORDER_LIQUIDITY + MIN_LIMIT_ORDER_LIQUIDITY,
+        min_sqrt_price,
+        max_sqrt_price,
+        bob
+    )
+    .unwrap();
+
+    // an unfilled order cannot be decreased below the minimum size
+    let index = MAX_LIMIT_ORDERS_PER_OWNER_PER_TICK as u32;
+    let error = decrease_liquidity!(
+        app,
+        dex,
+        index,
+        MIN_LIMIT_ORDER_LIQUIDITY + Liquidity::new(1),
+        min_sqrt_price,
+        max_sqrt_price,
+        bob
+    )
+    .unwrap_err();
+    assert!(error
+        .root_cause()
+        .to_string()
+        .contains(&ContractError::LimitOrderTooSmall {}.to_string()));
+    decrease_liquidity!(
+        app,
+        dex,
+        index,
+        MIN_LIMIT_ORDER_LIQUIDITY,
+        min_sqrt_price,
+        max_sqrt_price,
+        bob
+    )
+    .unwrap();
+
+    // the orders are listed by pages
+    let orders = get_limit_orders!(app, dex, bob, None, Some(2)).unwrap();
+    assert_eq!(
+        orders.iter().map(|order| order.index).collect::<Vec<_>>(),
+        vec![0, 1]
+    );
+    let orders = get_limit_orders!(app, dex, bob, Some(3), None).unwrap();
+    assert_eq!(
+        orders.iter().map(|order| order.index).collect::<Vec<_>>(),
+        vec![4, 5]
+    );
+    assert_eq!(orders[1].position.liquidity, MIN_LIMIT_ORDER_LIQUIDITY);
+}
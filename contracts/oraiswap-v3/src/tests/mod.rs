@@ -3,27 +3,40 @@ mod admin;
 mod change_fee_receiver;
 mod change_protocol_fee;
 mod claim;
+mod compound_fees;
 mod create_pool;
 mod cross;
 mod cross_both_size;
+mod decrease_liquidity;
+mod dynamic_fee;
+#[cfg(not(feature = "test-tube"))]
+mod flash;
+mod funds;
 mod get_liquidity_ticks;
 mod get_position_ticks;
 mod get_tickmap;
 mod helper;
 mod incentive;
+mod increase_liquidity;
 mod interaction_with_pool_on_removed_fee_tier;
+mod limit_order;
 mod limits;
 mod liquidity_gap;
 mod max_tick_cross;
 // mod migration;
 mod multiple_swap;
 mod nft;
+mod oracle;
 mod pool_status;
 mod position;
 mod position_list;
 mod position_slippage;
 mod protocol_fee;
+mod rebalance;
+mod receive;
 mod remove_fee_tier;
 mod slippage;
+mod snapshot_cumulatives_inside;
 mod swap;
 mod swap_route;
+mod swap_route_exact_out;
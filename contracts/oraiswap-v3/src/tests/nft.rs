@@ -1,10 +1,14 @@
-use cosmwasm_std::{attr, coins, Addr, Attribute};
+use cosmwasm_std::{attr, coins, Addr, Attribute, StdResult};
+use cw20::Expiration;
 use decimal::*;
 
 use crate::tests::helper::{macros::*, MockApp, FEE_DENOM};
 use oraiswap_v3_common::{
     error::ContractError,
-    interface::{NftInfoResponse, OwnerOfResponse, TokensResponse},
+    interface::{
+        AllNftInfoResponse, ContractInfoResponse, NftInfoResponse, OwnerOfResponse,
+        PositionMetadata, PositionTokensResponse, TokensResponse,
+    },
     math::{
         fee_growth::FeeGrowth,
         liquidity::Liquidity,
@@ -122,7 +126,7 @@ fn test_query_nft() {
         )
         .unwrap();
 
-    assert_eq!(nft_info.extension.pool_key, pool_key);
+    assert_eq!(nft_info.extension.position.pool_key, pool_key);
 
     let TokensResponse { tokens } = app
         .query(
@@ -137,6 +141,71 @@ fn test_query_nft() {
     assert_eq!(tokens.len(), 1)
 }
 
+#[test]
+fn test_query_position_tokens() {
+    let (mut app, accounts) = MockApp::new(&[("alice", &coins(100_000_000_000, FEE_DENOM))]);
+    let alice = &accounts[0];
+
+    let dex = create_dex!(app, Percentage::new(0), alice);
+    let (token_x, token_y) = create_tokens!(app, 500, 500, alice);
+    let fee_tier = FeeTier::new(Percentage::new(0), 1).unwrap();
+    add_fee_tier!(app, dex, fee_tier, alice).unwrap();
+    create_pool!(
+        app,
+        dex,
+        token_x,
+        token_y,
+        fee_tier,
+        calculate_sqrt_price(0).unwrap(),
+        0,
+        alice
+    )
+    .unwrap();
+    let pool_key = PoolKey::new(token_x.to_string(), token_y.to_string(), fee_tier).unwrap();
+    approve!(app, token_x, dex, 500, alice).unwrap();
+    approve!(app, token_y, dex, 500, alice).unwrap();
+    for _ in 0..3 {
+        create_position!(
+            app,
+            dex,
+            pool_key,
+            -10,
+            10,
+            Liquidity::new(10),
+            SqrtPrice::new(0),
+            SqrtPrice::max_instance(),
+            alice
+        )
+        .unwrap();
+    }
+
+    // pages by position index, the token ids stay numbers
+    let PositionTokensResponse { tokens } = app
+        .query(
+            dex.clone(),
+            &oraiswap_v3_common::oraiswap_v3_msg::QueryMsg::PositionTokens {
+                owner: Addr::unchecked(alice),
+                start_after: Some(1),
+                limit: Some(1),
+            },
+        )
+        .unwrap();
+    assert_eq!(tokens, vec![2]);
+
+    // `Tokens` pages by token id
+    let TokensResponse { tokens } = app
+        .query(
+            dex.clone(),
+            &oraiswap_v3_common::oraiswap_v3_msg::QueryMsg::Tokens {
+                owner: Addr::unchecked(alice),
+                start_after: Some(1),
+                limit: None,
+            },
+        )
+        .unwrap();
+    assert_eq!(tokens, vec![2, 3]);
+}
+
 #[test]
 fn test_burn_nft() {
     let (mut app, accounts) = MockApp::new(&[
@@ -229,7 +298,11 @@ fn test_burn_nft() {
 
     let token_id = 2;
     let NftInfoResponse {
-        extension: position_state,
+        extension: PositionMetadata {
+            position: position_state,
+            ..
+        },
+        ..
     } = app
         .query(
             dex.clone(),
@@ -481,7 +554,12 @@ fn test_transfer_nft() {
             )
             .unwrap();
         let NftInfoResponse {
-            extension: removed_position,
+            extension:
+                PositionMetadata {
+                    position: removed_position,
+                    ..
+                },
+            ..
         } = app
             .query(
                 dex.clone(),
@@ -490,7 +568,12 @@ fn test_transfer_nft() {
             .unwrap();
 
         let NftInfoResponse {
-            extension: last_position_before,
+            extension:
+                PositionMetadata {
+                    position: last_position_before,
+                    ..
+                },
+            ..
         } = app
             .query(
                 dex.clone(),
@@ -530,7 +613,12 @@ fn test_transfer_nft() {
             )
             .unwrap();
         let NftInfoResponse {
-            extension: recipient_position,
+            extension:
+                PositionMetadata {
+                    position: recipient_position,
+                    ..
+                },
+            ..
         } = app
             .query(
                 dex.clone(),
@@ -572,7 +660,12 @@ fn test_transfer_nft() {
             )
             .unwrap();
         let NftInfoResponse {
-            extension: owner_first_position_after,
+            extension:
+                PositionMetadata {
+                    position: owner_first_position_after,
+                    ..
+                },
+            ..
         } = app
             .query(
                 dex.clone(),
@@ -619,7 +712,12 @@ fn test_transfer_nft() {
             .unwrap();
 
         let NftInfoResponse {
-            extension: last_position_before,
+            extension:
+                PositionMetadata {
+                    position: last_position_before,
+                    ..
+                },
+            ..
         } = app
             .query(
                 dex.clone(),
@@ -683,7 +781,12 @@ fn test_transfer_nft() {
             )
             .unwrap();
         let NftInfoResponse {
-            extension: owner_first_position_after,
+            extension:
+                PositionMetadata {
+                    position: owner_first_position_after,
+                    ..
+                },
+            ..
         } = app
             .query(
                 dex.clone(),
@@ -722,7 +825,12 @@ fn test_transfer_nft() {
             )
             .unwrap();
         let NftInfoResponse {
-            extension: removed_position,
+            extension:
+                PositionMetadata {
+                    position: removed_position,
+                    ..
+                },
+            ..
         } = app
             .query(
                 dex.clone(),
@@ -772,7 +880,12 @@ fn test_transfer_nft() {
             )
             .unwrap();
         let NftInfoResponse {
-            extension: recipient_position,
+            extension:
+                PositionMetadata {
+                    position: recipient_position,
+                    ..
+                },
+            ..
         } = app
             .query(
                 dex.clone(),
@@ -808,7 +921,12 @@ fn test_transfer_nft() {
             )
             .unwrap();
         let NftInfoResponse {
-            extension: removed_position,
+            extension:
+                PositionMetadata {
+                    position: removed_position,
+                    ..
+                },
+            ..
         } = app
             .query(
                 dex.clone(),
@@ -859,7 +977,12 @@ fn test_transfer_nft() {
             )
             .unwrap();
         let NftInfoResponse {
-            extension: recipient_position,
+            extension:
+                PositionMetadata {
+                    position: recipient_position,
+                    ..
+                },
+            ..
         } = app
             .query(
                 dex.clone(),
@@ -923,7 +1046,12 @@ fn test_transfer_nft() {
             )
             .unwrap();
         let NftInfoResponse {
-            extension: removed_position,
+            extension:
+                PositionMetadata {
+                    position: removed_position,
+                    ..
+                },
+            ..
         } = app
             .query(
                 dex.clone(),
@@ -932,7 +1060,12 @@ fn test_transfer_nft() {
             .unwrap();
 
         let NftInfoResponse {
-            extension: last_position_before,
+            extension:
+                PositionMetadata {
+                    position: last_position_before,
+                    ..
+                },
+            ..
         } = app
             .query(
                 dex.clone(),
@@ -996,7 +1129,12 @@ fn test_transfer_nft() {
             )
             .unwrap();
         let NftInfoResponse {
-            extension: recipient_first_position_after,
+            extension:
+                PositionMetadata {
+                    position: recipient_first_position_after,
+                    ..
+                },
+            ..
         } = app
             .query(
                 dex.clone(),
@@ -1014,7 +1152,12 @@ fn test_transfer_nft() {
             )
             .unwrap();
         let NftInfoResponse {
-            extension: owner_new_position,
+            extension:
+                PositionMetadata {
+                    position: owner_new_position,
+                    ..
+                },
+            ..
         } = app
             .query(
                 dex.clone(),
@@ -1351,3 +1494,593 @@ fn test_approving_revoking() {
         }
     );
 }
+
+#[test]
+fn test_approved_position_actions() {
+    let (mut app, accounts) = MockApp::new(&[
+        ("alice", &coins(100_000_000_000, FEE_DENOM)),
+        ("keeper", &coins(100_000_000_000, FEE_DENOM)),
+        ("vault", &coins(100_000_000_000, FEE_DENOM)),
+    ]);
+    let alice = &accounts[0];
+    let keeper = &accounts[1];
+    let vault = &accounts[2];
+    let (dex, token_x, token_y) = init_dex_and_tokens!(app, alice);
+    init_basic_pool!(app, dex, token_x, token_y, alice);
+    approve!(app, token_x, dex, 10u128.pow(10), alice).unwrap();
+    approve!(app, token_y, dex, 10u128.pow(10), alice).unwrap();
+
+    let fee_tier = FeeTier::new(Percentage::from_scale(6, 3), 10).unwrap();
+    let pool_key = PoolKey::new(token_x.to_string(), token_y.to_string(), fee_tier).unwrap();
+    let mut token_ids = vec![];
+    for index in 0..2 {
+        create_position!(
+            app,
+            dex,
+            pool_key,
+            -10,
+            10,
+            Liquidity::from_integer(1_000_000),
+            SqrtPrice::new(0),
+            SqrtPrice::max_instance(),
+            alice
+        )
+        .unwrap();
+        let position = get_position!(app, dex, index, alice).unwrap();
+        token_ids.push(position.token_id);
+    }
+
+    let error = claim_fee_by_token_id!(app, dex, token_ids[0], None, keeper).unwrap_err();
+    assert!(error
+        .root_cause()
+        .to_string()
+        .contains(&ContractError::Unauthorized {}.to_string()));
+
+    // an approved spender manages the position and chooses where the tokens go
+    app.execute(
+        Addr::unchecked(alice),
+        dex.clone(),
+        &oraiswap_v3_common::oraiswap_v3_msg::ExecuteMsg::Approve {
+            spender: Addr::unchecked(keeper),
+            token_id: token_ids[0],
+            expires: None,
+        },
+        &[],
+    )
+    .unwrap();
+    claim_fee_by_token_id!(app, dex, token_ids[0], Some(vault.as_str()), keeper).unwrap();
+
+    let alice_x = balance_of!(app, token_x, alice);
+    let alice_y = balance_of!(app, token_y, alice);
+    remove_position_by_token_id!(app, dex, token_ids[0], Some(vault.as_str()), keeper).unwrap();
+    assert!(balance_of!(app, token_x, vault) > 0);
+    assert!(balance_of!(app, token_y, vault) > 0);
+    assert_eq!(balance_of!(app, token_x, alice), alice_x);
+    assert_eq!(balance_of!(app, token_y, alice), alice_y);
+    assert_eq!(get_all_positions!(app, dex, alice).len(), 1);
+
+    // the approval only covers its token, an operator covers all of them
+    let error = remove_position_by_token_id!(app, dex, token_ids[1], None, keeper).unwrap_err();
+    assert!(error
+        .root_cause()
+        .to_string()
+        .contains(&ContractError::Unauthorized {}.to_string()));
+    app.execute(
+        Addr::unchecked(alice),
+        dex.clone(),
+        &oraiswap_v3_common::oraiswap_v3_msg::ExecuteMsg::ApproveAll {
+            operator: Addr::unchecked(keeper),
+            expires: None,
+        },
+        &[],
+    )
+    .unwrap();
+
+    // without a recipient the tokens go to the owner
+    let vault_x = balance_of!(app, token_x, vault);
+    remove_position_by_token_id!(app, dex, token_ids[1], None, keeper).unwrap();
+    assert!(balance_of!(app, token_x, alice) > alice_x);
+    assert!(balance_of!(app, token_y, alice) > alice_y);
+    assert_eq!(balance_of!(app, token_x, vault), vault_x);
+    assert_eq!(balance_of!(app, token_x, keeper), 0);
+    assert!(get_all_positions!(app, dex, alice).is_empty());
+}
+
+#[test]
+fn test_nft_metadata() {
+    let (mut app, accounts) = MockApp::new(&[
+        ("alice", &coins(100_000_000_000, FEE_DENOM)),
+        ("bob", &coins(100_000_000_000, FEE_DENOM)),
+    ]);
+    let alice = &accounts[0];
+    let bob = &accounts[1];
+    let (dex, token_x, token_y) = init_dex_and_tokens!(app, alice);
+    init_basic_pool!(app, dex, token_x, token_y, alice);
+    approve!(app, token_x, dex, 10u128.pow(10), alice).unwrap();
+    approve!(app, token_y, dex, 10u128.pow(10), alice).unwrap();
+
+    let fee_tier = FeeTier::new(Percentage::from_scale(6, 3), 10).unwrap();
+    let pool_key = PoolKey::new(token_x.to_string(), token_y.to_string(), fee_tier).unwrap();
+    for (lower_tick, upper_tick) in [(-10, 10), (10, 20)] {
+        create_position!(
+            app,
+            dex,
+            pool_key,
+            lower_tick,
+            upper_tick,
+            Liquidity::from_integer(1_000_000),
+            SqrtPrice::new(0),
+            SqrtPrice::max_instance(),
+            alice
+        )
+        .unwrap();
+    }
+
+    let nft_info = |app: &MockApp, token_id: u64| -> NftInfoResponse {
+        app.query(
+            dex.clone(),
+            &oraiswap_v3_common::oraiswap_v3_msg::QueryMsg::NftInfo { token_id },
+        )
+        .unwrap()
+    };
+    let attribute = |info: &NftInfoResponse, trait_type: &str| -> String {
+        info.extension
+            .attributes
+            .iter()
+            .find(|attribute| attribute.trait_type == trait_type)
+            .unwrap()
+            .value
+            .clone()
+    };
+
+    let info = nft_info(&app, 1);
+    assert_eq!(info.token_uri, None);
+    assert_eq!(info.extension.name, "Oraiswap V3 Position #1");
+    assert_eq!(info.extension.position.token_id, 1);
+    assert_eq!(attribute(&info, "token_x"), pool_key.token_x);
+    assert_eq!(attribute(&info, "token_y"), pool_key.token_y);
+    assert_eq!(attribute(&info, "fee"), "0.6%");
+    assert_eq!(attribute(&info, "tick_spacing"), "10");
+    assert_eq!(attribute(&info, "lower_tick"), "-10");
+    assert_eq!(attribute(&info, "upper_tick"), "10");
+    assert!(attribute(&info, "lower_price").starts_with("0.999"));
+    assert!(attribute(&info, "upper_price").starts_with("1.001"));
+    assert_eq!(attribute(&info, "liquidity"), "1000000");
+    assert_eq!(attribute(&info, "in_range"), "true");
+    assert_eq!(attribute(&nft_info(&app, 2), "in_range"), "false");
+
+    // only the admin sets the token uri
+    let change_token_uri = oraiswap_v3_common::oraiswap_v3_msg::ExecuteMsg::ChangeTokenUri {
+        token_uri: Some("https://oraidex.io/positions/".to_string()),
+    };
+    let error = app
+        .execute(Addr::unchecked(bob), dex.clone(), &change_token_uri, &[])
+        .unwrap_err();
+    assert!(error
+        .root_cause()
+        .to_string()
+        .contains(&ContractError::Unauthorized {}.to_string()));
+    app.execute(Addr::unchecked(alice), dex.clone(), &change_token_uri, &[])
+        .unwrap();
+
+    assert_eq!(
+        nft_info(&app, 2).token_uri,
+        Some("https://oraidex.io/positions/2".to_string())
+    );
+    let all_nft_info: AllNftInfoResponse = app
+        .query(
+            dex.clone(),
+            &oraiswap_v3_common::oraiswap_v3_msg::QueryMsg::AllNftInfo {
+                token_id: 1,
+                include_expired: None,
+            },
+        )
+        .unwrap();
+    assert_eq!(all_nft_info.info, nft_info(&app, 1));
+
+    let contract_info: ContractInfoResponse = app
+        .query(
+            dex.clone(),
+            &oraiswap_v3_common::oraiswap_v3_msg::QueryMsg::ContractInfo {},
+        )
+        .unwrap();
+    assert_eq!(contract_info.name, "Oraiswap V3 Positions");
+    assert_eq!(contract_info.symbol, "ORAIX-V3-POS");
+}
+
+// the cw721 messages and responses as generic NFT tooling sends and reads them, with string
+// token ids and addresses
+mod cw721 {
+    use cosmwasm_schema::cw_serde;
+    use cw20::Expiration;
+    use serde::Deserialize;
+
+    #[cw_serde]
+    pub enum ExecuteMsg {
+        TransferNft {
+            recipient: String,
+            token_id: String,
+        },
+        Approve {
+            spender: String,
+            token_id: String,
+            expires: Option<Expiration>,
+        },
+        Revoke {
+            spender: String,
+            token_id: String,
+        },
+        ApproveAll {
+            operator: String,
+            expires: Option<Expiration>,
+        },
+        RevokeAll {
+            operator: String,
+        },
+        Burn {
+            token_id: String,
+        },
+    }
+
+    #[cw_serde]
+    pub enum QueryMsg {
+        OwnerOf {
+            token_id: String,
+            include_expired: Option<bool>,
+        },
+        Approval {
+            token_id: String,
+            spender: String,
+            include_expired: Option<bool>,
+        },
+        Approvals {
+            token_id: String,
+            include_expired: Option<bool>,
+        },
+        AllOperators {
+            owner: String,
+            include_expired: Option<bool>,
+            start_after: Option<String>,
+            limit: Option<u32>,
+        },
+        NumTokens {},
+        ContractInfo {},
+        NftInfo {
+            token_id: String,
+        },
+        AllNftInfo {
+            token_id: String,
+            include_expired: Option<bool>,
+        },
+        Tokens {
+            owner: String,
+            start_after: Option<String>,
+            limit: Option<u32>,
+        },
+        AllTokens {
+            start_after: Option<String>,
+            limit: Option<u32>,
+        },
+        Minter {},
+    }
+
+    #[cw_serde]
+    pub struct Approval {
+        pub spender: String,
+        pub expires: Expiration,
+    }
+
+    #[cw_serde]
+    pub struct OwnerOfResponse {
+        pub owner: String,
+        pub approvals: Vec<Approval>,
+    }
+
+    #[cw_serde]
+    pub struct ApprovalResponse {
+        pub approval: Approval,
+    }
+
+    #[cw_serde]
+    pub struct ApprovalsResponse {
+        pub approvals: Vec<Approval>,
+    }
+
+    #[cw_serde]
+    pub struct OperatorsResponse {
+        pub operators: Vec<Approval>,
+    }
+
+    #[cw_serde]
+    pub struct NumTokensResponse {
+        pub count: u64,
+    }
+
+    #[cw_serde]
+    pub struct ContractInfoResponse {
+        pub name: String,
+        pub symbol: String,
+    }
+
+    // only the common metadata fields, the position itself is ignored
+    #[derive(Deserialize)]
+    pub struct Metadata {
+        pub name: String,
+        pub description: String,
+    }
+
+    #[derive(Deserialize)]
+    pub struct NftInfoResponse {
+        pub token_uri: Option<String>,
+        pub extension: Metadata,
+    }
+
+    #[derive(Deserialize)]
+    pub struct AllNftInfoResponse {
+        pub access: OwnerOfResponse,
+        pub info: NftInfoResponse,
+    }
+
+    #[cw_serde]
+    pub struct TokensResponse {
+        pub tokens: Vec<String>,
+    }
+
+    #[cw_serde]
+    pub struct MinterResponse {
+        pub minter: Option<String>,
+    }
+}
+
+#[test]
+fn test_cw721_conformance() {
+    let (mut app, accounts) = MockApp::new(&[
+        ("alice", &coins(100_000_000_000, FEE_DENOM)),
+        ("bob", &coins(100_000_000_000, FEE_DENOM)),
+        ("charlie", &coins(100_000_000_000, FEE_DENOM)),
+    ]);
+    let alice = &accounts[0];
+    let bob = &accounts[1];
+    let charlie = &accounts[2];
+    let (dex, token_x, token_y) = init_dex_and_tokens!(app, alice);
+    init_basic_pool!(app, dex, token_x, token_y, alice);
+    approve!(app, token_x, dex, 10u128.pow(10), alice).unwrap();
+    approve!(app, token_y, dex, 10u128.pow(10), alice).unwrap();
+
+    let fee_tier = FeeTier::new(Percentage::from_scale(6, 3), 10).unwrap();
+    let pool_key = PoolKey::new(token_x.to_string(), token_y.to_string(), fee_tier).unwrap();
+    for _ in 0..2 {
+        create_position!(
+            app,
+            dex,
+            pool_key,
+            -10,
+            10,
+            Liquidity::from_integer(1_000_000),
+            SqrtPrice::new(0),
+            SqrtPrice::max_instance(),
+            alice
+        )
+        .unwrap();
+    }
+
+    let contract_info: cw721::ContractInfoResponse = app
+        .query(dex.clone(), &cw721::QueryMsg::ContractInfo {})
+        .unwrap();
+    assert_eq!(contract_info.name, "Oraiswap V3 Positions");
+    assert_eq!(contract_info.symbol, "ORAIX-V3-POS");
+    let minter: cw721::MinterResponse =
+        app.query(dex.clone(), &cw721::QueryMsg::Minter {}).unwrap();
+    assert_eq!(minter.minter, None);
+    let num_tokens: cw721::NumTokensResponse = app
+        .query(dex.clone(), &cw721::QueryMsg::NumTokens {})
+        .unwrap();
+    assert_eq!(num_tokens.count, 2);
+
+    // token ids are strings and paginate with the last token id seen
+    let tokens = |app: &MockApp, owner: &str, start_after: Option<&str>| -> Vec<String> {
+        let res: cw721::TokensResponse = app
+            .query(
+                dex.clone(),
+                &cw721::QueryMsg::Tokens {
+                    owner: owner.to_string(),
+                    start_after: start_after.map(String::from),
+                    limit: Some(1),
+                },
+            )
+            .unwrap();
+        res.tokens
+    };
+    assert_eq!(tokens(&app, alice, None), vec!["1"]);
+    assert_eq!(tokens(&app, alice, Some("1")), vec!["2"]);
+    assert!(tokens(&app, alice, Some("2")).is_empty());
+    let all_tokens: cw721::TokensResponse = app
+        .query(
+            dex.clone(),
+            &cw721::QueryMsg::AllTokens {
+                start_after: Some("1".to_string()),
+                limit: None,
+            },
+        )
+        .unwrap();
+    assert_eq!(all_tokens.tokens, vec!["2"]);
+
+    let owner_of: cw721::OwnerOfResponse = app
+        .query(
+            dex.clone(),
+            &cw721::QueryMsg::OwnerOf {
+                token_id: "1".to_string(),
+                include_expired: None,
+            },
+        )
+        .unwrap();
+    assert_eq!(owner_of.owner, *alice);
+    assert!(owner_of.approvals.is_empty());
+
+    let nft_info: cw721::AllNftInfoResponse = app
+        .query(
+            dex.clone(),
+            &cw721::QueryMsg::AllNftInfo {
+                token_id: "1".to_string(),
+                include_expired: None,
+            },
+        )
+        .unwrap();
+    assert_eq!(nft_info.access.owner, *alice);
+    assert_eq!(nft_info.info.token_uri, None);
+    assert_eq!(nft_info.info.extension.name, "Oraiswap V3 Position #1");
+    assert!(!nft_info.info.extension.description.is_empty());
+    let nft_info: cw721::NftInfoResponse = app
+        .query(
+            dex.clone(),
+            &cw721::QueryMsg::NftInfo {
+                token_id: "2".to_string(),
+            },
+        )
+        .unwrap();
+    assert_eq!(nft_info.extension.name, "Oraiswap V3 Position #2");
+
+    // approvals of a single token
+    app.execute(
+        Addr::unchecked(alice),
+        dex.clone(),
+        &cw721::ExecuteMsg::Approve {
+            spender: bob.to_string(),
+            token_id: "1".to_string(),
+            expires: None,
+        },
+        &[],
+    )
+    .unwrap();
+    let approval = |app: &MockApp, spender: &str| -> StdResult<cw721::ApprovalResponse> {
+        app.query(
+            dex.clone(),
+            &cw721::QueryMsg::Approval {
+                token_id: "1".to_string(),
+                spender: spender.to_string(),
+                include_expired: None,
+            },
+        )
+    };
+    assert_eq!(
+        approval(&app, bob).unwrap().approval,
+        cw721::Approval {
+            spender: bob.to_string(),
+            expires: Expiration::Never {},
+        }
+    );
+    assert_eq!(approval(&app, alice).unwrap().approval.spender, *alice);
+    assert!(approval(&app, charlie)
+        .unwrap_err()
+        .to_string()
+        .contains("not found"));
+    let approvals: cw721::ApprovalsResponse = app
+        .query(
+            dex.clone(),
+            &cw721::QueryMsg::Approvals {
+                token_id: "1".to_string(),
+                include_expired: None,
+            },
+        )
+        .unwrap();
+    assert_eq!(approvals.approvals.len(), 1);
+    assert_eq!(approvals.approvals[0].spender, *bob);
+
+    app.execute(
+        Addr::unchecked(alice),
+        dex.clone(),
+        &cw721::ExecuteMsg::Revoke {
+            spender: bob.to_string(),
+            token_id: "1".to_string(),
+        },
+        &[],
+    )
+    .unwrap();
+    assert!(approval(&app, bob).is_err());
+
+    // operators of all the tokens of the owner
+    app.execute(
+        Addr::unchecked(alice),
+        dex.clone(),
+        &cw721::ExecuteMsg::ApproveAll {
+            operator: charlie.to_string(),
+            expires: None,
+        },
+        &[],
+    )
+    .unwrap();
+    let operators: cw721::OperatorsResponse = app
+        .query(
+            dex.clone(),
+            &cw721::QueryMsg::AllOperators {
+                owner: alice.to_string(),
+                include_expired: None,
+                start_after: None,
+                limit: None,
+            },
+        )
+        .unwrap();
+    assert_eq!(operators.operators.len(), 1);
+    assert_eq!(operators.operators[0].spender, *charlie);
+
+    app.execute(
+        Addr::unchecked(charlie),
+        dex.clone(),
+        &cw721::ExecuteMsg::TransferNft {
+            recipient: bob.to_string(),
+            token_id: "2".to_string(),
+        },
+        &[],
+    )
+    .unwrap();
+    assert_eq!(tokens(&app, bob, None), vec!["2"]);
+    // the token id to start after must be one of the owner's
+    let res: StdResult<cw721::TokensResponse> = app.query(
+        dex.clone(),
+        &cw721::QueryMsg::Tokens {
+            owner: alice.to_string(),
+            start_after: Some("2".to_string()),
+            limit: None,
+        },
+    );
+    assert!(res.is_err());
+
+    app.execute(
+        Addr::unchecked(alice),
+        dex.clone(),
+        &cw721::ExecuteMsg::RevokeAll {
+            operator: charlie.to_string(),
+        },
+        &[],
+    )
+    .unwrap();
+    let error = app
+        .execute(
+            Addr::unchecked(charlie),
+            dex.clone(),
+            &cw721::ExecuteMsg::Burn {
+                token_id: "1".to_string(),
+            },
+            &[],
+        )
+        .unwrap_err();
+    assert!(error
+        .root_cause()
+        .to_string()
+        .contains(&ContractError::Unauthorized {}.to_string()));
+
+    app.execute(
+        Addr::unchecked(alice),
+        dex.clone(),
+        &cw721::ExecuteMsg::Burn {
+            token_id: "1".to_string(),
+        },
+        &[],
+    )
+    .unwrap();
+    assert!(tokens(&app, alice, None).is_empty());
+    let num_tokens: cw721::NumTokensResponse = app
+        .query(dex.clone(), &cw721::QueryMsg::NumTokens {})
+        .unwrap();
+    assert_eq!(num_tokens.count, 1);
+}
@@ -0,0 +1,180 @@
+use cosmwasm_std::coins;
+use decimal::{Decimal, Factories};
+use oraiswap_v3_common::{
+    error::ContractError,
+    math::{
+        liquidity::Liquidity, percentage::Percentage, seconds_per_liquidity::SecondsPerLiquidity,
+        sqrt_price::SqrtPrice, token_amount::TokenAmount, MIN_SQRT_PRICE,
+    },
+    storage::{FeeTier, PoolKey},
+};
+
+use crate::state::MAX_OBSERVATION_CARDINALITY;
+use crate::tests::helper::{macros::*, MockApp, FEE_DENOM};
+
+#[test]
+fn test_observe() {
+    let (mut app, accounts) = MockApp::new(&[
+        ("alice", &coins(100_000_000_000, FEE_DENOM)),
+        ("bob", &coins(100_000_000_000, FEE_DENOM)),
+    ]);
+    let alice = &accounts[0];
+    let bob = &accounts[1];
+    let (dex, token_x, token_y) = init_dex_and_tokens!(app, alice);
+    init_basic_pool!(app, dex, token_x, token_y, alice);
+
+    let fee_tier = FeeTier::new(Percentage::from_scale(6, 3), 10).unwrap();
+    let pool_key = PoolKey::new(token_x.to_string(), token_y.to_string(), fee_tier).unwrap();
+
+    // the oracle is initialized together with the pool
+    let pool = get_pool!(app, dex, token_x, token_y, fee_tier).unwrap();
+    assert_eq!(pool.observation_index, 0);
+    assert_eq!(pool.observation_cardinality, 1);
+    assert_eq!(pool.observation_cardinality_next, 1);
+
+    let result = observe!(app, dex, pool_key, vec![0]).unwrap();
+    assert_eq!(result.tick_cumulatives, vec![0]);
+    assert_eq!(
+        result.seconds_per_liquidity_cumulatives,
+        vec![SecondsPerLiquidity::new(0)]
+    );
+
+    init_basic_position!(app, dex, token_x, token_y, alice);
+    app.increase_time(100);
+
+    let result = observe!(app, dex, pool_key, vec![0, 100]).unwrap();
+    assert_eq!(result.tick_cumulatives, vec![0, 0]);
+    assert_eq!(
+        result.seconds_per_liquidity_cumulatives,
+        vec![
+            SecondsPerLiquidity::calculate_seconds_per_liquidity_global(
+                Liquidity::from_integer(1000000),
+                100,
+                0
+            )
+            .unwrap(),
+            SecondsPerLiquidity::new(0)
+        ]
+    );
+
+    // the swap records the price it started from
+    init_basic_swap!(app, dex, token_x, token_y, alice, bob);
+    let pool = get_pool!(app, dex, token_x, token_y, fee_tier).unwrap();
+    assert_ne!(pool.current_tick_index, 0);
+    app.increase_time(50);
+
+    let result = observe!(app, dex, pool_key, vec![0, 25, 50]).unwrap();
+    assert_eq!(
+        result.tick_cumulatives,
+        vec![
+            pool.current_tick_index as i64 * 50,
+            pool.current_tick_index as i64 * 25,
+            0
+        ]
+    );
+
+    // a single observation is kept, so the creation of the pool is gone
+    let error = observe!(app, dex, pool_key, vec![150]).unwrap_err();
+    assert!(error
+        .to_string()
+        .contains(&ContractError::OldObservation.to_string()));
+
+    // unknown pool
+    let other_key = PoolKey::new(
+        token_x.to_string(),
+        token_y.to_string(),
+        FeeTier::new(Percentage::from_scale(1, 2), 1).unwrap(),
+    )
+    .unwrap();
+    assert!(observe!(app, dex, other_key, vec![0]).is_err());
+}
+
+#[test]
+fn test_increase_observation_cardinality_next() {
+    let (mut app, accounts) = MockApp::new(&[
+        ("alice", &coins(100_000_000_000, FEE_DENOM)),
+        ("bob", &coins(100_000_000_000, FEE_DENOM)),
+    ]);
+    let alice = &accounts[0];
+    let bob = &accounts[1];
+    let (dex, token_x, token_y) = init_dex_and_tokens!(app, alice);
+    init_basic_pool!(app, dex, token_x, token_y, alice);
+    init_basic_position!(app, dex, token_x, token_y, alice);
+
+    let fee_tier = FeeTier::new(Percentage::from_scale(6, 3), 10).unwrap();
+    let pool_key = PoolKey::new(token_x.to_string(), token_y.to_string(), fee_tier).unwrap();
+
+    // anyone can pay for a bigger oracle
+    increase_observation_cardinality_next!(app, dex, pool_key, 3, bob).unwrap();
+    let pool = get_pool!(app, dex, token_x, token_y, fee_tier).unwrap();
+    assert_eq!(pool.observation_cardinality, 1);
+    assert_eq!(pool.observation_cardinality_next, 3);
+
+    // never shrinks
+    increase_observation_cardinality_next!(app, dex, pool_key, 2, bob).unwrap();
+    let pool = get_pool!(app, dex, token_x, token_y, fee_tier).unwrap();
+    assert_eq!(pool.observation_cardinality_next, 3);
+
+    // bounded, so nobody can grow it without limit
+    let error = increase_observation_cardinality_next!(
+        app,
+        dex,
+        pool_key,
+        MAX_OBSERVATION_CARDINALITY + 1,
+        bob
+    )
+    .unwrap_err();
+    assert!(error
+        .root_cause()
+        .to_string()
+        .contains(&ContractError::InvalidObservationCardinality {}.to_string()));
+
+    mint!(app, token_x, bob, 300, alice).unwrap();
+    approve!(app, token_x, dex, 300, bob).unwrap();
+
+    let mut ticks = vec![];
+    for _ in 0..3 {
+        app.increase_time(10);
+        swap!(
+            app,
+            dex,
+            pool_key,
+            true,
+            TokenAmount(100),
+            true,
+            SqrtPrice::new(MIN_SQRT_PRICE),
+            bob
+        )
+        .unwrap();
+        let pool = get_pool!(app, dex, token_x, token_y, fee_tier).unwrap();
+        ticks.push(pool.current_tick_index as i64);
+
+        if ticks.len() == 2 {
+            // observations at 0, 10 and 20 seconds
+            assert_eq!(pool.observation_cardinality, 3);
+            assert_eq!(pool.observation_index, 2);
+
+            let result = observe!(app, dex, pool_key, vec![0, 5, 10, 15, 20]).unwrap();
+            assert_eq!(
+                result.tick_cumulatives,
+                vec![ticks[0] * 10, ticks[0] * 5, 0, 0, 0]
+            );
+        }
+    }
+
+    // the ring buffer wrapped around, the oldest observation is now at 10 seconds
+    let pool = get_pool!(app, dex, token_x, token_y, fee_tier).unwrap();
+    assert_eq!(pool.observation_cardinality, 3);
+    assert_eq!(pool.observation_index, 0);
+
+    let result = observe!(app, dex, pool_key, vec![0, 20]).unwrap();
+    assert_eq!(
+        result.tick_cumulatives,
+        vec![ticks[0] * 10 + ticks[1] * 10, 0]
+    );
+
+    let error = observe!(app, dex, pool_key, vec![30]).unwrap_err();
+    assert!(error
+        .to_string()
+        .contains(&ContractError::OldObservation.to_string()));
+}
@@ -4,11 +4,13 @@ use decimal::*;
 use crate::tests::helper::{macros::*, MockApp, FEE_DENOM};
 
 use oraiswap_v3_common::{
+    error::ContractError,
     math::{
         fee_growth::FeeGrowth,
         liquidity::Liquidity,
         percentage::Percentage,
         sqrt_price::{calculate_sqrt_price, SqrtPrice},
+        MAX_SQRT_PRICE, MIN_SQRT_PRICE,
     },
     storage::{FeeTier, PoolKey},
 };
@@ -969,3 +971,63 @@ fn test_multiple_positions_on_same_tick() {
         assert!(pool_state.current_tick_index == init_tick);
     }
 }
+
+#[test]
+fn test_position_operations_by_token_id() {
+    let (mut app, accounts) = MockApp::new(&[
+        ("alice", &coins(100_000_000_000, FEE_DENOM)),
+        ("bob", &coins(100_000_000_000, FEE_DENOM)),
+    ]);
+    let alice = &accounts[0];
+    let bob = &accounts[1];
+    let (dex, token_x, token_y) = init_dex_and_tokens!(app, alice);
+    init_basic_pool!(app, dex, token_x, token_y, alice);
+    approve!(app, token_x, dex, 10u128.pow(10), alice).unwrap();
+    approve!(app, token_y, dex, 10u128.pow(10), alice).unwrap();
+
+    let fee_tier = FeeTier::new(Percentage::from_scale(6, 3), 10).unwrap();
+    let pool_key = PoolKey::new(token_x.to_string(), token_y.to_string(), fee_tier).unwrap();
+    let mut token_ids = vec![];
+    for (index, tick) in [10, 20, 30].into_iter().enumerate() {
+        create_position!(
+            app,
+            dex,
+            pool_key,
+            -tick,
+            tick,
+            Liquidity::from_integer(1_000_000),
+            SqrtPrice::new(MIN_SQRT_PRICE),
+            SqrtPrice::new(MAX_SQRT_PRICE),
+            alice
+        )
+        .unwrap();
+        let position = get_position!(app, dex, index as u32, alice).unwrap();
+        token_ids.push(position.token_id);
+    }
+
+    // removing the first position moves the last one to its index, its token id stays the same
+    remove_position_by_token_id!(app, dex, token_ids[0], None, alice).unwrap();
+    assert_eq!(
+        get_position!(app, dex, 0, alice).unwrap().token_id,
+        token_ids[2]
+    );
+    let position = get_position_by_token_id!(app, dex, token_ids[2]).unwrap();
+    assert_eq!(position.lower_tick_index, -30);
+    assert_eq!(position.upper_tick_index, 30);
+
+    let error = get_position_by_token_id!(app, dex, token_ids[0]).unwrap_err();
+    assert!(error.to_string().contains("not found"));
+
+    // the token id does not let others act on the position
+    let error = claim_fee_by_token_id!(app, dex, token_ids[2], None, bob).unwrap_err();
+    assert!(error
+        .root_cause()
+        .to_string()
+        .contains(&ContractError::Unauthorized {}.to_string()));
+    claim_fee_by_token_id!(app, dex, token_ids[2], None, alice).unwrap();
+
+    remove_position_by_token_id!(app, dex, token_ids[1], None, alice).unwrap();
+    let positions = get_all_positions!(app, dex, alice);
+    assert_eq!(positions.len(), 1);
+    assert_eq!(positions[0].token_id, token_ids[2]);
+}
@@ -0,0 +1,215 @@
+use cosmwasm_std::coins;
+use decimal::{Decimal, Factories};
+use oraiswap_v3_common::{
+    error::ContractError,
+    math::{
+        liquidity::Liquidity, percentage::Percentage, sqrt_price::calculate_sqrt_price,
+        sqrt_price::SqrtPrice, MAX_SQRT_PRICE, MIN_SQRT_PRICE,
+    },
+    storage::{FeeTier, PoolKey},
+};
+
+use crate::tests::helper::{macros::*, MockApp, FEE_DENOM};
+
+#[test]
+fn test_rebalance() {
+    let (mut app, accounts) = MockApp::new(&[
+        ("alice", &coins(100_000_000_000, FEE_DENOM)),
+        ("bob", &coins(100_000_000_000, FEE_DENOM)),
+    ]);
+    let alice = &accounts[0];
+    let bob = &accounts[1];
+    let initial_amount = 10u128.pow(10);
+    let dex = create_dex!(app, Percentage::new(0), alice);
+    let (token_x, token_y) = create_tokens!(app, initial_amount, initial_amount, alice);
+
+    let fee_tier = FeeTier::new(Percentage::from_scale(6, 3), 10).unwrap();
+    add_fee_tier!(app, dex, fee_tier, alice).unwrap();
+    create_pool!(
+        app,
+        dex,
+        token_x,
+        token_y,
+        fee_tier,
+        calculate_sqrt_price(0).unwrap(),
+        0,
+        alice
+    )
+    .unwrap();
+    let pool_key = PoolKey::new(token_x.to_string(), token_y.to_string(), fee_tier).unwrap();
+
+    // bob provides the liquidity the internal swaps go through
+    for token in [&token_x, &token_y] {
+        mint!(app, token, bob, initial_amount / 2, alice).unwrap();
+        approve!(app, token, dex, initial_amount / 2, bob).unwrap();
+        approve!(app, token, dex, initial_amount / 2, alice).unwrap();
+    }
+    create_position!(
+        app,
+        dex,
+        pool_key,
+        -1000,
+        1000,
+        Liquidity::from_integer(1_000_000_000),
+        SqrtPrice::new(MIN_SQRT_PRICE),
+        SqrtPrice::new(MAX_SQRT_PRICE),
+        bob
+    )
+    .unwrap();
+    create_position!(
+        app,
+        dex,
+        pool_key,
+        -100,
+        100,
+        Liquidity::from_integer(100_000_000),
+        SqrtPrice::new(MIN_SQRT_PRICE),
+        SqrtPrice::new(MAX_SQRT_PRICE),
+        alice
+    )
+    .unwrap();
+    let position_before = get_position!(app, dex, 0, alice).unwrap();
+
+    let error = rebalance!(
+        app,
+        dex,
+        0,
+        105,
+        300,
+        Percentage::from_scale(1, 2),
+        SqrtPrice::new(MIN_SQRT_PRICE),
+        SqrtPrice::new(MAX_SQRT_PRICE),
+        alice
+    )
+    .unwrap_err();
+    assert!(error
+        .root_cause()
+        .to_string()
+        .contains(&ContractError::InvalidTickSpacing.to_string()));
+
+    // the whole position has to be swapped into token x, which pays the pool fee
+    let error = rebalance!(
+        app,
+        dex,
+        0,
+        100,
+        300,
+        Percentage::new(0),
+        SqrtPrice::new(MIN_SQRT_PRICE),
+        SqrtPrice::new(MAX_SQRT_PRICE),
+        alice
+    )
+    .unwrap_err();
+    assert!(error
+        .root_cause()
+        .to_string()
+        .contains(&ContractError::AmountUnderMinimumAmountOut.to_string()));
+
+    // the price is out of the limits of the caller
+    let pool = get_pool!(app, dex, token_x, token_y, fee_tier).unwrap();
+    let error = rebalance!(
+        app,
+        dex,
+        0,
+        100,
+        300,
+        Percentage::from_scale(1, 2),
+        pool.sqrt_price + SqrtPrice::new(1),
+        SqrtPrice::new(MAX_SQRT_PRICE),
+        alice
+    )
+    .unwrap_err();
+    assert!(error
+        .root_cause()
+        .to_string()
+        .contains(&ContractError::PriceLimitReached.to_string()));
+
+    // the internal swap pushes the price above the limits of the caller
+    let error = rebalance!(
+        app,
+        dex,
+        0,
+        100,
+        300,
+        Percentage::from_scale(1, 2),
+        SqrtPrice::new(MIN_SQRT_PRICE),
+        pool.sqrt_price,
+        alice
+    )
+    .unwrap_err();
+    assert!(error
+        .root_cause()
+        .to_string()
+        .contains(&ContractError::PriceLimitReached.to_string()));
+
+    let alice_x = balance_of!(app, token_x, alice);
+    let alice_y = balance_of!(app, token_y, alice);
+    rebalance!(
+        app,
+        dex,
+        0,
+        100,
+        300,
+        Percentage::from_scale(1, 2),
+        SqrtPrice::new(MIN_SQRT_PRICE),
+        SqrtPrice::new(MAX_SQRT_PRICE),
+        alice
+    )
+    .unwrap();
+
+    let position = get_position!(app, dex, 0, alice).unwrap();
+    assert_eq!(position.token_id, position_before.token_id);
+    assert_eq!(position.lower_tick_index, 100);
+    assert_eq!(position.upper_tick_index, 300);
+    assert!(!position.liquidity.is_zero());
+    assert_eq!(get_all_positions!(app, dex, alice).len(), 1);
+
+    // only the dust that could not be deposited comes back
+    assert!(balance_of!(app, token_x, alice) - alice_x <= 10);
+    assert_eq!(balance_of!(app, token_y, alice), alice_y);
+
+    // the old lower tick is not used anymore, the shared one is kept
+    assert!(!is_tick_initialized!(app, dex, pool_key, -100));
+    assert!(is_tick_initialized!(app, dex, pool_key, 100));
+    assert!(is_tick_initialized!(app, dex, pool_key, 300));
+
+    // back around the current price, part of the tokens is swapped back
+    rebalance!(
+        app,
+        dex,
+        0,
+        -50,
+        50,
+        Percentage::from_scale(1, 2),
+        SqrtPrice::new(MIN_SQRT_PRICE),
+        SqrtPrice::new(MAX_SQRT_PRICE),
+        alice
+    )
+    .unwrap();
+    let position = get_position!(app, dex, 0, alice).unwrap();
+    assert_eq!(position.token_id, position_before.token_id);
+    assert_eq!(
+        (position.lower_tick_index, position.upper_tick_index),
+        (-50, 50)
+    );
+    assert!(!is_tick_initialized!(app, dex, pool_key, 100));
+    assert!(!is_tick_initialized!(app, dex, pool_key, 300));
+
+    let pool = get_pool!(app, dex, token_x, token_y, fee_tier).unwrap();
+    let bob_position = get_position!(app, dex, 0, bob).unwrap();
+    assert_eq!(pool.liquidity, bob_position.liquidity + position.liquidity);
+
+    let error = rebalance!(
+        app,
+        dex,
+        1,
+        -50,
+        50,
+        Percentage::from_scale(1, 2),
+        SqrtPrice::new(MIN_SQRT_PRICE),
+        SqrtPrice::new(MAX_SQRT_PRICE),
+        alice
+    )
+    .unwrap_err();
+    assert!(error.root_cause().to_string().contains("not found"));
+}
@@ -0,0 +1,204 @@
+use cosmwasm_std::coins;
+use decimal::{Decimal, Factories};
+use oraiswap_v3_common::{
+    error::ContractError,
+    interface::SwapHop,
+    math::{
+        liquidity::Liquidity, percentage::Percentage, sqrt_price::calculate_sqrt_price,
+        sqrt_price::SqrtPrice, token_amount::TokenAmount, MAX_SQRT_PRICE, MIN_SQRT_PRICE,
+    },
+    oraiswap_v3_msg::Cw20HookMsg,
+    storage::{FeeTier, PoolKey},
+};
+
+use crate::tests::helper::{macros::*, MockApp, FEE_DENOM};
+
+#[test]
+fn test_receive_swap() {
+    let (mut app, accounts) = MockApp::new(&[
+        ("alice", &coins(100_000_000_000, FEE_DENOM)),
+        ("bob", &coins(100_000_000_000, FEE_DENOM)),
+    ]);
+    let alice = &accounts[0];
+    let bob = &accounts[1];
+    let (dex, token_x, token_y) = init_dex_and_tokens!(app, alice);
+    init_basic_pool!(app, dex, token_x, token_y, alice);
+    init_basic_position!(app, dex, token_x, token_y, alice);
+
+    let fee_tier = FeeTier::new(Percentage::from_scale(6, 3), 10).unwrap();
+    let pool_key = PoolKey::new(token_x.to_string(), token_y.to_string(), fee_tier).unwrap();
+
+    mint!(app, token_x, bob, 1000, alice).unwrap();
+    mint!(app, token_y, bob, 1000, alice).unwrap();
+
+    let hook = Cw20HookMsg::Swap {
+        pool_key: pool_key.clone(),
+        x_to_y: true,
+        sqrt_price_limit: SqrtPrice::new(MIN_SQRT_PRICE),
+    };
+    let quote = quote!(
+        app,
+        dex,
+        pool_key,
+        true,
+        TokenAmount(1000),
+        true,
+        SqrtPrice::new(MIN_SQRT_PRICE)
+    )
+    .unwrap();
+
+    // the swap does not take the token sent
+    let error = send_with_hook!(app, token_y, dex, 1000, hook, bob).unwrap_err();
+    assert!(error.root_cause().to_string().contains(
+        &ContractError::UnexpectedFunds {
+            denom: token_y.to_string()
+        }
+        .to_string()
+    ));
+
+    // no allowance is needed
+    let dex_x_before = balance_of!(app, token_x, dex);
+    send_with_hook!(app, token_x, dex, 1000, hook, bob).unwrap();
+    assert_eq!(balance_of!(app, token_x, bob), 0);
+    assert_eq!(
+        balance_of!(app, token_y, bob),
+        1000 + quote.amount_out.get()
+    );
+    assert_eq!(balance_of!(app, token_x, dex), dex_x_before + 1000);
+}
+
+#[test]
+fn test_receive_swap_route() {
+    let (mut app, accounts) = MockApp::new(&[
+        ("alice", &coins(100_000_000_000, FEE_DENOM)),
+        ("bob", &coins(100_000_000_000, FEE_DENOM)),
+    ]);
+    let alice = &accounts[0];
+    let bob = &accounts[1];
+    let initial_amount = 10u128.pow(10);
+    let dex = create_dex!(app, Percentage::from_scale(1, 2), alice);
+    let (token_x, token_y, token_z) =
+        create_3_tokens!(app, initial_amount, initial_amount, initial_amount, alice);
+
+    let fee_tier = FeeTier::new(Percentage::from_scale(6, 3), 10).unwrap();
+    add_fee_tier!(app, dex, fee_tier, alice).unwrap();
+    for token in [&token_x, &token_y, &token_z] {
+        approve!(app, token, dex, initial_amount, alice).unwrap();
+    }
+
+    let mut pool_keys = vec![];
+    for (token_0, token_1) in [(&token_x, &token_y), (&token_y, &token_z)] {
+        create_pool!(
+            app,
+            dex,
+            token_0,
+            token_1,
+            fee_tier,
+            calculate_sqrt_price(0).unwrap(),
+            0,
+            alice
+        )
+        .unwrap();
+        let pool_key = PoolKey::new(token_0.to_string(), token_1.to_string(), fee_tier).unwrap();
+        create_position!(
+            app,
+            dex,
+            pool_key,
+            -1000,
+            1000,
+            Liquidity::from_integer(1_000_000_000),
+            SqrtPrice::new(MIN_SQRT_PRICE),
+            SqrtPrice::new(MAX_SQRT_PRICE),
+            alice
+        )
+        .unwrap();
+        pool_keys.push(pool_key);
+    }
+
+    mint!(app, token_x, bob, 1000, alice).unwrap();
+
+    let swaps = vec![
+        SwapHop {
+            pool_key: pool_keys[0].clone(),
+            x_to_y: true,
+        },
+        SwapHop {
+            pool_key: pool_keys[1].clone(),
+            x_to_y: true,
+        },
+    ];
+    let expected_amount_out = quote_route!(app, dex, TokenAmount(1000), swaps.clone()).unwrap();
+
+    send_with_hook!(
+        app,
+        token_x,
+        dex,
+        1000,
+        Cw20HookMsg::SwapRoute {
+            expected_amount_out,
+            slippage: Percentage::new(0),
+            swaps,
+        },
+        bob
+    )
+    .unwrap();
+    assert_eq!(balance_of!(app, token_x, bob), 0);
+    assert_eq!(balance_of!(app, token_y, bob), 0);
+    assert_eq!(balance_of!(app, token_z, bob), expected_amount_out.get());
+}
+
+#[test]
+fn test_receive_create_position() {
+    let (mut app, accounts) = MockApp::new(&[
+        ("alice", &coins(100_000_000_000, FEE_DENOM)),
+        ("bob", &coins(100_000_000_000, FEE_DENOM)),
+    ]);
+    let alice = &accounts[0];
+    let bob = &accounts[1];
+    let (dex, token_x, token_y) = init_dex_and_tokens!(app, alice);
+    init_basic_pool!(app, dex, token_x, token_y, alice);
+
+    let fee_tier = FeeTier::new(Percentage::from_scale(6, 3), 10).unwrap();
+    let pool_key = PoolKey::new(token_x.to_string(), token_y.to_string(), fee_tier).unwrap();
+
+    mint!(app, token_x, bob, 1000, alice).unwrap();
+    mint!(app, token_y, bob, 1000, alice).unwrap();
+
+    let hook = |lower_tick: i32, upper_tick: i32| Cw20HookMsg::CreatePosition {
+        pool_key: pool_key.clone(),
+        lower_tick,
+        upper_tick,
+        liquidity_delta: Liquidity::from_integer(100000),
+        slippage_limit_lower: SqrtPrice::new(MIN_SQRT_PRICE),
+        slippage_limit_upper: SqrtPrice::new(MAX_SQRT_PRICE),
+        limit_order: None,
+    };
+
+    // above the current tick only token x is needed, the excess is sent back
+    send_with_hook!(app, token_x, dex, 1000, hook(10, 20), bob).unwrap();
+    let bob_x = balance_of!(app, token_x, bob);
+    assert!(bob_x > 0 && bob_x < 1000);
+    assert_eq!(balance_of!(app, token_x, dex), 1000 - bob_x);
+    assert_eq!(balance_of!(app, token_y, bob), 1000);
+
+    let position = get_position!(app, dex, 0, bob).unwrap();
+    assert_eq!(position.pool_key, pool_key);
+    assert_eq!(position.liquidity, Liquidity::from_integer(100000));
+
+    // around the current tick the other token comes from the allowance
+    let error = send_with_hook!(app, token_x, dex, 100, hook(-10, 10), bob).unwrap_err();
+    assert!(error.root_cause().to_string().contains("allowance"));
+
+    approve!(app, token_y, dex, 1000, bob).unwrap();
+    send_with_hook!(app, token_x, dex, 100, hook(-10, 10), bob).unwrap();
+    assert!(balance_of!(app, token_x, bob) > bob_x - 100);
+    assert!(balance_of!(app, token_y, bob) < 1000);
+    assert_eq!(get_all_positions!(app, dex, bob).len(), 2);
+
+    // not enough tokens sent
+    let error = send_with_hook!(app, token_x, dex, 1, hook(10, 20), bob).unwrap_err();
+    assert!(error
+        .root_cause()
+        .to_string()
+        .contains("require minimum amount"));
+}
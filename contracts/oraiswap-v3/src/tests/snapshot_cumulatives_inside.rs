@@ -0,0 +1,100 @@
+use cosmwasm_std::coins;
+use decimal::{Decimal, Factories};
+use oraiswap_v3_common::{
+    error::ContractError,
+    math::{
+        liquidity::Liquidity, percentage::Percentage, seconds_per_liquidity::SecondsPerLiquidity,
+        sqrt_price::SqrtPrice,
+    },
+    storage::{FeeTier, PoolKey},
+};
+
+use crate::tests::helper::{macros::*, MockApp, FEE_DENOM};
+
+#[test]
+fn test_snapshot_cumulatives_inside() {
+    let (mut app, accounts) = MockApp::new(&[("alice", &coins(100_000_000_000, FEE_DENOM))]);
+    let alice = &accounts[0];
+    let (dex, token_x, token_y) = init_dex_and_tokens!(app, alice);
+    init_basic_pool!(app, dex, token_x, token_y, alice);
+    init_basic_position!(app, dex, token_x, token_y, alice);
+
+    let fee_tier = FeeTier::new(Percentage::from_scale(6, 3), 10).unwrap();
+    let pool_key = PoolKey::new(token_x.to_string(), token_y.to_string(), fee_tier).unwrap();
+
+    // position above the current price
+    let liquidity = Liquidity::from_integer(1000000);
+    create_position!(
+        app,
+        dex,
+        pool_key,
+        10,
+        20,
+        liquidity,
+        SqrtPrice::new(0),
+        SqrtPrice::max_instance(),
+        alice
+    )
+    .unwrap();
+
+    let in_range_before = snapshot_cumulatives_inside!(app, dex, pool_key, -20, 10).unwrap();
+    let out_of_range_before = snapshot_cumulatives_inside!(app, dex, pool_key, 10, 20).unwrap();
+    let full_range_before = snapshot_cumulatives_inside!(app, dex, pool_key, -20, 20).unwrap();
+
+    app.increase_time(100);
+
+    let in_range_after = snapshot_cumulatives_inside!(app, dex, pool_key, -20, 10).unwrap();
+    let out_of_range_after = snapshot_cumulatives_inside!(app, dex, pool_key, 10, 20).unwrap();
+    let full_range_after = snapshot_cumulatives_inside!(app, dex, pool_key, -20, 20).unwrap();
+
+    // only the liquidity of the first position was in range
+    let expected =
+        SecondsPerLiquidity::calculate_seconds_per_liquidity_global(liquidity, 100, 0).unwrap();
+    assert_eq!(
+        in_range_after
+            .seconds_per_liquidity_inside
+            .unchecked_sub(in_range_before.seconds_per_liquidity_inside),
+        expected
+    );
+    assert_eq!(
+        out_of_range_after
+            .seconds_per_liquidity_inside
+            .unchecked_sub(out_of_range_before.seconds_per_liquidity_inside),
+        SecondsPerLiquidity::new(0)
+    );
+    assert_eq!(
+        full_range_after
+            .seconds_per_liquidity_inside
+            .unchecked_sub(full_range_before.seconds_per_liquidity_inside),
+        expected
+    );
+
+    // the pool accumulator is persisted once the liquidity changes
+    remove_position!(app, dex, 1, alice).unwrap();
+    let pool = get_pool!(app, dex, token_x, token_y, fee_tier).unwrap();
+    assert_eq!(
+        pool.seconds_per_liquidity_global,
+        in_range_after.seconds_per_liquidity_inside
+    );
+}
+
+#[test]
+fn test_snapshot_cumulatives_inside_errors() {
+    let (mut app, accounts) = MockApp::new(&[("alice", &coins(100_000_000_000, FEE_DENOM))]);
+    let alice = &accounts[0];
+    let (dex, token_x, token_y) = init_dex_and_tokens!(app, alice);
+    init_basic_pool!(app, dex, token_x, token_y, alice);
+    init_basic_position!(app, dex, token_x, token_y, alice);
+
+    let fee_tier = FeeTier::new(Percentage::from_scale(6, 3), 10).unwrap();
+    let pool_key = PoolKey::new(token_x.to_string(), token_y.to_string(), fee_tier).unwrap();
+
+    let error = snapshot_cumulatives_inside!(app, dex, pool_key, 10, -20).unwrap_err();
+    assert!(error
+        .to_string()
+        .contains(&ContractError::InvalidTickIndex {}.to_string()));
+
+    // ticks must be initialized
+    let error = snapshot_cumulatives_inside!(app, dex, pool_key, -20, 20);
+    assert!(error.is_err());
+}
@@ -0,0 +1,134 @@
+use cosmwasm_std::{coins, Addr};
+use decimal::{Decimal, Factories};
+use oraiswap_v3_common::{
+    error::ContractError,
+    interface::SwapHop,
+    math::{
+        liquidity::Liquidity, percentage::Percentage, sqrt_price::calculate_sqrt_price,
+        sqrt_price::SqrtPrice, token_amount::TokenAmount, MAX_SQRT_PRICE, MIN_SQRT_PRICE,
+    },
+    oraiswap_v3_msg::Cw20HookMsg,
+    storage::{FeeTier, PoolKey},
+};
+
+use crate::tests::helper::{macros::*, MockApp, FEE_DENOM};
+
+// x -> y -> z through two pools with liquidity around tick 0
+fn init_route(app: &mut MockApp, alice: &str) -> (Addr, Addr, Addr, Addr, Vec<SwapHop>) {
+    let initial_amount = 10u128.pow(10);
+    let dex = create_dex!(app, Percentage::from_scale(1, 2), alice);
+    let (token_x, token_y, token_z) =
+        create_3_tokens!(app, initial_amount, initial_amount, initial_amount, alice);
+
+    let fee_tier = FeeTier::new(Percentage::from_scale(6, 3), 10).unwrap();
+    add_fee_tier!(app, dex, fee_tier, alice).unwrap();
+    for token in [&token_x, &token_y, &token_z] {
+        approve!(app, token, dex, initial_amount, alice).unwrap();
+    }
+
+    let mut swaps = vec![];
+    for (token_0, token_1) in [(&token_x, &token_y), (&token_y, &token_z)] {
+        create_pool!(
+            app,
+            dex,
+            token_0,
+            token_1,
+            fee_tier,
+            calculate_sqrt_price(0).unwrap(),
+            0,
+            alice
+        )
+        .unwrap();
+        let pool_key = PoolKey::new(token_0.to_string(), token_1.to_string(), fee_tier).unwrap();
+        create_position!(
+            app,
+            dex,
+            pool_key,
+            -1000,
+            1000,
+            Liquidity::from_integer(1_000_000_000),
+            SqrtPrice::new(MIN_SQRT_PRICE),
+            SqrtPrice::new(MAX_SQRT_PRICE),
+            alice
+        )
+        .unwrap();
+        swaps.push(SwapHop {
+            pool_key,
+            x_to_y: true,
+        });
+    }
+
+    (dex, token_x, token_y, token_z, swaps)
+}
+
+#[test]
+fn test_swap_route_exact_out() {
+    let (mut app, accounts) = MockApp::new(&[
+        ("alice", &coins(100_000_000_000, FEE_DENOM)),
+        ("bob", &coins(100_000_000_000, FEE_DENOM)),
+    ]);
+    let alice = &accounts[0];
+    let bob = &accounts[1];
+    let (dex, token_x, token_y, token_z, swaps) = init_route(&mut app, alice);
+
+    mint!(app, token_x, bob, 1000, alice).unwrap();
+    approve!(app, token_x, dex, 1000, bob).unwrap();
+
+    let amount_out = TokenAmount(500);
+    let amount_in = quote_route_exact_out!(app, dex, amount_out, swaps.clone()).unwrap();
+    assert!(amount_in > amount_out);
+    // the same input walked forward gives at least the amount out
+    let forward = quote_route!(app, dex, amount_in, swaps.clone()).unwrap();
+    assert!(forward >= amount_out);
+
+    let error = swap_route_exact_out!(
+        app,
+        dex,
+        amount_out,
+        TokenAmount(amount_in.get() - 1),
+        swaps.clone(),
+        bob
+    )
+    .unwrap_err();
+    assert!(error
+        .root_cause()
+        .to_string()
+        .contains(&ContractError::AmountOverMaximumAmountIn.to_string()));
+
+    let dex_y = balance_of!(app, token_y, dex);
+    swap_route_exact_out!(app, dex, amount_out, TokenAmount(1000), swaps, bob).unwrap();
+    assert_eq!(balance_of!(app, token_x, bob), 1000 - amount_in.get());
+    assert_eq!(balance_of!(app, token_y, bob), 0);
+    assert_eq!(balance_of!(app, token_z, bob), amount_out.get());
+    // the intermediate token bought by the first hop is all spent by the second one
+    assert_eq!(balance_of!(app, token_y, dex), dex_y);
+}
+
+#[test]
+fn test_swap_route_exact_out_receive() {
+    let (mut app, accounts) = MockApp::new(&[
+        ("alice", &coins(100_000_000_000, FEE_DENOM)),
+        ("bob", &coins(100_000_000_000, FEE_DENOM)),
+    ]);
+    let alice = &accounts[0];
+    let bob = &accounts[1];
+    let (dex, token_x, _, token_z, swaps) = init_route(&mut app, alice);
+
+    mint!(app, token_x, bob, 1000, alice).unwrap();
+
+    let amount_out = TokenAmount(500);
+    let amount_in = quote_route_exact_out!(app, dex, amount_out, swaps.clone()).unwrap();
+
+    // the tokens sent are the max amount in, the rest is sent back
+    send_with_hook!(
+        app,
+        token_x,
+        dex,
+        1000,
+        Cw20HookMsg::SwapRouteExactOut { amount_out, swaps },
+        bob
+    )
+    .unwrap();
+    assert_eq!(balance_of!(app, token_x, bob), 1000 - amount_in.get());
+    assert_eq!(balance_of!(app, token_z, bob), amount_out.get());
+}
@@ -6,7 +6,9 @@ use crate::msg::{ExecuteMsg, InstantiateMsg, MigrateMsg, QueryMsg};
 use crate::state::CONFIG;
 use crate::{entrypoints::*, Config};
 
-use cosmwasm_std::{to_json_binary, Binary, Deps, DepsMut, Env, MessageInfo, Response, StdResult};
+use cosmwasm_std::{
+    to_json_binary, Binary, Deps, DepsMut, Env, MessageInfo, Reply, Response, StdResult,
+};
 use cw2::set_contract_version;
 
 // version info for migration info
@@ -14,9 +16,10 @@ const CONTRACT_NAME: &str = "crates.io:zapper";
 const CONTRACT_VERSION: &str = env!("CARGO_PKG_VERSION");
 
 // id for each reply
-pub const ZAP_IN_LIQUIDITY_REPLY_ID: u64 = 1;
+pub const ZAP_IN_SWAP_REPLY_ID: u64 = 1;
 pub const ZAP_OUT_LIQUIDITY_REPLY_ID: u64 = 2;
 pub const ADD_LIQUIDITY_REPLY_ID: u64 = 3;
+pub const ZAP_OUT_SWAP_REPLY_ID: u64 = 4;
 
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn instantiate(
@@ -67,13 +70,47 @@ pub fn execute(
             routes,
             minimum_liquidity,
         ),
-        ExecuteMsg::ZapInAfterSwapOperation {} => internal::zap_in_liquidity(deps, env, info),
-        ExecuteMsg::RefundAfterZapInLiquidity {} => internal::refund_after_zap_in(deps, env, info),
+        ExecuteMsg::ZapInLiquidityAuto {
+            pool_key,
+            tick_lower_index,
+            tick_upper_index,
+            asset_in,
+            operations,
+            slippage_limit_lower,
+            slippage_limit_upper,
+            minimum_liquidity,
+        } => zap_in_liquidity_auto(
+            deps,
+            env,
+            info,
+            pool_key,
+            tick_lower_index,
+            tick_upper_index,
+            asset_in,
+            operations,
+            slippage_limit_lower,
+            slippage_limit_upper,
+            minimum_liquidity,
+        ),
+        ExecuteMsg::ZapInToPosition {
+            token_id,
+            asset_in,
+            routes,
+            minimum_liquidity,
+        } => zap_in_to_position(
+            deps,
+            env,
+            info,
+            token_id,
+            asset_in,
+            routes,
+            minimum_liquidity,
+        ),
         ExecuteMsg::ZapOutLiquidity {
             position_index,
             routes,
-        } => zap_out_liquidity(deps, env, info, position_index, routes),
-        ExecuteMsg::ZapOutAfterSwapOperation {} => internal::zap_out_liquidity(deps, env, info),
+            percentage,
+        } => zap_out_liquidity(deps, env, info, position_index, routes, percentage),
         ExecuteMsg::RegisterProtocolFee {
             percent,
             fee_receiver,
@@ -82,11 +119,48 @@ pub fn execute(
     }
 }
 
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn reply(deps: DepsMut, env: Env, msg: Reply) -> Result<Response, ContractError> {
+    match msg.id {
+        ZAP_IN_SWAP_REPLY_ID => internal::zap_in_swap_reply(deps, env, msg),
+        ZAP_OUT_LIQUIDITY_REPLY_ID => internal::zap_out_liquidity_reply(deps, env, msg),
+        ADD_LIQUIDITY_REPLY_ID => internal::add_liquidity_reply(deps, msg),
+        ZAP_OUT_SWAP_REPLY_ID => internal::zap_out_swap_reply(deps, env, msg),
+        id => Err(ContractError::UnrecognizedReplyId { id }),
+    }
+}
+
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn query(deps: Deps, _env: Env, msg: QueryMsg) -> StdResult<Binary> {
     match msg {
         QueryMsg::Config {} => to_json_binary(&get_config(deps)?),
         QueryMsg::ProtocolFee {} => to_json_binary(&get_protocol_fee(deps)?),
+        QueryMsg::SimulateZapIn {
+            pool_key,
+            tick_lower_index,
+            tick_upper_index,
+            asset_in,
+            routes,
+        } => to_json_binary(&simulate_zap_in(
+            deps,
+            pool_key,
+            tick_lower_index,
+            tick_upper_index,
+            asset_in,
+            routes,
+        )?),
+        QueryMsg::SimulateZapOut {
+            owner,
+            position_index,
+            routes,
+            percentage,
+        } => to_json_binary(&simulate_zap_out(
+            deps,
+            owner,
+            position_index,
+            routes,
+            percentage,
+        )?),
     }
 }
 
@@ -1,15 +1,25 @@
+use std::str::FromStr;
+
 use cosmwasm_std::{
-    coins, to_json_binary, wasm_execute, Addr, Api, CosmosMsg, MessageInfo, QuerierWrapper,
-    Uint128, WasmMsg,
+    coins, to_json_binary, wasm_execute, Addr, Api, CosmosMsg, Event, MessageInfo, QuerierWrapper,
+    StdError, SubMsg, Uint128, WasmMsg,
 };
 use cw20::Cw20ExecuteMsg;
 use oraiswap_v3_common::{
-    asset::{Asset, AssetInfo},
+    asset::{transfer_from_sender, Asset, AssetInfo},
     error::ContractError,
-    storage::PoolKey,
+    interface::SwapHop,
+    logic::{get_liquidity_by_x, get_liquidity_by_y, SingleTokenLiquidity},
+    math::{liquidity::Liquidity, percentage::Percentage, token_amount::TokenAmount},
+    oraiswap_v3_msg::{
+        Cw20HookMsg as V3Cw20HookMsg, ExecuteMsg as V3ExecuteMsg, QueryMsg as V3QueryMsg,
+    },
+    storage::{Pool, PoolKey},
 };
 
-use oraiswap::mixed_router::{self, Affiliate, SwapOperation};
+use oraiswap::mixed_router::{self, Affiliate, SimulateSwapOperationsResponse, SwapOperation};
+
+use crate::{msg::RouteOperation, Config, PendingSwap, RouteSwap, RouteSwaps};
 
 pub fn get_pool_v3_asset_info(api: &dyn Api, pool_key: &PoolKey) -> (AssetInfo, AssetInfo) {
     (
@@ -18,25 +28,15 @@ pub fn get_pool_v3_asset_info(api: &dyn Api, pool_key: &PoolKey) -> (AssetInfo,
     )
 }
 
+/// Pulls the zapped asset from the sender: cw20 tokens through their allowance, native coins must
+/// be sent with the message, the coins sent above the amount are refunded.
 pub fn validate_fund(
-    querier: &QuerierWrapper,
     info: &MessageInfo,
     address: String,
     asset: Asset,
     msgs: &mut Vec<CosmosMsg>,
 ) -> Result<(), ContractError> {
-    match asset.info.clone() {
-        AssetInfo::Token { contract_addr: _ } => {
-            asset.transfer_from(msgs, info, address)?;
-        }
-        AssetInfo::NativeToken { denom: _ } => {
-            let balance = asset.info.balance(querier, address)?;
-            if balance < asset.amount {
-                return Err(ContractError::NoFundSent {});
-            }
-        }
-    }
-    Ok(())
+    transfer_from_sender(msgs, info, None, address, &[asset])
 }
 
 pub fn build_swap_msg(
@@ -75,3 +75,396 @@ pub fn build_swap_msg(
         )?),
     }
 }
+
+pub fn build_v3_swap_msg(
+    asset: &AssetInfo,
+    dex_v3: Addr,
+    amount: Uint128,
+    swaps: Vec<SwapHop>,
+    minimum_receive: Option<Uint128>,
+) -> Result<WasmMsg, ContractError> {
+    // the expected amount out is the minimum to receive, so no slippage on top of it
+    let expected_amount_out = TokenAmount(minimum_receive.unwrap_or_default().u128());
+    match asset {
+        AssetInfo::Token { contract_addr } => Ok(wasm_execute(
+            contract_addr,
+            &Cw20ExecuteMsg::Send {
+                contract: dex_v3.to_string(),
+                amount,
+                msg: to_json_binary(&V3Cw20HookMsg::SwapRoute {
+                    expected_amount_out,
+                    slippage: Percentage(0),
+                    swaps,
+                })?,
+            },
+            vec![],
+        )?),
+        AssetInfo::NativeToken { denom } => Ok(wasm_execute(
+            dex_v3,
+            &V3ExecuteMsg::SwapRoute {
+                amount_in: TokenAmount(amount.u128()),
+                expected_amount_out,
+                slippage: Percentage(0),
+                swaps,
+            },
+            coins(amount.u128(), denom),
+        )?),
+    }
+}
+
+/// Splits the operations of a route after the first ones swapped on the same venue.
+fn split_route(mut operations: Vec<RouteOperation>) -> (Vec<RouteOperation>, Vec<RouteOperation>) {
+    let is_v3 = matches!(operations.first(), Some(RouteOperation::V3(_)));
+    let len = operations
+        .iter()
+        .position(|operation| matches!(operation, RouteOperation::V3(_)) != is_v3)
+        .unwrap_or(operations.len());
+    let rest = operations.split_off(len);
+    (operations, rest)
+}
+
+/// Returns the token a route operation swaps into.
+pub fn route_operation_output(api: &dyn Api, operation: &RouteOperation) -> AssetInfo {
+    match operation {
+        RouteOperation::V3(hop) => {
+            let (token_x, token_y) = get_pool_v3_asset_info(api, &hop.pool_key);
+            if hop.x_to_y {
+                token_y
+            } else {
+                token_x
+            }
+        }
+        RouteOperation::MixedRouter(operation) => match operation {
+            SwapOperation::OraiSwap { ask_asset_info, .. } => {
+                AssetInfo::from_denom(api, &ask_asset_info.to_string())
+            }
+            SwapOperation::SwapV3 { pool_key, x_to_y } => {
+                let (token_x, token_y) = get_pool_v3_asset_info(api, pool_key);
+                if *x_to_y {
+                    token_y
+                } else {
+                    token_x
+                }
+            }
+        },
+    }
+}
+
+/// Builds the submessage swapping the first operations of `swap` on the same venue, the tokens
+/// they return stay in this contract. The returned `PendingSwap` takes them from the events of
+/// the reply of the submessage.
+pub fn build_route_swap(
+    api: &dyn Api,
+    config: &Config,
+    swap: RouteSwap,
+    reply_id: u64,
+) -> Result<(SubMsg, PendingSwap), ContractError> {
+    let (run, rest) = split_route(swap.operations);
+    let token_out = match run.last() {
+        Some(operation) => route_operation_output(api, operation),
+        None => return Err(ContractError::MissingRouteSwap {}),
+    };
+    // the minimum is checked on the swap ending the route
+    let minimum_receive = if rest.is_empty() {
+        swap.minimum_receive
+    } else {
+        None
+    };
+
+    let swap_msg = if let Some(RouteOperation::V3(_)) = run.first() {
+        let swaps = run
+            .into_iter()
+            .filter_map(|operation| match operation {
+                RouteOperation::V3(hop) => Some(hop),
+                RouteOperation::MixedRouter(_) => None,
+            })
+            .collect();
+        build_v3_swap_msg(
+            &swap.token_in,
+            config.dex_v3.clone(),
+            swap.amount,
+            swaps,
+            minimum_receive,
+        )?
+    } else {
+        let operations = run
+            .into_iter()
+            .filter_map(|operation| match operation {
+                RouteOperation::MixedRouter(operation) => Some(operation),
+                RouteOperation::V3(_) => None,
+            })
+            .collect();
+        build_swap_msg(
+            &swap.token_in,
+            config.mixed_router.clone(),
+            swap.amount,
+            operations,
+            minimum_receive,
+            None,
+            None,
+        )?
+    };
+
+    Ok((
+        SubMsg::reply_on_success(swap_msg, reply_id),
+        PendingSwap {
+            token_out,
+            operations: rest,
+            minimum_receive: swap.minimum_receive,
+        },
+    ))
+}
+
+/// Settles the pending swap with the `events` of its reply, then builds the submessage of the
+/// next swap if any. A route goes on with what its previous swap returned, the output of a route
+/// once it ends is returned.
+pub fn next_route_swap(
+    api: &dyn Api,
+    contract_addr: &Addr,
+    config: &Config,
+    swaps: &mut RouteSwaps,
+    events: &[Event],
+    reply_id: u64,
+) -> Result<(Option<Asset>, Option<SubMsg>), ContractError> {
+    let mut output = None;
+    if let Some(pending) = swaps.pending.take() {
+        let amount = received_assets(events, contract_addr)?
+            .into_iter()
+            .find(|asset| asset.info.eq(&pending.token_out))
+            .map_or(Uint128::zero(), |asset| asset.amount);
+        let returned = Asset::new(pending.token_out, amount);
+        if pending.operations.is_empty() {
+            output = Some(returned);
+        } else {
+            swaps.queue.insert(
+                0,
+                RouteSwap {
+                    token_in: returned.info,
+                    amount: returned.amount,
+                    operations: pending.operations,
+                    minimum_receive: pending.minimum_receive,
+                },
+            );
+        }
+    }
+
+    if swaps.queue.is_empty() {
+        return Ok((output, None));
+    }
+    let (swap_msg, pending) = build_route_swap(api, config, swaps.queue.remove(0), reply_id)?;
+    swaps.pending = Some(pending);
+
+    Ok((output, Some(swap_msg)))
+}
+
+/// Returns the tokens transferred to `receiver` in the events of a reply, either as cw20
+/// transfers or as bank sends.
+pub fn received_assets(events: &[Event], receiver: &Addr) -> Result<Vec<Asset>, ContractError> {
+    let mut assets: Vec<Asset> = vec![];
+    for event in events {
+        let attribute = |key: &str| {
+            event
+                .attributes
+                .iter()
+                .find(|attribute| attribute.key == key)
+                .map(|attribute| attribute.value.as_str())
+        };
+        match event.ty.as_str() {
+            "wasm" => {
+                if !matches!(
+                    attribute("action"),
+                    Some("transfer" | "transfer_from" | "send" | "send_from")
+                ) || attribute("to") != Some(receiver.as_str())
+                {
+                    continue;
+                }
+                if let (Some(contract_addr), Some(amount)) =
+                    (attribute("_contract_address"), attribute("amount"))
+                {
+                    add_asset(
+                        &mut assets,
+                        Asset::new(
+                            AssetInfo::Token {
+                                contract_addr: Addr::unchecked(contract_addr),
+                            },
+                            Uint128::from_str(amount)?,
+                        ),
+                    );
+                }
+            }
+            "transfer" => {
+                if attribute("recipient") != Some(receiver.as_str()) {
+                    continue;
+                }
+                // the amount lists the coins sent, as "100orai,20ibc/..."
+                for coin in attribute("amount").unwrap_or_default().split(',') {
+                    let (amount, denom) = coin.split_at(
+                        coin.find(|c: char| !c.is_ascii_digit())
+                            .unwrap_or(coin.len()),
+                    );
+                    if denom.is_empty() {
+                        continue;
+                    }
+                    add_asset(
+                        &mut assets,
+                        Asset::new(
+                            AssetInfo::NativeToken {
+                                denom: denom.to_string(),
+                            },
+                            Uint128::from_str(amount)?,
+                        ),
+                    );
+                }
+            }
+            _ => {}
+        }
+    }
+    Ok(assets)
+}
+
+/// Returns the value of the `key` attribute `contract_addr` emitted in the events of a reply.
+pub fn reply_attribute(
+    events: &[Event],
+    contract_addr: &Addr,
+    key: &str,
+) -> Result<String, ContractError> {
+    events
+        .iter()
+        .filter(|event| {
+            event.ty == "wasm"
+                && event.attributes.iter().any(|attribute| {
+                    attribute.key == "_contract_address"
+                        && attribute.value == contract_addr.as_str()
+                })
+        })
+        .flat_map(|event| event.attributes.iter())
+        .find(|attribute| attribute.key == key)
+        .map(|attribute| attribute.value.clone())
+        .ok_or_else(|| {
+            StdError::generic_err(format!("attribute {} not found in the reply", key)).into()
+        })
+}
+
+/// Adds `asset` to the amount of the same token in `assets`.
+pub fn add_asset(assets: &mut Vec<Asset>, asset: Asset) {
+    match assets.iter_mut().find(|a| a.info.eq(&asset.info)) {
+        Some(a) => a.amount += asset.amount,
+        None => assets.push(asset),
+    }
+}
+
+/// Returns the liquidity of a position in the range with at most `x_amount` of token x and
+/// `y_amount` of token y at the current price of the pool.
+pub fn get_zap_in_liquidity(
+    pool: &Pool,
+    lower_tick: i32,
+    upper_tick: i32,
+    x_amount: Uint128,
+    y_amount: Uint128,
+) -> Result<Liquidity, ContractError> {
+    let mut res: SingleTokenLiquidity;
+
+    let is_in_range = lower_tick <= pool.current_tick_index && upper_tick > pool.current_tick_index;
+    if is_in_range {
+        res = get_liquidity_by_x(
+            TokenAmount(x_amount.u128()),
+            lower_tick,
+            upper_tick,
+            pool.sqrt_price,
+            false,
+        )?;
+        if res.amount > TokenAmount(y_amount.u128()) {
+            res = get_liquidity_by_y(
+                TokenAmount(y_amount.u128()),
+                lower_tick,
+                upper_tick,
+                pool.sqrt_price,
+                false,
+            )?;
+        }
+    } else if lower_tick > pool.current_tick_index {
+        res = get_liquidity_by_x(
+            TokenAmount(x_amount.u128()),
+            lower_tick,
+            upper_tick,
+            pool.sqrt_price,
+            false,
+        )?;
+    } else {
+        res = get_liquidity_by_y(
+            TokenAmount(y_amount.u128()),
+            lower_tick,
+            upper_tick,
+            pool.sqrt_price,
+            false,
+        )?;
+    }
+
+    Ok(res.l)
+}
+
+/// Simulates swapping `amount` of `token_in` along the operations of a route, each run of
+/// operations on the same venue is quoted on it. Returns what the route ends with.
+pub fn simulate_route_swap(
+    api: &dyn Api,
+    querier: &QuerierWrapper,
+    config: &Config,
+    token_in: AssetInfo,
+    amount: Uint128,
+    operations: Vec<RouteOperation>,
+    minimum_receive: Option<Uint128>,
+) -> Result<Asset, ContractError> {
+    let mut returned = Asset::new(token_in, amount);
+    let mut rest = operations;
+    while !rest.is_empty() {
+        let (run, next) = split_route(rest);
+        rest = next;
+        let token_out = match run.last() {
+            Some(operation) => route_operation_output(api, operation),
+            None => return Err(ContractError::MissingRouteSwap {}),
+        };
+
+        let amount = if let Some(RouteOperation::V3(_)) = run.first() {
+            let swaps: Vec<SwapHop> = run
+                .into_iter()
+                .filter_map(|operation| match operation {
+                    RouteOperation::V3(hop) => Some(hop),
+                    RouteOperation::MixedRouter(_) => None,
+                })
+                .collect();
+            let amount_out: TokenAmount = querier.query_wasm_smart(
+                config.dex_v3.to_string(),
+                &V3QueryMsg::QuoteRoute {
+                    amount_in: TokenAmount(returned.amount.u128()),
+                    swaps,
+                },
+            )?;
+            Uint128::new(amount_out.0)
+        } else {
+            let operations: Vec<SwapOperation> = run
+                .into_iter()
+                .filter_map(|operation| match operation {
+                    RouteOperation::MixedRouter(operation) => Some(operation),
+                    RouteOperation::V3(_) => None,
+                })
+                .collect();
+            let res: SimulateSwapOperationsResponse = querier.query_wasm_smart(
+                config.mixed_router.to_string(),
+                &mixed_router::QueryMsg::SimulateSwapOperations {
+                    offer_amount: returned.amount,
+                    operations,
+                },
+            )?;
+            res.amount
+        };
+        returned = Asset::new(token_out, amount);
+    }
+
+    if let Some(minimum_receive) = minimum_receive {
+        if returned.amount < minimum_receive {
+            return Err(ContractError::AmountUnderMinimumAmountOut);
+        }
+    }
+
+    Ok(returned)
+}
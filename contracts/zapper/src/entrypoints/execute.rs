@@ -1,23 +1,34 @@
 use cosmwasm_std::{
-    to_json_binary, Addr, CosmosMsg, Decimal, DepsMut, Env, MessageInfo, Response, Uint128, WasmMsg,
+    to_json_binary, Addr, CosmosMsg, Decimal, DepsMut, Env, MessageInfo, Response, SubMsg, Uint128,
+    WasmMsg,
 };
 
 use oraiswap_v3_common::{
-    asset::{Asset, AssetInfo},
+    asset::Asset,
     error::ContractError,
-    math::liquidity::Liquidity,
+    interface::{OwnerOfResponse, QuoteResult, SwapHop},
+    logic::get_rebalance_swap,
+    math::{
+        liquidity::Liquidity,
+        sqrt_price::{get_max_sqrt_price, get_min_sqrt_price, SqrtPrice},
+        token_amount::TokenAmount,
+    },
     oraiswap_v3_msg::{ExecuteMsg as V3ExecuteMsg, QueryMsg as V3QueryMsg},
-    storage::{PoolKey, Position},
+    storage::{Pool, PoolKey, Position},
 };
 
 use crate::{
+    contract::{ZAP_IN_SWAP_REPLY_ID, ZAP_OUT_LIQUIDITY_REPLY_ID},
     entrypoints::common::get_pool_v3_asset_info,
-    msg::{ExecuteMsg, Route},
-    state::{CONFIG, PENDING_POSITION, PROTOCOL_FEE, RECEIVER, SNAP_BALANCES, ZAP_OUT_ROUTES},
-    Config, PairBalance, PendingPosition,
+    msg::{Route, RouteOperation},
+    start_zap,
+    state::{CONFIG, PROTOCOL_FEE, ZAP_IN_OPERATIONS, ZAP_OUT_OPERATIONS},
+    Config, PendingPosition, RouteSwap, RouteSwaps, ZapInOperation, ZapOutOperation,
 };
 
-use super::{build_swap_msg, validate_fund};
+use super::{
+    internal::continue_zap_in, route_operation_output, simulate_route_swap, validate_fund,
+};
 
 pub fn update_config(
     deps: DepsMut,
@@ -85,18 +96,91 @@ pub fn zap_in_liquidity(
     routes: Vec<Route>,
     minimum_liquidity: Option<Liquidity>,
 ) -> Result<Response, ContractError> {
-    // init messages and submessages
-    let mut msgs: Vec<CosmosMsg> = vec![];
+    let position = PendingPosition::new(
+        pool_key,
+        tick_lower_index,
+        tick_upper_index,
+        None,
+        None,
+        None,
+        minimum_liquidity,
+    );
+
+    zap_in(deps, env, info, vec![], position, asset_in, routes)
+}
+
+pub fn zap_in_to_position(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    token_id: u64,
+    asset_in: Asset,
+    routes: Vec<Route>,
+    minimum_liquidity: Option<Liquidity>,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    let owner: OwnerOfResponse = deps.querier.query_wasm_smart(
+        config.dex_v3.to_string(),
+        &V3QueryMsg::OwnerOf {
+            token_id,
+            include_expired: None,
+        },
+    )?;
+    if owner.owner != info.sender {
+        return Err(ContractError::Unauthorized {});
+    }
+    let position: Position = deps.querier.query_wasm_smart(
+        config.dex_v3.to_string(),
+        &V3QueryMsg::PositionByTokenId { token_id },
+    )?;
+
+    // only the owner can add liquidity, so the position is held by this contract meanwhile
+    let msgs = vec![CosmosMsg::Wasm(WasmMsg::Execute {
+        contract_addr: config.dex_v3.to_string(),
+        msg: to_json_binary(&V3ExecuteMsg::TransferNft {
+            token_id,
+            recipient: env.contract.address.clone(),
+        })?,
+        funds: vec![],
+    })];
+
+    let pending_position = PendingPosition {
+        token_id: Some(token_id),
+        ..PendingPosition::new(
+            position.pool_key,
+            position.lower_tick_index,
+            position.upper_tick_index,
+            None,
+            None,
+            None,
+            minimum_liquidity,
+        )
+    };
+
+    zap_in(deps, env, info, msgs, pending_position, asset_in, routes)
+}
+
+fn zap_in(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    msgs: Vec<CosmosMsg>,
+    position: PendingPosition,
+    asset_in: Asset,
+    routes: Vec<Route>,
+) -> Result<Response, ContractError> {
+    let id = start_zap(deps.storage)?;
 
     // transfer the amount or check the fund is sent with request
+    let mut fund_msgs: Vec<CosmosMsg> = vec![];
     validate_fund(
-        &deps.querier,
         &info,
         env.contract.address.to_string(),
         asset_in.clone(),
-        &mut msgs,
+        &mut fund_msgs,
     )?;
 
+    let mut transfer_msgs: Vec<CosmosMsg> = vec![];
     let mut amount_after_fee = asset_in.amount;
     // handle deduct zap in fee
     if let Some(protocol_fee) = PROTOCOL_FEE.may_load(deps.storage)? {
@@ -104,9 +188,11 @@ pub fn zap_in_liquidity(
             let fee_amount = asset_in.amount * protocol_fee.percent;
             amount_after_fee -= fee_amount;
             // transfer fee to fee_receiver
-            asset_in
-                .info
-                .transfer(&mut msgs, protocol_fee.fee_receiver.to_string(), fee_amount)?;
+            asset_in.info.transfer(
+                &mut transfer_msgs,
+                protocol_fee.fee_receiver.to_string(),
+                fee_amount,
+            )?;
         }
     }
 
@@ -121,69 +207,219 @@ pub fn zap_in_liquidity(
         return Err(ContractError::InvalidFund {});
     }
 
-    // load config to get address
-    let config = CONFIG.load(deps.storage)?;
+    // each route must end in a token of the pool
+    let (token_x, token_y) = get_pool_v3_asset_info(deps.api, &position.pool_key);
+    for route in &routes {
+        match route.operations.last() {
+            Some(operation) => {
+                let token_out = route_operation_output(deps.api, operation);
+                if !token_out.eq(&token_x) && !token_out.eq(&token_y) {
+                    return Err(ContractError::ZapInRouteNotToPool {});
+                }
+            }
+            None => return Err(ContractError::MissingRouteSwap {}),
+        }
+    }
 
-    // snap pending position
-    let position_length = deps.querier.query_wasm_smart(
-        config.dex_v3.to_string(),
-        &V3QueryMsg::UserPositionAmount {
-            owner: env.contract.address.clone(),
+    // the amount not swapped goes to the position, or back to the sender if it cannot
+    let amount_left = amount_after_fee - total_swap_amount;
+    let mut amount_x = Uint128::zero();
+    let mut amount_y = Uint128::zero();
+    if asset_in.info.eq(&token_x) {
+        amount_x = amount_left;
+    } else if asset_in.info.eq(&token_y) {
+        amount_y = amount_left;
+    } else if !amount_left.is_zero() {
+        asset_in
+            .info
+            .transfer(&mut transfer_msgs, info.sender.to_string(), amount_left)?;
+    }
+
+    let operation = ZapInOperation {
+        receiver: info.sender,
+        position,
+        amount_x,
+        amount_y,
+        swaps: RouteSwaps {
+            queue: routes
+                .into_iter()
+                .map(|route| RouteSwap {
+                    token_in: asset_in.info.clone(),
+                    amount: route.offer_amount,
+                    operations: route.operations,
+                    minimum_receive: route.minimum_receive,
+                })
+                .collect(),
+            pending: None,
         },
-    )?;
+    };
 
-    let position = PendingPosition::new(
-        position_length,
-        pool_key.clone(),
-        tick_lower_index,
-        tick_upper_index,
-        None,
-        None,
-        None,
-        minimum_liquidity,
-    );
-    PENDING_POSITION.save(deps.storage, &position)?;
+    let response = Response::new().add_messages(msgs);
+    let response = match fund_msgs.pop() {
+        // the zap goes on once the cw20 tokens are received
+        Some(fund_msg) => {
+            ZAP_IN_OPERATIONS.save(deps.storage, id, &operation)?;
+            response.add_submessage(SubMsg::reply_on_success(fund_msg, ZAP_IN_SWAP_REPLY_ID))
+        }
+        None => response.add_submessages(continue_zap_in(deps, &env, id, operation, &[])?),
+    };
 
-    // snap receiver
-    RECEIVER.save(deps.storage, &info.sender)?;
+    Ok(response.add_messages(transfer_msgs))
+}
 
-    // Snap the balance of tokenX and tokenY in this contract
+#[allow(clippy::too_many_arguments)]
+pub fn zap_in_liquidity_auto(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    pool_key: PoolKey,
+    tick_lower_index: i32,
+    tick_upper_index: i32,
+    asset_in: Asset,
+    operations: Vec<RouteOperation>,
+    slippage_limit_lower: SqrtPrice,
+    slippage_limit_upper: SqrtPrice,
+    minimum_liquidity: Option<Liquidity>,
+) -> Result<Response, ContractError> {
+    // the token of the pool the asset is swapped into first, if it is not one of them
     let (token_x, token_y) = get_pool_v3_asset_info(deps.api, &pool_key);
-    let mut balance_x = token_x.balance(&deps.querier, env.contract.address.to_string())?;
-    let mut balance_y = token_y.balance(&deps.querier, env.contract.address.to_string())?;
-
-    if let AssetInfo::NativeToken { denom: _ } = &asset_in.info {
-        if asset_in.info.eq(&token_x) {
-            balance_x -= asset_in.amount;
-        } else if asset_in.info.eq(&token_y) {
-            balance_y -= asset_in.amount;
-        }
+    let token_in = match operations.last() {
+        Some(operation) => route_operation_output(deps.api, operation),
+        None => asset_in.info.clone(),
+    };
+    if !token_in.eq(&token_x) && !token_in.eq(&token_y) {
+        return Err(ContractError::ZapInAssetNotInPool {});
     }
 
-    PairBalance::save(deps.storage, &token_x, balance_x, &token_y, balance_y)?;
+    // the amount left once the protocol fee is deducted, as zap_in does
+    let mut amount_after_fee = asset_in.amount;
+    if let Some(protocol_fee) = PROTOCOL_FEE.may_load(deps.storage)? {
+        amount_after_fee -= asset_in.amount * protocol_fee.percent;
+    }
 
-    // 3. Create SubMsg to process swap operations in mixedRouter contract
-    // 4. Reply on success, if error occurs, revert the state
-    for i in 0..routes.len() {
-        let swap_msg = build_swap_msg(
-            &asset_in.info,
-            config.mixed_router.clone(),
-            routes[i].offer_amount,
-            routes[i].operations.clone(),
-            None,
-            None,
+    let config = CONFIG.load(deps.storage)?;
+    let amount_in = if operations.is_empty() {
+        amount_after_fee
+    } else {
+        simulate_route_swap(
+            deps.api,
+            &deps.querier,
+            &config,
+            asset_in.info.clone(),
+            amount_after_fee,
+            operations.clone(),
             None,
-        )?;
-        msgs.push(CosmosMsg::Wasm(swap_msg));
+        )?
+        .amount
+    };
+    let pool: Pool = deps.querier.query_wasm_smart(
+        config.dex_v3.to_string(),
+        &V3QueryMsg::Pool {
+            token_0: pool_key.token_x.clone(),
+            token_1: pool_key.token_y.clone(),
+            fee_tier: pool_key.fee_tier,
+        },
+    )?;
+    if pool.sqrt_price < slippage_limit_lower || pool.sqrt_price > slippage_limit_upper {
+        return Err(ContractError::PriceLimitReached);
     }
 
-    msgs.push(CosmosMsg::Wasm(WasmMsg::Execute {
-        contract_addr: env.contract.address.to_string(),
-        msg: to_json_binary(&ExecuteMsg::ZapInAfterSwapOperation {})?,
-        funds: vec![],
-    }));
+    // split the tokens between x and y in the ratio the range needs at the current price
+    let (x, y) = if token_in.eq(&token_x) {
+        (amount_in, Uint128::zero())
+    } else {
+        (Uint128::zero(), amount_in)
+    };
+    let mut routes = vec![];
+    match get_rebalance_swap(
+        TokenAmount(x.u128()),
+        TokenAmount(y.u128()),
+        tick_lower_index,
+        tick_upper_index,
+        pool.sqrt_price,
+    )? {
+        Some(swap) => {
+            // the swap must not move the price out of the limits of the caller
+            let tick_spacing = pool_key.fee_tier.tick_spacing;
+            let sqrt_price_limit = if swap.x_to_y {
+                let min_sqrt_price = get_min_sqrt_price(tick_spacing);
+                if slippage_limit_lower > min_sqrt_price {
+                    slippage_limit_lower
+                } else {
+                    min_sqrt_price
+                }
+            } else {
+                let max_sqrt_price = get_max_sqrt_price(tick_spacing);
+                if slippage_limit_upper < max_sqrt_price {
+                    slippage_limit_upper
+                } else {
+                    max_sqrt_price
+                }
+            };
+            let quote: QuoteResult = deps.querier.query_wasm_smart(
+                config.dex_v3.to_string(),
+                &V3QueryMsg::Quote {
+                    pool_key: pool_key.clone(),
+                    x_to_y: swap.x_to_y,
+                    amount: swap.amount_in,
+                    by_amount_in: true,
+                    sqrt_price_limit,
+                },
+            )?;
+
+            // the share of the asset swapped on into the other token of the pool
+            let offer_amount =
+                Uint128::new(swap.amount_in.0).multiply_ratio(amount_after_fee, amount_in);
+            if offer_amount < amount_after_fee && !operations.is_empty() {
+                routes.push(Route {
+                    token_in: asset_in.info.denom(),
+                    offer_amount: amount_after_fee - offer_amount,
+                    operations: operations.clone(),
+                    minimum_receive: None,
+                });
+            }
+            let mut swap_operations = operations;
+            swap_operations.push(
+                SwapHop {
+                    pool_key: pool_key.clone(),
+                    x_to_y: swap.x_to_y,
+                }
+                .into(),
+            );
+            routes.push(Route {
+                token_in: asset_in.info.denom(),
+                offer_amount,
+                minimum_receive: if swap_operations.len() == 1 {
+                    Some(Uint128::new(quote.amount_out.0))
+                } else {
+                    None
+                },
+                operations: swap_operations,
+            });
+        }
+        None => {
+            if !operations.is_empty() {
+                routes.push(Route {
+                    token_in: asset_in.info.denom(),
+                    offer_amount: amount_after_fee,
+                    operations,
+                    minimum_receive: None,
+                });
+            }
+        }
+    }
 
-    Ok(Response::new().add_messages(msgs))
+    let position = PendingPosition::new(
+        pool_key,
+        tick_lower_index,
+        tick_upper_index,
+        None,
+        Some(slippage_limit_lower),
+        Some(slippage_limit_upper),
+        minimum_liquidity,
+    );
+
+    zap_in(deps, env, info, vec![], position, asset_in, routes)
 }
 
 pub fn zap_out_liquidity(
@@ -192,8 +428,15 @@ pub fn zap_out_liquidity(
     info: MessageInfo,
     position_index: u32,
     routes: Vec<Route>,
+    percentage: Option<Decimal>,
 ) -> Result<Response, ContractError> {
-    let mut msgs: Vec<CosmosMsg> = vec![];
+    if let Some(percentage) = percentage {
+        if percentage.is_zero() || percentage.gt(&Decimal::one()) {
+            return Err(ContractError::InvalidPercentage {});
+        }
+    }
+
+    let id = start_zap(deps.storage)?;
     let config = CONFIG.load(deps.storage)?;
     let position: Position = deps.querier.query_wasm_smart(
         config.dex_v3.to_string(),
@@ -202,58 +445,61 @@ pub fn zap_out_liquidity(
             index: position_index,
         },
     )?;
-    let position_incentives: Vec<Asset> = deps.querier.query_wasm_smart(
-        config.dex_v3.to_string(),
-        &V3QueryMsg::PositionIncentives {
-            owner_id: info.sender.clone(),
-            index: position_index,
-        },
-    )?;
-    for incentive in position_incentives {
-        let balance = incentive
-            .info
-            .balance(&deps.querier, env.contract.address.to_string())
-            .unwrap();
-        SNAP_BALANCES.save(deps.storage, incentive.info.denom(), &balance)?;
-    }
 
     // 1. Transfer position to this contract
     // sender must be approve for contract first
-    msgs.push(CosmosMsg::Wasm(WasmMsg::Execute {
+    let transfer_msg = WasmMsg::Execute {
         contract_addr: config.dex_v3.to_string(),
         msg: to_json_binary(&V3ExecuteMsg::TransferNft {
             token_id: position.token_id,
             recipient: env.contract.address.clone(),
         })?,
         funds: vec![],
-    }));
-
-    // snap balance
-    let (token_x, token_y) = get_pool_v3_asset_info(deps.api, &position.pool_key);
-    let balance_x = token_x.balance(&deps.querier, env.contract.address.to_string())?;
-    let balance_y = token_y.balance(&deps.querier, env.contract.address.to_string())?;
-    SNAP_BALANCES.save(deps.storage, token_x.denom(), &balance_x)?;
-    SNAP_BALANCES.save(deps.storage, token_y.denom(), &balance_y)?;
-
-    RECEIVER.save(deps.storage, &info.sender)?;
-    ZAP_OUT_ROUTES.save(deps.storage, &routes)?;
+    };
 
     // 2. Create SubMsg to process remove liquidity in dex_v3 contract
-    msgs.push(CosmosMsg::Wasm(WasmMsg::Execute {
-        contract_addr: config.dex_v3.to_string(),
-        msg: to_json_binary(&V3ExecuteMsg::Burn {
+    let keep_position = percentage.map_or(false, |percentage| percentage.lt(&Decimal::one()));
+    let withdraw_msg = match percentage.filter(|_| keep_position) {
+        Some(percentage) => {
+            // withdraw part of the liquidity, the position is sent back once it replies
+            let liquidity_delta =
+                Liquidity((Uint128::new(position.liquidity.0) * percentage).u128());
+            let tick_spacing = position.pool_key.fee_tier.tick_spacing;
+            V3ExecuteMsg::DecreaseLiquidityByTokenId {
+                token_id: position.token_id,
+                liquidity_delta,
+                slippage_limit_lower: get_min_sqrt_price(tick_spacing),
+                slippage_limit_upper: get_max_sqrt_price(tick_spacing),
+            }
+        }
+        None => V3ExecuteMsg::Burn {
             token_id: position.token_id,
-        })?,
-        funds: vec![],
-    }));
+        },
+    };
 
-    msgs.push(CosmosMsg::Wasm(WasmMsg::Execute {
-        contract_addr: env.contract.address.to_string(),
-        msg: to_json_binary(&ExecuteMsg::ZapOutAfterSwapOperation {})?,
-        funds: vec![],
-    }));
+    ZAP_OUT_OPERATIONS.save(
+        deps.storage,
+        id,
+        &ZapOutOperation {
+            receiver: info.sender,
+            token_id: position.token_id,
+            keep_position,
+            routes,
+            assets: vec![],
+            swaps: RouteSwaps::default(),
+        },
+    )?;
 
-    Ok(Response::new().add_messages(msgs))
+    Ok(Response::new()
+        .add_message(transfer_msg)
+        .add_submessage(SubMsg::reply_on_success(
+            WasmMsg::Execute {
+                contract_addr: config.dex_v3.to_string(),
+                msg: to_json_binary(&withdraw_msg)?,
+                funds: vec![],
+            },
+            ZAP_OUT_LIQUIDITY_REPLY_ID,
+        )))
 }
 
 pub fn withdraw(
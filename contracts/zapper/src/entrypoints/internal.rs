@@ -1,189 +1,205 @@
 use std::vec;
 
 use cosmwasm_std::{
-    to_json_binary, Coin, CosmosMsg, Decimal, DepsMut, Env, MessageInfo, Order, Response,
-    StdResult, WasmMsg,
+    to_json_binary, Addr, Coin, CosmosMsg, Decimal, DepsMut, Env, Event, Reply, Response, StdError,
+    SubMsg, Uint128, WasmMsg,
 };
 
 use oraiswap_v3_common::{
-    asset::{Asset, AssetInfo},
+    asset::AssetInfo,
     error::ContractError,
-    logic::{get_liquidity_by_x, get_liquidity_by_y, SingleTokenLiquidity},
-    math::{
-        sqrt_price::{get_max_sqrt_price, get_min_sqrt_price},
-        token_amount::TokenAmount,
-    },
+    math::sqrt_price::{get_max_sqrt_price, get_min_sqrt_price},
     oraiswap_v3_msg::{ExecuteMsg as V3ExecuteMsg, QueryMsg as V3QueryMsg},
     storage::Pool,
 };
 
 use crate::{
-    msg::ExecuteMsg,
-    state::{
-        CONFIG, PENDING_POSITION, PROTOCOL_FEE, RECEIVER, SNAP_BALANCE, SNAP_BALANCES,
-        ZAP_OUT_ROUTES,
-    },
-    ProtocolFee,
+    contract::{ADD_LIQUIDITY_REPLY_ID, ZAP_IN_SWAP_REPLY_ID, ZAP_OUT_SWAP_REPLY_ID},
+    end_zap,
+    state::{CONFIG, PROTOCOL_FEE, ZAP_IN_OPERATIONS, ZAP_IN_PROGRESS, ZAP_OUT_OPERATIONS},
+    ProtocolFee, RouteSwap, ZapInOperation, ZapOutOperation,
 };
 
-use super::build_swap_msg;
+use super::{
+    add_asset, get_pool_v3_asset_info, get_zap_in_liquidity, next_route_swap, received_assets,
+    reply_attribute,
+};
+
+/// Returns the events of a reply, only successful submessages reply.
+fn reply_events(msg: Reply) -> Result<Vec<Event>, ContractError> {
+    Ok(msg
+        .result
+        .into_result()
+        .map_err(StdError::generic_err)?
+        .events)
+}
+
+/// Parses an amount the `dex_v3` contract emitted in the events of a reply.
+fn reply_amount(
+    events: &[Event],
+    contract_addr: &Addr,
+    key: &str,
+) -> Result<Uint128, ContractError> {
+    Ok(reply_attribute(events, contract_addr, key)?.parse::<Uint128>()?)
+}
+
+/// Handles the reply of a swap of the zap in, or of the cw20 tokens it pulled from the sender.
+pub fn zap_in_swap_reply(deps: DepsMut, env: Env, msg: Reply) -> Result<Response, ContractError> {
+    let id = ZAP_IN_PROGRESS.load(deps.storage)?;
+    let operation = ZAP_IN_OPERATIONS.load(deps.storage, id)?;
+    let events = reply_events(msg)?;
+
+    Ok(Response::new().add_submessages(continue_zap_in(deps, &env, id, operation, &events)?))
+}
 
-pub fn zap_in_liquidity(
+/// Runs the next swap of the zap in with the `events` of the reply of the previous one, once
+/// they are all done adds the liquidity with the amounts of token x and y they returned.
+pub fn continue_zap_in(
     deps: DepsMut,
-    env: Env,
-    info: MessageInfo,
-) -> Result<Response, ContractError> {
-    if info.sender != env.contract.address {
-        return Err(ContractError::Unauthorized {});
+    env: &Env,
+    id: u64,
+    mut operation: ZapInOperation,
+    events: &[Event],
+) -> Result<Vec<SubMsg>, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    let (output, swap_msg) = next_route_swap(
+        deps.api,
+        &env.contract.address,
+        &config,
+        &mut operation.swaps,
+        events,
+        ZAP_IN_SWAP_REPLY_ID,
+    )?;
+
+    // routes are checked to end in token x or y
+    let (token_x, token_y) = get_pool_v3_asset_info(deps.api, &operation.position.pool_key);
+    if let Some(output) = output {
+        if output.info.eq(&token_x) {
+            operation.amount_x += output.amount;
+        } else {
+            operation.amount_y += output.amount;
+        }
+    }
+    if let Some(swap_msg) = swap_msg {
+        ZAP_IN_OPERATIONS.save(deps.storage, id, &operation)?;
+        return Ok(vec![swap_msg]);
     }
 
     let mut msgs: Vec<CosmosMsg> = vec![];
-
-    // 5. Recheck the balance of tokenX and tokenY in this contract
-    let snap_balance = SNAP_BALANCE.load(deps.storage)?;
-    let token_x = snap_balance.token_x;
-    let token_y = snap_balance.token_y;
-
-    // 6. Minus with the previous balance of tokenX and tokenY snap in state
-    let x_amount_after = token_x
-        .info
-        .balance(&deps.querier, env.contract.address.to_string())?;
-    let y_amount_after = token_y
-        .info
-        .balance(&deps.querier, env.contract.address.to_string())?;
-    let x_amount = x_amount_after - token_x.amount;
-    let y_amount = y_amount_after - token_y.amount;
+    let x_amount = operation.amount_x;
+    let y_amount = operation.amount_y;
 
     // 7. Process create new position with amountX and amountY
-    let config = CONFIG.load(deps.storage)?;
-    let pending_position: crate::PendingPosition = PENDING_POSITION.load(deps.storage)?;
+    let pending_position = &operation.position;
     let pool_info: Pool = deps.querier.query_wasm_smart(
         config.dex_v3.to_string(),
         &V3QueryMsg::Pool {
-            token_0: token_x.info.denom(),
-            token_1: token_y.info.denom(),
+            token_0: token_x.denom(),
+            token_1: token_y.denom(),
             fee_tier: pending_position.pool_key.fee_tier,
         },
     )?;
 
-    let mut res: SingleTokenLiquidity;
-
-    let is_in_range = pending_position.lower_tick <= pool_info.current_tick_index
-        && pending_position.upper_tick > pool_info.current_tick_index;
-    if is_in_range {
-        res = get_liquidity_by_x(
-            TokenAmount(x_amount.u128()),
-            pending_position.lower_tick,
-            pending_position.upper_tick,
-            pool_info.sqrt_price,
-            false,
-        )?;
-        if res.amount > TokenAmount(y_amount.u128()) {
-            res = get_liquidity_by_y(
-                TokenAmount(y_amount.u128()),
-                pending_position.lower_tick,
-                pending_position.upper_tick,
-                pool_info.sqrt_price,
-                false,
-            )?;
-        }
-    } else if pending_position.lower_tick > pool_info.current_tick_index {
-        res = get_liquidity_by_x(
-            TokenAmount(x_amount.u128()),
-            pending_position.lower_tick,
-            pending_position.upper_tick,
-            pool_info.sqrt_price,
-            false,
-        )?;
-    } else {
-        res = get_liquidity_by_y(
-            TokenAmount(y_amount.u128()),
-            pending_position.lower_tick,
-            pending_position.upper_tick,
-            pool_info.sqrt_price,
-            false,
-        )?;
-    }
+    let liquidity = get_zap_in_liquidity(
+        &pool_info,
+        pending_position.lower_tick,
+        pending_position.upper_tick,
+        x_amount,
+        y_amount,
+    )?;
 
     // validate minimum liquidity
     if let Some(min_liquidity) = pending_position.minimum_liquidity {
-        if res.l.lt(&min_liquidity) {
+        if liquidity.lt(&min_liquidity) {
             return Err(ContractError::ZapInAssertionFailure {
                 minium_receive: min_liquidity,
-                return_amount: res.l,
+                return_amount: liquidity,
             });
         }
     }
 
     // approve tokenX and tokenY to dex_v3
     let mut coins: Vec<Coin> = vec![];
-    token_x
-        .info
-        .increase_allowance(&mut coins, &mut msgs, config.dex_v3.to_string(), x_amount)?;
-    token_y
-        .info
-        .increase_allowance(&mut coins, &mut msgs, config.dex_v3.to_string(), y_amount)?;
-
-    msgs.push(CosmosMsg::Wasm(WasmMsg::Execute {
-        contract_addr: config.dex_v3.to_string(),
-        msg: to_json_binary(&V3ExecuteMsg::CreatePosition {
+    token_x.increase_allowance(&mut coins, &mut msgs, config.dex_v3.to_string(), x_amount)?;
+    token_y.increase_allowance(&mut coins, &mut msgs, config.dex_v3.to_string(), y_amount)?;
+
+    let tick_spacing = pending_position.pool_key.fee_tier.tick_spacing;
+    let slippage_limit_lower = pending_position
+        .slippage_limit_lower
+        .unwrap_or(get_min_sqrt_price(tick_spacing));
+    let slippage_limit_upper = pending_position
+        .slippage_limit_upper
+        .unwrap_or(get_max_sqrt_price(tick_spacing));
+    let position_msg = match pending_position.token_id {
+        Some(token_id) => V3ExecuteMsg::IncreaseLiquidityByTokenId {
+            token_id,
+            liquidity_delta: liquidity,
+            slippage_limit_lower,
+            slippage_limit_upper,
+        },
+        None => V3ExecuteMsg::CreatePosition {
             pool_key: pending_position.pool_key.clone(),
             lower_tick: pending_position.lower_tick,
             upper_tick: pending_position.upper_tick,
-            liquidity_delta: res.l,
-            slippage_limit_lower: pending_position.slippage_limit_lower.unwrap_or(
-                get_min_sqrt_price(pending_position.pool_key.fee_tier.tick_spacing),
-            ),
-            slippage_limit_upper: pending_position.slippage_limit_upper.unwrap_or(
-                get_max_sqrt_price(pending_position.pool_key.fee_tier.tick_spacing),
-            ),
-        })?,
-        funds: coins,
-    }));
+            liquidity_delta: liquidity,
+            slippage_limit_lower,
+            slippage_limit_upper,
+            limit_order: None,
+        },
+    };
 
-    msgs.push(CosmosMsg::Wasm(WasmMsg::Execute {
-        contract_addr: env.contract.address.to_string(),
-        msg: to_json_binary(&ExecuteMsg::RefundAfterZapInLiquidity {})?,
-        funds: vec![],
-    }));
+    // its reply tells the token id of a new position and what it spent
+    ZAP_IN_OPERATIONS.save(deps.storage, id, &operation)?;
 
-    Ok(Response::new().add_messages(msgs))
+    let mut submsgs: Vec<SubMsg> = msgs.into_iter().map(SubMsg::new).collect();
+    submsgs.push(SubMsg::reply_on_success(
+        WasmMsg::Execute {
+            contract_addr: config.dex_v3.to_string(),
+            msg: to_json_binary(&position_msg)?,
+            funds: coins,
+        },
+        ADD_LIQUIDITY_REPLY_ID,
+    ));
+
+    Ok(submsgs)
 }
 
-pub fn refund_after_zap_in(
-    deps: DepsMut,
-    env: Env,
-    info: MessageInfo,
-) -> Result<Response, ContractError> {
-    if info.sender != env.contract.address {
-        return Err(ContractError::Unauthorized {});
-    }
+/// Handles the reply of the liquidity added by the zap in, sends the position to the receiver
+/// and refunds what was not spent.
+pub fn add_liquidity_reply(deps: DepsMut, msg: Reply) -> Result<Response, ContractError> {
+    let id = ZAP_IN_PROGRESS.load(deps.storage)?;
+    let operation = ZAP_IN_OPERATIONS.load(deps.storage, id)?;
+    let config = CONFIG.load(deps.storage)?;
+    let events = reply_events(msg)?;
 
     let mut msgs: Vec<CosmosMsg> = vec![];
 
     // 8. Refund unused tokenX and tokenY to user
-    let snap_balance = SNAP_BALANCE.load(deps.storage)?;
-    let token_x = snap_balance.token_x;
-    let token_y = snap_balance.token_y;
-
-    let x_amount_after = token_x
-        .info
-        .balance(&deps.querier, env.contract.address.to_string())?;
-    let y_amount_after = token_y
-        .info
-        .balance(&deps.querier, env.contract.address.to_string())?;
+    let (token_x, token_y) = get_pool_v3_asset_info(deps.api, &operation.position.pool_key);
+    let x_spent = reply_amount(&events, &config.dex_v3, "liquidity_x")?;
+    let y_spent = reply_amount(&events, &config.dex_v3, "liquidity_y")?;
     // amount to refund
-    let x_amount = x_amount_after - token_x.amount;
-    let y_amount = y_amount_after - token_y.amount;
+    let x_amount = operation
+        .amount_x
+        .checked_sub(x_spent)
+        .map_err(StdError::from)?;
+    let y_amount = operation
+        .amount_y
+        .checked_sub(y_spent)
+        .map_err(StdError::from)?;
 
     // 9. Transfer position to user
-    let config = CONFIG.load(deps.storage)?;
-    let pending_position = PENDING_POSITION.load(deps.storage)?;
-    let receiver = RECEIVER.load(deps.storage)?;
+    let receiver = operation.receiver;
+    let token_id = match operation.position.token_id {
+        Some(token_id) => token_id,
+        None => reply_attribute(&events, &config.dex_v3, "token_id")?
+            .parse::<u64>()
+            .map_err(|err| StdError::parse_err("u64", err))?,
+    };
     msgs.push(CosmosMsg::Wasm(WasmMsg::Execute {
         contract_addr: config.dex_v3.to_string(),
-        msg: to_json_binary(&V3ExecuteMsg::TransferPosition {
-            index: pending_position.index,
+        msg: to_json_binary(&V3ExecuteMsg::TransferPositionByTokenId {
+            token_id,
             receiver: receiver.to_string(),
         })?,
         funds: vec![],
@@ -191,60 +207,52 @@ pub fn refund_after_zap_in(
 
     // 10. Refund unused tokenX and tokenY to user
     if !x_amount.is_zero() {
-        token_x
-            .info
-            .transfer(&mut msgs, receiver.to_string(), x_amount)?;
+        token_x.transfer(&mut msgs, receiver.to_string(), x_amount)?;
     }
     if !y_amount.is_zero() {
-        token_y
-            .info
-            .transfer(&mut msgs, receiver.to_string(), y_amount)?;
+        token_y.transfer(&mut msgs, receiver.to_string(), y_amount)?;
     }
 
-    // remove pending position
-    PENDING_POSITION.remove(deps.storage);
-    SNAP_BALANCE.remove(deps.storage);
-    RECEIVER.remove(deps.storage);
+    // remove the zap state
+    ZAP_IN_OPERATIONS.remove(deps.storage, id);
+    end_zap(deps.storage);
 
     Ok(Response::new().add_messages(msgs))
 }
 
-pub fn zap_out_liquidity(
+/// Handles the reply of the liquidity withdrawn by the zap out, then swaps along its routes.
+pub fn zap_out_liquidity_reply(
     deps: DepsMut,
     env: Env,
-    info: MessageInfo,
+    msg: Reply,
 ) -> Result<Response, ContractError> {
-    if info.sender != env.contract.address {
-        return Err(ContractError::Unauthorized {});
-    }
-
-    let mut msgs: Vec<CosmosMsg> = vec![];
-    let receiver = RECEIVER.load(deps.storage)?;
-    let zap_out_routes = ZAP_OUT_ROUTES.load(deps.storage)?;
+    let id = ZAP_IN_PROGRESS.load(deps.storage)?;
+    let mut operation = ZAP_OUT_OPERATIONS.load(deps.storage, id)?;
     let config = CONFIG.load(deps.storage)?;
 
-    // no need to use hashMap because the number of tokens is very small
-    let mut all_balances: Vec<Asset> = SNAP_BALANCES
-        .range(deps.storage, None, None, Order::Ascending)
-        .map(|item| {
-            let (denom, amount) = item?;
-            let token_info = AssetInfo::from_denom(deps.api, &denom);
-            let current_balance =
-                token_info.balance(&deps.querier, env.contract.address.to_string())?;
-
-            Ok(Asset {
-                info: token_info,
-                amount: current_balance.checked_sub(amount)?,
-            })
-        })
-        .collect::<StdResult<Vec<Asset>>>()?;
+    let mut response = Response::new();
+    // send the position back when only part of its liquidity is withdrawn
+    if operation.keep_position {
+        response = response.add_message(WasmMsg::Execute {
+            contract_addr: config.dex_v3.to_string(),
+            msg: to_json_binary(&V3ExecuteMsg::TransferNft {
+                token_id: operation.token_id,
+                recipient: operation.receiver.clone(),
+            })?,
+            funds: vec![],
+        });
+    }
+
+    // what the position returned, with its fees and incentives
+    let mut all_balances = received_assets(&reply_events(msg)?, &env.contract.address)?;
 
+    let mut fee_msgs: Vec<CosmosMsg> = vec![];
     let protocol_fee = PROTOCOL_FEE.may_load(deps.storage)?.unwrap_or(ProtocolFee {
         percent: Decimal::zero(),
-        fee_receiver: receiver.clone(),
+        fee_receiver: operation.receiver.clone(),
     });
     // try swaps
-    for route in zap_out_routes {
+    for route in operation.routes.drain(..) {
         let token_info = AssetInfo::from_denom(deps.api, &route.token_in);
         if let Some(balance) = all_balances.iter_mut().find(|b| b.info.eq(&token_info)) {
             if balance.amount < route.offer_amount {
@@ -259,36 +267,73 @@ pub fn zap_out_liquidity(
 
                 // transfer fee to fee_receiver
                 token_info.transfer(
-                    &mut msgs,
+                    &mut fee_msgs,
                     protocol_fee.fee_receiver.to_string(),
                     fee_amount,
                 )?;
             }
 
-            let swap_msg = build_swap_msg(
-                &token_info,
-                config.mixed_router.clone(),
-                amount_to_swap,
-                route.operations,
-                route.minimum_receive,
-                Some(receiver.clone()),
-                None,
-            )?;
-            msgs.push(swap_msg.into());
+            operation.swaps.queue.push(RouteSwap {
+                token_in: token_info,
+                amount: amount_to_swap,
+                operations: route.operations,
+                minimum_receive: route.minimum_receive,
+            });
         }
     }
+    operation.assets = all_balances;
+
+    Ok(response
+        .add_submessages(continue_zap_out(deps, &env, id, operation, &[])?)
+        .add_messages(fee_msgs))
+}
 
-    // refund remaining asset to user
-    for balance in all_balances.iter().filter(|b| !b.amount.is_zero()) {
-        balance
+/// Handles the reply of a swap of the zap out.
+pub fn zap_out_swap_reply(deps: DepsMut, env: Env, msg: Reply) -> Result<Response, ContractError> {
+    let id = ZAP_IN_PROGRESS.load(deps.storage)?;
+    let operation = ZAP_OUT_OPERATIONS.load(deps.storage, id)?;
+    let events = reply_events(msg)?;
+
+    Ok(Response::new().add_submessages(continue_zap_out(deps, &env, id, operation, &events)?))
+}
+
+/// Runs the next swap of the zap out with the `events` of the reply of the previous one, once
+/// they are all done sends the tokens to the receiver.
+fn continue_zap_out(
+    deps: DepsMut,
+    env: &Env,
+    id: u64,
+    mut operation: ZapOutOperation,
+    events: &[Event],
+) -> Result<Vec<SubMsg>, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    let (output, swap_msg) = next_route_swap(
+        deps.api,
+        &env.contract.address,
+        &config,
+        &mut operation.swaps,
+        events,
+        ZAP_OUT_SWAP_REPLY_ID,
+    )?;
+    if let Some(output) = output {
+        add_asset(&mut operation.assets, output);
+    }
+    if let Some(swap_msg) = swap_msg {
+        ZAP_OUT_OPERATIONS.save(deps.storage, id, &operation)?;
+        return Ok(vec![swap_msg]);
+    }
+
+    // send the assets to user
+    let mut msgs: Vec<CosmosMsg> = vec![];
+    for asset in operation.assets.iter().filter(|a| !a.amount.is_zero()) {
+        asset
             .info
-            .transfer(&mut msgs, receiver.to_string(), balance.amount)?;
+            .transfer(&mut msgs, operation.receiver.to_string(), asset.amount)?;
     }
 
-    // remove pending states
-    SNAP_BALANCES.clear(deps.storage);
-    RECEIVER.remove(deps.storage);
-    ZAP_OUT_ROUTES.remove(deps.storage);
+    // remove the zap state
+    ZAP_OUT_OPERATIONS.remove(deps.storage, id);
+    end_zap(deps.storage);
 
-    Ok(Response::new().add_messages(msgs))
+    Ok(msgs.into_iter().map(SubMsg::new).collect())
 }
@@ -1,11 +1,32 @@
-use cosmwasm_std::Deps;
-use oraiswap_v3_common::error::ContractError;
+use cosmwasm_std::{Addr, Decimal, Deps, Uint128};
+use decimal::CheckedOps;
+use oraiswap_v3_common::{
+    asset::{Asset, AssetInfo},
+    error::ContractError,
+    interface::QuoteResult,
+    math::{
+        calculate_amount_delta,
+        fee_growth::calculate_fee_growth_inside,
+        get_tick_at_sqrt_price,
+        liquidity::Liquidity,
+        sqrt_price::{get_max_sqrt_price, get_min_sqrt_price},
+        token_amount::TokenAmount,
+    },
+    oraiswap_v3_msg::QueryMsg as V3QueryMsg,
+    storage::{Pool, PoolKey, Position, Tick},
+};
 
 use crate::{
+    msg::{Route, RouteOperation, SimulateZapInResponse, SimulateZapOutResponse},
     state::{CONFIG, PROTOCOL_FEE},
     Config, ProtocolFee,
 };
 
+use super::{
+    add_asset, get_pool_v3_asset_info, get_zap_in_liquidity, route_operation_output,
+    simulate_route_swap,
+};
+
 pub fn get_config(deps: Deps) -> Result<Config, ContractError> {
     let config = CONFIG.load(deps.storage)?;
     Ok(config)
@@ -15,3 +36,368 @@ pub fn get_protocol_fee(deps: Deps) -> Result<ProtocolFee, ContractError> {
     let protocol_fee = PROTOCOL_FEE.load(deps.storage)?;
     Ok(protocol_fee)
 }
+
+pub fn simulate_zap_in(
+    deps: Deps,
+    pool_key: PoolKey,
+    tick_lower_index: i32,
+    tick_upper_index: i32,
+    asset_in: Asset,
+    routes: Vec<Route>,
+) -> Result<SimulateZapInResponse, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+
+    // deduct zap in fee
+    let mut fees: Vec<Asset> = vec![];
+    let mut amount_after_fee = asset_in.amount;
+    if let Some(protocol_fee) = PROTOCOL_FEE.may_load(deps.storage)? {
+        let fee_amount = asset_in.amount * protocol_fee.percent;
+        if !fee_amount.is_zero() {
+            amount_after_fee -= fee_amount;
+            fees.push(Asset::new(asset_in.info.clone(), fee_amount));
+        }
+    }
+
+    let total_swap_amount: Uint128 = routes.iter().map(|route| route.offer_amount).sum();
+    if total_swap_amount.gt(&amount_after_fee) {
+        return Err(ContractError::InvalidFund {});
+    }
+
+    // the amount not swapped goes to the position, or back to the sender if it cannot
+    let (token_x, token_y) = get_pool_v3_asset_info(deps.api, &pool_key);
+    let amount_left = amount_after_fee - total_swap_amount;
+    let mut refunds: Vec<Asset> = vec![];
+    let mut amount_x = Uint128::zero();
+    let mut amount_y = Uint128::zero();
+    if asset_in.info.eq(&token_x) {
+        amount_x = amount_left;
+    } else if asset_in.info.eq(&token_y) {
+        amount_y = amount_left;
+    } else if !amount_left.is_zero() {
+        refunds.push(Asset::new(asset_in.info.clone(), amount_left));
+    }
+
+    let mut pool: Pool = deps.querier.query_wasm_smart(
+        config.dex_v3.to_string(),
+        &V3QueryMsg::Pool {
+            token_0: pool_key.token_x.clone(),
+            token_1: pool_key.token_y.clone(),
+            fee_tier: pool_key.fee_tier,
+        },
+    )?;
+
+    // the routes run one after the other, the ones swapping through the pool move its price
+    let mut pool_swaps = PoolSwaps::default();
+    for route in routes {
+        match route.operations.last() {
+            Some(operation) => {
+                let token_out = route_operation_output(deps.api, operation);
+                if !token_out.eq(&token_x) && !token_out.eq(&token_y) {
+                    return Err(ContractError::ZapInRouteNotToPool {});
+                }
+            }
+            None => return Err(ContractError::MissingRouteSwap {}),
+        }
+        let mut output = Asset::new(asset_in.info.clone(), route.offer_amount);
+        let mut operations = route.operations;
+        while !operations.is_empty() {
+            // hops through the pool are quoted one by one, the rest run by run as they swap
+            let len = match operations.iter().position(|operation| {
+                matches!(operation, RouteOperation::V3(hop) if hop.pool_key == pool_key)
+            }) {
+                Some(0) => 1,
+                Some(len) => len,
+                None => operations.len(),
+            };
+            let rest = operations.split_off(len);
+            output = match operations.first() {
+                Some(RouteOperation::V3(hop)) if hop.pool_key == pool_key => {
+                    let amount = pool_swaps.quote(
+                        deps,
+                        &config,
+                        &pool_key,
+                        &mut pool,
+                        hop.x_to_y,
+                        output.amount,
+                    )?;
+                    Asset::new(route_operation_output(deps.api, &operations[0]), amount)
+                }
+                _ => simulate_route_swap(
+                    deps.api,
+                    &deps.querier,
+                    &config,
+                    output.info,
+                    output.amount,
+                    operations,
+                    None,
+                )?,
+            };
+            operations = rest;
+        }
+        if let Some(minimum_receive) = route.minimum_receive {
+            if output.amount < minimum_receive {
+                return Err(ContractError::AmountUnderMinimumAmountOut);
+            }
+        }
+        if output.info.eq(&token_x) {
+            amount_x += output.amount;
+        } else {
+            amount_y += output.amount;
+        }
+    }
+
+    // the liquidity is added at the price the swaps left the pool at
+    let liquidity = get_zap_in_liquidity(
+        &pool,
+        tick_lower_index,
+        tick_upper_index,
+        amount_x,
+        amount_y,
+    )?;
+
+    // the position takes the amounts rounded up, what is left is refunded
+    let (spent_x, spent_y, _) = calculate_amount_delta(
+        pool.current_tick_index,
+        pool.sqrt_price,
+        liquidity,
+        true,
+        tick_upper_index,
+        tick_lower_index,
+    )?;
+    let spent_x = Uint128::new(spent_x.0).min(amount_x);
+    let spent_y = Uint128::new(spent_y.0).min(amount_y);
+    for refund in [
+        Asset::new(token_x, amount_x - spent_x),
+        Asset::new(token_y, amount_y - spent_y),
+    ] {
+        if !refund.amount.is_zero() {
+            add_asset(&mut refunds, refund);
+        }
+    }
+
+    Ok(SimulateZapInResponse {
+        liquidity,
+        amount_x: spent_x,
+        amount_y: spent_y,
+        refunds,
+        fees,
+    })
+}
+
+/// The swaps the routes of a simulated zap in made through the pool of the position so far.
+/// Only the swaps in the direction of the latest one are tracked: the pool cannot be quoted at a
+/// price moved by swaps in the other direction, so a route swapping back through the pool is
+/// quoted from the price on chain and the simulation is an estimate for such routes.
+#[derive(Default)]
+struct PoolSwaps {
+    x_to_y: bool,
+    amount_in: TokenAmount,
+    amount_out: TokenAmount,
+}
+
+impl PoolSwaps {
+    /// Quotes swapping `amount` through the pool after the earlier swaps, then moves `pool` to
+    /// the price it ends at. The quote starts from the price on chain, so the earlier swaps in
+    /// the same direction are quoted again along with it and what they returned is deducted.
+    fn quote(
+        &mut self,
+        deps: Deps,
+        config: &Config,
+        pool_key: &PoolKey,
+        pool: &mut Pool,
+        x_to_y: bool,
+        amount: Uint128,
+    ) -> Result<Uint128, ContractError> {
+        // swaps in the other direction start over from the price on chain
+        if x_to_y != self.x_to_y {
+            *self = PoolSwaps {
+                x_to_y,
+                ..Default::default()
+            };
+        }
+        let tick_spacing = pool_key.fee_tier.tick_spacing;
+        let amount_in = self.amount_in + TokenAmount(amount.u128());
+        let quote: QuoteResult = deps.querier.query_wasm_smart(
+            config.dex_v3.to_string(),
+            &V3QueryMsg::Quote {
+                pool_key: pool_key.clone(),
+                x_to_y,
+                amount: amount_in,
+                by_amount_in: true,
+                sqrt_price_limit: if x_to_y {
+                    get_min_sqrt_price(tick_spacing)
+                } else {
+                    get_max_sqrt_price(tick_spacing)
+                },
+            },
+        )?;
+        let amount_out = quote.amount_out - self.amount_out;
+        self.amount_in = amount_in;
+        self.amount_out = quote.amount_out;
+
+        pool.sqrt_price = quote.target_sqrt_price;
+        pool.current_tick_index = get_tick_at_sqrt_price(quote.target_sqrt_price, tick_spacing)?;
+
+        Ok(Uint128::new(amount_out.0))
+    }
+}
+
+pub fn simulate_zap_out(
+    deps: Deps,
+    owner: Addr,
+    position_index: u32,
+    routes: Vec<Route>,
+    percentage: Option<Decimal>,
+) -> Result<SimulateZapOutResponse, ContractError> {
+    if let Some(percentage) = percentage {
+        if percentage.is_zero() || percentage.gt(&Decimal::one()) {
+            return Err(ContractError::InvalidPercentage {});
+        }
+    }
+
+    let config = CONFIG.load(deps.storage)?;
+    let position: Position = deps.querier.query_wasm_smart(
+        config.dex_v3.to_string(),
+        &V3QueryMsg::Position {
+            owner_id: owner.clone(),
+            index: position_index,
+        },
+    )?;
+    let position_incentives: Vec<Asset> = deps.querier.query_wasm_smart(
+        config.dex_v3.to_string(),
+        &V3QueryMsg::PositionIncentives {
+            owner_id: owner,
+            index: position_index,
+        },
+    )?;
+    let pool_key = &position.pool_key;
+    let pool: Pool = deps.querier.query_wasm_smart(
+        config.dex_v3.to_string(),
+        &V3QueryMsg::Pool {
+            token_0: pool_key.token_x.clone(),
+            token_1: pool_key.token_y.clone(),
+            fee_tier: pool_key.fee_tier,
+        },
+    )?;
+    let lower_tick: Tick = deps.querier.query_wasm_smart(
+        config.dex_v3.to_string(),
+        &V3QueryMsg::Tick {
+            key: pool_key.clone(),
+            index: position.lower_tick_index,
+        },
+    )?;
+    let upper_tick: Tick = deps.querier.query_wasm_smart(
+        config.dex_v3.to_string(),
+        &V3QueryMsg::Tick {
+            key: pool_key.clone(),
+            index: position.upper_tick_index,
+        },
+    )?;
+
+    // the liquidity withdrawn and all the swap fees the position earned
+    let keep_position = percentage.map_or(false, |percentage| percentage.lt(&Decimal::one()));
+    let liquidity_delta = match percentage.filter(|_| keep_position) {
+        Some(percentage) => Liquidity((Uint128::new(position.liquidity.0) * percentage).u128()),
+        None => position.liquidity,
+    };
+    let (amount_x, amount_y, _) = calculate_amount_delta(
+        pool.current_tick_index,
+        pool.sqrt_price,
+        liquidity_delta,
+        false,
+        position.upper_tick_index,
+        position.lower_tick_index,
+    )?;
+    let (fee_growth_inside_x, fee_growth_inside_y) = calculate_fee_growth_inside(
+        lower_tick.index,
+        lower_tick.fee_growth_outside_x,
+        lower_tick.fee_growth_outside_y,
+        upper_tick.index,
+        upper_tick.fee_growth_outside_x,
+        upper_tick.fee_growth_outside_y,
+        pool.current_tick_index,
+        pool.fee_growth_global_x,
+        pool.fee_growth_global_y,
+    );
+    let fee_x = fee_growth_inside_x
+        .unchecked_sub(position.fee_growth_inside_x)
+        .to_fee(position.liquidity)?;
+    let fee_y = fee_growth_inside_y
+        .unchecked_sub(position.fee_growth_inside_y)
+        .to_fee(position.liquidity)?;
+
+    let (token_x, token_y) = get_pool_v3_asset_info(deps.api, pool_key);
+    let mut all_balances: Vec<Asset> = vec![];
+    add_asset(
+        &mut all_balances,
+        Asset::new(
+            token_x,
+            Uint128::new(
+                amount_x
+                    .checked_add(position.tokens_owed_x)?
+                    .checked_add(fee_x)?
+                    .0,
+            ),
+        ),
+    );
+    add_asset(
+        &mut all_balances,
+        Asset::new(
+            token_y,
+            Uint128::new(
+                amount_y
+                    .checked_add(position.tokens_owed_y)?
+                    .checked_add(fee_y)?
+                    .0,
+            ),
+        ),
+    );
+    // incentives stay on a position that is kept
+    if !keep_position {
+        for incentive in position_incentives {
+            add_asset(&mut all_balances, incentive);
+        }
+    }
+
+    // swap along the routes as the zap out does
+    let protocol_fee = PROTOCOL_FEE.may_load(deps.storage)?;
+    let mut fees: Vec<Asset> = vec![];
+    let mut outputs: Vec<Asset> = vec![];
+    for route in routes {
+        let token_info = AssetInfo::from_denom(deps.api, &route.token_in);
+        if let Some(balance) = all_balances.iter_mut().find(|b| b.info.eq(&token_info)) {
+            if balance.amount < route.offer_amount {
+                return Err(ContractError::ZapOutNotEnoughBalanceToSwap {});
+            }
+            balance.amount -= route.offer_amount;
+
+            let mut amount_to_swap = route.offer_amount;
+            if let Some(protocol_fee) = &protocol_fee {
+                let fee_amount = amount_to_swap * protocol_fee.percent;
+                if !fee_amount.is_zero() {
+                    amount_to_swap -= fee_amount;
+                    add_asset(&mut fees, Asset::new(token_info.clone(), fee_amount));
+                }
+            }
+
+            outputs.push(simulate_route_swap(
+                deps.api,
+                &deps.querier,
+                &config,
+                token_info,
+                amount_to_swap,
+                route.operations,
+                route.minimum_receive,
+            )?);
+        }
+    }
+    for output in outputs {
+        add_asset(&mut all_balances, output);
+    }
+    all_balances.retain(|asset| !asset.amount.is_zero());
+
+    Ok(SimulateZapOutResponse {
+        assets: all_balances,
+        fees,
+    })
+}
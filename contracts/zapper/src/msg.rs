@@ -1,7 +1,12 @@
 use cosmwasm_schema::{cw_serde, QueryResponses};
 use cosmwasm_std::{Addr, Decimal, Uint128};
 use oraiswap::mixed_router::SwapOperation;
-use oraiswap_v3_common::{asset::Asset, math::liquidity::Liquidity, storage::PoolKey};
+use oraiswap_v3_common::{
+    asset::Asset,
+    interface::SwapHop,
+    math::{liquidity::Liquidity, sqrt_price::SqrtPrice},
+    storage::PoolKey,
+};
 
 use crate::{Config, ProtocolFee};
 
@@ -27,13 +32,38 @@ pub enum ExecuteMsg {
         routes: Vec<Route>,
         minimum_liquidity: Option<Liquidity>,
     },
-    ZapInAfterSwapOperation {},
-    RefundAfterZapInLiquidity {},
+    /// Zaps `asset_in` into a new position without routes. An asset that is not a token of the
+    /// pool is first swapped into one of them along `operations`. The part to swap for the other
+    /// token is computed from the current price of the pool and swapped in the pool itself.
+    ZapInLiquidityAuto {
+        pool_key: PoolKey,
+        tick_lower_index: i32,
+        tick_upper_index: i32,
+        asset_in: Asset,
+        /// Swaps `asset_in` into a token of the pool, empty when it is one of them
+        operations: Vec<RouteOperation>,
+        /// The pool price has to stay in this range for the swap in the pool and the deposit
+        slippage_limit_lower: SqrtPrice,
+        slippage_limit_upper: SqrtPrice,
+        minimum_liquidity: Option<Liquidity>,
+    },
+    /// Zaps `asset_in` into the existing position minted as `token_id`, which the sender owns.
+    /// The zapper must be approved for the position, it is sent back once the liquidity is added.
+    ZapInToPosition {
+        #[serde(with = "oraiswap_v3_common::utils::token_id")]
+        #[schemars(with = "String")]
+        token_id: u64,
+        asset_in: Asset,
+        routes: Vec<Route>,
+        minimum_liquidity: Option<Liquidity>,
+    },
     ZapOutLiquidity {
         position_index: u32,
         routes: Vec<Route>,
+        /// Share of the liquidity to zap out, the position is kept and sent back when lower
+        /// than one. The whole position is burnt when unset.
+        percentage: Option<Decimal>,
     },
-    ZapOutAfterSwapOperation {},
     RegisterProtocolFee {
         percent: Decimal,
         fee_receiver: Addr,
@@ -52,6 +82,48 @@ pub enum QueryMsg {
 
     #[returns(ProtocolFee)]
     ProtocolFee {},
+
+    /// Simulates `ZapInLiquidity` at the current state of the pools the routes swap through.
+    /// Routes swapping through the pool of the position in both directions are only estimated.
+    #[returns(SimulateZapInResponse)]
+    SimulateZapIn {
+        pool_key: PoolKey,
+        tick_lower_index: i32,
+        tick_upper_index: i32,
+        asset_in: Asset,
+        routes: Vec<Route>,
+    },
+
+    /// Simulates `ZapOutLiquidity` of the position of `owner`, of the whole position unless
+    /// `percentage` is set.
+    #[returns(SimulateZapOutResponse)]
+    SimulateZapOut {
+        owner: Addr,
+        position_index: u32,
+        routes: Vec<Route>,
+        percentage: Option<Decimal>,
+    },
+}
+
+#[cw_serde]
+pub struct SimulateZapInResponse {
+    pub liquidity: Liquidity,
+    /// Amounts of token x and y added to the position
+    pub amount_x: Uint128,
+    pub amount_y: Uint128,
+    /// Tokens sent back to the sender
+    pub refunds: Vec<Asset>,
+    /// Protocol fees taken by the zapper
+    pub fees: Vec<Asset>,
+}
+
+#[cw_serde]
+pub struct SimulateZapOutResponse {
+    /// Tokens sent to the owner, the liquidity, fees and incentives of the position once
+    /// swapped along the routes
+    pub assets: Vec<Asset>,
+    /// Protocol fees taken by the zapper
+    pub fees: Vec<Asset>,
 }
 
 #[cw_serde]
@@ -61,6 +133,27 @@ pub struct MigrateMsg {}
 pub struct Route {
     pub token_in: String,
     pub offer_amount: Uint128,
-    pub operations: Vec<SwapOperation>,
+    pub operations: Vec<RouteOperation>,
     pub minimum_receive: Option<Uint128>,
 }
+
+/// A step of a route, either an operation of the mixed router or a hop through a pool of
+/// `dex_v3` swapped on it directly. Consecutive steps on the same venue are swapped at once.
+#[cw_serde]
+#[serde(untagged)]
+pub enum RouteOperation {
+    MixedRouter(SwapOperation),
+    V3(SwapHop),
+}
+
+impl From<SwapOperation> for RouteOperation {
+    fn from(operation: SwapOperation) -> Self {
+        RouteOperation::MixedRouter(operation)
+    }
+}
+
+impl From<SwapHop> for RouteOperation {
+    fn from(hop: SwapHop) -> Self {
+        RouteOperation::V3(hop)
+    }
+}
@@ -1,23 +1,17 @@
-use cosmwasm_std::{Addr, Uint128};
 use cw_storage_plus::{Item, Map};
-use oraiswap_v3_common::storage::Position;
 
-use crate::{msg::Route, Config, IncentiveBalance, PairBalance, PendingPosition, ProtocolFee};
+use crate::{Config, ProtocolFee, ZapInOperation, ZapOutOperation};
 
 pub const CONFIG: Item<Config> = Item::new("config");
 
-pub const SNAP_BALANCE: Item<PairBalance> = Item::new("snap_balance");
-
-pub const SNAP_INCENTIVE: Item<IncentiveBalance> = Item::new("snap_incentive");
-
-pub const PENDING_POSITION: Item<PendingPosition> = Item::new("pending_position");
-
-pub const ZAP_OUT_POSITION: Item<Position> = Item::new("zap_out_position");
+pub const PROTOCOL_FEE: Item<ProtocolFee> = Item::new("protocol_fee");
 
-pub const ZAP_OUT_ROUTES: Item<Vec<Route>> = Item::new("zap_out_routes");
+/// Id of the last zap, each zap keeps its state under its own id
+pub const LAST_ZAP_ID: Item<u64> = Item::new("last_zap_id");
 
-pub const RECEIVER: Item<Addr> = Item::new("receiver");
+/// Id of the zap whose submessages are running, no other zap can start meanwhile
+pub const ZAP_IN_PROGRESS: Item<u64> = Item::new("zap_in_progress");
 
-pub const PROTOCOL_FEE: Item<ProtocolFee> = Item::new("protocol_fee");
+pub const ZAP_IN_OPERATIONS: Map<u64, ZapInOperation> = Map::new("zap_in_operations");
 
-pub const SNAP_BALANCES: Map<String, Uint128> = Map::new("snap_balances");
+pub const ZAP_OUT_OPERATIONS: Map<u64, ZapOutOperation> = Map::new("zap_out_operations");
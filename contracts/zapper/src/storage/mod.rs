@@ -1,11 +1,9 @@
 pub mod config;
-pub mod incentive;
-pub mod pair;
 pub mod pending_position;
+pub mod zap_operation;
 pub mod zap_out_routes;
 
 pub use config::*;
-pub use incentive::*;
-pub use pair::*;
 pub use pending_position::*;
+pub use zap_operation::*;
 pub use zap_out_routes::*;
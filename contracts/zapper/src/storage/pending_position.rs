@@ -6,7 +6,6 @@ use oraiswap_v3_common::{
 
 #[cw_serde]
 pub struct PendingPosition {
-    pub index: u32,
     pub pool_key: PoolKey,
     pub lower_tick: i32,
     pub upper_tick: i32,
@@ -14,12 +13,12 @@ pub struct PendingPosition {
     pub slippage_limit_lower: Option<SqrtPrice>,
     pub slippage_limit_upper: Option<SqrtPrice>,
     pub minimum_liquidity: Option<Liquidity>,
+    /// Set when the liquidity is added to an existing position instead of a new one
+    pub token_id: Option<u64>,
 }
 
 impl PendingPosition {
-    #[allow(clippy::too_many_arguments)]
     pub fn new(
-        index: u32,
         pool_key: PoolKey,
         lower_tick: i32,
         upper_tick: i32,
@@ -29,7 +28,6 @@ impl PendingPosition {
         minimum_liquidity: Option<Liquidity>,
     ) -> Self {
         Self {
-            index,
             pool_key,
             lower_tick,
             upper_tick,
@@ -37,6 +35,7 @@ impl PendingPosition {
             slippage_limit_lower,
             slippage_limit_upper,
             minimum_liquidity,
+            token_id: None,
         }
     }
 }
@@ -0,0 +1,77 @@
+use cosmwasm_schema::cw_serde;
+use cosmwasm_std::{Addr, Storage, Uint128};
+use oraiswap_v3_common::{
+    asset::{Asset, AssetInfo},
+    error::ContractError,
+};
+
+use crate::{
+    msg::{Route, RouteOperation},
+    state::{LAST_ZAP_ID, ZAP_IN_PROGRESS},
+    PendingPosition,
+};
+
+/// Swaps `amount` of `token_in` along the operations of a route.
+#[cw_serde]
+pub struct RouteSwap {
+    pub token_in: AssetInfo,
+    pub amount: Uint128,
+    pub operations: Vec<RouteOperation>,
+    pub minimum_receive: Option<Uint128>,
+}
+
+/// A swap waiting for its reply, what it returned is the amount of `token_out` its reply
+/// transferred to this contract. The route goes on with `operations` when some are left.
+#[cw_serde]
+pub struct PendingSwap {
+    pub token_out: AssetInfo,
+    pub operations: Vec<RouteOperation>,
+    pub minimum_receive: Option<Uint128>,
+}
+
+/// The swaps of a zap, run one at a time.
+#[cw_serde]
+#[derive(Default)]
+pub struct RouteSwaps {
+    pub queue: Vec<RouteSwap>,
+    pub pending: Option<PendingSwap>,
+}
+
+#[cw_serde]
+pub struct ZapInOperation {
+    pub receiver: Addr,
+    pub position: PendingPosition,
+    /// Amounts of token x and y for the position, added up as the swaps reply
+    pub amount_x: Uint128,
+    pub amount_y: Uint128,
+    pub swaps: RouteSwaps,
+}
+
+#[cw_serde]
+pub struct ZapOutOperation {
+    pub receiver: Addr,
+    pub token_id: u64,
+    /// Set when only part of the liquidity is withdrawn, the position is then sent back
+    pub keep_position: bool,
+    pub routes: Vec<Route>,
+    /// Tokens held for the receiver, added up as the withdrawal and the swaps reply
+    pub assets: Vec<Asset>,
+    pub swaps: RouteSwaps,
+}
+
+/// Starts a zap and returns the id its state is kept under. Fails while another zap is in
+/// progress, so a zap cannot run within another one.
+pub fn start_zap(storage: &mut dyn Storage) -> Result<u64, ContractError> {
+    if ZAP_IN_PROGRESS.may_load(storage)?.is_some() {
+        return Err(ContractError::ZapInProgress {});
+    }
+    let id = LAST_ZAP_ID.may_load(storage)?.unwrap_or_default() + 1;
+    LAST_ZAP_ID.save(storage, &id)?;
+    ZAP_IN_PROGRESS.save(storage, &id)?;
+    Ok(id)
+}
+
+/// Ends the zap in progress.
+pub fn end_zap(storage: &mut dyn Storage) {
+    ZAP_IN_PROGRESS.remove(storage);
+}
@@ -114,7 +114,7 @@ pub fn init_basic_v3_pool(
     let reward_token = AssetInfo::Token {
         contract_addr: token_z.clone(),
     };
-    let total_reward = Some(TokenAmount::from_integer(1000000000));
+    let total_reward = TokenAmount::from_integer(1000000000);
     let reward_per_sec = TokenAmount(100);
     let start_timestamp: Option<u64> = None;
     create_incentive!(
@@ -17,7 +17,7 @@ use oraiswap_v3_common::{
 };
 
 use crate::{
-    msg::{self, Route},
+    msg::{self, Route, RouteOperation, SimulateZapInResponse, SimulateZapOutResponse},
     Config, ProtocolFee,
 };
 
@@ -50,7 +50,8 @@ impl MockApp {
                     crate::contract::execute,
                     crate::contract::instantiate,
                     crate::contract::query,
-                ),
+                )
+                .with_reply_empty(crate::contract::reply),
             ));
         }
         #[cfg(feature = "test-tube")]
@@ -69,11 +70,14 @@ impl MockApp {
         let oracle_id;
         #[cfg(not(feature = "test-tube"))]
         {
-            dex_v3_id = self.app.upload(Box::new(ContractWrapper::new_with_empty(
-                oraiswap_v3::contract::execute,
-                oraiswap_v3::contract::instantiate,
-                oraiswap_v3::contract::query,
-            )));
+            dex_v3_id = self.app.upload(Box::new(
+                ContractWrapper::new_with_empty(
+                    oraiswap_v3::contract::execute,
+                    oraiswap_v3::contract::instantiate,
+                    oraiswap_v3::contract::query,
+                )
+                .with_reply_empty(oraiswap_v3::contract::reply),
+            ));
             incentive_id = self.app.upload(Box::new(ContractWrapper::new_with_empty(
                 incentives_fund_manager::contract::execute,
                 incentives_fund_manager::contract::instantiate,
@@ -273,6 +277,59 @@ impl MockApp {
         )
     }
 
+    pub fn zap_in_to_position(
+        &mut self,
+        sender: &str,
+        zapper: &str,
+        token_id: u64,
+        asset_in: &Asset,
+        routes: Vec<Route>,
+        minimum_liquidity: Option<Liquidity>,
+    ) -> MockResult<ExecuteResponse> {
+        self.execute(
+            Addr::unchecked(sender),
+            Addr::unchecked(zapper),
+            &msg::ExecuteMsg::ZapInToPosition {
+                token_id,
+                asset_in: asset_in.to_owned(),
+                routes,
+                minimum_liquidity,
+            },
+            &[],
+        )
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    pub fn zap_in_liquidity_auto(
+        &mut self,
+        sender: &str,
+        zapper: &str,
+        pool_key: PoolKey,
+        tick_lower_index: i32,
+        tick_upper_index: i32,
+        asset_in: &Asset,
+        operations: Vec<RouteOperation>,
+        slippage_limit_lower: SqrtPrice,
+        slippage_limit_upper: SqrtPrice,
+        minimum_liquidity: Option<Liquidity>,
+    ) -> MockResult<ExecuteResponse> {
+        self.execute(
+            Addr::unchecked(sender),
+            Addr::unchecked(zapper),
+            &msg::ExecuteMsg::ZapInLiquidityAuto {
+                pool_key,
+                tick_lower_index,
+                tick_upper_index,
+                asset_in: asset_in.to_owned(),
+                operations,
+                slippage_limit_lower,
+                slippage_limit_upper,
+                minimum_liquidity,
+            },
+            &[],
+        )
+    }
+
     pub fn zap_out_liquidity(
         &mut self,
         sender: &str,
@@ -286,6 +343,27 @@ impl MockApp {
             &msg::ExecuteMsg::ZapOutLiquidity {
                 position_index,
                 routes,
+                percentage: None,
+            },
+            &[],
+        )
+    }
+
+    pub fn partial_zap_out_liquidity(
+        &mut self,
+        sender: &str,
+        zapper: &str,
+        position_index: u32,
+        routes: Vec<Route>,
+        percentage: StdDecimal,
+    ) -> MockResult<ExecuteResponse> {
+        self.execute(
+            Addr::unchecked(sender),
+            Addr::unchecked(zapper),
+            &msg::ExecuteMsg::ZapOutLiquidity {
+                position_index,
+                routes,
+                percentage: Some(percentage),
             },
             &[],
         )
@@ -336,6 +414,7 @@ impl MockApp {
                 liquidity_delta,
                 slippage_limit_lower,
                 slippage_limit_upper,
+                limit_order: None,
             },
             &[],
         )
@@ -387,13 +466,53 @@ impl MockApp {
         self.query(Addr::unchecked(zapper), &msg::QueryMsg::ProtocolFee {})
     }
 
+    pub fn simulate_zap_in(
+        &self,
+        zapper: &str,
+        pool_key: PoolKey,
+        tick_lower_index: i32,
+        tick_upper_index: i32,
+        asset_in: &Asset,
+        routes: Vec<Route>,
+    ) -> StdResult<SimulateZapInResponse> {
+        self.query(
+            Addr::unchecked(zapper),
+            &msg::QueryMsg::SimulateZapIn {
+                pool_key,
+                tick_lower_index,
+                tick_upper_index,
+                asset_in: asset_in.clone(),
+                routes,
+            },
+        )
+    }
+
+    pub fn simulate_zap_out(
+        &self,
+        zapper: &str,
+        owner: &str,
+        position_index: u32,
+        routes: Vec<Route>,
+        percentage: Option<StdDecimal>,
+    ) -> StdResult<SimulateZapOutResponse> {
+        self.query(
+            Addr::unchecked(zapper),
+            &msg::QueryMsg::SimulateZapOut {
+                owner: Addr::unchecked(owner),
+                position_index,
+                routes,
+                percentage,
+            },
+        )
+    }
+
     pub fn create_incentive(
         &mut self,
         sender: &str,
         dex: &str,
         pool_key: &PoolKey,
         reward_token: AssetInfo,
-        total_reward: Option<TokenAmount>,
+        total_reward: TokenAmount,
         reward_per_sec: TokenAmount,
         start_timestamp: Option<u64>,
     ) -> MockResult<ExecuteResponse> {
@@ -406,6 +525,7 @@ impl MockApp {
                 total_reward,
                 reward_per_sec,
                 start_timestamp,
+                end_timestamp: None,
             },
             &[],
         )
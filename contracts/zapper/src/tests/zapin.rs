@@ -1,10 +1,13 @@
-use cosmwasm_std::{coins, Decimal as StdDecimal, Uint128};
+use cosmwasm_std::{coins, Addr, Decimal as StdDecimal, Uint128};
 use decimal::*;
 use oraiswap::mixed_router::SwapOperation;
 use oraiswap_v3_common::asset::{Asset, AssetInfo};
 use oraiswap_v3_common::error::ContractError;
+use oraiswap_v3_common::interface::SwapHop;
 use oraiswap_v3_common::math::liquidity::Liquidity;
 use oraiswap_v3_common::math::percentage::Percentage;
+use oraiswap_v3_common::math::sqrt_price::SqrtPrice;
+use oraiswap_v3_common::math::{MAX_SQRT_PRICE, MIN_SQRT_PRICE};
 
 use oraiswap_v3_common::storage::{FeeTier, PoolKey};
 
@@ -60,7 +63,8 @@ fn test_zap_in_with_same_token() {
                 operations: vec![SwapOperation::SwapV3 {
                     pool_key: pool_key_x_y.clone(),
                     x_to_y: true,
-                }],
+                }
+                .into()],
                 minimum_receive: None,
             }],
             None,
@@ -93,7 +97,8 @@ fn test_zap_in_with_same_token() {
             operations: vec![SwapOperation::SwapV3 {
                 pool_key: pool_key_x_y.clone(),
                 x_to_y: false,
-            }],
+            }
+            .into()],
             minimum_receive: None,
         }],
         None,
@@ -155,11 +160,13 @@ fn test_zap_in_by_diff_token() {
                     SwapOperation::SwapV3 {
                         pool_key: pool_key_y_z.clone(),
                         x_to_y: false,
-                    },
+                    }
+                    .into(),
                     SwapOperation::SwapV3 {
                         pool_key: pool_key_x_y.clone(),
                         x_to_y: false,
-                    },
+                    }
+                    .into(),
                 ],
                 minimum_receive: None,
             },
@@ -169,7 +176,8 @@ fn test_zap_in_by_diff_token() {
                 operations: vec![SwapOperation::SwapV3 {
                     pool_key: pool_key_y_z.clone(),
                     x_to_y: false,
-                }],
+                }
+                .into()],
                 minimum_receive: None,
             },
         ],
@@ -182,6 +190,460 @@ fn test_zap_in_by_diff_token() {
     assert_eq!(all_positions.len(), 1);
 }
 
+#[test]
+fn test_simulate_zap_in() {
+    let (mut app, accounts) = MockApp::new(&[
+        ("alice", &coins(100_000_000_000, FEE_DENOM)),
+        ("bob", &coins(100_000_000_000, FEE_DENOM)),
+        ("charlie", &coins(100_000_000_000, FEE_DENOM)),
+    ]);
+    let alice = &accounts[0];
+    let bob = &accounts[1];
+    let charlie = &accounts[2];
+    let initial_amount = 10u128.pow(20);
+    let (token_x, token_y, token_z) =
+        create_3_tokens!(app, initial_amount, initial_amount, initial_amount, alice);
+
+    let zapper = create_zapper!(app, alice);
+    let config = app.get_zapper_config(zapper.as_str()).unwrap();
+
+    init_basic_v3_pool(
+        &mut app, &zapper, &token_x, &token_y, &token_z, &alice, &bob,
+    );
+
+    let protocol_fee = Percentage::from_scale(6, 3);
+    let fee_tier = FeeTier::new(protocol_fee, 1).unwrap();
+    let pool_key_x_y = PoolKey::new(token_x.to_string(), token_y.to_string(), fee_tier).unwrap();
+    let pool_key_y_z = PoolKey::new(token_y.to_string(), token_z.to_string(), fee_tier).unwrap();
+
+    // register protocol fee: 0.1%
+    app.register_protocol_fee(
+        &alice,
+        zapper.as_str(),
+        StdDecimal::from_ratio(1u128, 10u128),
+        &charlie,
+    )
+    .unwrap();
+
+    let asset_in = Asset {
+        info: AssetInfo::Token {
+            contract_addr: token_z.clone(),
+        },
+        amount: Uint128::new(1000),
+    };
+    let routes = vec![
+        Route {
+            token_in: token_z.to_string(),
+            offer_amount: Uint128::new(450),
+            operations: vec![
+                SwapOperation::SwapV3 {
+                    pool_key: pool_key_y_z.clone(),
+                    x_to_y: false,
+                }
+                .into(),
+                SwapHop {
+                    pool_key: pool_key_x_y.clone(),
+                    x_to_y: false,
+                }
+                .into(),
+            ],
+            minimum_receive: None,
+        },
+        Route {
+            token_in: token_z.to_string(),
+            offer_amount: Uint128::new(400),
+            operations: vec![SwapHop {
+                pool_key: pool_key_y_z.clone(),
+                x_to_y: false,
+            }
+            .into()],
+            minimum_receive: None,
+        },
+    ];
+
+    let simulation = app
+        .simulate_zap_in(
+            zapper.as_str(),
+            pool_key_x_y.clone(),
+            0,
+            10,
+            &asset_in,
+            routes.clone(),
+        )
+        .unwrap();
+    assert_eq!(
+        simulation.fees,
+        vec![Asset {
+            info: asset_in.info.clone(),
+            amount: Uint128::new(100),
+        }]
+    );
+
+    let balance_x_before = balance_of!(app, token_x, bob);
+    let balance_y_before = balance_of!(app, token_y, bob);
+    let balance_z_before = balance_of!(app, token_z, bob);
+    app.zap_in_liquidity(
+        &bob,
+        zapper.as_str(),
+        pool_key_x_y.clone(),
+        0,
+        10,
+        &asset_in,
+        routes,
+        None,
+    )
+    .unwrap();
+
+    let all_positions = get_all_positions!(app, config.dex_v3, bob);
+    assert_eq!(all_positions.len(), 1);
+    assert_eq!(all_positions[0].liquidity, simulation.liquidity);
+    assert_eq!(balance_of!(app, token_z, charlie), 100);
+
+    // the simulated refunds are what bob gets back
+    let refund_of = |token: &Addr| {
+        simulation
+            .refunds
+            .iter()
+            .find(|refund| {
+                refund.info.eq(&AssetInfo::Token {
+                    contract_addr: token.clone(),
+                })
+            })
+            .map_or(0, |refund| refund.amount.u128())
+    };
+    assert_eq!(
+        balance_of!(app, token_x, bob) - balance_x_before,
+        refund_of(&token_x)
+    );
+    assert_eq!(
+        balance_of!(app, token_y, bob) - balance_y_before,
+        refund_of(&token_y)
+    );
+    assert_eq!(
+        balance_z_before - balance_of!(app, token_z, bob),
+        1000 - refund_of(&token_z)
+    );
+}
+
+#[test]
+fn test_simulate_zap_in_moves_the_pool_price() {
+    let (mut app, accounts) = MockApp::new(&[
+        ("alice", &coins(100_000_000_000, FEE_DENOM)),
+        ("bob", &coins(100_000_000_000, FEE_DENOM)),
+    ]);
+    let alice = &accounts[0];
+    let bob = &accounts[1];
+    let initial_amount = 10u128.pow(20);
+    let (token_x, token_y, token_z) =
+        create_3_tokens!(app, initial_amount, initial_amount, initial_amount, alice);
+
+    let zapper = create_zapper!(app, alice);
+    let config = app.get_zapper_config(zapper.as_str()).unwrap();
+
+    init_basic_v3_pool(
+        &mut app, &zapper, &token_x, &token_y, &token_z, &alice, &bob,
+    );
+
+    let protocol_fee = Percentage::from_scale(6, 3);
+    let fee_tier = FeeTier::new(protocol_fee, 1).unwrap();
+    let pool_key_x_y = PoolKey::new(token_x.to_string(), token_y.to_string(), fee_tier).unwrap();
+    let pool_x = Addr::unchecked(&pool_key_x_y.token_x);
+
+    // both routes swap through the pool of the position, the second one after the first
+    let asset_in = Asset {
+        info: AssetInfo::Token {
+            contract_addr: pool_x.clone(),
+        },
+        amount: Uint128::new(2_000_000_000),
+    };
+    let route = Route {
+        token_in: pool_x.to_string(),
+        offer_amount: Uint128::new(500_000_000),
+        operations: vec![SwapHop {
+            pool_key: pool_key_x_y.clone(),
+            x_to_y: true,
+        }
+        .into()],
+        minimum_receive: None,
+    };
+    let routes = vec![route.clone(), route];
+
+    let pool_before = get_pool!(app, config.dex_v3, token_x, token_y, fee_tier).unwrap();
+    let simulation = app
+        .simulate_zap_in(
+            zapper.as_str(),
+            pool_key_x_y.clone(),
+            -10,
+            10,
+            &asset_in,
+            routes.clone(),
+        )
+        .unwrap();
+
+    app.zap_in_liquidity(
+        &bob,
+        zapper.as_str(),
+        pool_key_x_y.clone(),
+        -10,
+        10,
+        &asset_in,
+        routes,
+        None,
+    )
+    .unwrap();
+    let pool_after = get_pool!(app, config.dex_v3, token_x, token_y, fee_tier).unwrap();
+    assert!(pool_after.sqrt_price < pool_before.sqrt_price);
+
+    // the liquidity is simulated at the price the swaps moved the pool to
+    let all_positions = get_all_positions!(app, config.dex_v3, bob);
+    assert_eq!(all_positions.len(), 1);
+    let liquidity = all_positions[0].liquidity.get();
+    assert!(simulation.liquidity.get().abs_diff(liquidity) <= liquidity / 10000);
+}
+
+#[test]
+fn test_zap_in_with_v3_routes() {
+    let (mut app, accounts) = MockApp::new(&[
+        ("alice", &coins(100_000_000_000, FEE_DENOM)),
+        ("bob", &coins(100_000_000_000, FEE_DENOM)),
+    ]);
+    let alice = &accounts[0];
+    let bob = &accounts[1];
+    let initial_amount = 10u128.pow(20);
+    let (token_x, token_y, token_z) =
+        create_3_tokens!(app, initial_amount, initial_amount, initial_amount, alice);
+
+    let zapper = create_zapper!(app, alice);
+    let config = app.get_zapper_config(zapper.as_str()).unwrap();
+
+    init_basic_v3_pool(
+        &mut app, &zapper, &token_x, &token_y, &token_z, &alice, &bob,
+    );
+
+    let protocol_fee = Percentage::from_scale(6, 3);
+    let fee_tier = FeeTier::new(protocol_fee, 1).unwrap();
+    let pool_key_x_y = PoolKey::new(token_x.to_string(), token_y.to_string(), fee_tier).unwrap();
+    let pool_key_y_z = PoolKey::new(token_y.to_string(), token_z.to_string(), fee_tier).unwrap();
+
+    let tick_lower_index = 0;
+    let tick_upper_index = 10;
+
+    // asset_in = token_z
+    let asset_in = Asset {
+        info: AssetInfo::Token {
+            contract_addr: token_z.clone(),
+        },
+        amount: Uint128::new(1000),
+    };
+
+    // the first route swaps z to y on dex_v3 then y to x through the mixed router,
+    // the second one swaps z to y on dex_v3 only
+    app.zap_in_liquidity(
+        &bob,
+        zapper.as_str(),
+        pool_key_x_y.clone(),
+        tick_lower_index,
+        tick_upper_index,
+        &asset_in,
+        vec![
+            Route {
+                token_in: token_z.to_string(),
+                offer_amount: Uint128::new(500),
+                operations: vec![
+                    SwapHop {
+                        pool_key: pool_key_y_z.clone(),
+                        x_to_y: false,
+                    }
+                    .into(),
+                    SwapOperation::SwapV3 {
+                        pool_key: pool_key_x_y.clone(),
+                        x_to_y: false,
+                    }
+                    .into(),
+                ],
+                minimum_receive: None,
+            },
+            Route {
+                token_in: token_z.to_string(),
+                offer_amount: Uint128::new(500),
+                operations: vec![SwapHop {
+                    pool_key: pool_key_y_z.clone(),
+                    x_to_y: false,
+                }
+                .into()],
+                minimum_receive: None,
+            },
+        ],
+        None,
+    )
+    .unwrap();
+
+    // get all positions
+    let all_positions = get_all_positions!(app, config.dex_v3, bob);
+    assert_eq!(all_positions.len(), 1);
+
+    // nothing is left in the zapper
+    assert_eq!(balance_of!(app, token_x, zapper), 0);
+    assert_eq!(balance_of!(app, token_y, zapper), 0);
+    assert_eq!(balance_of!(app, token_z, zapper), 0);
+
+    // the minimum to receive is checked by dex_v3
+    let error = app
+        .zap_in_liquidity(
+            &bob,
+            zapper.as_str(),
+            pool_key_x_y.clone(),
+            tick_lower_index,
+            tick_upper_index,
+            &asset_in,
+            vec![Route {
+                token_in: token_z.to_string(),
+                offer_amount: Uint128::new(500),
+                operations: vec![SwapHop {
+                    pool_key: pool_key_y_z.clone(),
+                    x_to_y: false,
+                }
+                .into()],
+                minimum_receive: Some(Uint128::new(500)),
+            }],
+            None,
+        )
+        .unwrap_err();
+    assert!(error
+        .root_cause()
+        .to_string()
+        .contains(&ContractError::AmountUnderMinimumAmountOut.to_string()));
+}
+
+#[test]
+fn test_zap_in_to_position() {
+    let (mut app, accounts) = MockApp::new(&[
+        ("alice", &coins(100_000_000_000, FEE_DENOM)),
+        ("bob", &coins(100_000_000_000, FEE_DENOM)),
+    ]);
+    let alice = &accounts[0];
+    let bob = &accounts[1];
+    let initial_amount = 10u128.pow(20);
+    let (token_x, token_y, token_z) =
+        create_3_tokens!(app, initial_amount, initial_amount, initial_amount, alice);
+
+    let zapper = create_zapper!(app, alice);
+    let config = app.get_zapper_config(zapper.as_str()).unwrap();
+
+    init_basic_v3_pool(
+        &mut app, &zapper, &token_x, &token_y, &token_z, &alice, &bob,
+    );
+
+    let protocol_fee = Percentage::from_scale(6, 3);
+    let fee_tier = FeeTier::new(protocol_fee, 1).unwrap();
+    let pool_key_x_y = PoolKey::new(token_x.to_string(), token_y.to_string(), fee_tier).unwrap();
+    let pool_key_y_z = PoolKey::new(token_y.to_string(), token_z.to_string(), fee_tier).unwrap();
+
+    // asset_in = token_z
+    let asset_in = Asset {
+        info: AssetInfo::Token {
+            contract_addr: token_z.clone(),
+        },
+        amount: Uint128::new(1000),
+    };
+    let routes = vec![
+        Route {
+            token_in: token_z.to_string(),
+            offer_amount: Uint128::new(500),
+            operations: vec![
+                SwapOperation::SwapV3 {
+                    pool_key: pool_key_y_z.clone(),
+                    x_to_y: false,
+                }
+                .into(),
+                SwapOperation::SwapV3 {
+                    pool_key: pool_key_x_y.clone(),
+                    x_to_y: false,
+                }
+                .into(),
+            ],
+            minimum_receive: None,
+        },
+        Route {
+            token_in: token_z.to_string(),
+            offer_amount: Uint128::new(500),
+            operations: vec![SwapOperation::SwapV3 {
+                pool_key: pool_key_y_z.clone(),
+                x_to_y: false,
+            }
+            .into()],
+            minimum_receive: None,
+        },
+    ];
+
+    app.zap_in_liquidity(
+        &bob,
+        zapper.as_str(),
+        pool_key_x_y.clone(),
+        0,
+        10,
+        &asset_in,
+        routes.clone(),
+        None,
+    )
+    .unwrap();
+    let position = get_all_positions!(app, config.dex_v3, bob)[0].clone();
+
+    // only the owner can zap into the position
+    let error = app
+        .zap_in_to_position(
+            &alice,
+            zapper.as_str(),
+            position.token_id,
+            &asset_in,
+            routes.clone(),
+            None,
+        )
+        .unwrap_err();
+    assert!(error
+        .root_cause()
+        .to_string()
+        .contains(&ContractError::Unauthorized {}.to_string()));
+
+    // the zapper must be approved for the position
+    app.zap_in_to_position(
+        &bob,
+        zapper.as_str(),
+        position.token_id,
+        &asset_in,
+        routes.clone(),
+        None,
+    )
+    .unwrap_err();
+
+    app.approve_position(
+        &bob,
+        config.dex_v3.as_str(),
+        zapper.as_str(),
+        position.token_id,
+    )
+    .unwrap();
+    app.zap_in_to_position(
+        &bob,
+        zapper.as_str(),
+        position.token_id,
+        &asset_in,
+        routes,
+        None,
+    )
+    .unwrap();
+
+    // the liquidity is added to the same position, which is sent back
+    let all_positions = get_all_positions!(app, config.dex_v3, bob);
+    assert_eq!(all_positions.len(), 1);
+    assert_eq!(all_positions[0].token_id, position.token_id);
+    assert!(all_positions[0].liquidity > position.liquidity);
+    assert!(app
+        .get_all_positions(config.dex_v3.as_str(), zapper.as_str())
+        .unwrap()
+        .is_empty());
+}
+
 #[test]
 fn test_zap_in_with_asset_in_lt_total_swap() {
     let (mut app, accounts) = MockApp::new(&[
@@ -233,11 +695,13 @@ fn test_zap_in_with_asset_in_lt_total_swap() {
                         SwapOperation::SwapV3 {
                             pool_key: pool_key_y_z.clone(),
                             x_to_y: false,
-                        },
+                        }
+                        .into(),
                         SwapOperation::SwapV3 {
                             pool_key: pool_key_x_y.clone(),
                             x_to_y: false,
-                        },
+                        }
+                        .into(),
                     ],
                     minimum_receive: None,
                 },
@@ -247,7 +711,8 @@ fn test_zap_in_with_asset_in_lt_total_swap() {
                     operations: vec![SwapOperation::SwapV3 {
                         pool_key: pool_key_y_z.clone(),
                         x_to_y: false,
-                    }],
+                    }
+                    .into()],
                     minimum_receive: None,
                 },
             ],
@@ -313,11 +778,13 @@ fn test_zap_in_out_of_range() {
                     SwapOperation::SwapV3 {
                         pool_key: pool_key_y_z.clone(),
                         x_to_y: false,
-                    },
+                    }
+                    .into(),
                     SwapOperation::SwapV3 {
                         pool_key: pool_key_x_y.clone(),
                         x_to_y: false,
-                    },
+                    }
+                    .into(),
                 ],
                 minimum_receive: None,
             }],
@@ -343,7 +810,8 @@ fn test_zap_in_out_of_range() {
             operations: vec![SwapOperation::SwapV3 {
                 pool_key: pool_key_y_z.clone(),
                 x_to_y: false,
-            }],
+            }
+            .into()],
             minimum_receive: None,
         }],
         None,
@@ -374,7 +842,8 @@ fn test_zap_in_out_of_range() {
                 operations: vec![SwapOperation::SwapV3 {
                     pool_key: pool_key_y_z.clone(),
                     x_to_y: false,
-                }],
+                }
+                .into()],
                 minimum_receive: None,
             }],
             None,
@@ -400,11 +869,13 @@ fn test_zap_in_out_of_range() {
                 SwapOperation::SwapV3 {
                     pool_key: pool_key_y_z.clone(),
                     x_to_y: false,
-                },
+                }
+                .into(),
                 SwapOperation::SwapV3 {
                     pool_key: pool_key_x_y.clone(),
                     x_to_y: false,
-                },
+                }
+                .into(),
             ],
             minimum_receive: None,
         }],
@@ -470,11 +941,13 @@ fn test_zap_in_with_minimum_receive() {
                         SwapOperation::SwapV3 {
                             pool_key: pool_key_y_z.clone(),
                             x_to_y: false,
-                        },
+                        }
+                        .into(),
                         SwapOperation::SwapV3 {
                             pool_key: pool_key_x_y.clone(),
                             x_to_y: false,
-                        },
+                        }
+                        .into(),
                     ],
                     minimum_receive: None,
                 },
@@ -484,7 +957,8 @@ fn test_zap_in_with_minimum_receive() {
                     operations: vec![SwapOperation::SwapV3 {
                         pool_key: pool_key_y_z.clone(),
                         x_to_y: false,
-                    }],
+                    }
+                    .into()],
                     minimum_receive: None,
                 },
             ],
@@ -558,11 +1032,13 @@ fn test_zap_in_with_fee() {
                         SwapOperation::SwapV3 {
                             pool_key: pool_key_y_z.clone(),
                             x_to_y: false,
-                        },
+                        }
+                        .into(),
                         SwapOperation::SwapV3 {
                             pool_key: pool_key_x_y.clone(),
                             x_to_y: false,
-                        },
+                        }
+                        .into(),
                     ],
                     minimum_receive: None,
                 },
@@ -572,7 +1048,8 @@ fn test_zap_in_with_fee() {
                     operations: vec![SwapOperation::SwapV3 {
                         pool_key: pool_key_y_z.clone(),
                         x_to_y: false,
-                    }],
+                    }
+                    .into()],
                     minimum_receive: None,
                 },
             ],
@@ -597,11 +1074,13 @@ fn test_zap_in_with_fee() {
                     SwapOperation::SwapV3 {
                         pool_key: pool_key_y_z.clone(),
                         x_to_y: false,
-                    },
+                    }
+                    .into(),
                     SwapOperation::SwapV3 {
                         pool_key: pool_key_x_y.clone(),
                         x_to_y: false,
-                    },
+                    }
+                    .into(),
                 ],
                 minimum_receive: None,
             },
@@ -611,7 +1090,8 @@ fn test_zap_in_with_fee() {
                 operations: vec![SwapOperation::SwapV3 {
                     pool_key: pool_key_y_z.clone(),
                     x_to_y: false,
-                }],
+                }
+                .into()],
                 minimum_receive: None,
             },
         ],
@@ -691,4 +1171,332 @@ fn test_zap_in_no_routes() {
     // check balance of fee_receiver
     let fee_receiver_balance = balance_of!(app, token_x, charlie);
     assert_eq!(fee_receiver_balance, 100u128);
-}
\ No newline at end of file
+}
+#[test]
+fn test_zap_in_ignores_donated_tokens() {
+    let (mut app, accounts) = MockApp::new(&[
+        ("alice", &coins(100_000_000_000, FEE_DENOM)),
+        ("bob", &coins(100_000_000_000, FEE_DENOM)),
+    ]);
+    let alice = &accounts[0];
+    let bob = &accounts[1];
+    let initial_amount = 10u128.pow(20);
+    let (token_x, token_y, token_z) =
+        create_3_tokens!(app, initial_amount, initial_amount, initial_amount, alice);
+
+    let zapper = create_zapper!(app, alice);
+    let config = app.get_zapper_config(zapper.as_str()).unwrap();
+
+    init_basic_v3_pool(
+        &mut app, &zapper, &token_x, &token_y, &token_z, &alice, &bob,
+    );
+
+    let protocol_fee = Percentage::from_scale(6, 3);
+    let fee_tier = FeeTier::new(protocol_fee, 1).unwrap();
+    let pool_key_x_y = PoolKey::new(token_x.to_string(), token_y.to_string(), fee_tier).unwrap();
+
+    // tokens sent to the zapper outside of a zap
+    app.mint_token(alice, zapper.as_str(), token_x.as_str(), 1_000_000)
+        .unwrap();
+    app.mint_token(alice, zapper.as_str(), token_y.as_str(), 1_000_000)
+        .unwrap();
+    let bob_balance_x = balance_of!(app, token_x, bob);
+    let bob_balance_y = balance_of!(app, token_y, bob);
+
+    let asset_in = Asset {
+        info: AssetInfo::Token {
+            contract_addr: token_x.clone(),
+        },
+        amount: Uint128::new(1000),
+    };
+    app.zap_in_liquidity(
+        &bob,
+        zapper.as_str(),
+        pool_key_x_y.clone(),
+        25,
+        30,
+        &asset_in,
+        vec![],
+        None,
+    )
+    .unwrap();
+    let all_positions = get_all_positions!(app, config.dex_v3, bob);
+    assert_eq!(all_positions.len(), 1);
+
+    // bob only spent what the position took, the donations stay in the zapper
+    let spent_x = bob_balance_x - balance_of!(app, token_x, bob);
+    assert!(spent_x > 0 && spent_x <= 1000);
+    assert_eq!(balance_of!(app, token_y, bob), bob_balance_y);
+    assert_eq!(balance_of!(app, token_x, zapper), 1_000_000);
+    assert_eq!(balance_of!(app, token_y, zapper), 1_000_000);
+}
+
+#[test]
+fn test_zap_in_funds() {
+    let (mut app, accounts) = MockApp::new(&[
+        ("alice", &coins(100_000_000_000, FEE_DENOM)),
+        ("bob", &coins(100_000_000_000, FEE_DENOM)),
+    ]);
+    let alice = &accounts[0];
+    let bob = &accounts[1];
+    let initial_amount = 10u128.pow(20);
+    let (token_x, token_y, token_z) =
+        create_3_tokens!(app, initial_amount, initial_amount, initial_amount, alice);
+
+    let zapper = create_zapper!(app, alice);
+    init_basic_v3_pool(
+        &mut app, &zapper, &token_x, &token_y, &token_z, &alice, &bob,
+    );
+
+    let protocol_fee = Percentage::from_scale(6, 3);
+    let fee_tier = FeeTier::new(protocol_fee, 1).unwrap();
+    let pool_key_x_y = PoolKey::new(token_x.to_string(), token_y.to_string(), fee_tier).unwrap();
+
+    // native coins must be sent with the zap
+    let error = app
+        .zap_in_liquidity(
+            &bob,
+            zapper.as_str(),
+            pool_key_x_y.clone(),
+            25,
+            30,
+            &Asset {
+                info: AssetInfo::NativeToken {
+                    denom: FEE_DENOM.to_string(),
+                },
+                amount: Uint128::new(1000),
+            },
+            vec![],
+            None,
+        )
+        .unwrap_err();
+    assert!(error.root_cause().to_string().contains(
+        &ContractError::InvalidFunds {
+            transfer_amount: Uint128::new(1000)
+        }
+        .to_string()
+    ));
+
+    // coins the zap does not take are rejected
+    let error = app
+        .execute(
+            Addr::unchecked(bob),
+            zapper.clone(),
+            &crate::msg::ExecuteMsg::ZapInLiquidity {
+                pool_key: pool_key_x_y,
+                tick_lower_index: 25,
+                tick_upper_index: 30,
+                asset_in: Asset {
+                    info: AssetInfo::Token {
+                        contract_addr: token_x.clone(),
+                    },
+                    amount: Uint128::new(1000),
+                },
+                routes: vec![],
+                minimum_liquidity: None,
+            },
+            &coins(1000, FEE_DENOM),
+        )
+        .unwrap_err();
+    assert!(error.root_cause().to_string().contains(
+        &ContractError::UnexpectedFunds {
+            denom: FEE_DENOM.to_string()
+        }
+        .to_string()
+    ));
+}
+
+#[test]
+fn test_zap_in_liquidity_auto() {
+    let (mut app, accounts) = MockApp::new(&[
+        ("alice", &coins(100_000_000_000, FEE_DENOM)),
+        ("bob", &coins(100_000_000_000, FEE_DENOM)),
+    ]);
+    let alice = &accounts[0];
+    let bob = &accounts[1];
+    let initial_amount = 10u128.pow(20);
+    let (token_x, token_y, token_z) =
+        create_3_tokens!(app, initial_amount, initial_amount, initial_amount, alice);
+
+    let zapper = create_zapper!(app, alice);
+    let config = app.get_zapper_config(zapper.as_str()).unwrap();
+
+    init_basic_v3_pool(
+        &mut app, &zapper, &token_x, &token_y, &token_z, &alice, &bob,
+    );
+
+    let protocol_fee = Percentage::from_scale(6, 3);
+    let fee_tier = FeeTier::new(protocol_fee, 1).unwrap();
+    let pool_key_x_y = PoolKey::new(token_x.to_string(), token_y.to_string(), fee_tier).unwrap();
+    // the tokens in the order of the pool
+    let pool_x = Addr::unchecked(&pool_key_x_y.token_x);
+    let pool_y = Addr::unchecked(&pool_key_x_y.token_y);
+    let asset = |token: &Addr, amount: u128| Asset {
+        info: AssetInfo::Token {
+            contract_addr: token.clone(),
+        },
+        amount: Uint128::new(amount),
+    };
+
+    let min_sqrt_price = SqrtPrice::new(MIN_SQRT_PRICE);
+    let max_sqrt_price = SqrtPrice::new(MAX_SQRT_PRICE);
+
+    // an asset that is not a token of the pool needs operations swapping it into one of them
+    let err = app
+        .zap_in_liquidity_auto(
+            &bob,
+            zapper.as_str(),
+            pool_key_x_y.clone(),
+            0,
+            10,
+            &asset(&token_z, 1000000),
+            vec![],
+            min_sqrt_price,
+            max_sqrt_price,
+            None,
+        )
+        .unwrap_err();
+    assert!(err
+        .root_cause()
+        .to_string()
+        .contains(&ContractError::ZapInAssetNotInPool {}.to_string()));
+
+    // the price is out of the limits of the caller
+    let pool = get_pool!(app, config.dex_v3, token_x, token_y, fee_tier).unwrap();
+    let err = app
+        .zap_in_liquidity_auto(
+            &bob,
+            zapper.as_str(),
+            pool_key_x_y.clone(),
+            0,
+            10,
+            &asset(&pool_x, 1000000),
+            vec![],
+            pool.sqrt_price + SqrtPrice::new(1),
+            max_sqrt_price,
+            None,
+        )
+        .unwrap_err();
+    assert!(err
+        .root_cause()
+        .to_string()
+        .contains(&ContractError::PriceLimitReached.to_string()));
+
+    // the swap of token x for token y pushes the price below the limits of the caller
+    let err = app
+        .zap_in_liquidity_auto(
+            &bob,
+            zapper.as_str(),
+            pool_key_x_y.clone(),
+            0,
+            10,
+            &asset(&pool_x, 1000000),
+            vec![],
+            pool.sqrt_price,
+            max_sqrt_price,
+            None,
+        )
+        .unwrap_err();
+    assert!(err
+        .root_cause()
+        .to_string()
+        .contains(&ContractError::PriceLimitReached.to_string()));
+
+    // the position is not worth the minimum liquidity of the caller
+    let err = app
+        .zap_in_liquidity_auto(
+            &bob,
+            zapper.as_str(),
+            pool_key_x_y.clone(),
+            0,
+            10,
+            &asset(&pool_x, 1000000),
+            vec![],
+            min_sqrt_price,
+            max_sqrt_price,
+            Some(Liquidity::new(u128::MAX)),
+        )
+        .unwrap_err();
+    assert!(err
+        .root_cause()
+        .to_string()
+        .contains("Assertion failed; expect"));
+
+    // in range, about half of token x is swapped for token y
+    let bob_x = balance_of!(app, pool_x, bob);
+    let bob_y = balance_of!(app, pool_y, bob);
+    app.zap_in_liquidity_auto(
+        &bob,
+        zapper.as_str(),
+        pool_key_x_y.clone(),
+        0,
+        10,
+        &asset(&pool_x, 1000000),
+        vec![],
+        min_sqrt_price,
+        max_sqrt_price,
+        None,
+    )
+    .unwrap();
+    let all_positions = get_all_positions!(app, config.dex_v3, bob);
+    assert_eq!(all_positions.len(), 1);
+    assert!(!all_positions[0].liquidity.is_zero());
+
+    // only the swap fee is left over and refunded
+    let refund_x = balance_of!(app, pool_x, bob) + 1000000 - bob_x;
+    let refund_y = balance_of!(app, pool_y, bob) - bob_y;
+    assert!(refund_x + refund_y < 10000);
+    assert_eq!(balance_of!(app, pool_x, zapper), 0);
+    assert_eq!(balance_of!(app, pool_y, zapper), 0);
+
+    // above the current price the position only holds token x, all of token y is swapped
+    let bob_x = balance_of!(app, pool_x, bob);
+    let bob_y = balance_of!(app, pool_y, bob);
+    app.zap_in_liquidity_auto(
+        &bob,
+        zapper.as_str(),
+        pool_key_x_y.clone(),
+        25,
+        30,
+        &asset(&pool_y, 1000000),
+        vec![],
+        min_sqrt_price,
+        max_sqrt_price,
+        None,
+    )
+    .unwrap();
+    let all_positions = get_all_positions!(app, config.dex_v3, bob);
+    assert_eq!(all_positions.len(), 2);
+    assert_eq!(balance_of!(app, pool_y, bob), bob_y - 1000000);
+    assert!(balance_of!(app, pool_x, bob) - bob_x < 10);
+    assert_eq!(balance_of!(app, pool_x, zapper), 0);
+    assert_eq!(balance_of!(app, pool_y, zapper), 0);
+
+    // token z is swapped into token y through the mixed router first
+    let pool_key_y_z = PoolKey::new(token_y.to_string(), token_z.to_string(), fee_tier).unwrap();
+    let bob_z = balance_of!(app, token_z, bob);
+    app.zap_in_liquidity_auto(
+        &bob,
+        zapper.as_str(),
+        pool_key_x_y.clone(),
+        -10,
+        10,
+        &asset(&token_z, 1000000),
+        vec![SwapOperation::SwapV3 {
+            x_to_y: pool_key_y_z.token_x == token_z.to_string(),
+            pool_key: pool_key_y_z,
+        }
+        .into()],
+        min_sqrt_price,
+        max_sqrt_price,
+        None,
+    )
+    .unwrap();
+    let all_positions = get_all_positions!(app, config.dex_v3, bob);
+    assert_eq!(all_positions.len(), 3);
+    assert!(!all_positions[2].liquidity.is_zero());
+    assert_eq!(balance_of!(app, token_z, bob), bob_z - 1000000);
+    assert_eq!(balance_of!(app, token_z, zapper), 0);
+    assert_eq!(balance_of!(app, pool_x, zapper), 0);
+    assert_eq!(balance_of!(app, pool_y, zapper), 0);
+}
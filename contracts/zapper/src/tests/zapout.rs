@@ -1,6 +1,6 @@
 use std::ops::Add;
 
-use cosmwasm_std::{coins, Decimal as StdDecimal, Uint128};
+use cosmwasm_std::{coins, Addr, Decimal as StdDecimal, Uint128};
 use decimal::*;
 use oraiswap::mixed_router::SwapOperation;
 use oraiswap_v3_common::error::ContractError;
@@ -251,7 +251,8 @@ fn zap_out_position_not_enough_balance_to_swap() {
                 operations: vec![SwapOperation::SwapV3 {
                     pool_key: pool_key.clone(),
                     x_to_y: true,
-                }],
+                }
+                .into()],
                 minimum_receive: None,
             }],
         )
@@ -339,7 +340,8 @@ fn zap_out_position_with_slippage() {
                 operations: vec![SwapOperation::SwapV3 {
                     pool_key: pool_key.clone(),
                     x_to_y: true,
-                }],
+                }
+                .into()],
                 minimum_receive: Some(Uint128::new(quote.amount_out.0 + 10)),
             }],
         )
@@ -417,7 +419,8 @@ fn zap_out_position_with_routes_success() {
             operations: vec![SwapOperation::SwapV3 {
                 pool_key: pool_key.clone(),
                 x_to_y: true,
-            }],
+            }
+            .into()],
             minimum_receive: None,
         }],
     )
@@ -429,6 +432,177 @@ fn zap_out_position_with_routes_success() {
     assert!(balance_y_before.lt(&balance_y_after));
 }
 
+#[test]
+fn zap_out_position_ignores_donated_tokens() {
+    let (mut app, accounts) = MockApp::new(&[
+        ("alice", &coins(100_000_000_000, FEE_DENOM)),
+        ("bob", &coins(100_000_000_000, FEE_DENOM)),
+    ]);
+    let alice = &accounts[0];
+    let bob = &accounts[1];
+    let initial_amount = 10u128.pow(20);
+    let (token_x, token_y, token_z) =
+        create_3_tokens!(app, initial_amount, initial_amount, initial_amount, alice);
+
+    let zapper = create_zapper!(app, alice);
+    let config = app.get_zapper_config(zapper.as_str()).unwrap();
+
+    init_basic_v3_pool(
+        &mut app, &zapper, &token_x, &token_y, &token_z, &alice, &bob,
+    );
+
+    let protocol_fee = Percentage::from_scale(6, 3);
+    let fee_tier = FeeTier::new(protocol_fee, 1).unwrap();
+    let pool_key = PoolKey::new(token_x.to_string(), token_y.to_string(), fee_tier).unwrap();
+
+    let balance_x_before = balance_of!(app, token_x, bob);
+    create_position!(
+        app,
+        config.dex_v3,
+        pool_key,
+        -10,
+        10,
+        Liquidity::new(2u128.pow(60) - 1),
+        SqrtPrice::new(0),
+        SqrtPrice::max_instance(),
+        bob
+    )
+    .unwrap();
+    let amount_x_to_swap = balance_x_before - balance_of!(app, token_x, bob);
+
+    let all_positions = get_all_positions!(app, config.dex_v3, bob);
+    app.approve_position(
+        &bob,
+        config.dex_v3.as_str(),
+        zapper.as_str(),
+        all_positions[0].token_id,
+    )
+    .unwrap();
+
+    // tokens sent to the zapper outside of a zap
+    app.mint_token(alice, zapper.as_str(), token_x.as_str(), 1_000_000)
+        .unwrap();
+    app.mint_token(alice, zapper.as_str(), token_y.as_str(), 1_000_000)
+        .unwrap();
+
+    app.zap_out_liquidity(
+        &bob,
+        zapper.as_str(),
+        0,
+        vec![Route {
+            token_in: token_x.to_string(),
+            offer_amount: Uint128::new(amount_x_to_swap - 10),
+            operations: vec![SwapOperation::SwapV3 {
+                pool_key: pool_key.clone(),
+                x_to_y: true,
+            }
+            .into()],
+            minimum_receive: None,
+        }],
+    )
+    .unwrap();
+
+    // bob only got what the position and the swap returned, the donations stay in the zapper
+    assert_eq!(balance_of!(app, token_x, zapper), 1_000_000);
+    assert_eq!(balance_of!(app, token_y, zapper), 1_000_000);
+}
+
+#[test]
+fn simulate_zap_out_position_with_routes() {
+    let (mut app, accounts) = MockApp::new(&[
+        ("alice", &coins(100_000_000_000, FEE_DENOM)),
+        ("bob", &coins(100_000_000_000, FEE_DENOM)),
+        ("charlie", &coins(100_000_000_000, FEE_DENOM)),
+    ]);
+    let alice = &accounts[0];
+    let bob = &accounts[1];
+    let charlie = &accounts[2];
+    let initial_amount = 10u128.pow(20);
+    let (token_x, token_y, token_z) =
+        create_3_tokens!(app, initial_amount, initial_amount, initial_amount, alice);
+
+    let zapper = create_zapper!(app, alice);
+    let config = app.get_zapper_config(zapper.as_str()).unwrap();
+
+    init_basic_v3_pool(
+        &mut app, &zapper, &token_x, &token_y, &token_z, &alice, &bob,
+    );
+
+    let protocol_fee = Percentage::from_scale(6, 3);
+    let fee_tier = FeeTier::new(protocol_fee, 1).unwrap();
+    let pool_key = PoolKey::new(token_x.to_string(), token_y.to_string(), fee_tier).unwrap();
+
+    // register protocol fee: 0.1%
+    app.register_protocol_fee(
+        &alice,
+        zapper.as_str(),
+        StdDecimal::from_ratio(1u128, 10u128),
+        &charlie,
+    )
+    .unwrap();
+
+    let tick_lower_index = -10;
+    let tick_upper_index = 10;
+    let liquidity_delta = Liquidity::new(2u128.pow(60) - 1);
+
+    create_position!(
+        app,
+        config.dex_v3,
+        pool_key,
+        tick_lower_index,
+        tick_upper_index,
+        liquidity_delta,
+        SqrtPrice::new(0),
+        SqrtPrice::max_instance(),
+        bob
+    )
+    .unwrap();
+    let all_positions = get_all_positions!(app, config.dex_v3, bob);
+    assert_eq!(all_positions.len(), 1);
+    app.approve_position(
+        &bob,
+        config.dex_v3.as_str(),
+        zapper.as_str(),
+        all_positions[0].token_id,
+    )
+    .unwrap();
+
+    let routes = vec![Route {
+        token_in: token_x.to_string(),
+        offer_amount: Uint128::new(1000),
+        operations: vec![SwapOperation::SwapV3 {
+            pool_key: pool_key.clone(),
+            x_to_y: true,
+        }
+        .into()],
+        minimum_receive: None,
+    }];
+    let simulation = app
+        .simulate_zap_out(zapper.as_str(), &bob, 0, routes.clone(), None)
+        .unwrap();
+    assert_eq!(simulation.fees.len(), 1);
+    assert_eq!(simulation.fees[0].amount, Uint128::new(100));
+
+    let balance_x_before = balance_of!(app, token_x, bob);
+    let balance_y_before = balance_of!(app, token_y, bob);
+    app.zap_out_liquidity(&bob, zapper.as_str(), 0, routes)
+        .unwrap();
+    let balance_x_after = balance_of!(app, token_x, bob);
+    let balance_y_after = balance_of!(app, token_y, bob);
+
+    // the simulated assets are what bob receives
+    assert_eq!(simulation.assets.len(), 2);
+    assert_eq!(
+        simulation.assets[0].amount.u128(),
+        balance_x_after - balance_x_before
+    );
+    assert_eq!(
+        simulation.assets[1].amount.u128(),
+        balance_y_after - balance_y_before
+    );
+    assert_eq!(balance_of!(app, token_x, charlie), 100);
+}
+
 #[test]
 fn zap_out_position_with_fee() {
     let (mut app, accounts) = MockApp::new(&[
@@ -506,7 +680,8 @@ fn zap_out_position_with_fee() {
             operations: vec![SwapOperation::SwapV3 {
                 pool_key: pool_key.clone(),
                 x_to_y: true,
-            }],
+            }
+            .into()],
             minimum_receive: None,
         }],
     )
@@ -519,3 +694,113 @@ fn zap_out_position_with_fee() {
             .add((Uint128::new(amount_x_to_swap) * StdDecimal::from_ratio(1u128, 10u128)).u128())
     );
 }
+
+#[test]
+fn zap_out_position_partially() {
+    let (mut app, accounts) = MockApp::new(&[
+        ("alice", &coins(100_000_000_000, FEE_DENOM)),
+        ("bob", &coins(100_000_000_000, FEE_DENOM)),
+    ]);
+    let alice = &accounts[0];
+    let bob = &accounts[1];
+    let initial_amount = 10u128.pow(20);
+    let (token_x, token_y, token_z) =
+        create_3_tokens!(app, initial_amount, initial_amount, initial_amount, alice);
+
+    let zapper = create_zapper!(app, alice);
+    let config = app.get_zapper_config(zapper.as_str()).unwrap();
+
+    init_basic_v3_pool(
+        &mut app, &zapper, &token_x, &token_y, &token_z, &alice, &bob,
+    );
+
+    let protocol_fee = Percentage::from_scale(6, 3);
+    let fee_tier = FeeTier::new(protocol_fee, 1).unwrap();
+    let pool_key = PoolKey::new(token_x.to_string(), token_y.to_string(), fee_tier).unwrap();
+
+    let tick_lower_index = -10;
+    let tick_upper_index = 10;
+    let liquidity_delta = Liquidity::new(2u128.pow(60) - 1);
+
+    let balance_x_before = balance_of!(app, token_x, bob);
+    let balance_y_before = balance_of!(app, token_y, bob);
+    create_position!(
+        app,
+        config.dex_v3,
+        pool_key,
+        tick_lower_index,
+        tick_upper_index,
+        liquidity_delta,
+        SqrtPrice::new(0),
+        SqrtPrice::max_instance(),
+        bob
+    )
+    .unwrap();
+    let deposited_x = balance_x_before - balance_of!(app, token_x, bob);
+    let deposited_y = balance_y_before - balance_of!(app, token_y, bob);
+
+    let position = get_all_positions!(app, config.dex_v3, bob)[0].clone();
+    app.approve_position(
+        &bob,
+        config.dex_v3.as_str(),
+        zapper.as_str(),
+        position.token_id,
+    )
+    .unwrap();
+
+    // the percentage must be in (0, 1]
+    for percentage in [StdDecimal::zero(), StdDecimal::percent(101)] {
+        let error = app
+            .partial_zap_out_liquidity(&bob, zapper.as_str(), 0, vec![], percentage)
+            .unwrap_err();
+        assert!(error
+            .root_cause()
+            .to_string()
+            .contains(&ContractError::InvalidPercentage {}.to_string()));
+        assert!(app
+            .simulate_zap_out(zapper.as_str(), &bob, 0, vec![], Some(percentage))
+            .unwrap_err()
+            .to_string()
+            .contains(&ContractError::InvalidPercentage {}.to_string()));
+    }
+
+    let simulation = app
+        .simulate_zap_out(
+            zapper.as_str(),
+            &bob,
+            0,
+            vec![],
+            Some(StdDecimal::percent(50)),
+        )
+        .unwrap();
+    let balance_x_before = balance_of!(app, token_x, bob);
+    let balance_y_before = balance_of!(app, token_y, bob);
+    app.partial_zap_out_liquidity(&bob, zapper.as_str(), 0, vec![], StdDecimal::percent(50))
+        .unwrap();
+
+    // half of the liquidity is withdrawn, the position is kept and sent back
+    let all_positions = get_all_positions!(app, config.dex_v3, bob);
+    assert_eq!(all_positions.len(), 1);
+    assert_eq!(all_positions[0].token_id, position.token_id);
+    assert_eq!(
+        all_positions[0].liquidity,
+        Liquidity::new(position.liquidity.get() - position.liquidity.get() / 2)
+    );
+
+    let withdrawn_x = balance_of!(app, token_x, bob) - balance_x_before;
+    let withdrawn_y = balance_of!(app, token_y, bob) - balance_y_before;
+    assert!(withdrawn_x.abs_diff(deposited_x / 2).lt(&10u128));
+    assert!(withdrawn_y.abs_diff(deposited_y / 2).lt(&10u128));
+
+    // the simulation of the same percentage is what bob receives, without the incentives
+    let simulated = |token: &Addr| {
+        simulation
+            .assets
+            .iter()
+            .find(|asset| asset.info.denom() == token.as_str())
+            .map_or(0, |asset| asset.amount.u128())
+    };
+    assert_eq!(simulation.assets.len(), 2);
+    assert_eq!(simulated(&token_x), withdrawn_x);
+    assert_eq!(simulated(&token_y), withdrawn_y);
+}
@@ -216,3 +216,358 @@ impl Asset {
         Ok(())
     }
 }
+
+/// Pulls the assets from the sender into the recipient. Native coins must be sent with the message:
+/// coins above the required amounts are refunded, coins of any other denom are rejected.
+/// `received` holds the cw20 tokens already sent to the contract through a hook, they are handled
+/// the same way as the native coins.
+pub fn transfer_from_sender(
+    msgs: &mut Vec<CosmosMsg>,
+    info: &MessageInfo,
+    received: Option<&Asset>,
+    recipient: String,
+    assets: &[Asset],
+) -> Result<(), ContractError> {
+    if let Some(received) = received {
+        let mut expected = false;
+        let mut required = Uint128::zero();
+        for asset in assets.iter().filter(|asset| asset.info.eq(&received.info)) {
+            expected = true;
+            required += asset.amount;
+        }
+        if !expected {
+            return Err(ContractError::UnexpectedFunds {
+                denom: received.info.denom(),
+            });
+        }
+        if received.amount < required {
+            return Err(ContractError::InvalidFunds {
+                transfer_amount: required,
+            });
+        }
+        if received.amount > required {
+            received
+                .info
+                .transfer(msgs, info.sender.to_string(), received.amount - required)?;
+        }
+    }
+
+    let mut refunds: Vec<Coin> = vec![];
+    for coin in &info.funds {
+        let mut expected = false;
+        let mut required = Uint128::zero();
+        for asset in assets {
+            if let AssetInfo::NativeToken { denom } = &asset.info {
+                if denom.eq(&coin.denom) {
+                    expected = true;
+                    required += asset.amount;
+                }
+            }
+        }
+        if !expected {
+            return Err(ContractError::UnexpectedFunds {
+                denom: coin.denom.clone(),
+            });
+        }
+        if coin.amount < required {
+            return Err(ContractError::InvalidFunds {
+                transfer_amount: required,
+            });
+        }
+        if coin.amount > required {
+            refunds.push(Coin {
+                denom: coin.denom.clone(),
+                amount: coin.amount - required,
+            });
+        }
+    }
+
+    for asset in assets {
+        if asset.amount.is_zero() || received.is_some_and(|received| received.info.eq(&asset.info))
+        {
+            continue;
+        }
+        match &asset.info {
+            AssetInfo::Token { contract_addr } => msgs.push(
+                WasmMsg::Execute {
+                    contract_addr: contract_addr.to_string(),
+                    msg: to_json_binary(&Cw20ExecuteMsg::TransferFrom {
+                        owner: info.sender.to_string(),
+                        recipient: recipient.clone(),
+                        amount: asset.amount,
+                    })?,
+                    funds: vec![],
+                }
+                .into(),
+            ),
+            AssetInfo::NativeToken { denom } => {
+                if !info.funds.iter().any(|coin| coin.denom.eq(denom)) {
+                    return Err(ContractError::InvalidFunds {
+                        transfer_amount: asset.amount,
+                    });
+                }
+            }
+        }
+    }
+
+    if !refunds.is_empty() {
+        msgs.push(
+            BankMsg::Send {
+                to_address: info.sender.to_string(),
+                amount: refunds,
+            }
+            .into(),
+        );
+    }
+
+    Ok(())
+}
+
+/// Adds up the amounts of the same asset, in the order each asset first appears.
+pub fn merge_assets(assets: impl IntoIterator<Item = Asset>) -> Vec<Asset> {
+    let mut merged: Vec<Asset> = vec![];
+    for asset in assets {
+        match merged.iter_mut().find(|item| item.info.eq(&asset.info)) {
+            Some(item) => item.amount += asset.amount,
+            None => merged.push(asset),
+        }
+    }
+    merged
+}
+
+/// Sends the assets to the receiver with as few messages as possible: the native coins go in a
+/// single bank send and each cw20 token in a single transfer.
+pub fn transfer_assets(
+    msgs: &mut Vec<CosmosMsg>,
+    receiver: &Addr,
+    assets: impl IntoIterator<Item = Asset>,
+) -> Result<(), ContractError> {
+    let mut coins: Vec<Coin> = vec![];
+    for asset in merge_assets(assets) {
+        if asset.amount.is_zero() {
+            continue;
+        }
+        match asset.info {
+            AssetInfo::NativeToken { denom } => coins.push(Coin {
+                denom,
+                amount: asset.amount,
+            }),
+            AssetInfo::Token { .. } => {
+                asset
+                    .info
+                    .transfer(msgs, receiver.to_string(), asset.amount)?
+            }
+        }
+    }
+
+    if !coins.is_empty() {
+        coins.sort_by(|a, b| a.denom.cmp(&b.denom));
+        msgs.push(
+            BankMsg::Send {
+                to_address: receiver.to_string(),
+                amount: coins,
+            }
+            .into(),
+        );
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use cosmwasm_std::{coin, coins, testing::mock_info};
+
+    use super::*;
+
+    fn native(denom: &str, amount: u128) -> Asset {
+        Asset::new(
+            AssetInfo::NativeToken {
+                denom: denom.to_string(),
+            },
+            amount.into(),
+        )
+    }
+
+    #[test]
+    fn test_transfer_from_sender() {
+        let token = Asset::new(
+            AssetInfo::Token {
+                contract_addr: Addr::unchecked("token"),
+            },
+            Uint128::new(50),
+        );
+        // exact funds
+        {
+            let mut msgs = vec![];
+            let info = mock_info("sender", &coins(100, "orai"));
+            transfer_from_sender(
+                &mut msgs,
+                &info,
+                None,
+                "dex".to_string(),
+                &[native("orai", 100), token.clone()],
+            )
+            .unwrap();
+            assert_eq!(msgs.len(), 1);
+        }
+        // excess and zero amount assets are refunded
+        {
+            let mut msgs = vec![];
+            let info = mock_info("sender", &[coin(30, "atom"), coin(120, "orai")]);
+            transfer_from_sender(
+                &mut msgs,
+                &info,
+                None,
+                "dex".to_string(),
+                &[native("orai", 100), native("atom", 0)],
+            )
+            .unwrap();
+            assert_eq!(
+                msgs,
+                vec![CosmosMsg::Bank(BankMsg::Send {
+                    to_address: "sender".to_string(),
+                    amount: vec![coin(30, "atom"), coin(20, "orai")],
+                })]
+            );
+        }
+        // unexpected denom
+        {
+            let info = mock_info("sender", &[coin(100, "orai"), coin(1, "usdt")]);
+            let err = transfer_from_sender(
+                &mut vec![],
+                &info,
+                None,
+                "dex".to_string(),
+                &[native("orai", 100), token.clone()],
+            )
+            .unwrap_err();
+            assert!(matches!(err, ContractError::UnexpectedFunds { denom } if denom == "usdt"));
+            let info = mock_info("sender", &coins(1, "orai"));
+            let err = transfer_from_sender(
+                &mut vec![],
+                &info,
+                None,
+                "dex".to_string(),
+                &[token.clone()],
+            )
+            .unwrap_err();
+            assert!(matches!(err, ContractError::UnexpectedFunds { denom } if denom == "orai"));
+        }
+        // not enough funds
+        {
+            let info = mock_info("sender", &coins(99, "orai"));
+            let err = transfer_from_sender(
+                &mut vec![],
+                &info,
+                None,
+                "dex".to_string(),
+                &[native("orai", 100)],
+            )
+            .unwrap_err();
+            assert!(
+                matches!(err, ContractError::InvalidFunds { transfer_amount } if transfer_amount == Uint128::new(100))
+            );
+            let info = mock_info("sender", &[]);
+            let err = transfer_from_sender(
+                &mut vec![],
+                &info,
+                None,
+                "dex".to_string(),
+                &[native("orai", 100)],
+            )
+            .unwrap_err();
+            assert!(
+                matches!(err, ContractError::InvalidFunds { transfer_amount } if transfer_amount == Uint128::new(100))
+            );
+        }
+        // tokens received through a hook, the excess is sent back
+        {
+            let mut msgs = vec![];
+            let info = mock_info("sender", &[]);
+            let received = Asset::new(token.info.clone(), Uint128::new(80));
+            transfer_from_sender(
+                &mut msgs,
+                &info,
+                Some(&received),
+                "dex".to_string(),
+                &[token.clone()],
+            )
+            .unwrap();
+            assert_eq!(msgs.len(), 1);
+            let err = transfer_from_sender(
+                &mut vec![],
+                &info,
+                Some(&Asset::new(token.info.clone(), Uint128::new(49))),
+                "dex".to_string(),
+                &[token.clone()],
+            )
+            .unwrap_err();
+            assert!(matches!(err, ContractError::InvalidFunds { .. }));
+            let err = transfer_from_sender(
+                &mut vec![],
+                &info,
+                Some(&received),
+                "dex".to_string(),
+                &[native("orai", 0)],
+            )
+            .unwrap_err();
+            assert!(matches!(err, ContractError::UnexpectedFunds { denom } if denom == "token"));
+        }
+    }
+
+    #[test]
+    fn test_transfer_assets() {
+        let token = |contract_addr: &str, amount: u128| {
+            Asset::new(
+                AssetInfo::Token {
+                    contract_addr: Addr::unchecked(contract_addr),
+                },
+                amount.into(),
+            )
+        };
+        let assets = vec![
+            native("orai", 10),
+            token("token_a", 5),
+            native("atom", 1),
+            token("token_b", 0),
+            native("orai", 20),
+            token("token_a", 7),
+        ];
+        assert_eq!(
+            merge_assets(assets.clone()),
+            vec![
+                native("orai", 30),
+                token("token_a", 12),
+                native("atom", 1),
+                token("token_b", 0),
+            ]
+        );
+
+        // one transfer per cw20 token, then every native coin in a single bank send
+        let mut msgs = vec![];
+        transfer_assets(&mut msgs, &Addr::unchecked("receiver"), assets).unwrap();
+        assert_eq!(msgs.len(), 2);
+        assert_eq!(
+            msgs[1],
+            BankMsg::Send {
+                to_address: "receiver".to_string(),
+                amount: vec![coin(1, "atom"), coin(30, "orai")],
+            }
+            .into()
+        );
+        assert_eq!(
+            msgs[0],
+            WasmMsg::Execute {
+                contract_addr: "token_a".to_string(),
+                msg: to_json_binary(&Cw20ExecuteMsg::Transfer {
+                    recipient: "receiver".to_string(),
+                    amount: Uint128::new(12),
+                })
+                .unwrap(),
+                funds: vec![],
+            }
+            .into()
+        );
+    }
+}
@@ -43,6 +43,9 @@ pub enum ContractError {
     #[error("Assertion failed; require minimum amount: {transfer_amount}")]
     InvalidFunds { transfer_amount: Uint128 },
 
+    #[error("unexpected funds: {denom}")]
+    UnexpectedFunds { denom: String },
+
     #[error("multiplication overflow")]
     Mul,
 
@@ -139,12 +142,30 @@ pub enum ContractError {
     #[error("amount under minimum amount out")]
     AmountUnderMinimumAmountOut,
 
+    #[error("amount over maximum amount in")]
+    AmountOverMaximumAmountIn,
+
     #[error("pool already exist")]
     PoolAlreadyExist,
 
     #[error("FeeTierNotFound")]
     FeeTierNotFound,
 
+    #[error("incentive not found")]
+    IncentiveNotFound,
+
+    #[error("incentive retired")]
+    IncentiveRetired,
+
+    #[error("invalid end timestamp")]
+    InvalidEndTimestamp,
+
+    #[error("incentive duration below the minimum")]
+    IncentiveTooShort,
+
+    #[error("too many incentives on the pool")]
+    TooManyIncentives,
+
     #[error("NotEmptyTickDeinitialization")]
     NotEmptyTickDeinitialization,
 
@@ -169,8 +190,56 @@ pub enum ContractError {
     #[error("Error on zap out: not enough balance to swap")]
     ZapOutNotEnoughBalanceToSwap {},
 
+    #[error("Error on zap in: asset in is not a token of the pool")]
+    ZapInAssetNotInPool {},
+
+    #[error("Error on zap in: route does not swap into a token of the pool")]
+    ZapInRouteNotToPool {},
+
+    #[error("Error on zap: another zap is in progress")]
+    ZapInProgress {},
+
+    #[error("Invalid percentage")]
+    InvalidPercentage {},
+
     #[error("Pool paused")]
     PoolPaused {},
+
+    #[error("oracle has no observation")]
+    OracleUninitialized,
+
+    #[error("observation is older than the oldest stored one")]
+    OldObservation,
+
+    #[error("observation cardinality above the maximum")]
+    InvalidObservationCardinality {},
+
+    #[error("flash loan in progress")]
+    FlashLoanInProgress {},
+
+    #[error("flash loan not repaid")]
+    FlashLoanNotRepaid {},
+
+    #[error("no flash loan to repay")]
+    NoFlashLoan {},
+
+    #[error("limit order range must not contain the current tick")]
+    InvalidLimitOrder {},
+
+    #[error("limit order is not filled")]
+    LimitOrderNotFilled {},
+
+    #[error("limit order is filled, it can only be claimed")]
+    LimitOrderFilled {},
+
+    #[error("limit order liquidity below the minimum")]
+    LimitOrderTooSmall {},
+
+    #[error("too many limit orders on the tick")]
+    TooManyLimitOrders {},
+
+    #[error("invalid dynamic fee")]
+    InvalidDynamicFee {},
 }
 
 impl From<ContractError> for StdError {
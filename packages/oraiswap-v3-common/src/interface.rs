@@ -3,8 +3,12 @@ use cosmwasm_std::{to_json_binary, Addr, Binary, CosmosMsg, StdResult, WasmMsg};
 use cw20::Expiration;
 
 use crate::{
-    math::{fee_growth::FeeGrowth, sqrt_price::SqrtPrice, token_amount::TokenAmount},
-    storage::{Pool, PoolKey, Position, Tick},
+    asset::Asset,
+    math::{
+        fee_growth::FeeGrowth, percentage::Percentage, seconds_per_liquidity::SecondsPerLiquidity,
+        sqrt_price::SqrtPrice, token_amount::TokenAmount,
+    },
+    storage::{incentive::IncentiveRecord, Pool, PoolKey, Position, Tick},
 };
 
 #[cw_serde]
@@ -14,6 +18,7 @@ pub struct CalculateSwapResult {
     pub start_sqrt_price: SqrtPrice,
     pub target_sqrt_price: SqrtPrice,
     pub fee: TokenAmount,
+    pub fee_rate: Percentage,
     pub pool: Pool,
     pub ticks: Vec<Tick>,
 }
@@ -46,12 +51,55 @@ pub struct PoolWithPoolKey {
     pub pool_key: PoolKey,
 }
 
+#[cw_serde]
+pub struct PositionWithIndex {
+    pub index: u32,
+    pub position: Position,
+}
+
 #[cw_serde]
 pub struct QuoteResult {
     pub amount_in: TokenAmount,
     pub amount_out: TokenAmount,
     pub target_sqrt_price: SqrtPrice,
     pub ticks: Vec<Tick>,
+    // fee applied to the swap, differs from the fee tier when the pool has a dynamic fee
+    pub fee_rate: Percentage,
+}
+
+#[cw_serde]
+pub struct ObserveResponse {
+    pub tick_cumulatives: Vec<i64>,
+    pub seconds_per_liquidity_cumulatives: Vec<SecondsPerLiquidity>,
+}
+
+#[cw_serde]
+pub struct SnapshotCumulativesInsideResponse {
+    pub seconds_per_liquidity_inside: SecondsPerLiquidity,
+}
+
+#[cw_serde]
+pub enum IncentiveStatus {
+    /// The start time is not reached yet
+    Upcoming,
+    Active,
+    /// Nothing is emitted anymore, the program can be retired by anyone
+    Finished,
+    /// Growth is frozen until every eligible position is settled
+    Retired,
+}
+
+#[cw_serde]
+pub struct PoolIncentive {
+    pub incentive: IncentiveRecord,
+    pub status: IncentiveStatus,
+}
+
+#[cw_serde]
+pub struct PositionPendingIncentives {
+    pub index: u32,
+    pub pool_key: PoolKey,
+    pub incentives: Vec<Asset>,
 }
 
 #[cw_serde]
@@ -59,6 +107,14 @@ pub struct TokensResponse {
     /// Contains all token_ids in lexicographical ordering
     /// If there are more than `limit`, use `start_from` in future queries
     /// to achieve pagination.
+    #[serde(with = "crate::utils::token_id::vec")]
+    #[schemars(with = "Vec<String>")]
+    pub tokens: Vec<u64>,
+}
+
+#[cw_serde]
+pub struct PositionTokensResponse {
+    /// The token ids of the positions in the order of their indexes
     pub tokens: Vec<u64>,
 }
 
@@ -75,6 +131,21 @@ pub struct ApprovedForAllResponse {
     pub operators: Vec<Approval>,
 }
 
+#[cw_serde]
+pub struct ApprovalResponse {
+    pub approval: Approval,
+}
+
+#[cw_serde]
+pub struct ApprovalsResponse {
+    pub approvals: Vec<Approval>,
+}
+
+#[cw_serde]
+pub struct MinterResponse {
+    pub minter: Option<String>,
+}
+
 #[cw_serde]
 pub struct AllNftInfoResponse {
     /// Who can transfer the token
@@ -87,6 +158,8 @@ pub struct AllNftInfoResponse {
 #[cw_serde]
 pub struct Cw721ReceiveMsg {
     pub sender: Addr,
+    #[serde(with = "crate::utils::token_id")]
+    #[schemars(with = "String")]
     pub token_id: u64,
     pub msg: Option<Binary>,
 }
@@ -117,9 +190,73 @@ enum ReceiverHandleMsg {
     ReceiveNft(Cw721ReceiveMsg),
 }
 
+/// Callback sent to the recipient of a flash loan, the borrowed amounts plus the fees
+/// must be sent back to the pool contract before it returns.
+#[cw_serde]
+pub struct FlashCallbackMsg {
+    pub sender: Addr,
+    pub pool_key: PoolKey,
+    pub amount_x: TokenAmount,
+    pub amount_y: TokenAmount,
+    pub fee_x: TokenAmount,
+    pub fee_y: TokenAmount,
+    pub msg: Binary,
+}
+
+impl FlashCallbackMsg {
+    /// serializes the message
+    pub fn into_binary(self) -> StdResult<Binary> {
+        let msg = FlashReceiverHandleMsg::FlashCallback(self);
+        to_json_binary(&msg)
+    }
+
+    /// creates a cosmos_msg sending this struct to the named contract
+    pub fn into_cosmos_msg(self, contract_addr: String) -> StdResult<CosmosMsg> {
+        let msg = self.into_binary()?;
+        let execute = WasmMsg::Execute {
+            contract_addr,
+            msg,
+            funds: vec![],
+        };
+        Ok(execute.into())
+    }
+}
+
+/// This is just a helper to properly serialize the above message.
+/// The flash loan recipient should include this variant in its ExecuteMsg enum
+#[cw_serde]
+pub enum FlashReceiverHandleMsg {
+    FlashCallback(FlashCallbackMsg),
+}
+
 #[cw_serde]
 pub struct NftInfoResponse {
-    pub extension: Position,
+    /// Base uri configured by the admin followed by the token id, unset without a base uri
+    pub token_uri: Option<String>,
+    pub extension: PositionMetadata,
+}
+
+/// Metadata of a position NFT, `name`, `description` and `attributes` follow the cw721 on-chain
+/// metadata extension so wallets and marketplaces can display the position
+#[cw_serde]
+pub struct PositionMetadata {
+    pub name: String,
+    pub description: String,
+    pub attributes: Vec<Trait>,
+    pub position: Position,
+}
+
+#[cw_serde]
+pub struct Trait {
+    pub display_type: Option<String>,
+    pub trait_type: String,
+    pub value: String,
+}
+
+#[cw_serde]
+pub struct ContractInfoResponse {
+    pub name: String,
+    pub symbol: String,
 }
 
 #[cw_serde]
@@ -19,6 +19,14 @@ pub struct SingleTokenLiquidity {
     pub amount: TokenAmount,
 }
 
+#[derive(Debug)]
+pub struct RebalanceSwap {
+    pub x_to_y: bool,
+    pub amount_in: TokenAmount,
+    /// amount received at the current price, before fees and price impact
+    pub amount_out: TokenAmount,
+}
+
 #[allow(dead_code)]
 pub fn get_liquidity(
     x: TokenAmount,
@@ -93,6 +101,63 @@ pub fn get_liquidity(
     })
 }
 
+/// Finds the swap that brings `x` and `y` to the ratio the range needs at the current price, so
+/// that both can be deposited in full. Fees and price impact of the swap are not taken into account.
+pub fn get_rebalance_swap(
+    x: TokenAmount,
+    y: TokenAmount,
+    lower_tick: i32,
+    upper_tick: i32,
+    current_sqrt_price: SqrtPrice,
+) -> Result<Option<RebalanceSwap>, ContractError> {
+    if lower_tick < -MAX_TICK || upper_tick > MAX_TICK {
+        return Err(ContractError::InvalidTick);
+    }
+
+    let lower_sqrt_price = calculate_sqrt_price(lower_tick)?;
+    let upper_sqrt_price = calculate_sqrt_price(upper_tick)?;
+
+    let one = U256::from(SqrtPrice::from_integer(1).get());
+    let sqrt_price = U256::from(current_sqrt_price.get());
+    let x_in_y = |amount: U256| amount * sqrt_price / one * sqrt_price / one;
+    let y_in_x = |amount: U256| amount * one / sqrt_price * one / sqrt_price;
+    let (x, y) = (U256::from(x.get()), U256::from(y.get()));
+
+    let (x_to_y, amount_in) = if upper_sqrt_price < current_sqrt_price {
+        // single token y
+        (true, x)
+    } else if current_sqrt_price < lower_sqrt_price {
+        // single token x
+        (false, y)
+    } else {
+        // values in y of the amounts needed by one unit of liquidity
+        let upper_sqrt_price = U256::from(upper_sqrt_price.get());
+        let x_weight = sqrt_price * (upper_sqrt_price - sqrt_price) / upper_sqrt_price;
+        let y_weight = sqrt_price - U256::from(lower_sqrt_price.get());
+        let target_y = (x_in_y(x) + y) * y_weight / (x_weight + y_weight);
+        if y > target_y {
+            (false, y - target_y)
+        } else {
+            (true, y_in_x(target_y - y))
+        }
+    };
+
+    if amount_in.is_zero() {
+        return Ok(None);
+    }
+    let amount_out = if x_to_y {
+        x_in_y(amount_in)
+    } else {
+        y_in_x(amount_in)
+    };
+
+    Ok(Some(RebalanceSwap {
+        x_to_y,
+        amount_in: TokenAmount::new(amount_in.try_into()?),
+        amount_out: TokenAmount::new(amount_out.try_into()?),
+    }))
+}
+
 #[allow(dead_code)]
 pub fn get_liquidity_by_x(
     x: TokenAmount,
@@ -488,4 +553,65 @@ mod tests {
             assert_eq!(result_down.y, expected_y);
         }
     }
+
+    #[test]
+    fn test_get_rebalance_swap() {
+        let current_sqrt_price = calculate_sqrt_price(0).unwrap();
+        let x = TokenAmount(1000);
+        let y = TokenAmount(3000);
+
+        // out of range, everything goes to the token the range needs
+        let swap = get_rebalance_swap(x, y, 100, 200, current_sqrt_price)
+            .unwrap()
+            .unwrap();
+        assert!(!swap.x_to_y);
+        assert_eq!(swap.amount_in, y);
+        assert_eq!(swap.amount_out, y);
+        let swap = get_rebalance_swap(x, y, -200, -100, current_sqrt_price)
+            .unwrap()
+            .unwrap();
+        assert!(swap.x_to_y);
+        assert_eq!(swap.amount_in, x);
+        assert!(
+            get_rebalance_swap(x, TokenAmount(0), 100, 200, current_sqrt_price)
+                .unwrap()
+                .is_none()
+        );
+
+        // in range, the amounts after the swap give the same liquidity on both sides
+        let (lower_tick, upper_tick) = (-100, 300);
+        let swap = get_rebalance_swap(x, y, lower_tick, upper_tick, current_sqrt_price)
+            .unwrap()
+            .unwrap();
+        assert!(!swap.x_to_y);
+        let by_x = get_liquidity_by_x(
+            x + swap.amount_out,
+            lower_tick,
+            upper_tick,
+            current_sqrt_price,
+            false,
+        )
+        .unwrap();
+        let by_y = get_liquidity_by_y(
+            y - swap.amount_in,
+            lower_tick,
+            upper_tick,
+            current_sqrt_price,
+            false,
+        )
+        .unwrap();
+        let diff = by_x.l.get().abs_diff(by_y.l.get());
+        assert!(diff * 1000 < by_x.l.get());
+
+        // already balanced
+        assert!(get_rebalance_swap(
+            by_y.amount,
+            y - swap.amount_in,
+            lower_tick,
+            upper_tick,
+            current_sqrt_price
+        )
+        .unwrap()
+        .map_or(true, |swap| swap.amount_in <= TokenAmount(1)));
+    }
 }
@@ -226,6 +226,19 @@ pub fn get_min_sqrt_price(tick_spacing: u16) -> SqrtPrice {
     SqrtPrice::from_tick(min_tick).unwrap()
 }
 
+/// Returns the price of token x in token y at the square root price as a decimal string,
+/// without trailing zeros
+pub fn sqrt_price_to_price_string(sqrt_price: SqrtPrice) -> String {
+    let price = sqrt_price.big_mul(sqrt_price).get();
+    let one: u128 = SqrtPrice::one();
+    let (integer, fraction) = (price / one, price % one);
+    if fraction == 0 {
+        return integer.to_string();
+    }
+    let fraction = format!("{:0width$}", fraction, width = SqrtPrice::scale() as usize);
+    format!("{}.{}", integer, fraction.trim_end_matches('0'))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -292,6 +305,21 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_sqrt_price_to_price_string() {
+        let price = sqrt_price_to_price_string(calculate_sqrt_price(0).unwrap());
+        assert_eq!(price, "1");
+        // 1.0001^10000 and its inverse
+        let price = sqrt_price_to_price_string(calculate_sqrt_price(10000).unwrap());
+        assert!(price.starts_with("2.7181"), "{}", price);
+        let price = sqrt_price_to_price_string(calculate_sqrt_price(-10000).unwrap());
+        assert!(price.starts_with("0.367897"), "{}", price);
+        assert_eq!(
+            sqrt_price_to_price_string(SqrtPrice::from_scale(15, 1)),
+            "2.25"
+        );
+    }
+
     #[test]
     fn test_sqrt_price_limitation() {
         {
@@ -5,18 +5,20 @@ use crate::{
 };
 use cosmwasm_schema::{cw_serde, QueryResponses};
 use cosmwasm_std::{Addr, Binary, Uint64};
-use cw20::Expiration;
+use cw20::{Cw20ReceiveMsg, Expiration};
 
 use crate::{
     interface::{
-        AllNftInfoResponse, ApprovedForAllResponse, NftInfoResponse, NumTokensResponse,
-        OwnerOfResponse, PoolWithPoolKey, PositionTick, QuoteResult, SwapHop, TokensResponse,
+        AllNftInfoResponse, ApprovalResponse, ApprovalsResponse, ApprovedForAllResponse,
+        ContractInfoResponse, MinterResponse, NftInfoResponse, NumTokensResponse, ObserveResponse,
+        OwnerOfResponse, PoolIncentive, PoolWithPoolKey, PositionPendingIncentives, PositionTick,
+        PositionTokensResponse, PositionWithIndex, QuoteResult, SwapHop, TokensResponse,
     },
     math::{
         liquidity::Liquidity, percentage::Percentage, sqrt_price::SqrtPrice,
         token_amount::TokenAmount,
     },
-    storage::{FeeTier, LiquidityTick, Pool, PoolKey, Position, Tick},
+    storage::{DynamicFeeConfig, FeeTier, LiquidityTick, Pool, PoolKey, Position, Tick},
 };
 #[allow(unused_imports)]
 #[cw_serde]
@@ -53,6 +55,10 @@ pub enum ExecuteMsg {
         pool_key: PoolKey,
         fee_receiver: Addr,
     },
+    /// Sets the base of the token uri of position NFTs, none removes it
+    ChangeTokenUri {
+        token_uri: Option<String>,
+    },
     CreatePosition {
         pool_key: PoolKey,
         lower_tick: i32,
@@ -60,6 +66,8 @@ pub enum ExecuteMsg {
         liquidity_delta: Liquidity,
         slippage_limit_lower: SqrtPrice,
         slippage_limit_upper: SqrtPrice,
+        /// Opens the position as a limit order, its liquidity is withdrawn once the price crosses the range
+        limit_order: Option<bool>,
     },
     Swap {
         pool_key: PoolKey,
@@ -74,16 +82,142 @@ pub enum ExecuteMsg {
         slippage: Percentage,
         swaps: Vec<SwapHop>,
     },
+    /// Swaps along the route for exactly `amount_out` of the last token, spending at most `max_amount_in`
+    SwapRouteExactOut {
+        amount_out: TokenAmount,
+        max_amount_in: TokenAmount,
+        swaps: Vec<SwapHop>,
+    },
+    /// Runs a `Cw20HookMsg` with the cw20 tokens sent, no allowance is needed
+    Receive(Cw20ReceiveMsg),
+    /// Lends pool tokens to `recipient`, they must be repaid with `FlashRepay` together with a fee
+    /// based on the pool fee tier before its `FlashCallback` returns. Meanwhile the pool is locked,
+    /// while the other pools can be used, for instance to arbitrage against it
+    Flash {
+        pool_key: PoolKey,
+        amount_x: TokenAmount,
+        amount_y: TokenAmount,
+        recipient: Addr,
+        msg: Binary,
+    },
+    /// Repays the pending flash loan, native tokens are sent with the message and cw20 tokens
+    /// pulled from the sender allowance
+    FlashRepay {
+        amount_x: TokenAmount,
+        amount_y: TokenAmount,
+    },
     TransferPosition {
         index: u32,
         receiver: String,
     },
+    /// `TransferPosition` addressed by the token id, which does not change when other positions are removed.
+    /// Approved spenders and operators of the owner can call the `ByTokenId` variants of
+    /// `TransferPosition`, `ClaimFee`, `RemovePosition`, `ClaimFilledOrder` and `ClaimIncentive`
+    TransferPositionByTokenId {
+        #[serde(with = "crate::utils::token_id")]
+        #[schemars(with = "String")]
+        token_id: u64,
+        receiver: String,
+    },
     ClaimFee {
         index: u32,
     },
+    ClaimFeeByTokenId {
+        #[serde(with = "crate::utils::token_id")]
+        #[schemars(with = "String")]
+        token_id: u64,
+        /// Receives the tokens, defaults to the owner of the position
+        recipient: Option<Addr>,
+    },
+    /// Reinvests the fees of the position into its liquidity, swapping the part the range cannot take
+    CompoundFees {
+        index: u32,
+        /// Maximum loss of the swap against the current price, fee included
+        max_slippage: Percentage,
+        /// The pool price has to stay in this range before the swap and before the deposit
+        slippage_limit_lower: SqrtPrice,
+        slippage_limit_upper: SqrtPrice,
+    },
+    CompoundFeesByTokenId {
+        #[serde(with = "crate::utils::token_id")]
+        #[schemars(with = "String")]
+        token_id: u64,
+        max_slippage: Percentage,
+        slippage_limit_lower: SqrtPrice,
+        slippage_limit_upper: SqrtPrice,
+    },
+    /// Moves the liquidity and fees of the position to a new range, keeping its token id
+    Rebalance {
+        index: u32,
+        new_lower_tick: i32,
+        new_upper_tick: i32,
+        /// Maximum loss of the swap against the current price, fee included
+        slippage: Percentage,
+        /// The pool price has to stay in this range before the swap and before the deposit
+        slippage_limit_lower: SqrtPrice,
+        slippage_limit_upper: SqrtPrice,
+    },
+    RebalanceByTokenId {
+        #[serde(with = "crate::utils::token_id")]
+        #[schemars(with = "String")]
+        token_id: u64,
+        new_lower_tick: i32,
+        new_upper_tick: i32,
+        slippage: Percentage,
+        slippage_limit_lower: SqrtPrice,
+        slippage_limit_upper: SqrtPrice,
+    },
     RemovePosition {
         index: u32,
     },
+    RemovePositionByTokenId {
+        #[serde(with = "crate::utils::token_id")]
+        #[schemars(with = "String")]
+        token_id: u64,
+        /// Receives the tokens, defaults to the owner of the position
+        recipient: Option<Addr>,
+    },
+    /// Sends the tokens of a filled limit order to the owner and removes the position
+    ClaimFilledOrder {
+        index: u32,
+    },
+    ClaimFilledOrderByTokenId {
+        #[serde(with = "crate::utils::token_id")]
+        #[schemars(with = "String")]
+        token_id: u64,
+        /// Receives the tokens, defaults to the owner of the position
+        recipient: Option<Addr>,
+    },
+    /// Adds liquidity to an existing position without minting a new token id
+    IncreaseLiquidity {
+        index: u32,
+        liquidity_delta: Liquidity,
+        slippage_limit_lower: SqrtPrice,
+        slippage_limit_upper: SqrtPrice,
+    },
+    IncreaseLiquidityByTokenId {
+        #[serde(with = "crate::utils::token_id")]
+        #[schemars(with = "String")]
+        token_id: u64,
+        liquidity_delta: Liquidity,
+        slippage_limit_lower: SqrtPrice,
+        slippage_limit_upper: SqrtPrice,
+    },
+    /// Withdraws part of the liquidity of a position, keeping the position and its token id
+    DecreaseLiquidity {
+        index: u32,
+        liquidity_delta: Liquidity,
+        slippage_limit_lower: SqrtPrice,
+        slippage_limit_upper: SqrtPrice,
+    },
+    DecreaseLiquidityByTokenId {
+        #[serde(with = "crate::utils::token_id")]
+        #[schemars(with = "String")]
+        token_id: u64,
+        liquidity_delta: Liquidity,
+        slippage_limit_lower: SqrtPrice,
+        slippage_limit_upper: SqrtPrice,
+    },
     CreatePool {
         token_0: String,
         token_1: String,
@@ -100,6 +234,8 @@ pub enum ExecuteMsg {
     /// Transfer is a base message to move a token to another account without triggering actions
     TransferNft {
         recipient: Addr,
+        #[serde(with = "crate::utils::token_id")]
+        #[schemars(with = "String")]
         token_id: u64,
     },
     /// Mint a new NFT, can only be called by the contract minter
@@ -108,12 +244,16 @@ pub enum ExecuteMsg {
         extension: NftExtensionMsg,
     },
     Burn {
+        #[serde(with = "crate::utils::token_id")]
+        #[schemars(with = "String")]
         token_id: u64,
     },
     /// Send is a base message to transfer a token to a contract and trigger an action
     /// on the receiving contract.
     SendNft {
         contract: Addr,
+        #[serde(with = "crate::utils::token_id")]
+        #[schemars(with = "String")]
         token_id: u64,
         msg: Option<Binary>,
     },
@@ -121,12 +261,16 @@ pub enum ExecuteMsg {
     /// If expiration is set, then this allowance has a time/height limit
     Approve {
         spender: Addr,
+        #[serde(with = "crate::utils::token_id")]
+        #[schemars(with = "String")]
         token_id: u64,
         expires: Option<Expiration>,
     },
     /// Remove previously granted Approval
     Revoke {
         spender: Addr,
+        #[serde(with = "crate::utils::token_id")]
+        #[schemars(with = "String")]
         token_id: u64,
     },
     /// Allows operator to transfer / send any token from the owner's account.
@@ -139,15 +283,24 @@ pub enum ExecuteMsg {
     RevokeAll {
         operator: Addr,
     },
-    // create incentives for specific pool
+    /// Creates an incentive program for the pool, the whole `total_reward` must be deposited
+    /// and is held by the incentives fund manager
     CreateIncentive {
         pool_key: PoolKey,
         reward_token: AssetInfo,
-        total_reward: Option<TokenAmount>,
+        total_reward: TokenAmount,
         reward_per_sec: TokenAmount,
         start_timestamp: Option<u64>,
+        end_timestamp: Option<u64>,
+    },
+    /// Deposits more rewards into an incentive program, only its creator can top it up
+    TopUpIncentive {
+        pool_key: PoolKey,
+        incentive_id: u64,
+        amount: TokenAmount,
     },
-    // update  for specific pool
+    /// Updates an incentive program, only its creator can update it. `remaining_reward` can only
+    /// be lowered, the difference is refunded to the creator so setting it to zero stops the program
     UpdateIncentive {
         pool_key: PoolKey,
         incentive_id: u64,
@@ -155,15 +308,91 @@ pub enum ExecuteMsg {
         start_timestamp: Option<u64>,
         reward_per_sec: Option<TokenAmount>,
     },
+    /// Stops an incentive program and refunds the rewards left to its creator. The creator can
+    /// retire it at any time, anyone can once it finished
+    RetireIncentive {
+        pool_key: PoolKey,
+        incentive_id: u64,
+    },
     // Claim Incentives
     ClaimIncentive {
         index: u32,
     },
+    ClaimIncentiveByTokenId {
+        #[serde(with = "crate::utils::token_id")]
+        #[schemars(with = "String")]
+        token_id: u64,
+        /// Receives the tokens, defaults to the owner of the position
+        recipient: Option<Addr>,
+    },
+    /// Claims the fees and incentives of many positions of the sender at once, all of them when
+    /// `indexes` is unset. Filled limit orders are skipped.
+    ClaimAll {
+        indexes: Option<Vec<u32>>,
+        /// Only claims from the positions of this pool
+        pool_key: Option<PoolKey>,
+    },
     // update pool status
     UpdatePoolStatus {
         pool_key: PoolKey,
         status: Option<PoolStatus>,
     },
+    /// Grows the number of price observations stored for the pool oracle
+    IncreaseObservationCardinalityNext {
+        pool_key: PoolKey,
+        observation_cardinality_next: u16,
+    },
+    /// Sets the dynamic fee of the pool, none restores the fee of the fee tier
+    UpdateDynamicFee {
+        pool_key: PoolKey,
+        dynamic_fee: Option<DynamicFeeConfig>,
+    },
+}
+
+/// Actions paid with the cw20 tokens sent through `Receive`, any excess is sent back
+#[cw_serde]
+pub enum Cw20HookMsg {
+    /// Swaps all the tokens sent
+    Swap {
+        pool_key: PoolKey,
+        x_to_y: bool,
+        sqrt_price_limit: SqrtPrice,
+    },
+    /// Swaps all the tokens sent along the route
+    SwapRoute {
+        expected_amount_out: TokenAmount,
+        slippage: Percentage,
+        swaps: Vec<SwapHop>,
+    },
+    /// Swaps along the route for exactly `amount_out` of the last token, the tokens sent are the maximum input
+    SwapRouteExactOut {
+        amount_out: TokenAmount,
+        swaps: Vec<SwapHop>,
+    },
+    /// Opens a position, the other token of the pool is pulled from the sender allowance when needed
+    CreatePosition {
+        pool_key: PoolKey,
+        lower_tick: i32,
+        upper_tick: i32,
+        liquidity_delta: Liquidity,
+        slippage_limit_lower: SqrtPrice,
+        slippage_limit_upper: SqrtPrice,
+        limit_order: Option<bool>,
+    },
+    /// Creates an incentive program paying the tokens sent as its total reward
+    CreateIncentive {
+        pool_key: PoolKey,
+        reward_per_sec: TokenAmount,
+        start_timestamp: Option<u64>,
+        end_timestamp: Option<u64>,
+    },
+    /// Adds the tokens sent to the rewards of an incentive program
+    TopUpIncentive {
+        pool_key: PoolKey,
+        incentive_id: u64,
+    },
+    /// Repays the pending flash loan with the tokens sent
+    FlashRepay {},
 }
 
 #[cw_serde]
@@ -184,6 +413,14 @@ pub enum QueryMsg {
     #[returns(Position)]
     Position { owner_id: Addr, index: u32 },
 
+    /// Returns the position minted as `token_id`, whoever owns it
+    #[returns(Position)]
+    PositionByTokenId {
+        #[serde(with = "crate::utils::token_id")]
+        #[schemars(with = "String")]
+        token_id: u64,
+    },
+
     #[returns(Vec<Position>)]
     Positions {
         owner_id: Addr,
@@ -191,6 +428,14 @@ pub enum QueryMsg {
         offset: Option<u32>,
     },
 
+    /// Lists the limit orders of `owner` by position index, at most 100 per page
+    #[returns(Vec<PositionWithIndex>)]
+    LimitOrders {
+        owner: Addr,
+        start_after: Option<u32>,
+        limit: Option<u32>,
+    },
+
     #[returns(Vec<Position>)]
     AllPosition {
         limit: Option<u32>,
@@ -267,6 +512,13 @@ pub enum QueryMsg {
         swaps: Vec<SwapHop>,
     },
 
+    /// Returns the amount of the first token needed to receive `amount_out` of the last one
+    #[returns(TokenAmount)]
+    QuoteRouteExactOut {
+        amount_out: TokenAmount,
+        swaps: Vec<SwapHop>,
+    },
+
     ///
     ///
     ///  NFT methods
@@ -277,16 +529,48 @@ pub enum QueryMsg {
     #[returns(NumTokensResponse)]
     NumTokens {},
 
+    /// Name and symbol of the position NFTs
+    #[returns(ContractInfoResponse)]
+    ContractInfo {},
+
+    /// Positions are minted by providing liquidity, so there is no minter
+    #[returns(MinterResponse)]
+    Minter {},
+
     /// Return the owner of the given token, error if token does not exist
     /// Return type: OwnerOfResponse
     #[returns(OwnerOfResponse)]
     OwnerOf {
+        #[serde(with = "crate::utils::token_id")]
+        #[schemars(with = "String")]
+        token_id: u64,
+        /// unset or false will filter out expired approvals, you must set to true to see them
+        include_expired: Option<bool>,
+    },
+    /// Return the approval of `spender` for the given token, error if it has none.
+    /// The owner is always approved.
+    #[returns(ApprovalResponse)]
+    Approval {
+        #[serde(with = "crate::utils::token_id")]
+        #[schemars(with = "String")]
+        token_id: u64,
+        spender: Addr,
+        /// unset or false will filter out expired approvals, you must set to true to see them
+        include_expired: Option<bool>,
+    },
+    /// Return all the approvals of the given token
+    #[returns(ApprovalsResponse)]
+    Approvals {
+        #[serde(with = "crate::utils::token_id")]
+        #[schemars(with = "String")]
         token_id: u64,
         /// unset or false will filter out expired approvals, you must set to true to see them
         include_expired: Option<bool>,
     },
     /// List all operators that can access all of the owner's tokens.
+    /// Also answers to `AllOperators`, its name in later cw721 versions.
     #[returns(ApprovedForAllResponse)]
+    #[serde(alias = "all_operators")]
     ApprovedForAll {
         owner: Addr,
         /// unset or false will filter out expired approvals, you must set to true to see them
@@ -298,22 +582,39 @@ pub enum QueryMsg {
     /// Returns metadata about one particular token, based on *ERC721 Metadata JSON Schema*
     /// but directly from the contract: `NftInfoResponse`
     #[returns(NftInfoResponse)]
-    NftInfo { token_id: u64 },
+    NftInfo {
+        #[serde(with = "crate::utils::token_id")]
+        #[schemars(with = "String")]
+        token_id: u64,
+    },
     /// With MetaData Extension.
     /// Returns the result of both `NftInfo` and `OwnerOf` as one query as an optimization
     #[returns(AllNftInfoResponse)]
     AllNftInfo {
+        #[serde(with = "crate::utils::token_id")]
+        #[schemars(with = "String")]
         token_id: u64,
         /// unset or false will filter out expired approvals, you must set to true to see them
         include_expired: Option<bool>,
     },
+    /// Returns the token ids of the owner's positions starting from the position index
+    /// `start_after`, as `Tokens` did before it followed cw721
+    #[returns(PositionTokensResponse)]
+    PositionTokens {
+        owner: Addr,
+        start_after: Option<u32>,
+        limit: Option<u32>,
+    },
     /// With Enumerable extension.
     /// Returns all tokens owned by the given address, [] if unset.
+    /// Tokens are listed in the order of the owner's positions, `start_after` must be one of them.
     /// Return type: TokensResponse.
     #[returns(TokensResponse)]
     Tokens {
         owner: Addr,
-        start_after: Option<u32>,
+        #[serde(default, with = "crate::utils::token_id::option")]
+        #[schemars(with = "Option<String>")]
+        start_after: Option<u64>,
         limit: Option<u32>,
     },
     /// With Enumerable extension.
@@ -321,6 +622,8 @@ pub enum QueryMsg {
     /// Return type: TokensResponse.
     #[returns(TokensResponse)]
     AllTokens {
+        #[serde(default, with = "crate::utils::token_id::option")]
+        #[schemars(with = "Option<String>")]
         start_after: Option<u64>,
         limit: Option<u32>,
     },
@@ -328,6 +631,43 @@ pub enum QueryMsg {
     #[returns(Vec<Asset>)]
     PositionIncentives { owner_id: Addr, index: u32 },
 
+    #[returns(Vec<Asset>)]
+    PositionIncentivesByTokenId {
+        #[serde(with = "crate::utils::token_id")]
+        #[schemars(with = "String")]
+        token_id: u64,
+    },
+
+    /// Returns the incentive programs of a pool with their remaining rewards and status as of
+    /// the current block time
+    #[returns(Vec<PoolIncentive>)]
+    PoolIncentives { pool_key: PoolKey },
+
+    /// Returns the rewards each position of the owner would claim at the current block time,
+    /// positions are paginated by index
+    #[returns(Vec<PositionPendingIncentives>)]
+    PendingIncentives {
+        owner: Addr,
+        start_after: Option<u32>,
+        limit: Option<u32>,
+    },
+
     #[returns(Vec<PoolWithPoolKey>)]
     PoolsByPoolKeys { pool_keys: Vec<PoolKey> },
+
+    /// Returns the seconds per liquidity accumulated inside a tick range, only differences
+    /// between snapshots taken while the range stays initialized are meaningful
+    #[returns(SnapshotCumulativesInsideResponse)]
+    SnapshotCumulativesInside {
+        pool_key: PoolKey,
+        lower_tick: i32,
+        upper_tick: i32,
+    },
+
+    /// Returns the oracle cumulatives as of each `seconds_ago` from the current block time
+    #[returns(ObserveResponse)]
+    Observe {
+        pool_key: PoolKey,
+        seconds_agos: Vec<u64>,
+    },
 }
@@ -9,4 +9,6 @@ pub struct Config {
     pub fee_tiers: Vec<FeeTier>,
     pub protocol_fee: Percentage,
     pub incentives_fund_manager: Addr,
+    /// Base of the token uri of position NFTs, the token id is appended to it
+    pub token_uri: Option<String>,
 }
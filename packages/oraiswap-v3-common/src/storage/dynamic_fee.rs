@@ -0,0 +1,145 @@
+use cosmwasm_schema::cw_serde;
+use decimal::*;
+
+use crate::{error::ContractError, math::types::percentage::Percentage};
+
+#[cw_serde]
+#[derive(Copy)]
+pub struct DynamicFeeConfig {
+    pub base_fee: Percentage,
+    pub max_fee: Percentage,
+    // length of the volatility window in seconds
+    pub window: u64,
+}
+
+/// Fee of a pool growing with the price movement since the start of the volatility window.
+#[cw_serde]
+#[derive(Copy)]
+pub struct DynamicFee {
+    pub config: DynamicFeeConfig,
+    // block time in seconds at which the current window started
+    pub window_start: u64,
+    // tick of the pool when the current window started
+    pub reference_tick: i32,
+}
+
+impl DynamicFee {
+    pub fn new(
+        config: DynamicFeeConfig,
+        current_tick: i32,
+        current_timestamp: u64,
+    ) -> Result<Self, ContractError> {
+        if config.base_fee > config.max_fee
+            || config.max_fee > Percentage::from_integer(1)
+            || config.window == 0
+        {
+            return Err(ContractError::InvalidDynamicFee {});
+        }
+
+        Ok(Self {
+            config,
+            window_start: current_timestamp,
+            reference_tick: current_tick,
+        })
+    }
+
+    /// Starts a new window from the current tick once the last one has elapsed.
+    pub fn update_window(&mut self, current_tick: i32, current_timestamp: u64) {
+        if current_timestamp >= self.window_start.saturating_add(self.config.window) {
+            self.window_start = current_timestamp;
+            self.reference_tick = current_tick;
+        }
+    }
+
+    /// The base fee is added once more for every tick spacing the price moved
+    /// since the window started, up to the max fee.
+    pub fn get_fee(&self, current_tick: i32, tick_spacing: u16) -> Percentage {
+        let moves = current_tick.abs_diff(self.reference_tick) / tick_spacing as u32;
+        let fee = self.config.base_fee.get().saturating_mul(moves as u64 + 1);
+
+        Percentage::new(fee.min(self.config.max_fee.get()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config() -> DynamicFeeConfig {
+        DynamicFeeConfig {
+            base_fee: Percentage::from_scale(1, 3),
+            max_fee: Percentage::from_scale(1, 2),
+            window: 60,
+        }
+    }
+
+    #[test]
+    fn test_new() {
+        let dynamic_fee = DynamicFee::new(config(), -10, 100).unwrap();
+        assert_eq!(dynamic_fee.window_start, 100);
+        assert_eq!(dynamic_fee.reference_tick, -10);
+
+        // base fee above max fee
+        let result = DynamicFee::new(
+            DynamicFeeConfig {
+                base_fee: Percentage::from_scale(2, 2),
+                ..config()
+            },
+            0,
+            0,
+        );
+        assert!(matches!(result, Err(ContractError::InvalidDynamicFee {})));
+        // max fee above 100%
+        let result = DynamicFee::new(
+            DynamicFeeConfig {
+                max_fee: Percentage::from_scale(11, 1),
+                ..config()
+            },
+            0,
+            0,
+        );
+        assert!(matches!(result, Err(ContractError::InvalidDynamicFee {})));
+        // empty window
+        let result = DynamicFee::new(
+            DynamicFeeConfig {
+                window: 0,
+                ..config()
+            },
+            0,
+            0,
+        );
+        assert!(matches!(result, Err(ContractError::InvalidDynamicFee {})));
+    }
+
+    #[test]
+    fn test_get_fee() {
+        let dynamic_fee = DynamicFee::new(config(), 0, 0).unwrap();
+
+        // no movement
+        assert_eq!(dynamic_fee.get_fee(0, 10), Percentage::from_scale(1, 3));
+        // less than a tick spacing
+        assert_eq!(dynamic_fee.get_fee(-9, 10), Percentage::from_scale(1, 3));
+        // both directions
+        assert_eq!(dynamic_fee.get_fee(10, 10), Percentage::from_scale(2, 3));
+        assert_eq!(dynamic_fee.get_fee(-35, 10), Percentage::from_scale(4, 3));
+        // capped by the max fee
+        assert_eq!(dynamic_fee.get_fee(1000, 10), Percentage::from_scale(1, 2));
+    }
+
+    #[test]
+    fn test_update_window() {
+        let mut dynamic_fee = DynamicFee::new(config(), 0, 100).unwrap();
+
+        // within the window
+        dynamic_fee.update_window(50, 159);
+        assert_eq!(dynamic_fee.window_start, 100);
+        assert_eq!(dynamic_fee.reference_tick, 0);
+        assert_eq!(dynamic_fee.get_fee(50, 10), Percentage::from_scale(6, 3));
+
+        // new window
+        dynamic_fee.update_window(50, 160);
+        assert_eq!(dynamic_fee.window_start, 160);
+        assert_eq!(dynamic_fee.reference_tick, 50);
+        assert_eq!(dynamic_fee.get_fee(50, 10), Percentage::from_scale(1, 3));
+    }
+}
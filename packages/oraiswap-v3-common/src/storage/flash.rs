@@ -0,0 +1,16 @@
+use cosmwasm_schema::cw_serde;
+
+use crate::{math::types::token_amount::TokenAmount, storage::PoolKey};
+
+/// A flash loan waiting for its repayment to be checked.
+#[cw_serde]
+pub struct FlashLoan {
+    pub pool_key: PoolKey,
+    pub amount_x: TokenAmount,
+    pub amount_y: TokenAmount,
+    pub fee_x: TokenAmount,
+    pub fee_y: TokenAmount,
+    // paid back with `FlashRepay` so far
+    pub paid_x: TokenAmount,
+    pub paid_y: TokenAmount,
+}
@@ -1,4 +1,5 @@
 use cosmwasm_schema::cw_serde;
+use cosmwasm_std::Addr;
 
 use crate::{asset::AssetInfo, error::ContractError, math::{fee_growth::FeeGrowth, liquidity::Liquidity, token_amount::TokenAmount}};
 
@@ -11,6 +12,25 @@ pub struct IncentiveRecord {
     pub start_timestamp: u64,
     pub incentive_growth_global: FeeGrowth,
     pub last_updated: u64,
+    /// Account that funded the program, records created before programs were funded have none
+    #[serde(default)]
+    pub creator: Option<Addr>,
+    /// Nothing is emitted after this time
+    #[serde(default)]
+    pub end_timestamp: Option<u64>,
+    #[serde(default)]
+    pub retirement: Option<IncentiveRetirement>,
+}
+
+/// State of an incentive program frozen when it was retired. The pool no longer updates it and
+/// the record is pruned once every position open at that time has settled its rewards.
+#[cw_serde]
+pub struct IncentiveRetirement {
+    /// pool tick when the program was retired, the tick incentives are not crossed anymore
+    pub tick_index: i32,
+    /// positions minted afterwards never earned from the program
+    pub last_token_id: u64,
+    pub unsettled_positions: u64,
 }
 
 #[cw_serde]
@@ -25,14 +45,37 @@ pub struct PositionIncentives {
     pub incentive_id: u64,
     pub pending_rewards: TokenAmount,
     pub incentive_growth_inside: FeeGrowth,
+    /// the program was retired and the position was paid all its rewards
+    #[serde(default)]
+    pub settled: bool,
 }
 
 impl IncentiveRecord {
+    pub fn is_retired(&self) -> bool {
+        self.retirement.is_some()
+    }
+
+    /// The program emits nothing anymore, either it ran out of rewards or its end time passed
+    pub fn is_finished(&self, current_timestamp: u64) -> bool {
+        self.remaining.is_zero()
+            || self
+                .end_timestamp
+                .map_or(false, |end_timestamp| current_timestamp >= end_timestamp)
+    }
+
     pub fn update_global_incentive_growth(
         &mut self,
         pool_liquidity: Liquidity,
         current_timestamp: u64,
     ) -> Result<(), ContractError> {
+        if self.is_retired() {
+            return Ok(());
+        }
+        let current_timestamp = self
+            .end_timestamp
+            .map_or(current_timestamp, |end_timestamp| {
+                current_timestamp.min(end_timestamp)
+            });
         if current_timestamp.lt(&self.start_timestamp) || current_timestamp.lt(&self.last_updated) {
             return Ok(());
         }
@@ -114,6 +157,9 @@ mod tests {
             start_timestamp: 1000,
             incentive_growth_global: FeeGrowth(0),
             last_updated: 1000,
+            creator: None,
+            end_timestamp: None,
+            retirement: None,
         };
         let mut pool_liquidity = Liquidity::new(1000000);
 
@@ -190,6 +236,53 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_update_global_incentive_growth_until_end() {
+        let mut record = IncentiveRecord {
+            id: 0,
+            reward_per_sec: TokenAmount(100),
+            reward_token: AssetInfo::NativeToken {
+                denom: "orai".to_string(),
+            },
+            remaining: TokenAmount(1000000),
+            start_timestamp: 1000,
+            incentive_growth_global: FeeGrowth(0),
+            last_updated: 1000,
+            creator: None,
+            end_timestamp: Some(1100),
+            retirement: None,
+        };
+        let pool_liquidity = Liquidity::new(1000);
+
+        // emission stops at the end time
+        record
+            .update_global_incentive_growth(pool_liquidity, 1500)
+            .unwrap();
+        assert_eq!(record.last_updated, 1100);
+        assert_eq!(record.remaining, TokenAmount(990000));
+        assert!(!record.is_finished(1099));
+        assert!(record.is_finished(1100));
+
+        record
+            .update_global_incentive_growth(pool_liquidity, 1600)
+            .unwrap();
+        assert_eq!(record.remaining, TokenAmount(990000));
+
+        // a retired program is frozen
+        record.end_timestamp = None;
+        record.retirement = Some(IncentiveRetirement {
+            tick_index: 0,
+            last_token_id: 0,
+            unsettled_positions: 0,
+        });
+        let growth = record.incentive_growth_global;
+        record
+            .update_global_incentive_growth(pool_liquidity, 2000)
+            .unwrap();
+        assert_eq!(record.last_updated, 1100);
+        assert_eq!(record.incentive_growth_global, growth);
+    }
+
     #[test]
     fn test_calculate_incentive_growth_inside() {
         // <──────────────                    ──────────────>
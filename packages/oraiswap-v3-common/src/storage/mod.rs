@@ -1,6 +1,9 @@
 pub mod config;
+pub mod dynamic_fee;
 pub mod fee_tier;
+pub mod flash;
 pub mod incentive;
+pub mod oracle;
 pub mod pool;
 pub mod pool_key;
 pub mod position;
@@ -8,7 +11,10 @@ pub mod tick;
 pub mod tickmap;
 
 pub use config::*;
+pub use dynamic_fee::*;
 pub use fee_tier::*;
+pub use flash::*;
+pub use oracle::*;
 pub use pool::*;
 pub use pool_key::*;
 pub use position::*;
@@ -0,0 +1,193 @@
+use crate::error::ContractError;
+use crate::math::types::{liquidity::Liquidity, seconds_per_liquidity::SecondsPerLiquidity};
+use cosmwasm_schema::cw_serde;
+use decimal::*;
+
+#[cw_serde]
+#[derive(Default, Copy)]
+pub struct Observation {
+    // block time in seconds at which the observation was written
+    pub block_timestamp: u64,
+    // tick index accumulated over time since the first observation
+    pub tick_cumulative: i64,
+    // seconds divided by in range liquidity accumulated over time since the first observation
+    pub seconds_per_liquidity_cumulative: SecondsPerLiquidity,
+    pub initialized: bool,
+}
+
+impl Observation {
+    pub fn new(block_timestamp: u64) -> Self {
+        Self {
+            block_timestamp,
+            tick_cumulative: 0,
+            seconds_per_liquidity_cumulative: SecondsPerLiquidity::new(0),
+            initialized: true,
+        }
+    }
+
+    /// Returns the observation accumulated from this one up to `block_timestamp`,
+    /// assuming `tick` and `liquidity` were constant in between.
+    pub fn transform(
+        &self,
+        block_timestamp: u64,
+        tick: i32,
+        liquidity: Liquidity,
+    ) -> Result<Self, ContractError> {
+        if block_timestamp < self.block_timestamp {
+            return Err(ContractError::TimestampCheckFailed);
+        }
+        if block_timestamp == self.block_timestamp {
+            return Ok(*self);
+        }
+
+        let delta = block_timestamp - self.block_timestamp;
+        // zero liquidity is accounted as the smallest unit of liquidity
+        let liquidity = if liquidity.is_zero() {
+            Liquidity::new(1)
+        } else {
+            liquidity
+        };
+
+        Ok(Self {
+            block_timestamp,
+            tick_cumulative: self
+                .tick_cumulative
+                .wrapping_add((tick as i64).wrapping_mul(delta as i64)),
+            seconds_per_liquidity_cumulative: self.seconds_per_liquidity_cumulative.unchecked_add(
+                SecondsPerLiquidity::calculate_seconds_per_liquidity_global(
+                    liquidity,
+                    block_timestamp,
+                    self.block_timestamp,
+                )?,
+            ),
+            initialized: true,
+        })
+    }
+
+    /// Linearly interpolates the cumulatives between `self` and a later observation `after`.
+    pub fn interpolate(&self, after: &Self, target: u64) -> Result<Self, ContractError> {
+        if target < self.block_timestamp || target > after.block_timestamp {
+            return Err(ContractError::TimestampCheckFailed);
+        }
+        if target == self.block_timestamp {
+            return Ok(*self);
+        }
+        if target == after.block_timestamp {
+            return Ok(*after);
+        }
+
+        let observation_time_delta = after.block_timestamp - self.block_timestamp;
+        let target_delta = target - self.block_timestamp;
+
+        let tick_cumulative = self.tick_cumulative.wrapping_add(
+            after
+                .tick_cumulative
+                .wrapping_sub(self.tick_cumulative)
+                .wrapping_div(observation_time_delta as i64)
+                .wrapping_mul(target_delta as i64),
+        );
+
+        let seconds_per_liquidity_delta = U256::from(
+            after
+                .seconds_per_liquidity_cumulative
+                .unchecked_sub(self.seconds_per_liquidity_cumulative)
+                .get(),
+        ) * U256::from(target_delta)
+            / U256::from(observation_time_delta);
+
+        Ok(Self {
+            block_timestamp: target,
+            tick_cumulative,
+            seconds_per_liquidity_cumulative: self.seconds_per_liquidity_cumulative.unchecked_add(
+                SecondsPerLiquidity::new(seconds_per_liquidity_delta.try_into()?),
+            ),
+            initialized: true,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_transform() {
+        let observation = Observation::new(100);
+
+        // same timestamp
+        {
+            let result = observation
+                .transform(100, 10, Liquidity::from_integer(1))
+                .unwrap();
+            assert_eq!(result, observation);
+        }
+        // past timestamp
+        {
+            let result = observation
+                .transform(99, 10, Liquidity::from_integer(1))
+                .unwrap_err();
+            assert!(matches!(result, ContractError::TimestampCheckFailed));
+        }
+        // positive tick
+        {
+            let result = observation
+                .transform(110, 10, Liquidity::from_integer(2))
+                .unwrap();
+            assert_eq!(result.block_timestamp, 110);
+            assert_eq!(result.tick_cumulative, 100);
+            assert_eq!(
+                result.seconds_per_liquidity_cumulative,
+                SecondsPerLiquidity::from_integer(5)
+            );
+        }
+        // negative tick
+        {
+            let result = observation
+                .transform(110, -10, Liquidity::from_integer(2))
+                .unwrap();
+            assert_eq!(result.tick_cumulative, -100);
+        }
+        // zero liquidity
+        {
+            let result = observation.transform(110, 0, Liquidity::new(0)).unwrap();
+            assert_eq!(
+                result.seconds_per_liquidity_cumulative,
+                SecondsPerLiquidity::calculate_seconds_per_liquidity_global(
+                    Liquidity::new(1),
+                    110,
+                    100
+                )
+                .unwrap()
+            );
+        }
+    }
+
+    #[test]
+    fn test_interpolate() {
+        let before = Observation::new(100);
+        let after = before
+            .transform(120, -30, Liquidity::from_integer(4))
+            .unwrap();
+
+        // bounds
+        assert_eq!(before.interpolate(&after, 100).unwrap(), before);
+        assert_eq!(before.interpolate(&after, 120).unwrap(), after);
+        assert!(before.interpolate(&after, 99).is_err());
+        assert!(before.interpolate(&after, 121).is_err());
+
+        // middle
+        let result = before.interpolate(&after, 110).unwrap();
+        assert_eq!(result.block_timestamp, 110);
+        assert_eq!(result.tick_cumulative, -300);
+        assert_eq!(
+            result.seconds_per_liquidity_cumulative,
+            SecondsPerLiquidity::from_scale(25, 1)
+        );
+        assert_eq!(
+            result,
+            before
+                .transform(110, -30, Liquidity::from_integer(4))
+                .unwrap()
+        );
+    }
+}
@@ -4,11 +4,14 @@ use crate::math::{
     clamm::*,
     log::get_tick_at_sqrt_price,
     types::{
-        fee_growth::FeeGrowth, liquidity::Liquidity, percentage::Percentage, sqrt_price::SqrtPrice,
+        fee_growth::FeeGrowth, liquidity::Liquidity, percentage::Percentage,
+        seconds_per_liquidity::SecondsPerLiquidity, sqrt_price::SqrtPrice,
         token_amount::TokenAmount,
     },
 };
-use crate::storage::{fee_tier::FeeTier, incentive::IncentiveRecord, tick::Tick};
+use crate::storage::{
+    dynamic_fee::DynamicFee, fee_tier::FeeTier, incentive::IncentiveRecord, tick::Tick,
+};
 
 use cosmwasm_schema::cw_serde;
 use cosmwasm_std::Addr;
@@ -31,6 +34,31 @@ pub struct Pool {
 
     #[serde(default)]
     pub incentives: Vec<IncentiveRecord>,
+
+    // seconds divided by in range liquidity accumulated since the pool was created
+    #[serde(default)]
+    pub seconds_per_liquidity_global: SecondsPerLiquidity,
+
+    // index of the last written observation
+    #[serde(default)]
+    pub observation_index: u16,
+    // number of observations currently stored
+    #[serde(default)]
+    pub observation_cardinality: u16,
+    // number of observations to store, takes effect once the ring buffer wraps
+    #[serde(default)]
+    pub observation_cardinality_next: u16,
+
+    // replaces the fee of the fee tier when set
+    #[serde(default)]
+    pub dynamic_fee: Option<DynamicFee>,
+
+    // number of open positions, retired incentives wait for all of them to settle
+    #[serde(default)]
+    pub position_count: u64,
+    // ids are not reused once retired incentives are pruned
+    #[serde(default)]
+    pub next_incentive_id: u64,
 }
 
 #[cw_serde]
@@ -200,6 +228,18 @@ impl Pool {
         Ok((total_amount, remaining_amount, has_crossed))
     }
 
+    /// Returns the fee of a swap starting now, the dynamic fee window moves forward
+    /// once it has elapsed.
+    pub fn swap_fee(&mut self, fee_tier: FeeTier, current_timestamp: u64) -> Percentage {
+        match self.dynamic_fee.as_mut() {
+            Some(dynamic_fee) => {
+                dynamic_fee.update_window(self.current_tick_index, current_timestamp / 1000);
+                dynamic_fee.get_fee(self.current_tick_index, fee_tier.tick_spacing)
+            }
+            None => fee_tier.fee,
+        }
+    }
+
     pub fn withdraw_protocol_fee(&mut self) -> (TokenAmount, TokenAmount) {
         let fee_protocol_token_x = self.fee_protocol_token_x;
         let fee_protocol_token_y = self.fee_protocol_token_y;
@@ -210,6 +250,32 @@ impl Pool {
         (fee_protocol_token_x, fee_protocol_token_y)
     }
 
+    /// Accumulates the time elapsed since `last_timestamp` per unit of in range liquidity.
+    /// Timestamps are in milliseconds while the accumulator grows by whole seconds,
+    /// it must be called before the liquidity of the pool changes.
+    pub fn update_seconds_per_liquidity_global(
+        &mut self,
+        current_timestamp: u64,
+    ) -> Result<(), ContractError> {
+        let current_seconds = current_timestamp / 1000;
+        let last_seconds = self.last_timestamp / 1000;
+
+        if !self.liquidity.is_zero() && current_seconds > last_seconds {
+            let seconds_per_liquidity =
+                SecondsPerLiquidity::calculate_seconds_per_liquidity_global(
+                    self.liquidity,
+                    current_seconds,
+                    last_seconds,
+                )?;
+            self.seconds_per_liquidity_global = self
+                .seconds_per_liquidity_global
+                .unchecked_add(seconds_per_liquidity);
+        }
+        self.last_timestamp = current_timestamp;
+
+        Ok(())
+    }
+
     pub fn update_global_incentives(
         &mut self,
         current_timestamp: u64,
@@ -220,12 +286,31 @@ impl Pool {
 
         Ok(())
     }
+
+    /// Returns the id of a new incentive record, pools created before ids were tracked
+    /// have never pruned a record so their ids follow the records
+    pub fn next_incentive_id(&mut self) -> u64 {
+        let id = self.next_incentive_id.max(self.incentives.len() as u64);
+        self.next_incentive_id = id + 1;
+        id
+    }
+
+    /// Removes the retired incentives every position has settled
+    pub fn prune_incentives(&mut self) {
+        self.incentives.retain(|record| {
+            record
+                .retirement
+                .as_ref()
+                .map_or(true, |retirement| retirement.unsettled_positions > 0)
+        });
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use crate::math::types::sqrt_price::calculate_sqrt_price;
     use crate::math::MAX_TICK;
+    use crate::storage::DynamicFeeConfig;
     use decimal::Factories;
 
     use super::*;
@@ -626,4 +711,87 @@ mod tests {
             assert_eq!(pool.liquidity, Liquidity::from_integer(5))
         }
     }
+
+    #[test]
+    fn test_update_seconds_per_liquidity_global() {
+        // accumulates whole seconds
+        {
+            let mut pool = Pool {
+                liquidity: Liquidity::from_integer(4),
+                last_timestamp: 10_500,
+                ..Default::default()
+            };
+            pool.update_seconds_per_liquidity_global(30_999).unwrap();
+
+            assert_eq!(
+                pool.seconds_per_liquidity_global,
+                SecondsPerLiquidity::from_integer(5)
+            );
+            assert_eq!(pool.last_timestamp, 30_999);
+
+            // same second, nothing to accumulate
+            pool.update_seconds_per_liquidity_global(30_999).unwrap();
+            assert_eq!(
+                pool.seconds_per_liquidity_global,
+                SecondsPerLiquidity::from_integer(5)
+            );
+        }
+        // no liquidity in range
+        {
+            let mut pool = Pool {
+                liquidity: Liquidity::new(0),
+                last_timestamp: 10_000,
+                seconds_per_liquidity_global: SecondsPerLiquidity::from_integer(7),
+                ..Default::default()
+            };
+            pool.update_seconds_per_liquidity_global(20_000).unwrap();
+
+            assert_eq!(
+                pool.seconds_per_liquidity_global,
+                SecondsPerLiquidity::from_integer(7)
+            );
+            assert_eq!(pool.last_timestamp, 20_000);
+        }
+    }
+
+    #[test]
+    fn test_swap_fee() {
+        let fee_tier = FeeTier::new(Percentage::from_scale(6, 3), 10).unwrap();
+        // fee of the fee tier
+        {
+            let mut pool = Pool::default();
+            assert_eq!(pool.swap_fee(fee_tier, 0), fee_tier.fee);
+        }
+        // dynamic fee
+        {
+            let config = DynamicFeeConfig {
+                base_fee: Percentage::from_scale(1, 3),
+                max_fee: Percentage::from_scale(1, 2),
+                window: 60,
+            };
+            let mut pool = Pool {
+                current_tick_index: 0,
+                dynamic_fee: Some(DynamicFee::new(config, 0, 100).unwrap()),
+                ..Default::default()
+            };
+            assert_eq!(
+                pool.swap_fee(fee_tier, 100_000),
+                Percentage::from_scale(1, 3)
+            );
+
+            pool.current_tick_index = 25;
+            assert_eq!(
+                pool.swap_fee(fee_tier, 159_999),
+                Percentage::from_scale(3, 3)
+            );
+
+            // the window elapsed, the current tick becomes the reference
+            assert_eq!(
+                pool.swap_fee(fee_tier, 160_000),
+                Percentage::from_scale(1, 3)
+            );
+            assert_eq!(pool.dynamic_fee.unwrap().window_start, 160);
+            assert_eq!(pool.dynamic_fee.unwrap().reference_tick, 25);
+        }
+    }
 }
@@ -16,6 +16,15 @@ use crate::{
 use cosmwasm_schema::cw_serde;
 use decimal::*;
 
+/// A position closed automatically once the price crosses its far tick.
+#[cw_serde]
+#[derive(Copy)]
+pub struct LimitOrder {
+    // tick whose crossing fills the order
+    pub tick_index: i32,
+    pub filled: bool,
+}
+
 #[cw_serde]
 #[derive(Default)]
 pub struct Position {
@@ -35,6 +44,8 @@ pub struct Position {
     pub token_id: u64,
     #[serde(default)]
     pub incentives: Vec<PositionIncentives>,
+    #[serde(default)]
+    pub limit_order: Option<LimitOrder>,
 }
 
 impl Position {
@@ -49,7 +60,7 @@ impl Position {
         current_timestamp: u64,
         tick_spacing: u16,
     ) -> Result<(TokenAmount, TokenAmount), ContractError> {
-        pool.last_timestamp = current_timestamp;
+        pool.update_seconds_per_liquidity_global(current_timestamp)?;
 
         // calculate dynamically limit allows easy modification
         let max_liquidity_per_tick = calculate_max_liquidity_per_tick(tick_spacing);
@@ -91,6 +102,22 @@ impl Position {
     ) -> Result<(), ContractError> {
         // try update incentives
         for record in &pool.incentives {
+            // retired incentives are computed from the pool as it was when they were retired
+            let current_tick_index = match &record.retirement {
+                Some(retirement) => {
+                    if self.token_id > retirement.last_token_id
+                        || self
+                            .incentives
+                            .iter()
+                            .any(|i| i.incentive_id == record.id && i.settled)
+                    {
+                        continue;
+                    }
+                    retirement.tick_index
+                }
+                None => pool.current_tick_index,
+            };
+
             let tick_lower_incentive_growth_outside = lower_tick
                 .incentives
                 .iter()
@@ -112,7 +139,7 @@ impl Position {
                 tick_lower_incentive_growth_outside,
                 upper_tick.index,
                 tick_upper_incentive_growth_outside,
-                pool.current_tick_index,
+                current_tick_index,
                 record.incentive_growth_global,
             );
 
@@ -135,6 +162,7 @@ impl Position {
                 incentive_id: record.id,
                 pending_rewards,
                 incentive_growth_inside,
+                settled: false,
             };
             self.incentives.push(incentive);
         }
@@ -142,6 +170,46 @@ impl Position {
         Ok(())
     }
 
+    /// Marks the retired incentives whose rewards were all paid to the position as settled and
+    /// prunes the ones every position has settled. Pending incentives must be taken before.
+    pub fn settle_retired_incentives(&mut self, pool: &mut Pool) {
+        for record in pool.incentives.iter_mut() {
+            let retirement = match record.retirement.as_mut() {
+                Some(retirement) => retirement,
+                None => continue,
+            };
+            if self.token_id > retirement.last_token_id {
+                continue;
+            }
+            match self
+                .incentives
+                .iter_mut()
+                .find(|i| i.incentive_id == record.id)
+            {
+                Some(incentive) => {
+                    if incentive.settled || !incentive.pending_rewards.is_zero() {
+                        continue;
+                    }
+                    incentive.settled = true;
+                }
+                None => self.incentives.push(PositionIncentives {
+                    incentive_id: record.id,
+                    pending_rewards: TokenAmount::new(0),
+                    incentive_growth_inside: FeeGrowth::new(0),
+                    settled: true,
+                }),
+            }
+            retirement.unsettled_positions = retirement.unsettled_positions.saturating_sub(1);
+        }
+
+        pool.prune_incentives();
+        self.incentives.retain(|i| {
+            pool.incentives
+                .iter()
+                .any(|record| record.id == i.incentive_id)
+        });
+    }
+
     pub fn update(
         &mut self,
         sign: bool,
@@ -219,8 +287,12 @@ impl Position {
         lower_tick: &Tick,
     ) -> Result<Vec<Asset>, ContractError> {
         self.update_incentives(pool, upper_tick, lower_tick)?;
-        let incentives: Vec<Asset> = self
-            .incentives
+        Ok(self.take_pending_incentives(pool))
+    }
+
+    /// Resets the already accumulated incentives and returns them as assets.
+    pub fn take_pending_incentives(&mut self, pool: &Pool) -> Vec<Asset> {
+        self.incentives
             .iter_mut()
             .filter_map(|incentive| {
                 if incentive.pending_rewards.is_zero() {
@@ -240,9 +312,43 @@ impl Position {
                 }
                 None
             })
-            .collect();
+            .collect()
+    }
 
-        Ok(incentives)
+    pub fn is_filled_order(&self) -> bool {
+        self.limit_order.map_or(false, |order| order.filled)
+    }
+
+    /// Withdraws the whole liquidity of a limit order, the tokens and the fees are kept
+    /// as owed tokens until the order is claimed.
+    pub fn fill(
+        &mut self,
+        pool: &mut Pool,
+        upper_tick: &mut Tick,
+        lower_tick: &mut Tick,
+        current_timestamp: u64,
+    ) -> Result<(), ContractError> {
+        // settle incentives before the liquidity is gone
+        self.update_incentives(pool, upper_tick, lower_tick)?;
+
+        let (amount_x, amount_y) = self.modify(
+            pool,
+            upper_tick,
+            lower_tick,
+            self.liquidity,
+            false,
+            current_timestamp,
+            self.pool_key.fee_tier.tick_spacing,
+        )?;
+
+        self.tokens_owed_x = self.tokens_owed_x.checked_add(amount_x)?;
+        self.tokens_owed_y = self.tokens_owed_y.checked_add(amount_y)?;
+
+        if let Some(order) = self.limit_order.as_mut() {
+            order.filled = true;
+        }
+
+        Ok(())
     }
     #[allow(clippy::too_many_arguments)]
     pub fn create(
@@ -264,10 +370,12 @@ impl Position {
         let incentives: Vec<PositionIncentives> = pool
             .incentives
             .iter()
+            .filter(|record| !record.is_retired())
             .map(|record| PositionIncentives {
                 incentive_id: record.id,
                 pending_rewards: TokenAmount::new(0),
                 incentive_growth_inside: FeeGrowth::new(0),
+                settled: false,
             })
             .collect();
 
@@ -285,6 +393,7 @@ impl Position {
             tokens_owed_y: TokenAmount::new(0),
             approvals: vec![],
             incentives,
+            limit_order: None,
         };
 
         // try update incentives first
@@ -362,6 +471,9 @@ impl Position {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::asset::AssetInfo;
+    use crate::storage::incentive::{IncentiveRecord, IncentiveRetirement};
+    use crate::storage::FeeTier;
 
     #[test]
     fn test_calculate_new_liquidity() {
@@ -581,4 +693,144 @@ mod tests {
             assert_eq!({ position.tokens_owed_x }, TokenAmount(151167));
         }
     }
+
+    #[test]
+    fn test_fill() {
+        // range below the current tick only holds token y
+        let liquidity = Liquidity::from_integer(1000000);
+        let mut position = Position {
+            pool_key: PoolKey {
+                fee_tier: FeeTier {
+                    tick_spacing: 10,
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+            liquidity,
+            lower_tick_index: -20,
+            upper_tick_index: -10,
+            tokens_owed_y: TokenAmount(5),
+            limit_order: Some(LimitOrder {
+                tick_index: -20,
+                filled: false,
+            }),
+            ..Default::default()
+        };
+        let mut pool = Pool {
+            current_tick_index: 0,
+            liquidity: Liquidity::from_integer(10),
+            ..Default::default()
+        };
+        let mut upper_tick = Tick {
+            index: -10,
+            sign: false,
+            liquidity_change: liquidity,
+            liquidity_gross: liquidity,
+            ..Default::default()
+        };
+        let mut lower_tick = Tick {
+            index: -20,
+            sign: true,
+            liquidity_change: liquidity,
+            liquidity_gross: liquidity,
+            ..Default::default()
+        };
+
+        assert!(!position.is_filled_order());
+        position
+            .fill(&mut pool, &mut upper_tick, &mut lower_tick, 0)
+            .unwrap();
+
+        assert!(position.is_filled_order());
+        assert_eq!(position.liquidity, Liquidity::new(0));
+        assert_eq!(position.tokens_owed_x, TokenAmount(0));
+        assert_eq!(position.tokens_owed_y, TokenAmount(5 + 499));
+        assert_eq!(upper_tick.liquidity_gross, Liquidity::new(0));
+        assert_eq!(lower_tick.liquidity_gross, Liquidity::new(0));
+        // out of range liquidity is not active
+        assert_eq!(pool.liquidity, Liquidity::from_integer(10));
+    }
+
+    #[test]
+    fn test_settle_retired_incentives() {
+        let record = |id: u64, retirement: Option<IncentiveRetirement>| IncentiveRecord {
+            id,
+            reward_per_sec: TokenAmount(0),
+            reward_token: AssetInfo::NativeToken {
+                denom: "orai".to_string(),
+            },
+            remaining: TokenAmount(0),
+            start_timestamp: 0,
+            incentive_growth_global: FeeGrowth::new(0),
+            last_updated: 0,
+            creator: None,
+            end_timestamp: None,
+            retirement,
+        };
+        let mut pool = Pool {
+            incentives: vec![
+                record(0, None),
+                record(
+                    1,
+                    Some(IncentiveRetirement {
+                        tick_index: 0,
+                        last_token_id: 2,
+                        unsettled_positions: 2,
+                    }),
+                ),
+            ],
+            ..Default::default()
+        };
+        let entry = |incentive_id: u64, pending_rewards: u128| PositionIncentives {
+            incentive_id,
+            pending_rewards: TokenAmount(pending_rewards),
+            incentive_growth_inside: FeeGrowth::new(0),
+            settled: false,
+        };
+
+        // rewards not paid yet
+        let mut first = Position {
+            token_id: 1,
+            incentives: vec![entry(0, 0), entry(1, 10)],
+            ..Default::default()
+        };
+        first.settle_retired_incentives(&mut pool);
+        assert!(!first.incentives[1].settled);
+
+        first.incentives[1].pending_rewards = TokenAmount(0);
+        first.settle_retired_incentives(&mut pool);
+        assert!(first.incentives[1].settled);
+        // settling twice does not count twice
+        first.settle_retired_incentives(&mut pool);
+        assert_eq!(
+            pool.incentives[1]
+                .retirement
+                .as_ref()
+                .unwrap()
+                .unsettled_positions,
+            1
+        );
+
+        // positions minted after the retirement never earned from it
+        let mut later = Position {
+            token_id: 3,
+            incentives: vec![entry(0, 0)],
+            ..Default::default()
+        };
+        later.settle_retired_incentives(&mut pool);
+        assert_eq!(pool.incentives.len(), 2);
+
+        // the last position without an entry settles and the record is pruned
+        let mut second = Position {
+            token_id: 2,
+            incentives: vec![entry(0, 0)],
+            ..Default::default()
+        };
+        second.settle_retired_incentives(&mut pool);
+        assert_eq!(pool.incentives.len(), 1);
+        assert_eq!(second.incentives, vec![entry(0, 0)]);
+
+        first.settle_retired_incentives(&mut pool);
+        assert_eq!(first.incentives, vec![entry(0, 0)]);
+    }
 }
@@ -4,6 +4,7 @@ use crate::{
     math::types::{
         fee_growth::FeeGrowth,
         liquidity::Liquidity,
+        seconds_per_liquidity::SecondsPerLiquidity,
         sqrt_price::{calculate_sqrt_price, SqrtPrice},
     },
 };
@@ -25,6 +26,9 @@ pub struct Tick {
 
     #[serde(default)]
     pub incentives: Vec<TickIncentive>,
+
+    #[serde(default)]
+    pub seconds_per_liquidity_outside: SecondsPerLiquidity,
 }
 
 pub const MAX_RESULT_SIZE: usize = 16 * 1024 * 8;
@@ -69,6 +73,7 @@ impl Tick {
         let incentives: Vec<TickIncentive> = pool
             .incentives
             .iter()
+            .filter(|record| !record.is_retired())
             .map(|record| TickIncentive {
                 incentive_id: record.id,
                 incentive_growth_outside: match below_current_tick {
@@ -94,6 +99,10 @@ impl Tick {
                 true => current_timestamp - pool.start_timestamp,
                 false => 0,
             },
+            seconds_per_liquidity_outside: match below_current_tick {
+                true => pool.seconds_per_liquidity_global,
+                false => SecondsPerLiquidity::new(0),
+            },
             incentives,
             ..Self::default()
         }
@@ -113,11 +122,22 @@ impl Tick {
             .ok_or(ContractError::TimestampSubOverflow)?;
         self.seconds_outside = seconds_passed.wrapping_sub(self.seconds_outside);
 
-        pool.last_timestamp = current_timestamp;
+        // accumulate with the liquidity from before the cross
+        pool.update_seconds_per_liquidity_global(current_timestamp)?;
+        self.seconds_per_liquidity_outside = pool
+            .seconds_per_liquidity_global
+            .unchecked_sub(self.seconds_per_liquidity_outside);
+
+        // drop the incentives pruned from the pool
+        self.incentives.retain(|i| {
+            pool.incentives
+                .iter()
+                .any(|record| record.id == i.incentive_id)
+        });
 
         // ensure update global incentive before
-        // Iterate through the pool incentives
-        for record in &pool.incentives {
+        // Iterate through the pool incentives, retired ones stay as they were when retired
+        for record in pool.incentives.iter().filter(|record| !record.is_retired()) {
             // Check if the incentive ID exists in the existing set
             if let Some(incentive) = self
                 .incentives
@@ -241,6 +261,7 @@ mod tests {
                 last_timestamp: 315360015,
                 start_timestamp: 4,
                 current_tick_index: 7,
+                seconds_per_liquidity_global: SecondsPerLiquidity::from_integer(78840),
                 ..Default::default()
             };
             let result_tick = Tick {
@@ -248,6 +269,7 @@ mod tests {
                 fee_growth_outside_y: FeeGrowth::new(10),
                 index: 3,
                 seconds_outside: 315360006,
+                seconds_per_liquidity_outside: SecondsPerLiquidity::from_integer(78840),
                 liquidity_change: Liquidity::from_integer(1),
                 ..Default::default()
             };
@@ -300,6 +322,13 @@ mod tests {
         }
         // fee_growth_outside should underflow
         {
+            let seconds_per_liquidity_global =
+                SecondsPerLiquidity::calculate_seconds_per_liquidity_global(
+                    Liquidity::from_integer(14),
+                    31536,
+                    0,
+                )
+                .unwrap();
             let mut pool = Pool {
                 fee_growth_global_x: FeeGrowth::new(3402),
                 fee_growth_global_y: FeeGrowth::new(3401),
@@ -324,6 +353,7 @@ mod tests {
                 last_timestamp: 31536000,
                 start_timestamp: 15,
                 current_tick_index: 9,
+                seconds_per_liquidity_global,
                 ..Default::default()
             };
             let result_tick = Tick {
@@ -331,6 +361,7 @@ mod tests {
                 fee_growth_outside_y: FeeGrowth::new(340282366920938463463374607431766958269),
                 index: 45,
                 seconds_outside: 31535911,
+                seconds_per_liquidity_outside: seconds_per_liquidity_global,
                 liquidity_change: Liquidity::new(10),
                 ..Default::default()
             };
@@ -341,6 +372,14 @@ mod tests {
         }
         // seconds_per_liquidity_outside should underflow
         {
+            let seconds_per_liquidity_global =
+                SecondsPerLiquidity::calculate_seconds_per_liquidity_global(
+                    Liquidity::new(14),
+                    315360,
+                    0,
+                )
+                .unwrap();
+            let seconds_per_liquidity_outside = SecondsPerLiquidity::max_instance();
             let mut pool = Pool {
                 fee_growth_global_x: FeeGrowth::new(145),
                 fee_growth_global_y: FeeGrowth::new(364),
@@ -355,6 +394,7 @@ mod tests {
                 fee_growth_outside_y: FeeGrowth::new(256),
                 index: 45,
                 seconds_outside: 74,
+                seconds_per_liquidity_outside,
                 liquidity_change: Liquidity::new(10),
                 ..Default::default()
             };
@@ -365,6 +405,7 @@ mod tests {
                 last_timestamp: 315360000,
                 start_timestamp: 15,
                 current_tick_index: 9,
+                seconds_per_liquidity_global,
                 ..Default::default()
             };
             let result_tick = Tick {
@@ -372,6 +413,8 @@ mod tests {
                 fee_growth_outside_y: FeeGrowth::new(108),
                 index: 45,
                 seconds_outside: 315359911,
+                seconds_per_liquidity_outside: seconds_per_liquidity_global
+                    .unchecked_sub(seconds_per_liquidity_outside),
                 liquidity_change: Liquidity::new(10),
                 ..Default::default()
             };
@@ -382,6 +425,32 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_create() {
+        let pool = Pool {
+            current_tick_index: 0,
+            start_timestamp: 1000,
+            seconds_per_liquidity_global: SecondsPerLiquidity::from_integer(3),
+            ..Default::default()
+        };
+
+        // below or at the current tick, everything so far happened below it
+        let tick = Tick::create(-10, &pool, 5000);
+        assert_eq!(tick.seconds_outside, 4000);
+        assert_eq!(
+            tick.seconds_per_liquidity_outside,
+            SecondsPerLiquidity::from_integer(3)
+        );
+
+        // above the current tick
+        let tick = Tick::create(10, &pool, 5000);
+        assert_eq!(tick.seconds_outside, 0);
+        assert_eq!(
+            tick.seconds_per_liquidity_outside,
+            SecondsPerLiquidity::new(0)
+        );
+    }
+
     #[test]
     fn test_update_liquidity_change() {
         // update when tick sign and sign of liquidity change are the same
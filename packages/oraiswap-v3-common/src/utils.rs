@@ -0,0 +1,186 @@
+/// Serde helpers for position token ids. cw721 uses string token ids, so they are serialized
+/// as strings and both strings and numbers are accepted when deserializing.
+pub mod token_id {
+    use std::fmt;
+
+    use serde::{
+        self,
+        de::{self, SeqAccess, Visitor},
+        ser::SerializeSeq,
+        Deserializer, Serializer,
+    };
+
+    struct TokenIdVisitor;
+
+    impl<'de> Visitor<'de> for TokenIdVisitor {
+        type Value = u64;
+
+        fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+            formatter.write_str("a token id as a string or a number")
+        }
+
+        fn visit_u64<E: de::Error>(self, value: u64) -> Result<u64, E> {
+            Ok(value)
+        }
+
+        fn visit_i64<E: de::Error>(self, value: i64) -> Result<u64, E> {
+            u64::try_from(value).map_err(|_| E::custom(format!("invalid token id: {}", value)))
+        }
+
+        fn visit_str<E: de::Error>(self, value: &str) -> Result<u64, E> {
+            value
+                .parse()
+                .map_err(|_| E::custom(format!("invalid token id: {}", value)))
+        }
+    }
+
+    pub fn serialize<S: Serializer>(token_id: &u64, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&token_id.to_string())
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<u64, D::Error> {
+        deserializer.deserialize_any(TokenIdVisitor)
+    }
+
+    pub mod option {
+        use super::*;
+
+        struct OptionVisitor;
+
+        impl<'de> Visitor<'de> for OptionVisitor {
+            type Value = Option<u64>;
+
+            fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+                formatter.write_str("an optional token id")
+            }
+
+            fn visit_none<E: de::Error>(self) -> Result<Self::Value, E> {
+                Ok(None)
+            }
+
+            fn visit_unit<E: de::Error>(self) -> Result<Self::Value, E> {
+                Ok(None)
+            }
+
+            fn visit_some<D: Deserializer<'de>>(
+                self,
+                deserializer: D,
+            ) -> Result<Self::Value, D::Error> {
+                super::deserialize(deserializer).map(Some)
+            }
+        }
+
+        pub fn serialize<S: Serializer>(
+            token_id: &Option<u64>,
+            serializer: S,
+        ) -> Result<S::Ok, S::Error> {
+            match token_id {
+                Some(token_id) => serializer.serialize_some(&token_id.to_string()),
+                None => serializer.serialize_none(),
+            }
+        }
+
+        pub fn deserialize<'de, D: Deserializer<'de>>(
+            deserializer: D,
+        ) -> Result<Option<u64>, D::Error> {
+            deserializer.deserialize_option(OptionVisitor)
+        }
+    }
+
+    pub mod vec {
+        use super::*;
+
+        struct VecVisitor;
+
+        impl<'de> Visitor<'de> for VecVisitor {
+            type Value = Vec<u64>;
+
+            fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+                formatter.write_str("a list of token ids")
+            }
+
+            fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+                let mut token_ids = Vec::with_capacity(seq.size_hint().unwrap_or_default());
+                while let Some(TokenId(token_id)) = seq.next_element()? {
+                    token_ids.push(token_id);
+                }
+                Ok(token_ids)
+            }
+        }
+
+        struct TokenId(u64);
+
+        impl<'de> serde::Deserialize<'de> for TokenId {
+            fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+                super::deserialize(deserializer).map(TokenId)
+            }
+        }
+
+        pub fn serialize<S: Serializer>(
+            token_ids: &[u64],
+            serializer: S,
+        ) -> Result<S::Ok, S::Error> {
+            let mut seq = serializer.serialize_seq(Some(token_ids.len()))?;
+            for token_id in token_ids {
+                seq.serialize_element(&token_id.to_string())?;
+            }
+            seq.end()
+        }
+
+        pub fn deserialize<'de, D: Deserializer<'de>>(
+            deserializer: D,
+        ) -> Result<Vec<u64>, D::Error> {
+            deserializer.deserialize_seq(VecVisitor)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use cosmwasm_schema::cw_serde;
+    use cosmwasm_std::{from_json, to_json_binary, Binary, StdResult};
+
+    #[cw_serde]
+    struct Msg {
+        #[serde(with = "super::token_id")]
+        #[schemars(with = "String")]
+        token_id: u64,
+        #[serde(default, with = "super::token_id::option")]
+        #[schemars(with = "Option<String>")]
+        start_after: Option<u64>,
+        #[serde(with = "super::token_id::vec")]
+        #[schemars(with = "Vec<String>")]
+        tokens: Vec<u64>,
+    }
+
+    fn parse(json: &str) -> StdResult<Msg> {
+        from_json(&Binary::from(json.as_bytes()))
+    }
+
+    #[test]
+    fn test_token_id_serde() {
+        let msg = Msg {
+            token_id: 7,
+            start_after: Some(3),
+            tokens: vec![1, 2],
+        };
+        let json = to_json_binary(&msg).unwrap();
+        assert_eq!(
+            String::from_utf8(json.to_vec()).unwrap(),
+            r#"{"token_id":"7","start_after":"3","tokens":["1","2"]}"#
+        );
+        assert_eq!(from_json::<Msg>(&json).unwrap(), msg);
+
+        // numbers are still accepted
+        let parsed = parse(r#"{"token_id":7,"start_after":3,"tokens":[1,"2"]}"#).unwrap();
+        assert_eq!(parsed, msg);
+
+        let parsed = parse(r#"{"token_id":"7","start_after":null,"tokens":[]}"#).unwrap();
+        assert_eq!(parsed.start_after, None);
+        let parsed = parse(r#"{"token_id":"7","tokens":[]}"#).unwrap();
+        assert_eq!(parsed.start_after, None);
+
+        assert!(parse(r#"{"token_id":"x","tokens":[]}"#).is_err());
+        assert!(parse(r#"{"token_id":-1,"tokens":[]}"#).is_err());
+    }
+}
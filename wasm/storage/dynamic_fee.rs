@@ -0,0 +1,39 @@
+use crate::types::percentage::Percentage;
+use decimal::*;
+use serde::{Deserialize, Serialize};
+use tsify::Tsify;
+
+#[derive(Default, Debug, Copy, Clone, PartialEq, Serialize, Deserialize, Eq, Tsify)]
+#[tsify(into_wasm_abi, from_wasm_abi)]
+pub struct DynamicFeeConfig {
+    pub base_fee: Percentage,
+    pub max_fee: Percentage,
+    #[tsify(type = "number")]
+    pub window: u64,
+}
+
+#[derive(Default, Debug, Copy, Clone, PartialEq, Serialize, Deserialize, Eq, Tsify)]
+#[tsify(into_wasm_abi, from_wasm_abi)]
+pub struct DynamicFee {
+    pub config: DynamicFeeConfig,
+    #[tsify(type = "number")]
+    pub window_start: u64,
+    #[tsify(type = "number")]
+    pub reference_tick: i32,
+}
+
+impl DynamicFee {
+    pub fn update_window(&mut self, current_tick: i32, current_timestamp: u64) {
+        if current_timestamp >= self.window_start.saturating_add(self.config.window) {
+            self.window_start = current_timestamp;
+            self.reference_tick = current_tick;
+        }
+    }
+
+    pub fn get_fee(&self, current_tick: i32, tick_spacing: u16) -> Percentage {
+        let moves = current_tick.abs_diff(self.reference_tick) / tick_spacing as u32;
+        let fee = self.config.base_fee.get().saturating_mul(moves as u64 + 1);
+
+        Percentage::new(fee.min(self.config.max_fee.get()))
+    }
+}
@@ -1,4 +1,5 @@
 pub mod config;
+pub mod dynamic_fee;
 pub mod errors;
 pub mod fee_tier;
 pub mod pool;
@@ -8,6 +9,7 @@ pub mod swap_simulation;
 pub mod tick;
 
 pub use config::*;
+pub use dynamic_fee::*;
 pub use errors::*;
 pub use fee_tier::*;
 pub use pool::*;
@@ -3,8 +3,10 @@ use crate::clamm::{
     calculate_amount_delta, is_enough_amount_to_change_price, AmountDeltaResult, SwapResult,
 };
 use crate::fee_growth::FeeGrowth;
-use crate::types::{liquidity::Liquidity, sqrt_price::SqrtPrice, token_amount::TokenAmount};
-use crate::{get_tick_at_sqrt_price, FeeTier, LiquidityTick};
+use crate::types::{
+    liquidity::Liquidity, percentage::Percentage, sqrt_price::SqrtPrice, token_amount::TokenAmount,
+};
+use crate::{get_tick_at_sqrt_price, DynamicFee, FeeTier, LiquidityTick};
 use decimal::CheckedOps;
 use serde::{Deserialize, Serialize};
 use traceable_result::*;
@@ -28,9 +30,21 @@ pub struct Pool {
     pub last_timestamp: u64,
     #[tsify(type = "string")]
     pub fee_receiver: String,
+    #[serde(default)]
+    pub dynamic_fee: Option<DynamicFee>,
 }
 
 impl Pool {
+    pub fn swap_fee(&mut self, fee_tier: FeeTier, current_timestamp: u64) -> Percentage {
+        match self.dynamic_fee.as_mut() {
+            Some(dynamic_fee) => {
+                dynamic_fee.update_window(self.current_tick_index, current_timestamp / 1000);
+                dynamic_fee.get_fee(self.current_tick_index, fee_tier.tick_spacing)
+            }
+            None => fee_tier.fee,
+        }
+    }
+
     pub fn update_liquidity(
         &mut self,
         liquidity_delta: Liquidity,
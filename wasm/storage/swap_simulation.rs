@@ -1,6 +1,8 @@
 use serde::{Deserialize, Serialize};
 
-use crate::{sqrt_price::SqrtPrice, token_amount::TokenAmount, LiquidityTick};
+use crate::{
+    percentage::Percentage, sqrt_price::SqrtPrice, token_amount::TokenAmount, LiquidityTick,
+};
 
 #[derive(Serialize, Deserialize, Debug, tsify::Tsify)]
 #[tsify(from_wasm_abi, into_wasm_abi)]
@@ -9,6 +11,7 @@ pub struct CalculateSwapResult {
     pub amount_in: TokenAmount,
     pub amount_out: TokenAmount,
     pub fee: TokenAmount,
+    pub fee_rate: Percentage,
     pub start_sqrt_price: SqrtPrice,
     pub target_sqrt_price: SqrtPrice,
     pub crossed_ticks: Vec<LiquidityTick>,
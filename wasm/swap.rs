@@ -21,11 +21,18 @@ pub fn simulate_swap(
     amount: TokenAmount,
     by_amount_in: bool,
     sqrt_price_limit: SqrtPrice,
+    current_timestamp: u64,
 ) -> TrackableResult<CalculateSwapResult> {
     if amount.is_zero() {
         return Err(err!("Amount is zero"));
     }
 
+    // same fee as the contract would apply at the given block time in milliseconds
+    let fee_tier = FeeTier {
+        fee: pool.swap_fee(fee_tier, current_timestamp),
+        ..fee_tier
+    };
+
     if x_to_y {
         if pool.sqrt_price <= sqrt_price_limit || sqrt_price_limit > SqrtPrice::new(MAX_SQRT_PRICE)
         {
@@ -166,6 +173,7 @@ pub fn simulate_swap(
         start_sqrt_price,
         target_sqrt_price: pool.sqrt_price,
         fee: total_fee_amount,
+        fee_rate: fee_tier.fee,
         crossed_ticks,
         global_insufficient_liquidity,
         state_outdated,